@@ -0,0 +1,3 @@
+pub(crate) mod post_now;
+pub(crate) mod publishing;
+pub(crate) mod redis_layer;
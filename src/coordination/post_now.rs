@@ -0,0 +1,42 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio::time::sleep;
+
+/// Wakes the poster up when a queued content has to be published right away, instead of waiting for its next refresh
+///
+/// The Discord bot and the poster both run in the frontend, so they share it within the process
+#[derive(Debug, Default)]
+pub struct PostNow {
+    /// The content published regardless of the posting days and the daily cap, until the poster takes it
+    shortcode: Mutex<Option<String>>,
+    notify: Notify,
+}
+
+impl PostNow {
+    pub fn request(&self, shortcode: &str) {
+        *self.shortcode.lock().unwrap() = Some(shortcode.to_string());
+        self.notify.notify_one();
+    }
+
+    pub fn is_requested(&self, shortcode: &str) -> bool {
+        self.shortcode.lock().unwrap().as_deref() == Some(shortcode)
+    }
+
+    /// Called once the poster took the content, whether it was published or not
+    pub fn clear(&self, shortcode: &str) {
+        let mut requested_shortcode = self.shortcode.lock().unwrap();
+        if requested_shortcode.as_deref() == Some(shortcode) {
+            *requested_shortcode = None;
+        }
+    }
+
+    /// Sleeps until the next refresh of the poster, or until a content is asked to be published now
+    pub async fn wait(&self, refresh_rate: Duration) {
+        tokio::select! {
+            _ = sleep(refresh_rate) => {}
+            _ = self.notify.notified() => {}
+        }
+    }
+}
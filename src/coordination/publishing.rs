@@ -0,0 +1,40 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// The accounts of the process publishing right now, the restart replaces the whole process so it waits for all of them
+static PUBLISHES_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+/// Set once the restart is about to happen, no account starts publishing anymore
+static IS_PUBLISHING_HELD: AtomicBool = AtomicBool::new(false);
+
+/// Held by the poster of an account while it goes through the queue and the stories
+pub struct PublishingGuard;
+
+impl PublishingGuard {
+    /// Returns None while the publishing is held for a restart
+    pub fn start() -> Option<PublishingGuard> {
+        // Counted before checking the hold, so that the restart either sees this publish or it never starts
+        PUBLISHES_IN_FLIGHT.fetch_add(1, Ordering::SeqCst);
+        if IS_PUBLISHING_HELD.load(Ordering::SeqCst) {
+            PUBLISHES_IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+            return None;
+        }
+        Some(PublishingGuard)
+    }
+}
+
+impl Drop for PublishingGuard {
+    fn drop(&mut self) {
+        PUBLISHES_IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub fn hold_publishing() {
+    IS_PUBLISHING_HELD.store(true, Ordering::SeqCst);
+}
+
+pub fn release_publishing() {
+    IS_PUBLISHING_HELD.store(false, Ordering::SeqCst);
+}
+
+pub fn publishes_in_flight() -> usize {
+    PUBLISHES_IN_FLIGHT.load(Ordering::SeqCst)
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures::StreamExt;
+use redis::aio::{ConnectionManager, PubSub};
+use redis::AsyncCommands;
+
+/// Optional Redis integration, shared between the processes running the same account
+///
+/// When no "redis_url" is configured in the credentials every operation is a no-op,
+/// so the callers never need to check whether Redis is available
+#[derive(Clone)]
+pub struct Coordinator {
+    username: String,
+    /// Kept for the subscriptions, which need a connection of their own
+    client: Option<redis::Client>,
+    connection: Option<ConnectionManager>,
+}
+
+impl Coordinator {
+    pub async fn new(username: &str, credentials: &HashMap<String, String>) -> Self {
+        let client = match credentials.get("redis_url") {
+            Some(redis_url) if !redis_url.is_empty() => match redis::Client::open(redis_url.as_str()) {
+                Ok(client) => Some(client),
+                Err(e) => {
+                    tracing::warn!("Invalid Redis url, continuing without it: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        let connection = match &client {
+            Some(client) => match client.get_connection_manager().await {
+                Ok(connection) => Some(connection),
+                Err(e) => {
+                    tracing::warn!("Couldn't connect to Redis, continuing without it: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let client = client.filter(|_| connection.is_some());
+
+        Coordinator { username: username.to_string(), client, connection }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("repost_rusty:{}:{}", self.username, name)
+    }
+
+    /// Publishes an event to every process subscribed to this account, e.g. "new_content:<shortcode>"
+    pub async fn publish_event(&self, event: &str) {
+        if let Some(mut connection) = self.connection.clone() {
+            let result: redis::RedisResult<()> = connection.publish(self.key("events"), event).await;
+            if let Err(e) = result {
+                tracing::warn!("Couldn't publish event {}: {}", event, e);
+            }
+        }
+    }
+
+    /// Subscribes to the events published for this account by every process, None when Redis is not available
+    pub async fn subscribe_to_events(&self) -> Option<PubSub> {
+        let mut pubsub = match self.client.as_ref()?.get_async_pubsub().await {
+            Ok(pubsub) => pubsub,
+            Err(e) => {
+                tracing::warn!("Couldn't subscribe to the events: {}", e);
+                return None;
+            }
+        };
+        if let Err(e) = pubsub.subscribe(self.key("events")).await {
+            tracing::warn!("Couldn't subscribe to the events: {}", e);
+            return None;
+        }
+        Some(pubsub)
+    }
+
+    /// Waits for the next event of the subscription, never returns without one
+    pub async fn wait_for_event(&self, events: &mut Option<PubSub>) -> String {
+        if let Some(pubsub) = events {
+            if let Some(message) = pubsub.on_message().next().await {
+                return message.get_payload().unwrap_or_default();
+            }
+            // The connection was lost, the periodic refreshes take over from here
+            tracing::warn!("The subscription to the events ended");
+            *events = None;
+        }
+        std::future::pending().await
+    }
+
+    /// Increments a counter that expires after the given window, returns the updated count
+    pub async fn increment_counter(&self, name: &str, window: Duration) -> Option<u64> {
+        let mut connection = self.connection.clone()?;
+        let key = self.key(&format!("counter:{}", name));
+
+        let result: redis::RedisResult<u64> = connection.incr(&key, 1).await;
+        match result {
+            Ok(count) => {
+                // Only the first increment starts the window
+                if count == 1 {
+                    let _: redis::RedisResult<()> = connection.expire(&key, window.as_secs() as i64).await;
+                }
+                Some(count)
+            }
+            Err(e) => {
+                tracing::warn!("Couldn't increment counter {}: {}", name, e);
+                None
+            }
+        }
+    }
+
+    /// Counts one more request towards a budget shared between processes, returns whether it was still within the budget
+    ///
+    /// Checked against the count returned by the increment itself, so that two processes can't both take the last request
+    pub async fn try_spend(&self, name: &str, budget: u64, window: Duration) -> bool {
+        match self.increment_counter(name, window).await {
+            Some(count) => count <= budget,
+            None => true,
+        }
+    }
+
+    pub async fn get_cached(&self, name: &str) -> Option<String> {
+        let mut connection = self.connection.clone()?;
+        let result: redis::RedisResult<Option<String>> = connection.get(self.key(&format!("cache:{}", name))).await;
+        result.unwrap_or_default()
+    }
+
+    pub async fn set_cached(&self, name: &str, value: &str, ttl: Duration) {
+        if let Some(mut connection) = self.connection.clone() {
+            let result: redis::RedisResult<()> = connection.set_ex(self.key(&format!("cache:{}", name)), value, ttl.as_secs()).await;
+            if let Err(e) = result {
+                tracing::warn!("Couldn't cache {}: {}", name, e);
+            }
+        }
+    }
+}
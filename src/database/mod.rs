@@ -1,4 +1,8 @@
 //pub mod database_diesel;
 pub(crate) mod database;
+pub(crate) mod purge;
+pub(crate) mod schedule;
+pub(crate) mod settings;
+pub(crate) mod slots;
 pub mod schemas;
 pub mod wrappers;
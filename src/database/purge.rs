@@ -0,0 +1,19 @@
+use chrono::Utc;
+
+use crate::database::database::Database;
+use crate::{PURGE_INTERVAL, SOFT_DELETE_RETENTION};
+
+/// Permanently deletes the removed content once it can't be restored anymore
+pub async fn run_purge_loop(database: Database, username: String) {
+    loop {
+        let deleted_before = (Utc::now() - SOFT_DELETE_RETENTION).to_rfc3339();
+
+        let mut tx = database.begin_transaction().await;
+        let purged_rows = tx.purge_deleted_content(&deleted_before).await;
+        if purged_rows > 0 {
+            println!(" [{}] Purged {} removed rows", username, purged_rows);
+        }
+
+        tokio::time::sleep(PURGE_INTERVAL).await;
+    }
+}
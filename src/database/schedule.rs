@@ -0,0 +1,71 @@
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc, Weekday};
+
+use crate::database::database::UserSettings;
+
+/// How far ahead a day with room is looked for, past that the post time is kept as it is
+const MAX_SCHEDULE_LOOKAHEAD_DAYS: i64 = 14;
+
+const WEEKDAYS: [(&str, Weekday); 7] = [("mon", Weekday::Mon), ("tue", Weekday::Tue), ("wed", Weekday::Wed), ("thu", Weekday::Thu), ("fri", Weekday::Fri), ("sat", Weekday::Sat), ("sun", Weekday::Sun)];
+
+/// Parses the days of the week typed on Discord, e.g. "mon tue wed thu fri", in the order of the week and without repetitions
+pub fn parse_posting_days(posting_days: &[&str]) -> Result<Vec<Weekday>, String> {
+    let mut parsed = Vec::new();
+    for posting_day in posting_days {
+        match WEEKDAYS.iter().find(|(name, _)| posting_day.to_lowercase().starts_with(name)) {
+            Some((_, weekday)) => parsed.push(*weekday),
+            None => return Err(format!("A posting day has to be a day of the week like mon or tuesday, `{}` isn't one", posting_day)),
+        }
+    }
+    if parsed.is_empty() {
+        return Err("At least one day of the week has to be a posting day".to_string());
+    }
+    parsed.sort_by_key(|weekday| weekday.num_days_from_monday());
+    parsed.dedup();
+    Ok(parsed)
+}
+
+/// e.g. "mon,tue,wed,thu,fri", the way the days are stored in the user settings
+pub fn format_posting_days(posting_days: &[Weekday]) -> String {
+    WEEKDAYS.iter().filter(|(_, weekday)| posting_days.contains(weekday)).map(|(name, _)| *name).collect::<Vec<&str>>().join(",")
+}
+
+impl UserSettings {
+    /// The days of the week the account posts on, in the timezone of the account. Every day when none can be read
+    pub fn posting_days(&self) -> Vec<Weekday> {
+        let posting_days: Vec<&str> = self.posting_days.split(',').map(str::trim).filter(|posting_day| !posting_day.is_empty()).collect();
+        parse_posting_days(&posting_days).unwrap_or_else(|_| WEEKDAYS.iter().map(|(_, weekday)| *weekday).collect())
+    }
+
+    pub fn is_posting_day(&self, weekday: Weekday) -> bool {
+        self.posting_days().contains(&weekday)
+    }
+
+    /// Whether one more post fits on the day of the given time, given the posts already published or scheduled
+    pub fn allows_post_on(&self, post_times: &[DateTime<Utc>], time: &DateTime<Utc>) -> bool {
+        if !self.is_posting_day(time.weekday()) {
+            return false;
+        }
+        self.max_posts_per_day == 0 || post_times.iter().filter(|post_time| post_time.date_naive() == time.date_naive()).count() < self.max_posts_per_day as usize
+    }
+}
+
+/// Moves the post time to the next day with room when its day isn't a posting day or is already full
+///
+/// The post then comes the posting interval after the last post of that day, or after the start of the day when it has none
+pub fn next_allowed_post_time(user_settings: &UserSettings, post_times: &[DateTime<Utc>], post_time: DateTime<Utc>, posting_interval: Duration) -> DateTime<Utc> {
+    let mut new_post_time = post_time;
+    for _ in 0..MAX_SCHEDULE_LOOKAHEAD_DAYS {
+        if user_settings.allows_post_on(post_times, &new_post_time) {
+            return new_post_time;
+        }
+
+        let next_day = new_post_time.date_naive() + Duration::days(1);
+        new_post_time = match post_times.iter().filter(|post_time| post_time.date_naive() == next_day).max() {
+            Some(last_post_time) => *last_post_time + posting_interval,
+            None => next_day.and_time(NaiveTime::MIN).and_utc() + posting_interval,
+        };
+    }
+
+    tracing::warn!("No day with room for a post in the next {} days, keeping {}", MAX_SCHEDULE_LOOKAHEAD_DAYS, post_time.to_rfc3339());
+    post_time
+}
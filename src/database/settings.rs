@@ -0,0 +1,164 @@
+use chrono::Duration;
+
+use crate::database::database::UserSettings;
+
+/// The bounds of a numeric user setting, the values out of them are refused from Discord and clamped when the settings are loaded
+#[derive(Debug, Clone, Copy)]
+pub struct SettingRange {
+    pub name: &'static str,
+    pub min: i64,
+    pub max: i64,
+    pub unit: &'static str,
+}
+
+/// At least a minute apart, so that a typo can't make the poster hammer the Graph API
+pub const POSTING_INTERVAL: SettingRange = SettingRange {
+    name: "posting_interval",
+    min: 1,
+    max: 24 * 60,
+    unit: "minutes",
+};
+/// Also kept below the posting interval, otherwise two posts could be scheduled at the same time
+pub const RANDOM_INTERVAL_VARIANCE: SettingRange = SettingRange {
+    name: "random_interval_variance",
+    min: 0,
+    max: 12 * 60,
+    unit: "minutes",
+};
+pub const REJECTED_CONTENT_LIFESPAN: SettingRange = SettingRange {
+    name: "rejected_content_lifespan",
+    min: 1,
+    max: 7 * 24 * 60,
+    unit: "minutes",
+};
+/// Discord rate limits the edits of the messages well before a second
+pub const INTERFACE_UPDATE_INTERVAL: SettingRange = SettingRange {
+    name: "interface_update_interval",
+    min: 1000,
+    max: 10 * 60 * 1000,
+    unit: "milliseconds",
+};
+pub const TIMEZONE_OFFSET: SettingRange = SettingRange { name: "timezone_offset", min: -12, max: 14, unit: "hours" };
+pub const QUIET_HOURS_START: SettingRange = SettingRange {
+    name: "quiet_hours_start",
+    min: 0,
+    max: 23,
+    unit: "o'clock",
+};
+pub const QUIET_HOURS_END: SettingRange = SettingRange { name: "quiet_hours_end", min: 0, max: 23, unit: "o'clock" };
+pub const SCRAPE_WINDOW_START: SettingRange = SettingRange {
+    name: "scrape_window_start",
+    min: 0,
+    max: 23,
+    unit: "o'clock",
+};
+pub const SCRAPE_WINDOW_END: SettingRange = SettingRange {
+    name: "scrape_window_end",
+    min: 0,
+    max: 23,
+    unit: "o'clock",
+};
+pub const APPROVALS_REQUIRED: SettingRange = SettingRange {
+    name: "approvals_required",
+    min: 1,
+    max: 10,
+    unit: "approvals",
+};
+/// 0 turns the check off
+pub const MAX_SOURCE_AGE_DAYS: SettingRange = SettingRange {
+    name: "max_source_age_days",
+    min: 0,
+    max: 10 * 365,
+    unit: "days",
+};
+
+/// 0 turns the cap off
+pub const MAX_POSTS_PER_DAY: SettingRange = SettingRange { name: "max_posts_per_day", min: 0, max: 50, unit: "posts" };
+/// The published reels are removed from the bucket a day after they went live, the story has to be shared well before
+pub const STORY_SHARE_DELAY: SettingRange = SettingRange {
+    name: "story_share_delay",
+    min: 0,
+    max: 12 * 60,
+    unit: "minutes",
+};
+
+impl SettingRange {
+    /// Parses the value typed on Discord, the error says what was expected
+    pub fn parse(&self, value: &str) -> Result<i64, String> {
+        match value.parse::<i64>() {
+            Ok(parsed) => self.validate(parsed),
+            Err(_) => Err(format!("{} has to be a whole number of {}, `{}` isn't one", self.name, self.unit, value)),
+        }
+    }
+
+    pub fn validate(&self, value: i64) -> Result<i64, String> {
+        if (self.min..=self.max).contains(&value) {
+            Ok(value)
+        } else {
+            Err(format!("{} has to be between {} and {} {}, `{}` isn't", self.name, self.min, self.max, self.unit, value))
+        }
+    }
+
+    fn clamp<T: Into<i64> + TryFrom<i64> + Copy>(&self, value: &mut T, username: &str) {
+        let original: i64 = (*value).into();
+        let clamped = original.clamp(self.min, self.max);
+        if clamped != original {
+            tracing::warn!("The {} of {} was {}, out of its range, using {} instead", self.name, username, original, clamped);
+            // The bounds always fit in the type of the setting
+            *value = T::try_from(clamped).ok().unwrap_or(*value);
+        }
+    }
+}
+
+impl UserSettings {
+    /// Brings the values set straight in the database, or before the ranges existed, back within their range
+    pub fn clamp_to_ranges(&mut self) {
+        let username = self.username.clone();
+        POSTING_INTERVAL.clamp(&mut self.posting_interval, &username);
+        RANDOM_INTERVAL_VARIANCE.clamp(&mut self.random_interval_variance, &username);
+        REJECTED_CONTENT_LIFESPAN.clamp(&mut self.rejected_content_lifespan, &username);
+        INTERFACE_UPDATE_INTERVAL.clamp(&mut self.interface_update_interval, &username);
+        TIMEZONE_OFFSET.clamp(&mut self.timezone_offset, &username);
+        QUIET_HOURS_START.clamp(&mut self.quiet_hours_start, &username);
+        QUIET_HOURS_END.clamp(&mut self.quiet_hours_end, &username);
+        SCRAPE_WINDOW_START.clamp(&mut self.scrape_window_start, &username);
+        SCRAPE_WINDOW_END.clamp(&mut self.scrape_window_end, &username);
+        APPROVALS_REQUIRED.clamp(&mut self.approvals_required, &username);
+        MAX_SOURCE_AGE_DAYS.clamp(&mut self.max_source_age_days, &username);
+        STORY_SHARE_DELAY.clamp(&mut self.story_share_delay, &username);
+        MAX_POSTS_PER_DAY.clamp(&mut self.max_posts_per_day, &username);
+
+        if self.random_interval_variance >= self.posting_interval {
+            tracing::warn!("The random_interval_variance of {} was {}, not below the posting interval, using {} instead", username, self.random_interval_variance, self.posting_interval - 1);
+            self.random_interval_variance = self.posting_interval - 1;
+        }
+    }
+
+    /// The variance has to stay below the posting interval, or the randomized interval could go down to nothing
+    pub fn validate_random_interval_variance(&self, random_interval_variance: i32) -> Result<i32, String> {
+        if random_interval_variance >= self.posting_interval {
+            return Err(format!("random_interval_variance has to be below the posting interval of {} minutes, `{}` isn't", self.posting_interval, random_interval_variance));
+        }
+        Ok(random_interval_variance)
+    }
+
+    pub fn posting_interval(&self) -> Duration {
+        Duration::minutes(self.posting_interval as i64)
+    }
+
+    pub fn random_interval_variance(&self) -> Duration {
+        Duration::minutes(self.random_interval_variance as i64)
+    }
+
+    pub fn rejected_content_lifespan(&self) -> Duration {
+        Duration::minutes(self.rejected_content_lifespan as i64)
+    }
+
+    pub fn interface_update_interval(&self) -> Duration {
+        Duration::milliseconds(self.interface_update_interval)
+    }
+
+    pub fn story_share_delay(&self) -> Duration {
+        Duration::minutes(self.story_share_delay as i64)
+    }
+}
@@ -0,0 +1,69 @@
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+
+use crate::database::database::UserSettings;
+
+/// How far ahead a free slot is looked for, past that the posting interval takes over
+const MAX_SLOT_LOOKAHEAD_DAYS: i64 = 60;
+
+/// Parses the daily posting slots typed on Discord, e.g. "09:00 13:00 19:00", sorted and without repetitions
+pub fn parse_posting_slots(posting_slots: &[&str]) -> Result<Vec<NaiveTime>, String> {
+    let mut parsed = Vec::new();
+    for posting_slot in posting_slots {
+        match NaiveTime::parse_from_str(posting_slot, "%H:%M") {
+            Ok(posting_slot) => parsed.push(posting_slot),
+            Err(_) => return Err(format!("A posting slot has to be a time of the day like 09:00, `{}` isn't one", posting_slot)),
+        }
+    }
+    parsed.sort();
+    parsed.dedup();
+    Ok(parsed)
+}
+
+/// e.g. "09:00,13:00,19:00", the way the slots are stored in the user settings
+pub fn format_posting_slots(posting_slots: &[NaiveTime]) -> String {
+    posting_slots.iter().map(|posting_slot| posting_slot.format("%H:%M").to_string()).collect::<Vec<String>>().join(",")
+}
+
+impl UserSettings {
+    /// The times of the day the content is posted at, in the timezone of the account. Empty when the posting interval is used instead
+    pub fn posting_slots(&self) -> Vec<NaiveTime> {
+        let posting_slots: Vec<&str> = self.posting_slots.split(',').map(str::trim).filter(|posting_slot| !posting_slot.is_empty()).collect();
+        parse_posting_slots(&posting_slots).unwrap_or_default()
+    }
+}
+
+/// A posting slot on a given day, it lasts until the next slot starts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SlotWindow {
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+impl SlotWindow {
+    pub fn contains(&self, time: &DateTime<Utc>) -> bool {
+        self.starts_at <= *time && *time < self.ends_at
+    }
+}
+
+/// The slot windows starting after the given time, over the given amount of days
+///
+/// The times are in the timezone of the account, like the rest of the times of the database
+pub fn slot_windows(posting_slots: &[NaiveTime], after: DateTime<Utc>, days: i64) -> Vec<SlotWindow> {
+    let first_day = after.date_naive();
+    let mut starts: Vec<DateTime<Utc>> = (0..=days).flat_map(|day| posting_slots.iter().map(move |posting_slot| (first_day + Duration::days(day)).and_time(*posting_slot).and_utc())).collect();
+    starts.sort();
+
+    starts
+        .windows(2)
+        .map(|starts| SlotWindow { starts_at: starts[0], ends_at: starts[1] })
+        .filter(|slot_window| slot_window.starts_at > after && slot_window.starts_at < after + Duration::days(days))
+        .collect()
+}
+
+/// Returns the first slot after the given time that none of the posts falls in and that is allowed, None if they are all taken for a while
+pub fn next_free_slot(posting_slots: &[NaiveTime], post_times: &[DateTime<Utc>], after: DateTime<Utc>, is_allowed: impl Fn(&DateTime<Utc>) -> bool) -> Option<DateTime<Utc>> {
+    slot_windows(posting_slots, after, MAX_SLOT_LOOKAHEAD_DAYS)
+        .into_iter()
+        .find(|slot_window| !post_times.iter().any(|post_time| slot_window.contains(post_time)) && is_allowed(&slot_window.starts_at))
+        .map(|slot_window| slot_window.starts_at)
+}
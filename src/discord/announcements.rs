@@ -0,0 +1,98 @@
+use chrono::{DateTime, Duration, Utc};
+use serenity::all::{ChannelId, Context, CreateMessage, EditMessage};
+
+use crate::database::database::{ContentInfo, DatabaseTransaction, PostAnnouncement, QueuedContent};
+use crate::discord::bot::Handler;
+use crate::discord::state::ContentStatus;
+use crate::discord::utils::{handle_msg_deletion, send_message_with_retry};
+use crate::ANNOUNCEMENT_LEAD;
+
+impl Handler {
+    fn community_channel_id(&self) -> Option<ChannelId> {
+        self.credentials.get("community_channel_id").and_then(|channel_id| channel_id.parse::<u64>().ok()).map(ChannelId::new)
+    }
+
+    fn announcement_lead(&self) -> Duration {
+        let minutes = self.credentials.get("announcement_lead_minutes").and_then(|minutes| minutes.parse::<i64>().ok()).unwrap_or(ANNOUNCEMENT_LEAD.as_secs() as i64 / 60);
+        Duration::minutes(minutes)
+    }
+
+    /// Whether the teaser is deleted once the reel is live, instead of being updated with the link to it
+    fn deletes_announcements_after_post(&self) -> bool {
+        self.credentials.get("announcement_after_post").is_some_and(|value| value == "delete")
+    }
+
+    /// Teases the content about to be published in the community channel, the teaser follows the content until it's published or dropped
+    pub(crate) async fn announce_upcoming_posts(&self, ctx: &Context, tx: &mut DatabaseTransaction, content_queue: &[QueuedContent], content_info_vec: &[ContentInfo], now: DateTime<Utc>) {
+        let Some(community_channel_id) = self.community_channel_id() else {
+            return;
+        };
+        let announcements = tx.load_post_announcements().await;
+
+        for queued_post in content_queue {
+            let Ok(will_post_at) = DateTime::parse_from_rfc3339(&queued_post.will_post_at) else {
+                continue;
+            };
+            let teaser = format!("New reel dropping at {} 👀", will_post_at.format("%H:%M"));
+
+            match announcements.iter().find(|announcement| announcement.original_shortcode == queued_post.original_shortcode) {
+                // The content was rescheduled since it was announced
+                Some(announcement) if announcement.will_post_at != queued_post.will_post_at => {
+                    if let Err(e) = community_channel_id.edit_message(&ctx.http, announcement.message_id, EditMessage::new().content(teaser)).await {
+                        tracing::error!("Error updating the announcement of {}: {:?}", queued_post.original_shortcode, e);
+                    }
+                    tx.save_post_announcement(&PostAnnouncement {
+                        will_post_at: queued_post.will_post_at.clone(),
+                        ..announcement.clone()
+                    })
+                    .await;
+                }
+                Some(_) => {}
+                None => {
+                    let time_left = will_post_at.with_timezone(&Utc) - now;
+                    if time_left <= Duration::zero() || time_left > self.announcement_lead() {
+                        continue;
+                    }
+
+                    let msg = send_message_with_retry(ctx, community_channel_id, CreateMessage::new().content(teaser)).await;
+                    let announcement = PostAnnouncement {
+                        username: self.username.clone(),
+                        original_shortcode: queued_post.original_shortcode.clone(),
+                        message_id: msg.id,
+                        will_post_at: queued_post.will_post_at.clone(),
+                    };
+                    tx.save_post_announcement(&announcement).await;
+                }
+            }
+        }
+
+        for announcement in announcements {
+            if content_queue.iter().any(|queued_post| queued_post.original_shortcode == announcement.original_shortcode) {
+                continue;
+            }
+
+            let status = content_info_vec.iter().find(|content_info| content_info.original_shortcode == announcement.original_shortcode).map(|content_info| &content_info.status);
+            let permalink = if matches!(status, Some(ContentStatus::Published { .. })) {
+                // The poster marks the content as published right before recording the publication
+                let Some(published_content) = tx.get_published_content_by_shortcode(&announcement.original_shortcode).await else {
+                    continue;
+                };
+                Some(published_content.permalink).filter(|permalink| !published_content.is_simulated && !permalink.is_empty())
+            } else {
+                None
+            };
+
+            // The teaser of a content that didn't make it is taken down as well
+            match permalink {
+                Some(permalink) if !self.deletes_announcements_after_post() => {
+                    let msg = EditMessage::new().content(format!("New reel out now! {}", permalink));
+                    if let Err(e) = community_channel_id.edit_message(&ctx.http, announcement.message_id, msg).await {
+                        tracing::error!("Error updating the announcement of {}: {:?}", announcement.original_shortcode, e);
+                    }
+                }
+                _ => handle_msg_deletion(community_channel_id.delete_message(&ctx.http, announcement.message_id).await),
+            }
+            tx.remove_post_announcement(&announcement.original_shortcode).await;
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashSet;
+
+use chrono::{Duration, Utc};
+use serenity::all::{ChannelId, Context, GetMessages, MessageId};
+use tokio::time::sleep;
+
+use crate::database::database::DatabaseTransaction;
+use crate::discord::bot::{ChannelIdMap, Handler};
+use crate::discord::utils::handle_msg_deletion;
+use crate::{ARCHIVE_AFTER, ARCHIVE_BATCH_DELAY, ARCHIVE_INTERVAL, POSTED_CHANNEL_ID};
+
+/// Discord only fetches 100 messages at a time and only bulk deletes up to 100 messages
+const ARCHIVE_BATCH_SIZE: u8 = 100;
+/// How many batches of messages are looked at in each channel per run
+const MAX_ARCHIVE_BATCHES: usize = 10;
+/// Messages older than this can't be bulk deleted and have to be deleted one by one
+const BULK_DELETE_MAX_AGE: Duration = Duration::days(14);
+
+impl Handler {
+    /// Periodically deletes the old messages of the bot that aren't showing any content anymore
+    pub async fn run_archival_loop(&self, ctx: Context) {
+        loop {
+            sleep(ARCHIVE_INTERVAL).await;
+
+            let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+            let mut tx = self.database.begin_transaction().await;
+            let mut archived_messages = 0;
+            for channel_id in [channel_id, POSTED_CHANNEL_ID] {
+                archived_messages += self.archive_stale_messages(&ctx, &mut tx, channel_id).await;
+            }
+
+            if archived_messages > 0 {
+                println!(" [{}] Archived {} stale messages", self.username, archived_messages);
+            }
+        }
+    }
+
+    async fn archive_stale_messages(&self, ctx: &Context, tx: &mut DatabaseTransaction, channel_id: ChannelId) -> usize {
+        let archive_before = Utc::now() - self.archive_after();
+        let bulk_delete_after = Utc::now() - BULK_DELETE_MAX_AGE + Duration::hours(1);
+
+        // Cards of the content that is still around are kept, whatever their age
+        let mut live_messages: HashSet<MessageId> = tx.load_content_mapping().await.iter().map(|content_info| content_info.message_id).collect();
+        live_messages.insert(tx.load_bot_status().await.message_id);
+        live_messages.extend(tx.load_story_queue().await.iter().map(|story| story.message_id));
+        live_messages.extend(tx.load_source_suggestions().await.iter().filter(|suggestion| suggestion.status == "pending").map(|suggestion| suggestion.message_id));
+        live_messages.extend(tx.load_login_challenge().await.map(|challenge| challenge.message_id));
+
+        // The posted channel is shared between the accounts, only the messages of this bot are looked at
+        let bot_id = ctx.cache.current_user().id;
+
+        let mut stale_messages = Vec::new();
+        let mut old_stale_messages = Vec::new();
+        let mut before = None;
+        for _ in 0..MAX_ARCHIVE_BATCHES {
+            let mut request = GetMessages::new().limit(ARCHIVE_BATCH_SIZE);
+            if let Some(before) = before {
+                request = request.before(before);
+            }
+
+            let messages = match channel_id.messages(&ctx.http, request).await {
+                Ok(messages) => messages,
+                Err(e) => {
+                    tracing::error!("Error fetching messages to archive: {:?}", e);
+                    break;
+                }
+            };
+
+            for message in &messages {
+                let sent_at = *message.timestamp;
+                if message.author.id != bot_id || live_messages.contains(&message.id) || message.content.contains("Welcome back!") || sent_at > archive_before {
+                    continue;
+                }
+
+                if sent_at > bulk_delete_after {
+                    stale_messages.push(message.id);
+                } else {
+                    old_stale_messages.push(message.id);
+                }
+            }
+
+            match messages.last() {
+                Some(last_message) if messages.len() == ARCHIVE_BATCH_SIZE as usize => before = Some(last_message.id),
+                _ => break,
+            }
+        }
+
+        let archived_messages = stale_messages.len() + old_stale_messages.len();
+
+        // Batches are spaced out so that the rest of the bot doesn't get rate limited
+        for batch in stale_messages.chunks(ARCHIVE_BATCH_SIZE as usize) {
+            if let Err(e) = channel_id.delete_messages(&ctx.http, batch).await {
+                tracing::error!("Error bulk deleting stale messages: {:?}", e);
+            }
+            sleep(ARCHIVE_BATCH_DELAY).await;
+        }
+
+        for message_id in old_stale_messages {
+            handle_msg_deletion(ctx.http.delete_message(channel_id, message_id, None).await);
+            sleep(ARCHIVE_BATCH_DELAY).await;
+        }
+
+        archived_messages
+    }
+
+    /// How old the stale messages have to be before being archived, can be overridden per account in the credentials
+    fn archive_after(&self) -> Duration {
+        let hours = self.credentials.get("archive_after_hours").and_then(|hours| hours.parse::<i64>().ok()).unwrap_or(ARCHIVE_AFTER.as_secs() as i64 / 3600);
+        Duration::hours(hours)
+    }
+}
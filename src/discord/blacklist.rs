@@ -0,0 +1,65 @@
+use serenity::all::Message;
+
+use crate::database::database::BlacklistEntry;
+use crate::discord::bot::Handler;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::blacklist::BLACKLIST_KINDS;
+
+impl Handler {
+    /// Keeps the scraper away from an author, a shortcode or the captions containing a keyword, or lists what is blacklisted
+    pub(crate) async fn command_blacklist(&self, msg: &Message, arguments: &[&str]) -> String {
+        let mut tx = self.database.begin_transaction().await;
+        let (kind, value) = match arguments {
+            [] => return list_blacklist(&tx.load_blacklist().await),
+            [kind, value @ ..] if BLACKLIST_KINDS.contains(kind) && !value.is_empty() => (*kind, blacklist_value(kind, value)),
+            _ => return format!("Usage: `{}blacklist <{}> <value>`", COMMAND_PREFIX, BLACKLIST_KINDS.join("|")),
+        };
+
+        let user_settings = tx.load_user_settings().await;
+        let blacklist_entry = BlacklistEntry {
+            username: self.username.clone(),
+            kind: kind.to_string(),
+            value: value.clone(),
+            added_by: msg.author.name.clone(),
+            added_at: now_in_my_timezone(&user_settings).to_rfc3339(),
+        };
+        if !tx.save_blacklist_entry(&blacklist_entry).await {
+            return format!("The {} {} is already blacklisted.", kind, value);
+        }
+        format!("Blacklisted the {} {}, the scraper won't take any content from it anymore.", kind, value)
+    }
+
+    pub(crate) async fn command_unblacklist(&self, arguments: &[&str]) -> String {
+        let (kind, value) = match arguments {
+            [kind, value @ ..] if BLACKLIST_KINDS.contains(kind) && !value.is_empty() => (*kind, blacklist_value(kind, value)),
+            _ => return format!("Usage: `{}unblacklist <{}> <value>`", COMMAND_PREFIX, BLACKLIST_KINDS.join("|")),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        if !tx.remove_blacklist_entry(kind, &value).await {
+            return format!("The {} {} isn't blacklisted.", kind, value);
+        }
+        format!("Removed the {} {} from the blacklist.", kind, value)
+    }
+}
+
+/// The keywords can be made of several words, the authors are stored without the "@" and both are matched regardless of the case
+fn blacklist_value(kind: &str, value: &[&str]) -> String {
+    match kind {
+        "shortcode" => value.join(" "),
+        _ => value.join(" ").trim_start_matches('@').to_lowercase(),
+    }
+}
+
+fn list_blacklist(blacklist: &[BlacklistEntry]) -> String {
+    if blacklist.is_empty() {
+        return "Nothing is blacklisted.".to_string();
+    }
+
+    let mut list = "Blacklisted:".to_string();
+    for blacklist_entry in blacklist {
+        list.push_str(&format!("\n- {} {}, by {}", blacklist_entry.kind, blacklist_entry.value, blacklist_entry.added_by));
+    }
+    list
+}
@@ -7,18 +7,30 @@ use rand::prelude::{SliceRandom, StdRng};
 use rand::SeedableRng;
 use s3::Bucket;
 use serde::{Deserialize, Serialize};
-use serenity::all::{Builder, ChannelId, CreateInteractionResponse, CreateMessage, GetMessages, Interaction, MessageId, RatelimitInfo};
+use serenity::all::{Builder, ChannelId, CreateInteractionResponse, GuildMemberUpdateEvent, Interaction, Member, MessageId, RatelimitInfo};
 use serenity::async_trait;
 use serenity::model::channel::Message;
 use serenity::prelude::*;
 use tokio::sync::Mutex;
 use tokio::time::sleep;
 
+use crate::coordination::post_now::PostNow;
+use crate::coordination::redis_layer::Coordinator;
 use crate::database::database::{Database, DatabaseTransaction, UserSettings};
-use crate::discord::interactions::{EditedContent, EditedContentKind};
+use crate::discord::category_mix::{read_category_targets, CategoryTargets};
+use crate::discord::challenge::{CHALLENGE_CODE_BUTTON, CHALLENGE_CODE_MODAL};
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::discord::interactions::{EditedContent, EditedContentKind, REJECTION_NOTE_MODAL, REJECTION_REASON_PREFIX, REJECT_OTHER_BUTTON};
+use crate::discord::onboarding::TOUR_BUTTON_PREFIX;
 use crate::discord::state::ContentStatus;
-use crate::discord::utils::{clear_all_messages, prune_expired_content};
-use crate::{crab, DISCORD_REFRESH_RATE, GUILD_ID, POSTED_CHANNEL_ID, STATUS_CHANNEL_ID};
+use crate::discord::utils::prune_expired_content;
+use crate::discord::view::sort_cards_to_send;
+use crate::discord::warm_up::{warm_up, WarmUpProgressMap};
+use crate::self_check::report::SelfCheckReport;
+use crate::{DISCORD_REFRESH_RATE, MESSAGE_LOOKUP_CACHE_TTL};
+
+/// Name under which the Discord messages are stored in the frontend_messages table
+pub(crate) const FRONTEND_NAME: &str = "discord";
 
 #[derive(Clone)]
 pub struct Handler {
@@ -26,12 +38,18 @@ pub struct Handler {
     pub database: Database,
     pub credentials: HashMap<String, String>,
     pub bucket: Bucket,
+    pub coordinator: Coordinator,
+    pub post_now: Arc<PostNow>,
     pub ui_definitions: UiDefinitions,
     pub edited_content: Arc<Mutex<Option<EditedContent>>>,
     pub interaction_mutex: Arc<Mutex<()>>,
     pub global_last_updated_at: Arc<Mutex<DateTime<Utc>>>,
     pub is_first_iteration: Arc<AtomicBool>,
     pub has_started: Arc<AtomicBool>,
+    /// Order the review cards are currently shown in, None until the first cards are sent
+    pub shown_newest_first: Arc<Mutex<Option<bool>>>,
+    /// The mix of categories the queue and the review cards are steered towards, empty when the account has none
+    pub category_targets: CategoryTargets,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -57,10 +75,15 @@ impl EventHandler for Handler {
     async fn message(&self, ctx: Context, msg: Message) {
         let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
 
+        // A lone "!" is used to clear a caption while editing, so commands are only handled outside of edits
+        if msg.channel_id == channel_id && !msg.author.bot && msg.content.len() > COMMAND_PREFIX.len() && msg.content.starts_with(COMMAND_PREFIX) && self.edited_content.lock().await.is_none() {
+            self.handle_command(&ctx, &msg).await;
+            return;
+        }
+
         if msg.channel_id == channel_id && !msg.author.bot {
-            let edited_content = self.edited_content.lock().await;
-            if edited_content.is_some() {
-                let mut edited_content = edited_content.clone().unwrap();
+            let edited_content = self.edited_content.lock().await.clone();
+            if let Some(mut edited_content) = edited_content {
 
                 let mut received_edit = "".to_string();
                 if msg.content != "!" {
@@ -70,15 +93,14 @@ impl EventHandler for Handler {
                 let mut tx = self.database.begin_transaction().await;
                 let user_settings = tx.load_user_settings().await;
 
-                match edited_content.kind {
-                    EditedContentKind::Caption => {
-                        edited_content.content_info.caption = received_edit;
-                    }
-                    EditedContentKind::Hashtags => {
-                        edited_content.content_info.hashtags = received_edit;
-                    }
-                }
+                let previous_value = match edited_content.kind {
+                    EditedContentKind::Caption => std::mem::replace(&mut edited_content.content_info.caption, received_edit),
+                    EditedContentKind::Hashtags => std::mem::replace(&mut edited_content.content_info.hashtags, received_edit),
+                    EditedContentKind::AltText => std::mem::replace(&mut edited_content.content_info.alt_text, received_edit),
+                    EditedContentKind::Location => return self.receive_location_search(&ctx, &user_settings, &mut tx, &msg, edited_content).await,
+                };
 
+                self.record_caption_revision(&user_settings, &mut tx, &edited_content.content_info, edited_content.kind.as_revision_kind(), &previous_value, msg.author.id).await;
                 tx.save_content_info(&edited_content.content_info).await;
 
                 msg.delete(&ctx.http).await.unwrap();
@@ -92,6 +114,18 @@ impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, _ready: serenity::model::gateway::Ready) {
 
         if !self.has_started.swap(true, Ordering::SeqCst) {
+            let archival_handler = self.clone();
+            let archival_ctx = ctx.clone();
+            tokio::spawn(async move { archival_handler.run_archival_loop(archival_ctx).await });
+
+            let update_check_handler = self.clone();
+            let update_check_ctx = ctx.clone();
+            tokio::spawn(async move { update_check_handler.run_update_check_loop(update_check_ctx).await });
+
+            // The scraper might be running in another process, so new content is announced through the database
+            let mut new_content_listener = self.database.listen_for_new_content().await;
+            // Along with the events of the other processes when Redis is configured, e.g. a content that was just published
+            let mut events = self.coordinator.subscribe_to_events().await;
             loop {
                 let mut tx = self.database.begin_transaction().await;
                 let user_settings = tx.load_user_settings().await;
@@ -102,18 +136,40 @@ impl EventHandler for Handler {
                 self.ready_loop(&ctx, &user_settings, &mut tx, global_last_updated_at, &mut rng).await;
 
                 if self.is_first_iteration.swap(false, Ordering::SeqCst) {
+                    if let Some(mut progress) = ctx.data.write().await.remove::<WarmUpProgressMap>() {
+                        progress.finish().await;
+                    }
                     let mut tx = self.database.begin_transaction().await;
-                    println!(" [{}] Discord bot finished warming up.", self.username);
                     let mut bot_status = tx.load_bot_status().await;
                     bot_status.is_discord_warmed_up = true;
                     tx.save_bot_status(&bot_status).await;
                 }
 
-                sleep(DISCORD_REFRESH_RATE).await;
+                tokio::select! {
+                    _ = self.database.wait_for_new_content(&mut new_content_listener, DISCORD_REFRESH_RATE) => {}
+                    event = self.coordinator.wait_for_event(&mut events) => tracing::debug!("Refreshing after the event {}", event),
+                }
             }
         }
     }
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        // The buttons opening a modal have to respond with it, instead of acknowledging the interaction
+        match &interaction {
+            Interaction::Component(component) if component.data.custom_id == CHALLENGE_CODE_BUTTON => {
+                self.interaction_enter_challenge_code(&ctx, component).await;
+                return;
+            }
+            Interaction::Component(component) if component.data.custom_id == REJECT_OTHER_BUTTON => {
+                self.interaction_ask_rejection_note(&ctx, component).await;
+                return;
+            }
+            Interaction::Modal(modal) if modal.data.custom_id == CHALLENGE_CODE_MODAL => {
+                self.interaction_challenge_code_submitted(&ctx, modal).await;
+                return;
+            }
+            _ => {}
+        }
+
         let response = CreateInteractionResponse::Acknowledge;
 
         match response.execute(&ctx.http, (interaction.id(), interaction.token())).await {
@@ -130,20 +186,58 @@ impl EventHandler for Handler {
 
         let _is_handling_interaction = self.interaction_mutex.lock().await;
 
-        let original_message_id = interaction.clone().message_component().unwrap().message.id;
+        // The rejection note is the only modal going through here, it's tied to the card of the button that opened it
+        let (original_message_id, interaction_type) = match &interaction {
+            Interaction::Component(component) => (component.message.id, component.data.custom_id.clone()),
+            Interaction::Modal(modal) if modal.message.is_some() => (modal.message.as_ref().unwrap().id, modal.data.custom_id.clone()),
+            _ => return,
+        };
 
         let mut tx = self.database.begin_transaction().await;
 
-        let interaction_message = interaction.clone().message_component().unwrap();
-        let interaction_type = interaction_message.clone().data.custom_id;
-
         let global_last_updated_at = Arc::clone(&self.global_last_updated_at);
 
-        // Check if the original message id is in the content mapping
+        // The onboarding tour is sent in DMs, it isn't tied to any content
+        if let Some(page) = interaction_type.strip_prefix(TOUR_BUTTON_PREFIX) {
+            self.interaction_tour_page(&ctx, &interaction, page.parse().unwrap_or(0)).await;
+            return;
+        }
+
+        // The location search results are sent under the card, in a message of their own
+        if interaction_type == "select_location" {
+            let user_settings = tx.load_user_settings().await;
+            self.interaction_select_location(&ctx, &user_settings, &mut tx, &interaction).await;
+            return;
+        }
+
+        // The stories have cards of their own, they aren't part of the content mapping
+        if let Some(story) = tx.get_story_by_message_id(original_message_id).await {
+            let user_settings = tx.load_user_settings().await;
+            let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+            self.interaction_story(&ctx, &user_settings, &mut tx, channel_id, &interaction_type, story).await;
+            return;
+        }
+
+        // So do the suggested source accounts
+        if let Some(suggestion) = tx.get_source_suggestion_by_message_id(original_message_id).await {
+            let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+            self.interaction_source_suggestion(&ctx, &mut tx, channel_id, &interaction_type, suggestion).await;
+            return;
+        }
+
+        // Check if the original message id belongs to any content
         let mut found_content = None;
-        for content in tx.load_content_mapping().await {
-            if content.message_id == original_message_id {
-                found_content = Some(content);
+        match self.find_shortcode_by_message_id(&mut tx, original_message_id).await {
+            Some(shortcode) => {
+                found_content = Some(tx.get_content_info_by_shortcode(&shortcode).await);
+            }
+            None => {
+                // Content shown before the frontend_messages table existed is only tracked in the content mapping
+                for content in tx.load_content_mapping().await {
+                    if content.message_id == original_message_id {
+                        found_content = Some(content);
+                    }
+                }
             }
         }
 
@@ -176,14 +270,27 @@ impl EventHandler for Handler {
                 "publish_now" => {
                     self.interaction_publish_now(&user_settings, &mut content, &mut tx).await;
                 }
+                "toggle_paid_partnership" => {
+                    self.interaction_toggle_paid_partnership(&user_settings, &mut content).await;
+                }
+                "toggle_trial_reel" => {
+                    self.interaction_toggle_trial_reel(&user_settings, &mut content).await;
+                }
                 "accept" => {
-                    self.interaction_accepted(&ctx, &user_settings, &mut content, &mut tx, global_last_updated_at).await;
+                    self.interaction_approved(&ctx, &interaction, &user_settings, &mut content, &mut tx, global_last_updated_at).await;
+                }
+                "accept_anyway" => {
+                    content.needs_age_override = false;
+                    self.interaction_approved(&ctx, &interaction, &user_settings, &mut content, &mut tx, global_last_updated_at).await;
                 }
                 "remove_from_queue" => {
                     self.interaction_remove_from_queue(&ctx, &user_settings, &mut content, &mut tx, global_last_updated_at).await;
                 }
                 "reject" => {
-                    self.interaction_rejected(&ctx, &user_settings, &mut content, &mut tx, global_last_updated_at).await;
+                    self.interaction_choose_rejection_reason(&user_settings, &mut tx, &ctx, &mut content).await;
+                }
+                reason if reason.starts_with(REJECTION_REASON_PREFIX) || reason == REJECTION_NOTE_MODAL => {
+                    self.interaction_rejected(&ctx, &interaction, &user_settings, &mut content, &mut tx, global_last_updated_at).await;
                 }
                 "undo_rejected" => {
                     self.interaction_undo_rejected(&ctx, &user_settings, &mut content, &mut tx, global_last_updated_at).await;
@@ -200,6 +307,12 @@ impl EventHandler for Handler {
                 "go_back" => {
                     self.interaction_go_back(&user_settings, &mut tx, &ctx, &mut content).await;
                 }
+                "caption_history" => {
+                    self.interaction_caption_history(&user_settings, &mut tx, &ctx, &mut content).await;
+                }
+                "revert_caption" => {
+                    self.interaction_revert_caption(&user_settings, &mut tx, &ctx, &interaction, &mut content).await;
+                }
                 "edit_caption" => {
                     if self.edited_content.lock().await.is_none() {
                         self.interaction_edit_caption(&ctx, &interaction, &mut content).await;
@@ -210,6 +323,16 @@ impl EventHandler for Handler {
                         self.interaction_edit_hashtags(&ctx, &interaction, &mut content).await;
                     }
                 }
+                "set_location" => {
+                    if self.edited_content.lock().await.is_none() {
+                        self.interaction_set_location(&ctx, &interaction, &mut content).await;
+                    }
+                }
+                "edit_alt_text" => {
+                    if self.edited_content.lock().await.is_none() {
+                        self.interaction_edit_alt_text(&ctx, &interaction, &mut content).await;
+                    }
+                }
                 _ => {
                     tracing::error!("Unhandled interaction type: {:?}", interaction_type);
                 }
@@ -218,6 +341,12 @@ impl EventHandler for Handler {
         }
     }
 
+    async fn guild_member_update(&self, ctx: Context, _old_if_available: Option<Member>, new: Option<Member>, _event: GuildMemberUpdateEvent) {
+        if let Some(member) = new {
+            self.onboard_moderator_if_needed(&ctx, &member).await;
+        }
+    }
+
     async fn ratelimit(&self, data: RatelimitInfo) {
         // Disable rate limit logic for the first iteration
         if !self.is_first_iteration.load(Ordering::SeqCst) {
@@ -238,13 +367,14 @@ impl Handler {
         }
 
         self.process_bot_status(ctx, user_settings, tx, Arc::clone(&global_last_updated_at)).await;
-        let content_mapping = if self.is_first_iteration.load(Ordering::SeqCst) {
-            tx.load_content_mapping().await
-        } else {
-            let mut content_mapping = tx.load_content_mapping().await;
+        self.resort_review_channel_if_needed(ctx, user_settings, tx).await;
+
+        let mut content_mapping = tx.load_content_mapping().await;
+        if !self.is_first_iteration.load(Ordering::SeqCst) {
             content_mapping.shuffle(rng);
-            content_mapping
-        };
+        }
+        let category_counts = self.category_targets.load_category_counts(user_settings, tx).await;
+        sort_cards_to_send(user_settings, &self.category_targets, &category_counts, &mut content_mapping);
 
         if content_mapping.is_empty() {
             sleep(DISCORD_REFRESH_RATE).await;
@@ -275,6 +405,20 @@ impl Handler {
         }
     }
 
+    /// Looks up the content shown in a message, hot lookups are cached in Redis when available
+    async fn find_shortcode_by_message_id(&self, tx: &mut DatabaseTransaction, message_id: MessageId) -> Option<String> {
+        let cache_key = format!("message:{}", message_id);
+        if let Some(shortcode) = self.coordinator.get_cached(&cache_key).await {
+            return Some(shortcode);
+        }
+
+        let shortcode = tx.get_shortcode_by_message_id(FRONTEND_NAME, message_id).await;
+        if let Some(shortcode) = &shortcode {
+            self.coordinator.set_cached(&cache_key, shortcode, MESSAGE_LOOKUP_CACHE_TTL).await;
+        }
+        shortcode
+    }
+
     async fn is_bot_busy(&self) -> bool {
         // Check if the bot is currently editing a message
         {
@@ -301,7 +445,7 @@ impl Handler {
 }
 
 impl DiscordBot {
-    pub async fn new(database: Database, bucket: Bucket, credentials: HashMap<String, String>, is_first_run: bool) -> Self {
+    pub async fn new(database: Database, bucket: Bucket, coordinator: Coordinator, post_now: Arc<PostNow>, credentials: HashMap<String, String>, is_first_run: bool, self_check_report: &SelfCheckReport) -> Self {
         let ui_definitions_yaml_data = include_str!("../../config/ui_definitions.yaml");
         let ui_definitions: UiDefinitions = serde_yaml::from_str(ui_definitions_yaml_data).expect("Error parsing config file");
 
@@ -310,7 +454,8 @@ impl DiscordBot {
         let token = credentials.get("discord_token").expect("No discord token found in credentials");
 
         // Set gateway intents, which decides what events the bot will be notified about
-        let intents = GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT;
+        // GUILD_MEMBERS is needed to notice new moderators, it has to be enabled in the developer portal
+        let intents = GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT | GatewayIntents::GUILD_MEMBERS;
 
         // let interaction_shard = Shard::new();
         // Create a new instance of the Client, logging in as a bot.
@@ -320,98 +465,21 @@ impl DiscordBot {
                 credentials: credentials.clone(),
                 database: database.clone(),
                 bucket,
+                coordinator,
+                post_now,
                 ui_definitions: ui_definitions.clone(),
                 edited_content: Arc::new(Mutex::new(None)),
                 interaction_mutex: Arc::new(Mutex::new(())),
                 global_last_updated_at: Arc::new(Mutex::new(Utc::now())),
                 is_first_iteration: Arc::new(AtomicBool::new(true)),
                 has_started: Arc::new(AtomicBool::new(false)),
+                shown_newest_first: Arc::new(Mutex::new(None)),
+                category_targets: read_category_targets(username).await,
             })
             .await
             .expect("Err creating client");
 
-        let mut user_channel = None;
-
-        let guild = client.http.get_guild(GUILD_ID).await.unwrap();
-
-        let guild_channels = guild.channels(&client.http).await.unwrap();
-        for (_channel_id, channel) in guild_channels {
-            if channel.name == *username {
-                user_channel = Some(channel);
-            }
-        }
-
-        let channel_id = match user_channel {
-            Some(channel) => channel.id,
-            None => {
-                let mut map = HashMap::new();
-                map.insert("name".to_string(), username.clone());
-                let channel = client.http.create_channel(guild.id, &map, None).await.unwrap();
-                channel.id
-            }
-        };
-
-        let mut tx = database.begin_transaction().await;
-
-        clear_all_messages(&mut tx, &client.http, channel_id, true).await;
-        
-        let welcome_message = format!("Welcome back! {}", crab!("!,!"));
-        
-        if is_first_run {
-            // Set up the posted channel
-            let messages = POSTED_CHANNEL_ID.messages(&client.http, GetMessages::new()).await.unwrap();
-
-            let mut is_message_there = false;
-            for (i, message) in messages.iter().enumerate() {
-                if i == 0 && message.author.bot && message.content.contains(&welcome_message) {
-                    is_message_there = true;
-                    continue;
-                } else {
-                    message.delete(&client.http).await.unwrap();
-                }
-            }
-
-            if !is_message_there {
-                let msg = CreateMessage::new().content(&welcome_message);
-                let _ = client.http.send_message(POSTED_CHANNEL_ID, vec![], &msg).await;
-            }
-
-            // Set up the status channel
-            tx.clear_all_other_bot_statuses().await;
-
-            let messages = STATUS_CHANNEL_ID.messages(&client.http, GetMessages::new()).await.unwrap();
-
-            let mut tx = database.begin_transaction().await;
-            let mut bot_status = tx.load_bot_status().await;
-            let mut is_message_there = false;
-            for message in messages {
-                if message.author.name == *username && message.author.bot && message.content.contains("Last updated at") {
-                    if bot_status.message_id == message.id {
-                        is_message_there = true;
-                    } else {
-                        is_message_there = true;
-                        bot_status.message_id = message.id;
-                    }
-                } else {
-                    message.delete(&client.http).await.unwrap();
-                }
-            }
-
-            // If the message not is there, and the message id is not 1, then reset the message id
-            // so that it will be sent by view.rs
-            if !is_message_there && bot_status.message_id.get() != 1 {
-                bot_status.message_id = MessageId::new(1);
-            }
-
-            // Reset the message ids for the alerts to function properly when restarting the bot
-            bot_status.halt_alert_message_id = MessageId::new(1);
-            bot_status.queue_alert_1_message_id = MessageId::new(1);
-
-            tx.save_bot_status(&bot_status).await;
-        }
-
-        let msg = CreateMessage::new().content(welcome_message);
-        let _ = client.http.send_message(channel_id, vec![], &msg).await;
+        let channel_id = warm_up(&client, &database, username, is_first_run, self_check_report).await;
 
         {
             let mut data = client.data.write().await;
@@ -0,0 +1,145 @@
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc};
+
+use crate::database::schedule::{format_posting_days, parse_posting_days};
+use crate::database::settings::MAX_POSTS_PER_DAY;
+use crate::database::slots::{format_posting_slots, parse_posting_slots, slot_windows};
+use crate::discord::bot::Handler;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::discord::utils::now_in_my_timezone;
+
+const DEFAULT_CALENDAR_DAYS: i64 = 3;
+/// Longer calendars don't fit in a Discord message
+const MAX_CALENDAR_DAYS: i64 = 7;
+
+impl Handler {
+    /// Sets the times of the day the accepted content is posted at, e.g. "!slots 09:00 13:00 19:00", or goes back to the posting interval with "!slots off"
+    ///
+    /// The content already queued keeps its time, only the content accepted from now on fills the slots
+    pub(crate) async fn command_slots(&self, arguments: &[&str]) -> String {
+        let posting_slots = match arguments {
+            [] => return format!("Usage: `{}slots <HH:MM> [HH:MM]...` or `{}slots off`", COMMAND_PREFIX, COMMAND_PREFIX),
+            ["off"] => vec![],
+            posting_slots => match parse_posting_slots(posting_slots) {
+                Ok(posting_slots) => posting_slots,
+                Err(e) => return e,
+            },
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.posting_slots = format_posting_slots(&posting_slots);
+        tx.save_user_settings(&user_settings).await;
+
+        if posting_slots.is_empty() {
+            format!("The accepted content will be posted every {} minutes again.", user_settings.posting_interval)
+        } else {
+            format!("The accepted content will fill the next free slot out of {} every day.", user_settings.posting_slots.replace(',', ", "))
+        }
+    }
+
+    /// Caps how many posts are published on the same day, e.g. "!dailycap 3", the rest waits for the next day with room
+    pub(crate) async fn command_daily_cap(&self, arguments: &[&str]) -> String {
+        let max_posts_per_day = match arguments.first() {
+            Some(&"off") => 0,
+            Some(posts) => match MAX_POSTS_PER_DAY.parse(posts) {
+                Ok(posts) if posts > 0 => posts as i32,
+                Ok(_) => return format!("Use `{}dailycap off` to remove the cap", COMMAND_PREFIX),
+                Err(e) => return e,
+            },
+            None => return format!("Usage: `{}dailycap <posts|off>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.max_posts_per_day = max_posts_per_day;
+        tx.save_user_settings(&user_settings).await;
+
+        if max_posts_per_day == 0 {
+            "There's no longer a cap on the posts of a day.".to_string()
+        } else {
+            format!("At most {} posts will be published on the same day, the content already queued past that is moved when its turn comes.", max_posts_per_day)
+        }
+    }
+
+    /// Sets the days of the week the account posts on, e.g. "!postingdays mon tue wed thu fri sat", or every day with "!postingdays all"
+    pub(crate) async fn command_posting_days(&self, arguments: &[&str]) -> String {
+        let posting_days = match arguments {
+            [] => return format!("Usage: `{}postingdays <day> [day]...` or `{}postingdays all`", COMMAND_PREFIX, COMMAND_PREFIX),
+            ["all"] => parse_posting_days(&["mon", "tue", "wed", "thu", "fri", "sat", "sun"]),
+            posting_days => parse_posting_days(posting_days),
+        };
+        let posting_days = match posting_days {
+            Ok(posting_days) => posting_days,
+            Err(e) => return e,
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.posting_days = format_posting_days(&posting_days);
+        tx.save_user_settings(&user_settings).await;
+
+        format!("The account will post on {}, the content already queued on the other days is moved when its turn comes.", user_settings.posting_days.replace(',', ", "))
+    }
+
+    /// Shows what is scheduled in the coming days, slot by slot when the account has posting slots, e.g. "!calendar 7"
+    pub(crate) async fn command_calendar(&self, arguments: &[&str]) -> String {
+        let days = match arguments.first().map(|days| days.parse::<i64>()) {
+            None => DEFAULT_CALENDAR_DAYS,
+            Some(Ok(days)) if (1..=MAX_CALENDAR_DAYS).contains(&days) => days,
+            _ => return format!("Usage: `{}calendar [days]`, up to {} days", COMMAND_PREFIX, MAX_CALENDAR_DAYS),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+        let content_queue = tx.load_content_queue().await;
+        let posted_content = tx.load_posted_content().await;
+
+        let now = now_in_my_timezone(&user_settings);
+        // Today's slots are shown from the start of the day, along with what was already posted in them
+        let start_of_today = now.date_naive().and_time(NaiveTime::MIN).and_utc() - Duration::seconds(1);
+        let parse_time = |time: &str| DateTime::parse_from_rfc3339(time).unwrap().with_timezone(&Utc);
+
+        let posting_slots = user_settings.posting_slots();
+        if posting_slots.is_empty() {
+            let mut calendar = format!("No posting slots are set, the content is posted every {} minutes, `{}slots` sets them.", user_settings.posting_interval, COMMAND_PREFIX);
+            let mut current_day = None;
+            for queued_content in content_queue.iter().filter(|queued_content| parse_time(&queued_content.will_post_at) < start_of_today + Duration::days(days)) {
+                let will_post_at = parse_time(&queued_content.will_post_at);
+                if current_day != Some(will_post_at.date_naive()) {
+                    current_day = Some(will_post_at.date_naive());
+                    calendar.push_str(&format!("\n**{}**", will_post_at.format("%A %Y-%m-%d")));
+                }
+                calendar.push_str(&format!("\n`{}` {} by {}", will_post_at.format("%H:%M"), queued_content.original_shortcode, queued_content.original_author));
+            }
+            return calendar;
+        }
+
+        let mut calendar = format!("Posting calendar, slots at {}", user_settings.posting_slots.replace(',', ", "));
+        let mut current_day = None;
+        for slot_window in slot_windows(&posting_slots, start_of_today, days) {
+            if current_day != Some(slot_window.starts_at.date_naive()) {
+                current_day = Some(slot_window.starts_at.date_naive());
+                calendar.push_str(&format!("\n**{}**", slot_window.starts_at.format("%A %Y-%m-%d")));
+                if !user_settings.is_posting_day(slot_window.starts_at.weekday()) {
+                    calendar.push_str(" no posting");
+                }
+            }
+            if !user_settings.is_posting_day(slot_window.starts_at.weekday()) {
+                continue;
+            }
+
+            let mut entries = Vec::new();
+            for published_content in posted_content.iter().filter(|published_content| slot_window.contains(&parse_time(&published_content.published_at))) {
+                entries.push(format!("✅ {} by {}", published_content.original_shortcode, published_content.original_author));
+            }
+            for queued_content in content_queue.iter().filter(|queued_content| slot_window.contains(&parse_time(&queued_content.will_post_at))) {
+                entries.push(format!("🕒 {} by {} at {}", queued_content.original_shortcode, queued_content.original_author, parse_time(&queued_content.will_post_at).format("%H:%M")));
+            }
+            if entries.is_empty() {
+                entries.push(if slot_window.starts_at < now { "missed".to_string() } else { "free".to_string() });
+            }
+            calendar.push_str(&format!("\n`{}` {}", slot_window.starts_at.format("%H:%M"), entries.join(", ")));
+        }
+        calendar
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+use crate::database::database::{ContentInfo, DatabaseTransaction, QueuedContent, UserSettings};
+use crate::discord::priority::expected_performance;
+use crate::discord::utils::now_in_my_timezone;
+use crate::discord::variety::{movable_queue, reorder_queue};
+use crate::CATEGORY_MIX_DAYS;
+
+const CATEGORY_TARGETS_PATH: &str = "config/category_targets.yaml";
+/// The categories without a target of their own are counted under this one
+const OTHER_CATEGORY: &str = "other";
+
+/// The share of the published content each category of hashtags.yaml should get, e.g. 60% cats, 30% dogs and 10% other
+#[derive(Debug, Clone, Default)]
+pub struct CategoryTargets {
+    /// Add up to 1
+    shares: HashMap<String, f64>,
+}
+
+impl CategoryTargets {
+    fn new(percentages: HashMap<String, f64>) -> Self {
+        let total: f64 = percentages.values().filter(|percentage| **percentage > 0.0).sum();
+        let shares = percentages.into_iter().filter(|(_, percentage)| *percentage > 0.0).map(|(category, percentage)| (category, percentage / total)).collect();
+        CategoryTargets { shares }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shares.is_empty()
+    }
+
+    /// The category the content counts towards, the ones without a target fall under "other"
+    fn target_category<'a>(&self, category: &'a str) -> &'a str {
+        if self.shares.contains_key(category) {
+            category
+        } else {
+            OTHER_CATEGORY
+        }
+    }
+
+    /// How far below its target share the category currently is, negative when it's above
+    pub fn deficit(&self, category: &str, category_counts: &HashMap<String, i64>) -> f64 {
+        let category = self.target_category(category);
+        let target = self.shares.get(category).copied().unwrap_or_default();
+        let total: i64 = category_counts.values().sum();
+        if total == 0 {
+            return target;
+        }
+        target - category_counts.get(category).copied().unwrap_or_default() as f64 / total as f64
+    }
+
+    /// How many contents of each category were published over the last days, the ones without a target are counted under "other"
+    pub async fn load_category_counts(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction) -> HashMap<String, i64> {
+        let since = (now_in_my_timezone(user_settings) - Duration::days(CATEGORY_MIX_DAYS - 1)).format("%Y-%m-%d").to_string();
+        let mut category_counts = HashMap::new();
+        for (category, count) in tx.load_category_counts_since(&since).await {
+            *category_counts.entry(self.target_category(&category).to_string()).or_default() += count;
+        }
+        category_counts
+    }
+
+    /// e.g. "cats 52% (target 60%), dogs 38% (target 30%), other 10% (target 10%)"
+    pub fn describe_mix(&self, category_counts: &HashMap<String, i64>) -> String {
+        let total: i64 = category_counts.values().sum();
+        let mut categories: Vec<(&String, &f64)> = self.shares.iter().collect();
+        categories.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+
+        let share = |category: &str| if total == 0 { 0.0 } else { category_counts.get(category).copied().unwrap_or_default() as f64 / total as f64 };
+        let mut mix: Vec<String> = categories.iter().map(|(category, target)| format!("{} {:.0}% (target {:.0}%)", category, share(category.as_str()) * 100.0, *target * 100.0)).collect();
+        if !self.shares.contains_key(OTHER_CATEGORY) && category_counts.contains_key(OTHER_CATEGORY) {
+            mix.push(format!("{} {:.0}% (no target)", OTHER_CATEGORY, share(OTHER_CATEGORY) * 100.0));
+        }
+        mix.join(", ")
+    }
+}
+
+/// Reads the category targets of the given account, the file is optional
+pub async fn read_category_targets(username: &str) -> CategoryTargets {
+    let mut file = match File::open(CATEGORY_TARGETS_PATH).await {
+        Ok(file) => file,
+        Err(_) => return CategoryTargets::default(),
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.expect("Unable to read the category targets file");
+    let mut category_targets: HashMap<String, HashMap<String, f64>> = serde_yaml::from_str(&contents).expect("Error parsing the category targets file");
+    CategoryTargets::new(category_targets.remove(username).unwrap_or_default())
+}
+
+/// Hands the posting times out so that the category furthest below its target goes next, returns whether anything was moved
+///
+/// Within a category the content keeps the order of the queue, or the best performing goes first when the queue is prioritized
+pub(crate) async fn balance_queue(user_settings: &UserSettings, tx: &mut DatabaseTransaction, category_targets: &CategoryTargets, content_queue: &[QueuedContent], now: DateTime<Utc>) -> bool {
+    let movable_queue = movable_queue(content_queue, now);
+
+    let content_mapping = tx.load_content_mapping().await;
+    let content_by_shortcode: HashMap<&str, &ContentInfo> = content_mapping.iter().map(|content_info| (content_info.original_shortcode.as_str(), content_info)).collect();
+    let category_of = |shortcode: &str| content_by_shortcode.get(shortcode).map_or("", |content_info| content_info.category.as_str());
+
+    // The content about to be posted can't be moved anymore, it's as good as published
+    let mut category_counts = category_targets.load_category_counts(user_settings, tx).await;
+    for queued_content in content_queue.iter().filter(|queued_content| !movable_queue.iter().any(|movable| movable.original_shortcode == queued_content.original_shortcode)) {
+        *category_counts.entry(category_targets.target_category(category_of(&queued_content.original_shortcode)).to_string()).or_default() += 1;
+    }
+
+    let shortcodes: Vec<&str> = movable_queue.iter().map(|queued_content| queued_content.original_shortcode.as_str()).collect();
+    let mut remaining = shortcodes.clone();
+    if user_settings.prioritize_by_performance {
+        let score = |shortcode: &str| content_by_shortcode.get(shortcode).map_or(0.0, |content_info| expected_performance(content_info));
+        remaining.sort_by(|a, b| score(b).total_cmp(&score(a)));
+    }
+
+    let mut balanced = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        // On a tie the content that comes first wins
+        let deficit = |index: usize| category_targets.deficit(category_of(remaining[index]), &category_counts);
+        let next = (0..remaining.len()).max_by(|a, b| deficit(*a).total_cmp(&deficit(*b)).then(b.cmp(a))).unwrap();
+
+        let shortcode = remaining.remove(next);
+        *category_counts.entry(category_targets.target_category(category_of(shortcode)).to_string()).or_default() += 1;
+        balanced.push(shortcode);
+    }
+    if balanced == shortcodes {
+        return false;
+    }
+
+    reorder_queue(user_settings, tx, &movable_queue, balanced, now).await;
+    true
+}
@@ -0,0 +1,106 @@
+use serenity::all::{ChannelId, ComponentInteraction, Context, CreateActionRow, CreateInputText, CreateInteractionResponse, CreateMessage, CreateModal, EditMessage, InputTextStyle, ModalInteraction};
+
+use crate::database::database::{DatabaseTransaction, LoginChallenge, UserSettings};
+use crate::discord::bot::{ChannelIdMap, Handler};
+use crate::discord::utils::{get_challenge_buttons, modal_input_value, notification_mention, send_message_with_retry};
+use crate::LOGIN_CHALLENGE_TIMEOUT;
+
+pub(crate) const CHALLENGE_CODE_BUTTON: &str = "enter_challenge_code";
+pub(crate) const CHALLENGE_CODE_MODAL: &str = "challenge_code";
+const CHALLENGE_CODE_INPUT: &str = "security_code";
+
+impl Handler {
+    /// Shows the prompt for the security code Instagram asked for while logging in, and the outcome of the codes entered
+    pub(crate) async fn process_login_challenge(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, channel_id: ChannelId) {
+        let Some(mut challenge) = tx.load_login_challenge().await else {
+            return;
+        };
+
+        match challenge.status.as_str() {
+            "awaiting_code" if challenge.message_id.get() == 1 => {
+                let mention = notification_mention(user_settings, true);
+                let msg_caption = format!("{mention}\n{}", self.challenge_caption(&challenge, ""));
+                let msg = CreateMessage::new().content(msg_caption).components(get_challenge_buttons(&self.ui_definitions));
+                challenge.message_id = send_message_with_retry(ctx, channel_id, msg).await.id;
+                tx.save_login_challenge(&challenge).await;
+            }
+            // The prompt stays up so that another code can be entered
+            "code_rejected" => {
+                let msg_caption = self.challenge_caption(&challenge, "❌  The code was rejected, try again");
+                self.edit_challenge_prompt(ctx, channel_id, &challenge, EditMessage::new().content(msg_caption).components(get_challenge_buttons(&self.ui_definitions))).await;
+                challenge.status = "awaiting_code".to_string();
+                tx.save_login_challenge(&challenge).await;
+            }
+            "resolved" | "failed" => {
+                let status_caption = if challenge.status == "resolved" { "✅  Logged in, the bot resumed" } else { "⚠️  The checkpoint couldn't be cleared, log in manually then hit Resume" };
+                let msg_caption = self.challenge_caption(&challenge, status_caption);
+                self.edit_challenge_prompt(ctx, channel_id, &challenge, EditMessage::new().content(msg_caption).components(vec![])).await;
+                tx.remove_login_challenge().await;
+            }
+            _ => {}
+        }
+    }
+
+    /// The security code is entered in a modal, which has to be the response to the button
+    pub(crate) async fn interaction_enter_challenge_code(&self, ctx: &Context, interaction: &ComponentInteraction) {
+        let mut tx = self.database.begin_transaction().await;
+        match tx.load_login_challenge().await {
+            Some(challenge) if challenge.message_id == interaction.message.id => {}
+            _ => return,
+        }
+
+        let code_input = CreateInputText::new(InputTextStyle::Short, "Security code", CHALLENGE_CODE_INPUT).placeholder("123456").min_length(6).max_length(8);
+        let modal = CreateModal::new(CHALLENGE_CODE_MODAL, "Instagram checkpoint").components(vec![CreateActionRow::InputText(code_input)]);
+        if let Err(e) = interaction.create_response(&ctx.http, CreateInteractionResponse::Modal(modal)).await {
+            tracing::warn!("Couldn't show the security code modal: {}", e);
+        }
+    }
+
+    /// Hands the code over to the scraper, which is waiting for it to finish logging in
+    pub(crate) async fn interaction_challenge_code_submitted(&self, ctx: &Context, interaction: &ModalInteraction) {
+        let mut tx = self.database.begin_transaction().await;
+        let mut challenge = match tx.load_login_challenge().await {
+            Some(challenge) if interaction.message.as_ref().is_some_and(|message| message.id == challenge.message_id) => challenge,
+            _ => return,
+        };
+
+        if let Err(e) = interaction.create_response(&ctx.http, CreateInteractionResponse::Acknowledge).await {
+            tracing::warn!("Failed to acknowledge the security code: {}", e);
+        }
+
+        let Some(security_code) = modal_input_value(interaction, CHALLENGE_CODE_INPUT) else {
+            return;
+        };
+
+        challenge.security_code = security_code.trim().to_string();
+        challenge.status = "code_submitted".to_string();
+        tx.save_login_challenge(&challenge).await;
+
+        let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+        let msg_caption = self.challenge_caption(&challenge, "⏳  Checking the code...");
+        self.edit_challenge_prompt(ctx, channel_id, &challenge, EditMessage::new().content(msg_caption).components(vec![])).await;
+    }
+
+    fn challenge_caption(&self, challenge: &LoginChallenge, status_caption: &str) -> String {
+        let mut caption = format!(
+            "🔑  Instagram asked for a security code to log in as {}, it was sent by {} to {}\nEnter it within {} minutes, the bot is halted until then",
+            self.username,
+            challenge.challenge_type,
+            challenge.contact_point,
+            LOGIN_CHALLENGE_TIMEOUT.as_secs() / 60
+        );
+        if !status_caption.is_empty() {
+            caption = format!("{status_caption}\n{caption}");
+        }
+        caption
+    }
+
+    async fn edit_challenge_prompt(&self, ctx: &Context, channel_id: ChannelId, challenge: &LoginChallenge, edit: EditMessage) {
+        if challenge.message_id.get() == 1 {
+            return;
+        }
+        if let Err(e) = channel_id.edit_message(&ctx.http, challenge.message_id, edit).await {
+            tracing::warn!("Couldn't update the login challenge prompt: {}", e);
+        }
+    }
+}
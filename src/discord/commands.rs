@@ -0,0 +1,531 @@
+use chrono::{DateTime, Duration, Utc};
+use serenity::all::{Context, Message};
+
+use crate::database::settings::{APPROVALS_REQUIRED, MAX_SOURCE_AGE_DAYS, SCRAPE_WINDOW_END, SCRAPE_WINDOW_START, STORY_SHARE_DELAY};
+use crate::discord::bot::Handler;
+use crate::discord::sla::rejection_summary;
+use crate::discord::utils::now_in_my_timezone;
+use crate::{CATEGORY_MIX_DAYS, MAX_MEDIA_NOT_FOUND_ATTEMPTS};
+
+/// Prefix of the text commands accepted in the user channel
+pub(crate) const COMMAND_PREFIX: &str = "!";
+/// Far enough back for the stats, while keeping the date arithmetic in range
+const MAX_STATS_DAYS: i64 = 365 * 5;
+
+impl Handler {
+    /// Handles a text command sent in the user channel, e.g. "!drain 6"
+    pub async fn handle_command(&self, ctx: &Context, msg: &Message) {
+        let mut arguments = msg.content.trim_start_matches(COMMAND_PREFIX).split_whitespace();
+        let command = arguments.next().unwrap_or_default().to_lowercase();
+        let arguments: Vec<&str> = arguments.collect();
+
+        let response = match command.as_str() {
+            "drain" => self.command_drain(&arguments).await,
+            "undrain" => self.command_undrain().await,
+            "postnow" => self.command_post_now(&arguments).await,
+            "dryrun" => self.command_dry_run(&arguments).await,
+            "order" => self.command_order(&arguments).await,
+            "quorum" => self.command_quorum(&arguments).await,
+            "variety" => self.command_variety(&arguments).await,
+            "prioritize" => self.command_prioritize(&arguments).await,
+            "trialreels" => self.command_trial_reels(&arguments).await,
+            "maxage" => self.command_max_age(&arguments).await,
+            "scrapewindow" => self.command_scrape_window(&arguments).await,
+            "scraping" => self.command_scraping(&arguments).await,
+            "storyshare" => self.command_story_share(&arguments).await,
+            "slots" => self.command_slots(&arguments).await,
+            "calendar" => self.command_calendar(&arguments).await,
+            "dailycap" => self.command_daily_cap(&arguments).await,
+            "postingdays" => self.command_posting_days(&arguments).await,
+            "stats" => self.command_stats(&arguments).await,
+            "explain" => self.command_explain(&arguments).await,
+            "operators" => self.command_operators(&arguments).await,
+            "override" => self.command_override(&arguments).await,
+            // The export is sent as a file, so it replies on its own
+            "exporthashes" => return self.command_export_hashes(ctx, msg).await,
+            "provenance" => return self.command_provenance(ctx, msg, &arguments).await,
+            "preview" => self.command_preview(&arguments).await,
+            "importhashes" => self.command_import_hashes(msg, &arguments).await,
+            "ingest" => self.command_ingest(msg, &arguments).await,
+            "blacklist" => self.command_blacklist(msg, &arguments).await,
+            "restart" => self.command_restart(ctx, msg, &arguments).await,
+            "unblacklist" => self.command_unblacklist(&arguments).await,
+            "permission" => self.command_permission(msg, &arguments).await,
+            "permissions" => self.command_permissions().await,
+            "help" => command_help(),
+            _ => format!("Unknown command `{}`, try `{}help`", command, COMMAND_PREFIX),
+        };
+
+        if let Err(e) = msg.channel_id.say(&ctx.http, response).await {
+            tracing::error!("Error responding to command {}: {:?}", command, e);
+        }
+    }
+
+    /// Stops the scraper and only lets the poster publish the content scheduled within the next hours
+    async fn command_drain(&self, arguments: &[&str]) -> String {
+        let hours = match arguments.first().map(|hours| hours.parse::<i64>()) {
+            Some(Ok(hours)) if hours >= 0 => hours,
+            _ => return format!("Usage: `{}drain <hours>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+        let mut bot_status = tx.load_bot_status().await;
+
+        let drain_until = now_in_my_timezone(&user_settings) + Duration::hours(hours);
+        bot_status.drain_until = drain_until.to_rfc3339();
+        tx.save_bot_status(&bot_status).await;
+
+        let remaining = tx.load_content_queue().await.iter().filter(|queued_content| DateTime::parse_from_rfc3339(&queued_content.will_post_at).unwrap().with_timezone(&Utc) <= drain_until).count();
+        format!("Draining started, no new content will be scraped and only the {} posts scheduled before {} will be published. I'll let you know when it's safe to stop.", remaining, drain_until.format("%Y-%m-%d %H:%M:%S"))
+    }
+
+    async fn command_undrain(&self) -> String {
+        let mut tx = self.database.begin_transaction().await;
+        let mut bot_status = tx.load_bot_status().await;
+
+        if bot_status.drain_until.is_empty() {
+            return "The queue is not being drained.".to_string();
+        }
+
+        bot_status.drain_until = "".to_string();
+        tx.save_bot_status(&bot_status).await;
+        "Draining stopped, back to business as usual.".to_string()
+    }
+
+    /// Publishes a queued content right away, like the button of its card, e.g. "!postnow C7x9Yb1Nq2w"
+    async fn command_post_now(&self, arguments: &[&str]) -> String {
+        let Some(shortcode) = arguments.first() else {
+            return format!("Usage: `{}postnow <shortcode>`", COMMAND_PREFIX);
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        if !tx.promote_to_front_of_queue(shortcode).await {
+            return format!("`{}` isn't in the queue.", shortcode);
+        }
+        self.post_now.request(shortcode);
+
+        let user_settings = tx.load_user_settings().await;
+        let mut content_info = tx.get_content_info_by_shortcode(&shortcode.to_string()).await;
+        content_info.last_updated_at = (now_in_my_timezone(&user_settings) - user_settings.interface_update_interval()).to_rfc3339();
+        tx.save_content_info(&content_info).await;
+
+        if user_settings.can_post {
+            format!("Publishing `{}` now, the rest of the queue moves behind it.", shortcode)
+        } else {
+            format!("`{}` is at the front of the queue, it will be published as soon as posting is turned back on.", shortcode)
+        }
+    }
+
+    /// Toggles the dry run mode, where the poster does everything except the actual upload
+    async fn command_dry_run(&self, arguments: &[&str]) -> String {
+        let dry_run = match arguments.first().copied() {
+            Some("on") => true,
+            Some("off") => false,
+            _ => return format!("Usage: `{}dryrun <on|off>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.dry_run = dry_run;
+        tx.save_user_settings(&user_settings).await;
+
+        if dry_run {
+            "Dry run enabled, queued posts will be simulated instead of being uploaded.".to_string()
+        } else {
+            "Dry run disabled, queued posts will be uploaded again.".to_string()
+        }
+    }
+
+    /// Changes the order of the review cards, the channel is re-sorted on the next refresh
+    async fn command_order(&self, arguments: &[&str]) -> String {
+        let newest_first = match arguments.first().copied() {
+            Some("newest") => true,
+            Some("oldest") => false,
+            _ => return format!("Usage: `{}order <newest|oldest>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        if user_settings.newest_first == newest_first {
+            return "The cards are already in this order.".to_string();
+        }
+
+        user_settings.newest_first = newest_first;
+        tx.save_user_settings(&user_settings).await;
+
+        if newest_first {
+            "The newest content will now be shown first, re-sorting the cards...".to_string()
+        } else {
+            "The oldest content will now be shown first, re-sorting the cards...".to_string()
+        }
+    }
+
+    /// Sets how many distinct moderators have to accept a content before it's queued
+    async fn command_quorum(&self, arguments: &[&str]) -> String {
+        let approvals_required = match arguments.first().map(|approvals| APPROVALS_REQUIRED.parse(approvals)) {
+            Some(Ok(approvals)) => approvals as i32,
+            Some(Err(e)) => return e,
+            None => return format!("Usage: `{}quorum <approvals>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.approvals_required = approvals_required;
+        tx.save_user_settings(&user_settings).await;
+
+        if approvals_required == 1 {
+            "Quorum disabled, a single approval queues the content.".to_string()
+        } else {
+            format!("Quorum enabled, the content is queued once {} different moderators approved it.", approvals_required)
+        }
+    }
+
+    /// Enables the reordering of the queue that keeps similar videos apart
+    async fn command_variety(&self, arguments: &[&str]) -> String {
+        let enforce_variety = match arguments.first().copied() {
+            Some("on") => true,
+            Some("off") => false,
+            _ => return format!("Usage: `{}variety <on|off>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.enforce_variety = enforce_variety;
+        tx.save_user_settings(&user_settings).await;
+
+        if enforce_variety {
+            "Variety enforced, similar videos in the queue will be spaced out.".to_string()
+        } else {
+            "Variety no longer enforced, the queue keeps the order in which the content was accepted.".to_string()
+        }
+    }
+
+    /// Sets whether the queue is reordered by how well the content did on its source account
+    async fn command_prioritize(&self, arguments: &[&str]) -> String {
+        let prioritize_by_performance = match arguments.first().copied() {
+            Some("on") => true,
+            Some("off") => false,
+            _ => return format!("Usage: `{}prioritize <on|off>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.prioritize_by_performance = prioritize_by_performance;
+        tx.save_user_settings(&user_settings).await;
+
+        if prioritize_by_performance {
+            "The content with the most views per day on its source account will be posted first.".to_string()
+        } else {
+            "The queue is no longer prioritized, the content keeps the order in which it was accepted.".to_string()
+        }
+    }
+
+    /// Sets whether the new content is published as a trial reel by default, each card can still be toggled
+    async fn command_trial_reels(&self, arguments: &[&str]) -> String {
+        let trial_reels_by_default = match arguments.first().copied() {
+            Some("on") => true,
+            Some("off") => false,
+            _ => return format!("Usage: `{}trialreels <on|off>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.trial_reels_by_default = trial_reels_by_default;
+        tx.save_user_settings(&user_settings).await;
+
+        if trial_reels_by_default {
+            "The new content will be published as trial reels, shown to non-followers first.".to_string()
+        } else {
+            "The new content will be published as regular reels.".to_string()
+        }
+    }
+
+    /// Sets how old the content can be on the source account before it has to be accepted anyway
+    async fn command_max_age(&self, arguments: &[&str]) -> String {
+        let max_source_age_days = match arguments.first() {
+            Some(&"off") => 0,
+            Some(days) => match MAX_SOURCE_AGE_DAYS.parse(days) {
+                Ok(days) if days > 0 => days as i32,
+                Ok(_) => return format!("Usage: `{}maxage <days|off>`", COMMAND_PREFIX),
+                Err(e) => return e,
+            },
+            None => return format!("Usage: `{}maxage <days|off>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.max_source_age_days = max_source_age_days;
+        tx.save_user_settings(&user_settings).await;
+
+        if max_source_age_days > 0 {
+            format!("The new content posted more than {} days ago on the source account will have to be accepted anyway.", max_source_age_days)
+        } else {
+            "The new content can be accepted regardless of when it was posted on the source account.".to_string()
+        }
+    }
+
+    /// Sets the hours of the day during which the scraper is active, in the timezone of the account
+    async fn command_scrape_window(&self, arguments: &[&str]) -> String {
+        let (start, end) = match arguments {
+            ["off"] => (0, 0),
+            [start, end] => match (SCRAPE_WINDOW_START.parse(start), SCRAPE_WINDOW_END.parse(end)) {
+                (Ok(start), Ok(end)) => (start as i32, end as i32),
+                (Err(e), _) | (_, Err(e)) => return e,
+            },
+            _ => return format!("Usage: `{}scrapewindow <start hour> <end hour>|off`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.scrape_window_start = start;
+        user_settings.scrape_window_end = end;
+        tx.save_user_settings(&user_settings).await;
+
+        if start != end {
+            format!("The scraper will only be active between {}:00 and {}:00, it idles the rest of the day.", start, end)
+        } else {
+            "The scraper is active at any time of the day.".to_string()
+        }
+    }
+
+    /// Pauses or resumes the scraper of this account, the review and the posting carry on as usual
+    async fn command_scraping(&self, arguments: &[&str]) -> String {
+        let scraping_enabled = match arguments.first().copied() {
+            Some("on") => true,
+            Some("off") => false,
+            _ => return format!("Usage: `{}scraping <on|off>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.scraping_enabled = scraping_enabled;
+        tx.save_user_settings(&user_settings).await;
+
+        if scraping_enabled {
+            "Scraping resumed.".to_string()
+        } else {
+            "Scraping paused, the content already scraped will still be reviewed and posted. The scraper finishes the post it's on before it stops.".to_string()
+        }
+    }
+
+    /// Sets whether the published reels are shared on the story of the account, and how long after, e.g. "!storyshare on 30"
+    async fn command_story_share(&self, arguments: &[&str]) -> String {
+        let (story_share_enabled, story_share_delay) = match arguments {
+            ["on"] => (true, None),
+            ["on", minutes] => match STORY_SHARE_DELAY.parse(minutes) {
+                Ok(minutes) => (true, Some(minutes as i32)),
+                Err(e) => return e,
+            },
+            ["off"] => (false, None),
+            _ => return format!("Usage: `{}storyshare <on|off> [minutes]`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut user_settings = tx.load_user_settings().await;
+        user_settings.story_share_enabled = story_share_enabled;
+        if let Some(story_share_delay) = story_share_delay {
+            user_settings.story_share_delay = story_share_delay;
+        }
+        tx.save_user_settings(&user_settings).await;
+
+        if story_share_enabled {
+            format!("The published reels will be shared on the story {} minutes after they go live.", user_settings.story_share_delay)
+        } else {
+            "The published reels will no longer be shared on the story, the ones already scheduled still will.".to_string()
+        }
+    }
+
+    /// Sums up what happened to the content over the last days, including why the scraper skipped posts
+    async fn command_stats(&self, arguments: &[&str]) -> String {
+        let days = match arguments.first().map(|days| days.parse::<i64>()) {
+            None => 7,
+            Some(Ok(days)) if (1..=MAX_STATS_DAYS).contains(&days) => days,
+            _ => return format!("Usage: `{}stats [days]`, with at most {} days", COMMAND_PREFIX, MAX_STATS_DAYS),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+        let since = (now_in_my_timezone(&user_settings) - Duration::days(days - 1)).format("%Y-%m-%d").to_string();
+
+        let rollups = tx.load_daily_rollups_since(&since).await;
+        let scraped: i32 = rollups.iter().map(|rollup| rollup.scraped).sum();
+        let published: i32 = rollups.iter().map(|rollup| rollup.published).sum();
+        let rejected: i32 = rollups.iter().map(|rollup| rollup.rejected).sum();
+        let failed: i32 = rollups.iter().map(|rollup| rollup.failed).sum();
+        let duplicates: i32 = rollups.iter().map(|rollup| rollup.duplicates).sum();
+
+        let mut stats = format!("Stats since {}:\n{} scraped, {} published, {} rejected, {} failed, {} duplicates", since, scraped, published, rejected, failed, duplicates);
+
+        let skip_counts = tx.load_skip_counts_since(&since).await;
+        if skip_counts.is_empty() {
+            stats.push_str("\nNo posts were skipped by the scraper.");
+        } else {
+            stats.push_str("\nSkipped by the scraper:");
+            for (reason, count) in skip_counts {
+                stats.push_str(&format!("\n- {}: {}", reason.replace('_', " "), count));
+            }
+        }
+
+        stats.push_str(&rejection_summary(&tx.load_rejection_log_since(&since).await));
+
+        let expired_pending = tx.count_expired_pending_since(&since).await;
+        if expired_pending > 0 {
+            stats.push_str(&format!("\n{} pending content evicted by the scraper to make room for fresh content", expired_pending));
+        }
+
+        let rate_limit_events = tx.load_rate_limit_events_since(&since).await;
+        if !rate_limit_events.is_empty() {
+            stats.push_str("\nRate limits of the scraper:");
+            for (event, count) in rate_limit_events {
+                stats.push_str(&format!("\n- {}: {}", event.replace('_', " "), count));
+            }
+        }
+
+        let rule_hits = tx.load_rule_hits_since(&since).await;
+        if !rule_hits.is_empty() {
+            stats.push_str("\nDecided by the automation rules and filters:");
+            for (rule, count) in rule_hits {
+                stats.push_str(&format!("\n- {}: {}", rule, count));
+            }
+        }
+
+        if !self.category_targets.is_empty() {
+            let category_counts = self.category_targets.load_category_counts(&user_settings, &mut tx).await;
+            stats.push_str(&format!("\nMix of the last {} days: {}", CATEGORY_MIX_DAYS, self.category_targets.describe_mix(&category_counts)));
+        }
+        stats
+    }
+
+    /// Lists everything that happened to a content, to figure out why it never got posted
+    async fn command_explain(&self, arguments: &[&str]) -> String {
+        let shortcode = match arguments.first() {
+            Some(shortcode) => shortcode.to_string(),
+            None => return format!("Usage: `{}explain <shortcode>`", COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut trail: Vec<(String, String)> = Vec::new();
+
+        if let Some(media_retry) = tx.load_media_retries().await.into_iter().find(|media_retry| media_retry.original_shortcode == shortcode) {
+            let outcome = if media_retry.attempts >= MAX_MEDIA_NOT_FOUND_ATTEMPTS { "written off" } else { "will be retried" };
+            trail.push((media_retry.last_attempt_at, format!("media not found {} time(s), {}", media_retry.attempts, outcome)));
+        }
+
+        if let Some(duplicate_content) = tx.get_duplicate_content_by_shortcode(&shortcode).await {
+            let detail = if duplicate_content.matched_shortcode.is_empty() { "".to_string() } else { format!(" of {} (distance {})", duplicate_content.matched_shortcode, duplicate_content.distance) };
+            trail.push(("".to_string(), format!("rejected by the scraper as a duplicate{}", detail)));
+        }
+
+        match tx.get_content_state_by_shortcode(&shortcode).await {
+            Some(content_state) => {
+                trail.push((content_state.added_at, "scraped and sent for review".to_string()));
+                if content_state.encountered_errors > 0 {
+                    trail.push(("".to_string(), format!("encountered {} error(s) while being handled", content_state.encountered_errors)));
+                }
+                if !content_state.deleted_at.is_empty() {
+                    trail.push((content_state.deleted_at, format!("removed, last status was {}", content_state.status)));
+                } else {
+                    trail.push(("".to_string(), format!("current status is {}", content_state.status)));
+                }
+            }
+            None if trail.is_empty() => return format!("`{}` was never handled, it was either not scraped yet or skipped before being downloaded.", shortcode),
+            None => {}
+        }
+
+        if let Some(queued_content) = tx.get_queued_content_by_shortcode(&shortcode).await {
+            trail.push((queued_content.will_post_at, "scheduled to be posted".to_string()));
+        }
+        if let Some(rejected_content) = tx.get_rejected_content_by_shortcode(&shortcode).await {
+            trail.push((rejected_content.rejected_at, "rejected".to_string()));
+        }
+        if let Some(expired_pending) = tx.get_expired_pending_by_shortcode(&shortcode).await {
+            trail.push((expired_pending.expired_at, "evicted while pending, the backlog was full".to_string()));
+        }
+        if let Some(failed_content) = tx.get_failed_content_by_shortcode(&shortcode).await {
+            trail.push((failed_content.failed_at, "failed to be posted".to_string()));
+        }
+        if let Some(published_content) = tx.get_published_content_by_shortcode(&shortcode).await {
+            let detail = if published_content.is_simulated { "published (dry run)" } else { "published" };
+            let detail = if published_content.is_trial_reel { format!("{} as a trial reel", detail) } else { detail.to_string() };
+            trail.push((published_content.published_at, detail));
+        }
+        for cross_post in tx.load_cross_posts_by_shortcode(&shortcode).await {
+            let detail = if cross_post.status == "published" { format!("cross-posted to {}", cross_post.platform) } else { format!("failed to be cross-posted to {} ({})", cross_post.platform, cross_post.error) };
+            trail.push((cross_post.attempted_at, detail));
+        }
+        if let Some(story_share) = tx.get_story_share_by_shortcode(&shortcode).await {
+            match story_share.status.as_str() {
+                "published" => trail.push((story_share.attempted_at, "shared on the story".to_string())),
+                "failed" => trail.push((story_share.attempted_at, format!("failed to be shared on the story ({})", story_share.error))),
+                _ => trail.push((story_share.share_at, "to be shared on the story".to_string())),
+            }
+        }
+
+        for caption_revision in tx.load_caption_revisions_by_shortcode(&shortcode).await {
+            trail.push((caption_revision.edited_at, format!("{} edited by {}", caption_revision.kind, caption_revision.editor)));
+        }
+        for audit_entry in tx.load_audit_entries_by_shortcode(&shortcode).await {
+            let detail = if audit_entry.detail.is_empty() { "".to_string() } else { format!(" ({})", audit_entry.detail) };
+            trail.push((audit_entry.created_at, format!("{} by {}{}", audit_entry.action, audit_entry.operator, detail)));
+        }
+
+        // Entries without a timestamp are facts about the content rather than events, they are listed first
+        trail.sort_by_key(|(at, _)| DateTime::parse_from_rfc3339(at).ok());
+
+        let mut explanation = format!("Decision trail for `{}`:", shortcode);
+        for (at, event) in trail {
+            match DateTime::parse_from_rfc3339(&at) {
+                Ok(at) => explanation.push_str(&format!("\n`{}` {}", at.format("%Y-%m-%d %H:%M"), event)),
+                Err(_) => explanation.push_str(&format!("\n- {}", event)),
+            }
+        }
+        explanation
+    }
+}
+
+/// The text commands of the review channel, with their arguments and what they do
+pub(crate) const COMMANDS: [(&str, &str); 34] = [
+    ("drain <hours>", "stop scraping and only publish what is scheduled in the next <hours>"),
+    ("undrain", "stop draining"),
+    ("postnow <shortcode>", "publish a queued content right away, the rest of the queue moves behind it"),
+    ("dryrun <on|off>", "simulate posts instead of uploading them"),
+    ("order <newest|oldest>", "choose which content is shown first in the review channel"),
+    ("quorum <approvals>", "require <approvals> different moderators to accept a content before it's queued, 1 to disable"),
+    ("variety <on|off>", "reorder the queue so that similar videos aren't posted back to back"),
+    ("prioritize <on|off>", "reorder the queue so that the videos doing best on their source account are posted first"),
+    ("trialreels <on|off>", "publish the new content as trial reels, shown to non-followers first"),
+    ("maxage <days|off>", "require the new content posted more than <days> ago on the source account to be accepted anyway"),
+    ("scrapewindow <start hour> <end hour>|off", "only let the scraper reach Instagram and the other sources between these hours"),
+    ("scraping <on|off>", "pause or resume the scraper of this account without stopping the posting"),
+    ("slots <HH:MM> [HH:MM]...|off", "post the accepted content at these times of the day, each one filling the next free slot, instead of every posting interval"),
+    ("dailycap <posts|off>", "publish at most <posts> on the same day, the rest waits for the next day with room"),
+    ("postingdays <day> [day]...|all", "only post on these days of the week, e.g. `mon tue wed thu fri`"),
+    ("calendar [days]", "show what is scheduled in the next [days], slot by slot, 3 by default"),
+    ("storyshare <on|off> [minutes]", "share the published reels on the story of the account [minutes] after they go live, an hour at first"),
+    ("stats [days]", "show what happened to the content over the last [days], 7 by default"),
+    ("explain <shortcode>", "show why a content was or wasn't posted"),
+    ("provenance <shortcode>", "send where a content came from and what was done to it until it was published, for when its origin is disputed"),
+    ("preview <shortcode>", "send short-lived public links to a content through the CDN, to share it outside Discord"),
+    ("operators [YYYY-MM]", "show what each moderator did during the month, the current one by default"),
+    ("override <setting> <value> <hours>", "change a setting for the next <hours>, `off` instead of the value reverts it right away"),
+    ("override", "list the overridden settings"),
+    ("exporthashes", "send the hashes of the videos seen by this account, to share them with another account"),
+    ("importhashes <account>", "skip the videos already seen by another account, or attach the file of `exporthashes` instead of the account"),
+    ("ingest <url|shortcode>", "download a reel and show it for review like the scraped content"),
+    ("blacklist <author|shortcode|keyword> <value>", "never scrape the content of an author, a shortcode or the captions containing a keyword"),
+    ("blacklist", "list what is blacklisted"),
+    ("unblacklist <author|shortcode|keyword> <value>", "remove an entry from the blacklist"),
+    ("permission <author> [link]", "record that the author allowed reposting their content, with the link to the conversation or a screenshot of it attached, `revoke` instead of the link removes it"),
+    ("permissions", "list the authors of the queued and published posts who didn't give their permission"),
+    ("restart [hours]", "publish what is scheduled in the next [hours], apply the update and restart the bot, owner only"),
+    ("help", "show this message"),
+];
+
+pub(crate) fn command_help() -> String {
+    let mut help = "Available commands:".to_string();
+    for (command, description) in COMMANDS {
+        help.push_str(&format!("\n`{}{}` - {}", COMMAND_PREFIX, command, description));
+    }
+    help
+}
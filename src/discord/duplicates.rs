@@ -0,0 +1,46 @@
+use serenity::all::{ChannelId, Context, CreateAttachment, CreateMessage};
+
+use crate::database::database::{DatabaseTransaction, DuplicateReport};
+use crate::discord::bot::Handler;
+use crate::discord::utils::send_message_with_retry;
+use crate::video::processing::DUPLICATE_MAX_DISTANCE;
+
+impl Handler {
+    /// Shows the duplicates found by the scraper since the last refresh, along with the frames that matched
+    pub(crate) async fn report_duplicates(&self, ctx: &Context, tx: &mut DatabaseTransaction, channel_id: ChannelId) {
+        for duplicate_report in tx.load_duplicate_reports().await {
+            let mut msg = CreateMessage::new().content(duplicate_caption(&duplicate_report));
+            if !duplicate_report.composite.is_empty() {
+                let attachment = CreateAttachment::bytes(duplicate_report.composite.clone(), format!("duplicate_{}.jpg", duplicate_report.original_shortcode));
+                msg = msg.add_file(attachment);
+            }
+
+            send_message_with_retry(ctx, channel_id, msg).await;
+            tx.remove_duplicate_report(&duplicate_report.original_shortcode).await;
+        }
+    }
+}
+
+fn duplicate_caption(duplicate_report: &DuplicateReport) -> String {
+    let frame_distances: Vec<u32> = duplicate_report.frame_distances.split(',').filter_map(|frame_distance| frame_distance.parse().ok()).collect();
+    let matched_frames: Vec<String> = frame_distances.iter().enumerate().filter(|(_, frame_distance)| **frame_distance <= DUPLICATE_MAX_DISTANCE).map(|(frame, _)| (frame + 1).to_string()).collect();
+
+    // The matches from the imported hashes are credited to the account they come from
+    let matched_account = if duplicate_report.matched_account.is_empty() || duplicate_report.matched_account == duplicate_report.username { String::new() } else { format!(" from {}", duplicate_report.matched_account) };
+    let mut caption = format!(
+        "Skipped `{}` by {}, it's a duplicate of `{}`{} (average distance {})",
+        duplicate_report.original_shortcode, duplicate_report.original_author, duplicate_report.matched_shortcode, matched_account, duplicate_report.distance
+    );
+    if !frame_distances.is_empty() {
+        caption.push_str(&format!(
+            "\nFrames matched: {} of {}, distances {}",
+            if matched_frames.is_empty() { "none".to_string() } else { matched_frames.join(", ") },
+            frame_distances.len(),
+            duplicate_report.frame_distances.replace(',', ", ")
+        ));
+    }
+    if !duplicate_report.composite.is_empty() {
+        caption.push_str("\nTop row is the new video, bottom row the one it matched, green frames matched");
+    }
+    caption
+}
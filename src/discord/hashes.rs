@@ -0,0 +1,75 @@
+use serenity::all::{Context, CreateAttachment, CreateMessage, Message};
+
+use crate::discord::bot::Handler;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::discord::utils::now_in_my_timezone;
+use crate::video::hash_export::HashExport;
+
+impl Handler {
+    /// Sends the frame hashes of the account as a JSON file, to be imported by another account
+    pub(crate) async fn command_export_hashes(&self, ctx: &Context, msg: &Message) {
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+        let now = now_in_my_timezone(&user_settings);
+
+        let hashed_videos = tx.load_hashed_videos().await;
+        let hash_export = HashExport::new(&self.username, &now.to_rfc3339(), &hashed_videos);
+        let file = serde_json::to_vec(&hash_export).unwrap();
+
+        let attachment = CreateAttachment::bytes(file, format!("hashes_{}_{}.json", self.username, now.format("%Y-%m-%d")));
+        let response = CreateMessage::new()
+            .content(format!("Here are the hashes of {} videos, send them to another account with `{}importhashes`", hashed_videos.len(), COMMAND_PREFIX))
+            .add_file(attachment);
+        if let Err(e) = msg.channel_id.send_message(&ctx.http, response).await {
+            tracing::error!("Error sending the exported hashes: {:?}", e);
+        }
+    }
+
+    /// Imports the hashes of another account, either from the file attached to the message or from the same database
+    ///
+    /// The scraper is notified, so that it skips the videos of the imported hashes right away
+    pub(crate) async fn command_import_hashes(&self, msg: &Message, arguments: &[&str]) -> String {
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+        let imported_at = now_in_my_timezone(&user_settings).to_rfc3339();
+
+        let hashed_videos = match (msg.attachments.first(), arguments.first()) {
+            (Some(attachment), _) => {
+                let file = match attachment.download().await {
+                    Ok(file) => file,
+                    Err(e) => return format!("Couldn't download `{}`: {}", attachment.filename, e),
+                };
+                match serde_json::from_slice::<HashExport>(&file) {
+                    Ok(hash_export) => hash_export.into_hashed_videos(),
+                    Err(e) => return format!("`{}` is not an export of `{}exporthashes`: {}", attachment.filename, COMMAND_PREFIX, e),
+                }
+            }
+            (None, Some(account)) => tx.load_hashed_videos_of(account).await,
+            (None, None) => return format!("Usage: `{}importhashes <account>`, or attach the file of `{}exporthashes`", COMMAND_PREFIX, COMMAND_PREFIX),
+        };
+
+        let Some(account) = hashed_videos.first().map(|hashed_video| hashed_video.username.clone()) else {
+            return "There are no hashes to import.".to_string();
+        };
+        if account == self.username {
+            return "These are already the hashes of this account.".to_string();
+        }
+
+        let mut imported = 0;
+        for hashed_video in &hashed_videos {
+            if tx.save_imported_hashed_video(hashed_video, &imported_at).await {
+                imported += 1;
+            }
+        }
+        if imported > 0 {
+            tx.notify_imported_hashes(&imported_at).await;
+        }
+
+        format!(
+            "Imported {} new hashes from {} ({} were already there), the videos already posted there will be skipped from now on.",
+            imported,
+            account,
+            hashed_videos.len() - imported
+        )
+    }
+}
@@ -0,0 +1,125 @@
+use serenity::all::{ComponentInteractionDataKind, Context, CreateActionRow, CreateButton, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, EditMessage, Interaction, UserId};
+
+use crate::database::database::{CaptionRevision, ContentInfo, DatabaseTransaction, UserSettings};
+use crate::discord::bot::{ChannelIdMap, Handler};
+use crate::discord::interactions::EditedContentKind;
+use crate::discord::utils::{generate_full_caption, now_in_my_timezone};
+
+/// How many revisions are listed in the history, Discord limits select menus to 25 options
+const MAX_REVISIONS_SHOWN: usize = 10;
+/// Values longer than this are cut in the history, to stay within the message length limit
+const MAX_REVISION_PREVIEW_LEN: usize = 60;
+
+impl EditedContentKind {
+    pub fn as_revision_kind(&self) -> &'static str {
+        match self {
+            EditedContentKind::Caption => "caption",
+            EditedContentKind::Hashtags => "hashtags",
+            EditedContentKind::AltText => "alt_text",
+            EditedContentKind::Location => "location",
+        }
+    }
+}
+
+impl Handler {
+    /// Keeps track of an edit, so that it can be reviewed and reverted later
+    pub async fn record_caption_revision(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction, content_info: &ContentInfo, kind: &str, previous_value: &str, editor: UserId) {
+        let new_value = match kind {
+            "caption" => &content_info.caption,
+            "alt_text" => &content_info.alt_text,
+            _ => &content_info.hashtags,
+        };
+
+        if previous_value == new_value {
+            return;
+        }
+
+        let caption_revision = CaptionRevision {
+            id: 0,
+            username: content_info.username.clone(),
+            original_shortcode: content_info.original_shortcode.clone(),
+            kind: kind.to_string(),
+            previous_value: previous_value.to_string(),
+            new_value: new_value.clone(),
+            editor: editor.to_string(),
+            edited_at: now_in_my_timezone(user_settings).to_rfc3339(),
+        };
+        tx.save_caption_revision(&caption_revision).await;
+    }
+
+    /// Shows the latest edits of the content on its card, with a menu to revert any of them
+    pub async fn interaction_caption_history(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction, ctx: &Context, content_info: &mut ContentInfo) {
+        let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+
+        let mut revisions = tx.load_caption_revisions_by_shortcode(&content_info.original_shortcode).await;
+        revisions.reverse();
+        revisions.truncate(MAX_REVISIONS_SHOWN);
+
+        let full_caption = generate_full_caption(user_settings, tx, &self.ui_definitions, content_info).await;
+        let history_caption = self.ui_definitions.labels.get("history_caption").unwrap();
+
+        let mut history = format!("{full_caption}\n{history_caption}\n");
+        if revisions.is_empty() {
+            history.push_str("No edits yet");
+        }
+        for revision in &revisions {
+            let edited_at = chrono::DateTime::parse_from_rfc3339(&revision.edited_at).unwrap().format("%Y-%m-%d %H:%M");
+            history.push_str(&format!("`#{}` {} <@{}> changed the {} to \"{}\"\n", revision.id, edited_at, revision.editor, revision.kind, preview(&revision.new_value)));
+        }
+
+        let go_back = self.ui_definitions.buttons.get("go_back").unwrap();
+        let mut msg_buttons = vec![CreateActionRow::Buttons(vec![CreateButton::new("go_back").label(go_back)])];
+        if !revisions.is_empty() {
+            let options = revisions
+                .iter()
+                .map(|revision| CreateSelectMenuOption::new(format!("Undo #{}, back to \"{}\"", revision.id, preview(&revision.previous_value)), revision.id.to_string()))
+                .collect();
+            let revert = self.ui_definitions.buttons.get("revert").unwrap();
+            let select_menu = CreateSelectMenu::new("revert_caption", CreateSelectMenuKind::String { options }).placeholder(revert);
+            msg_buttons.insert(0, CreateActionRow::SelectMenu(select_menu));
+        }
+
+        let edited_msg = EditMessage::new().content(history).components(msg_buttons);
+        ctx.http.edit_message(channel_id, content_info.message_id, &edited_msg, vec![]).await.unwrap();
+    }
+
+    /// Restores the value the content had before the selected edit, the revert is itself recorded as an edit
+    pub async fn interaction_revert_caption(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction, ctx: &Context, interaction: &Interaction, content_info: &mut ContentInfo) {
+        let component = interaction.clone().message_component().unwrap();
+        let revision_id = match &component.data.kind {
+            ComponentInteractionDataKind::StringSelect { values } => values.first().and_then(|value| value.parse::<i32>().ok()),
+            _ => None,
+        };
+
+        let revision = match revision_id {
+            Some(revision_id) => tx.get_caption_revision(revision_id).await,
+            None => None,
+        };
+
+        let revision = match revision {
+            Some(revision) if revision.original_shortcode == content_info.original_shortcode => revision,
+            _ => {
+                tracing::error!("Caption revision not found for {}", content_info.original_shortcode);
+                return;
+            }
+        };
+
+        let previous_value = match revision.kind.as_str() {
+            "caption" => std::mem::replace(&mut content_info.caption, revision.previous_value.clone()),
+            "alt_text" => std::mem::replace(&mut content_info.alt_text, revision.previous_value.clone()),
+            _ => std::mem::replace(&mut content_info.hashtags, revision.previous_value.clone()),
+        };
+        self.record_caption_revision(user_settings, tx, content_info, &revision.kind, &previous_value, component.user.id).await;
+        tx.save_content_info(content_info).await;
+
+        self.interaction_go_back(user_settings, tx, ctx, content_info).await;
+    }
+}
+
+fn preview(value: &str) -> String {
+    if value.chars().count() > MAX_REVISION_PREVIEW_LEN {
+        format!("{}...", value.chars().take(MAX_REVISION_PREVIEW_LEN).collect::<String>())
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,50 @@
+use serenity::all::Message;
+
+use crate::database::database::IngestRequest;
+use crate::discord::bot::Handler;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::discord::utils::now_in_my_timezone;
+
+/// The parts of the Instagram urls followed by the shortcode
+const SHORTCODE_PATH_SEGMENTS: [&str; 4] = ["reel", "reels", "p", "tv"];
+
+impl Handler {
+    /// Asks the scraper to download a reel, which then shows up for review like the scraped content
+    pub(crate) async fn command_ingest(&self, msg: &Message, arguments: &[&str]) -> String {
+        let Some(shortcode) = arguments.first().and_then(|argument| shortcode_from_argument(argument)) else {
+            return format!("Usage: `{}ingest <url|shortcode>`", COMMAND_PREFIX);
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        if tx.does_content_exist_with_shortcode(&shortcode).await {
+            return format!("The reel {} has already been scraped.", shortcode);
+        }
+
+        let user_settings = tx.load_user_settings().await;
+        let ingest_request = IngestRequest {
+            username: self.username.clone(),
+            original_shortcode: shortcode.clone(),
+            requested_by: msg.author.name.clone(),
+            requested_at: now_in_my_timezone(&user_settings).to_rfc3339(),
+        };
+        if !tx.save_ingest_request(&ingest_request).await {
+            return format!("The reel {} has already been asked for.", shortcode);
+        }
+        format!("The reel {} will show up for review once the scraper has downloaded it.", shortcode)
+    }
+}
+
+/// Takes either the shortcode itself or a url like https://www.instagram.com/reel/C7x9Yb1Nq2w/?igsh=...
+fn shortcode_from_argument(argument: &str) -> Option<String> {
+    let argument = argument.trim_matches(['<', '>']);
+    let shortcode = match argument.split_once("instagram.com/") {
+        Some((_, path)) => {
+            let mut segments = path.split(['?', '#']).next().unwrap_or_default().split('/');
+            segments.find(|segment| SHORTCODE_PATH_SEGMENTS.contains(segment)).and_then(|_| segments.next())?
+        }
+        None => argument,
+    };
+
+    let is_shortcode = !shortcode.is_empty() && shortcode.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    is_shortcode.then(|| shortcode.to_string())
+}
@@ -2,23 +2,30 @@ use std::ops::Deref;
 use std::sync::Arc;
 
 use chrono::{DateTime, Duration, Utc};
-use serenity::all::{Context, CreateMessage, EditMessage, Interaction, Mention, MessageId, MessageReference};
+use serenity::all::{ComponentInteraction, Context, CreateActionRow, CreateInputText, CreateInteractionResponse, CreateMessage, CreateModal, EditMessage, InputTextStyle, Interaction, Mention, MessageId, MessageReference};
 use tokio::sync::Mutex;
 
-use crate::database::database::{BotStatus, ContentInfo, DatabaseTransaction, QueuedContent, RejectedContent, UserSettings};
+use crate::database::database::{BotStatus, ContentInfo, DatabaseTransaction, QueuedContent, RejectedContent, RejectionReason, UserSettings};
 use crate::discord::bot::{ChannelIdMap, Handler};
+use crate::discord::operators::{APPROVED_ACTION, REJECTED_ACTION};
 use crate::discord::state::ContentStatus;
-use crate::discord::utils::{generate_full_caption, get_edit_buttons, get_pending_buttons, now_in_my_timezone};
+use crate::discord::utils::{generate_full_caption, get_edit_buttons, get_pending_buttons, get_rejection_reason_buttons, modal_input_value, now_in_my_timezone};
 use crate::discord::view::handle_content_deletion;
 use crate::s3::helper::update_presigned_url;
 use crate::{POSTED_CHANNEL_ID, S3_EXPIRATION_TIME};
 
+/// The buttons of the reasons are named after them, e.g. "reject_low_quality"
+pub(crate) const REJECTION_REASON_PREFIX: &str = "reject_";
+pub(crate) const REJECT_OTHER_BUTTON: &str = "reject_other";
+pub(crate) const REJECTION_NOTE_MODAL: &str = "rejection_note";
+const REJECTION_NOTE_INPUT: &str = "reason_note";
+
 impl Handler {
     pub async fn interaction_resume_from_halt(&self, user_settings: &mut UserSettings, bot_status: &mut BotStatus, tx: &mut DatabaseTransaction) {
         bot_status.status = 0;
         user_settings.can_post = true;
         bot_status.status_message = "resuming...".to_string();
-        bot_status.last_updated_at = (now_in_my_timezone(user_settings) - Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+        bot_status.last_updated_at = (now_in_my_timezone(user_settings) - user_settings.interface_update_interval()).to_rfc3339();
         tx.save_user_settings(user_settings).await;
         tx.save_bot_status(bot_status).await
     }
@@ -26,26 +33,59 @@ impl Handler {
     pub async fn interaction_enable_manual_mode(&self, user_settings: &UserSettings, bot_status: &mut BotStatus, tx: &mut DatabaseTransaction) {
         bot_status.manual_mode = true;
         bot_status.status_message = "manual mode  🟡".to_string();
-        bot_status.last_updated_at = (now_in_my_timezone(user_settings) - Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+        bot_status.last_updated_at = (now_in_my_timezone(user_settings) - user_settings.interface_update_interval()).to_rfc3339();
         tx.save_bot_status(bot_status).await
     }
 
     pub async fn interaction_disable_manual_mode(&self, user_settings: &UserSettings, bot_status: &mut BotStatus, tx: &mut DatabaseTransaction) {
         bot_status.manual_mode = false;
         bot_status.status_message = "disabling manual mode...".to_string();
-        bot_status.last_updated_at = (now_in_my_timezone(user_settings) - Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+        bot_status.last_updated_at = (now_in_my_timezone(user_settings) - user_settings.interface_update_interval()).to_rfc3339();
         tx.save_bot_status(bot_status).await
     }
 
+    /// Publishes the content right away, the poster is woken up instead of waiting for its next refresh
     pub async fn interaction_publish_now(&self, user_settings: &UserSettings, content_info: &mut ContentInfo, tx: &mut DatabaseTransaction) {
-        let now = now_in_my_timezone(user_settings);
+        if tx.promote_to_front_of_queue(&content_info.original_shortcode).await {
+            self.post_now.request(&content_info.original_shortcode);
+        }
 
-        let mut queued_content = tx.get_queued_content_by_shortcode(&content_info.original_shortcode).await.unwrap();
-        queued_content.will_post_at = (now + Duration::seconds(30)).to_rfc3339();
-        tx.save_queued_content(&queued_content).await;
+        content_info.last_updated_at = (now_in_my_timezone(user_settings) - user_settings.interface_update_interval()).to_rfc3339();
+    }
+    /// Marks the content as sponsored, or back as regular content
+    pub async fn interaction_toggle_paid_partnership(&self, user_settings: &UserSettings, content_info: &mut ContentInfo) {
+        content_info.is_paid_partnership = !content_info.is_paid_partnership;
+        content_info.last_updated_at = (now_in_my_timezone(user_settings) - user_settings.interface_update_interval()).to_rfc3339();
+    }
+
+    /// Publishes the content as a trial reel, or back as a regular reel
+    pub async fn interaction_toggle_trial_reel(&self, user_settings: &UserSettings, content_info: &mut ContentInfo) {
+        content_info.is_trial_reel = !content_info.is_trial_reel;
+        content_info.last_updated_at = (now_in_my_timezone(user_settings) - user_settings.interface_update_interval()).to_rfc3339();
+    }
+
+    /// Counts the approval of the moderator, in quorum mode the content is only queued once enough distinct moderators approved it
+    pub async fn interaction_approved(&self, ctx: &Context, interaction: &Interaction, user_settings: &UserSettings, content_info: &mut ContentInfo, tx: &mut DatabaseTransaction, global_last_updated_at: Arc<Mutex<DateTime<Utc>>>) {
+        let user_id = interaction.clone().message_component().unwrap().user.id;
+        if user_settings.approvals_required > 1 {
+            let now = now_in_my_timezone(user_settings);
+            if tx.save_content_vote(&content_info.original_shortcode, user_id, &now.to_rfc3339()).await {
+                self.record_review(user_settings, tx, content_info, APPROVED_ACTION, user_id).await;
+            }
+
+            if tx.count_content_votes(&content_info.original_shortcode).await < user_settings.approvals_required as i64 {
+                // Refresh the card to show the new vote count
+                content_info.last_updated_at = (now - user_settings.interface_update_interval()).to_rfc3339();
+                return;
+            }
+        } else {
+            self.record_review(user_settings, tx, content_info, APPROVED_ACTION, user_id).await;
+        }
 
-        content_info.last_updated_at = (now - Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+        tx.remove_content_votes(&content_info.original_shortcode).await;
+        self.interaction_accepted(ctx, user_settings, content_info, tx, global_last_updated_at).await;
     }
+
     pub async fn interaction_accepted(&self, ctx: &Context, user_settings: &UserSettings, content_info: &mut ContentInfo, tx: &mut DatabaseTransaction, global_last_updated_at: Arc<Mutex<DateTime<Utc>>>) {
         content_info.status = ContentStatus::Queued { shown: true };
 
@@ -66,21 +106,57 @@ impl Handler {
             original_author: content_info.original_author.clone(),
             original_shortcode: content_info.original_shortcode.clone(),
             will_post_at,
+            location_id: "".to_string(),
+            location_name: "".to_string(),
         };
 
         tx.save_queued_content(&queued_content).await;
 
-        content_info.last_updated_at = (now - Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+        content_info.last_updated_at = (now - user_settings.interface_update_interval()).to_rfc3339();
         {
             let mut locked_global_last_updated_at = global_last_updated_at.lock().await;
-            *locked_global_last_updated_at = *locked_global_last_updated_at - Duration::milliseconds(user_settings.interface_update_interval);
+            *locked_global_last_updated_at = *locked_global_last_updated_at - user_settings.interface_update_interval();
         }
         self.process_queued(ctx, user_settings, tx, content_info, global_last_updated_at).await;
     }
 
-    pub async fn interaction_rejected(&self, ctx: &Context, user_settings: &UserSettings, content_info: &mut ContentInfo, tx: &mut DatabaseTransaction, global_last_updated_at: Arc<Mutex<DateTime<Utc>>>) {
+    /// Rejecting takes a second step, the reason is picked among the buttons that replace the ones of the card
+    pub async fn interaction_choose_rejection_reason(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction, ctx: &Context, content_info: &mut ContentInfo) {
+        let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+
+        let msg_caption = generate_full_caption(user_settings, tx, &self.ui_definitions.clone(), content_info).await;
+        let msg_buttons = get_rejection_reason_buttons(&self.ui_definitions);
+
+        let edited_msg = EditMessage::new().content(msg_caption).components(msg_buttons);
+        ctx.http.edit_message(channel_id, content_info.message_id, &edited_msg, vec![]).await.unwrap();
+
+        // The card only goes back to its usual buttons after the next interface update
+        content_info.last_updated_at = now_in_my_timezone(user_settings).to_rfc3339();
+    }
+
+    /// The note of the "other" reason is entered in a modal, which has to be the response to the button
+    pub async fn interaction_ask_rejection_note(&self, ctx: &Context, interaction: &ComponentInteraction) {
+        let note_input = CreateInputText::new(InputTextStyle::Paragraph, "Note", REJECTION_NOTE_INPUT).placeholder("Why is the content rejected?").max_length(200);
+        let modal = CreateModal::new(REJECTION_NOTE_MODAL, "Rejection reason").components(vec![CreateActionRow::InputText(note_input)]);
+        if let Err(e) = interaction.create_response(&ctx.http, CreateInteractionResponse::Modal(modal)).await {
+            tracing::warn!("Couldn't show the rejection note modal: {}", e);
+        }
+    }
+
+    /// Rejects the content for the reason of the button pressed, or for the "other" reason when the note was entered
+    pub async fn interaction_rejected(&self, ctx: &Context, interaction: &Interaction, user_settings: &UserSettings, content_info: &mut ContentInfo, tx: &mut DatabaseTransaction, global_last_updated_at: Arc<Mutex<DateTime<Utc>>>) {
         content_info.status = ContentStatus::Rejected { shown: true };
 
+        let (user_id, reason, reason_note) = match interaction {
+            Interaction::Modal(modal) => (modal.user.id, RejectionReason::Other, modal_input_value(modal, REJECTION_NOTE_INPUT).unwrap_or_default()),
+            _ => {
+                let component = interaction.clone().message_component().unwrap();
+                let reason = component.data.custom_id.strip_prefix(REJECTION_REASON_PREFIX).and_then(RejectionReason::parse).unwrap_or(RejectionReason::Other);
+                (component.user.id, reason, "".to_string())
+            }
+        };
+        self.record_review(user_settings, tx, content_info, REJECTED_ACTION, user_id).await;
+
         let now = now_in_my_timezone(user_settings);
         let rejected_content = RejectedContent {
             username: content_info.username.clone(),
@@ -90,14 +166,20 @@ impl Handler {
             original_author: content_info.original_author.clone(),
             original_shortcode: content_info.original_shortcode.clone(),
             rejected_at: now.to_rfc3339(),
+            reason: reason.as_str().to_string(),
+            reason_note: reason_note.trim().to_string(),
         };
         tx.save_rejected_content(&rejected_content).await;
+        tx.record_rejection(&rejected_content, 1).await;
+        // The approvals don't carry over if the rejection is undone
+        tx.remove_content_votes(&content_info.original_shortcode).await;
+        self.set_content_thread_archived(ctx, user_settings, tx, content_info, true).await;
 
         // Force the update of the message
-        content_info.last_updated_at = (now - Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+        content_info.last_updated_at = (now - user_settings.interface_update_interval()).to_rfc3339();
         {
             let mut locked_global_last_updated_at = global_last_updated_at.lock().await;
-            *locked_global_last_updated_at = *locked_global_last_updated_at - Duration::milliseconds(user_settings.interface_update_interval);
+            *locked_global_last_updated_at = *locked_global_last_updated_at - user_settings.interface_update_interval();
         }
 
         self.process_rejected(ctx, user_settings, tx, content_info, global_last_updated_at).await;
@@ -112,10 +194,10 @@ impl Handler {
         }
 
         let now = now_in_my_timezone(user_settings);
-        content_info.last_updated_at = (now - Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+        content_info.last_updated_at = (now - user_settings.interface_update_interval()).to_rfc3339();
         {
             let mut locked_global_last_updated_at = global_last_updated_at.lock().await;
-            *locked_global_last_updated_at = *locked_global_last_updated_at - Duration::milliseconds(user_settings.interface_update_interval);
+            *locked_global_last_updated_at = *locked_global_last_updated_at - user_settings.interface_update_interval();
         }
 
         self.process_pending(context, user_settings, tx, content_info, global_last_updated_at).await;
@@ -124,13 +206,20 @@ impl Handler {
     pub async fn interaction_undo_rejected(&self, context: &Context, user_settings: &UserSettings, content_info: &mut ContentInfo, tx: &mut DatabaseTransaction, global_last_updated_at: Arc<Mutex<DateTime<Utc>>>) {
         content_info.status = ContentStatus::Pending { shown: true };
 
+        // The undone rejection doesn't count against the source account, the ones from before the reasons were never counted
+        if let Some(rejected_content) = tx.get_rejected_content_by_shortcode(&content_info.original_shortcode).await {
+            if !rejected_content.reason.is_empty() {
+                tx.record_rejection(&rejected_content, -1).await;
+            }
+        }
         tx.remove_rejected_content_with_shortcode(&content_info.original_shortcode).await;
+        self.set_content_thread_archived(context, user_settings, tx, content_info, false).await;
 
         let now = now_in_my_timezone(user_settings);
-        content_info.last_updated_at = (now - Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+        content_info.last_updated_at = (now - user_settings.interface_update_interval()).to_rfc3339();
         {
             let mut locked_global_last_updated_at = global_last_updated_at.lock().await;
-            *locked_global_last_updated_at = *locked_global_last_updated_at - Duration::milliseconds(user_settings.interface_update_interval);
+            *locked_global_last_updated_at = *locked_global_last_updated_at - user_settings.interface_update_interval();
         }
 
         self.process_pending(context, user_settings, tx, content_info, global_last_updated_at).await;
@@ -149,7 +238,7 @@ impl Handler {
         let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
 
         let msg_caption = generate_full_caption(user_settings, tx, &self.ui_definitions.clone(), content_info).await;
-        let msg_buttons = get_pending_buttons(&self.ui_definitions);
+        let msg_buttons = get_pending_buttons(&self.ui_definitions, content_info);
 
         let edited_msg = EditMessage::new();
         let edited_msg = edited_msg.content(msg_caption).components(msg_buttons);
@@ -191,6 +280,22 @@ impl Handler {
             last_updated_at: content_info.last_updated_at.clone(),
             added_at: content_info.added_at.clone(),
             encountered_errors: content_info.encountered_errors,
+            preview_url: content_info.preview_url.clone(),
+            clip_url: content_info.clip_url.clone(),
+            alt_text: content_info.alt_text.clone(),
+            is_paid_partnership: content_info.is_paid_partnership,
+            is_trial_reel: content_info.is_trial_reel,
+            original_posted_at: content_info.original_posted_at.clone(),
+            needs_age_override: content_info.needs_age_override,
+            source_views: content_info.source_views,
+            source_likes: content_info.source_likes,
+            audio_id: content_info.audio_id.clone(),
+            audio_name: content_info.audio_name.clone(),
+            category: content_info.category.clone(),
+            source_deleted: content_info.source_deleted,
+            caption_language: content_info.caption_language.clone(),
+            is_caption_translated: content_info.is_caption_translated,
+            profanity: content_info.profanity.clone(),
         };
 
         *self.edited_content.lock().await = Some(EditedContent {
@@ -214,18 +319,37 @@ impl Handler {
             message_to_delete: Some(msg.id),
         });
     }
+
+    pub async fn interaction_edit_alt_text(&self, ctx: &Context, interaction: &Interaction, content_info: &mut ContentInfo) {
+        let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+
+        let mention = Mention::User(interaction.clone().message_component().unwrap().user.id);
+        let referenced_message = MessageReference::from(interaction.clone().message_component().unwrap().message.deref());
+        let msg = CreateMessage::new().content(format!(" {mention} - Please enter the alt text for the content, or `!` to go back to the default one.")).reference_message(referenced_message);
+        let msg = ctx.http.send_message(channel_id, vec![], &msg).await.unwrap();
+
+        *self.edited_content.lock().await = Some(EditedContent {
+            kind: EditedContentKind::AltText,
+            content_info: content_info.clone(),
+            message_to_delete: Some(msg.id),
+        });
+    }
 }
 
 #[derive(Clone)]
 pub enum EditedContentKind {
     Caption,
     Hashtags,
+    AltText,
+    Location,
 }
 #[derive(Clone)]
 pub struct EditedContent {
     /// The kind of content that is being edited.
     /// 0 - Caption
     /// 1 - Hashtags
+    /// 2 - Alt text
+    /// 3 - Location, the message is a search rather than the new value
     pub(crate) kind: EditedContentKind,
     pub(crate) content_info: ContentInfo,
     pub(crate) message_to_delete: Option<MessageId>,
@@ -0,0 +1,115 @@
+use std::ops::Deref;
+
+use serenity::all::{ComponentInteractionDataKind, Context, CreateActionRow, CreateMessage, CreateSelectMenu, CreateSelectMenuKind, CreateSelectMenuOption, Interaction, Mention, Message, MessageReference};
+
+use crate::database::database::{ContentInfo, DatabaseTransaction, UserSettings};
+use crate::discord::bot::{ChannelIdMap, Handler};
+use crate::discord::interactions::{EditedContent, EditedContentKind};
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::graph_api::search_locations;
+
+/// Discord limits select menus to 25 options
+const MAX_LOCATIONS_SHOWN: usize = 25;
+/// Select menu values are limited to 100 characters, the value holds both the id and the name
+const MAX_LOCATION_VALUE_LEN: usize = 100;
+/// Select menu labels are limited to 100 characters as well
+const MAX_LOCATION_LABEL_LEN: usize = 100;
+
+impl Handler {
+    /// Asks for a place to look up, the results are then shown in a select menu under the card
+    pub async fn interaction_set_location(&self, ctx: &Context, interaction: &Interaction, content_info: &mut ContentInfo) {
+        let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+
+        let mention = Mention::User(interaction.clone().message_component().unwrap().user.id);
+        let referenced_message = MessageReference::from(interaction.clone().message_component().unwrap().message.deref());
+        let msg = CreateMessage::new().content(format!(" {mention} - Please enter the place to search for, or `!` to remove the location.")).reference_message(referenced_message);
+        let msg = ctx.http.send_message(channel_id, vec![], &msg).await.unwrap();
+
+        *self.edited_content.lock().await = Some(EditedContent {
+            kind: EditedContentKind::Location,
+            content_info: content_info.clone(),
+            message_to_delete: Some(msg.id),
+        });
+    }
+
+    /// Handles the message sent after pressing the location button, either a search or "!" to remove the location
+    pub async fn receive_location_search(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, msg: &Message, mut edited_content: EditedContent) {
+        let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+
+        msg.delete(&ctx.http).await.unwrap();
+        ctx.http.delete_message(channel_id, edited_content.message_to_delete.unwrap(), None).await.unwrap();
+
+        if msg.content == "!" {
+            self.save_location(user_settings, tx, &edited_content.content_info.original_shortcode, "", "").await;
+            *self.edited_content.lock().await = None;
+            return;
+        }
+
+        let access_token = self.credentials.get("fb_access_token").unwrap();
+        let referenced_message = MessageReference::from((channel_id, edited_content.content_info.message_id));
+        let reply = match search_locations(access_token, &msg.content).await {
+            Ok(locations) if !locations.is_empty() => {
+                let options = locations
+                    .iter()
+                    .take(MAX_LOCATIONS_SHOWN)
+                    .map(|location| {
+                        let label: String = location.name.chars().take(MAX_LOCATION_LABEL_LEN).collect();
+                        let value: String = format!("{}:{}", location.id, location.name).chars().take(MAX_LOCATION_VALUE_LEN).collect();
+                        CreateSelectMenuOption::new(label, value)
+                    })
+                    .collect();
+                let select_menu = CreateSelectMenu::new("select_location", CreateSelectMenuKind::String { options }).placeholder(format!("Locations matching \"{}\"", msg.content));
+                CreateMessage::new().components(vec![CreateActionRow::SelectMenu(select_menu)])
+            }
+            Ok(_) => CreateMessage::new().content(format!("No location found for \"{}\", please try another search.", msg.content)),
+            Err(e) => CreateMessage::new().content(format!("Couldn't search for \"{}\": {}, please try again.", msg.content, e)),
+        };
+        let reply = ctx.http.send_message(channel_id, vec![], &reply.reference_message(referenced_message)).await.unwrap();
+
+        // Another search can be sent in place of picking one of the results
+        edited_content.message_to_delete = Some(reply.id);
+        *self.edited_content.lock().await = Some(edited_content);
+    }
+
+    /// Tags the location picked from the search results on the queued content
+    pub async fn interaction_select_location(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, interaction: &Interaction) {
+        let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+
+        let mut locked_edited_content = self.edited_content.lock().await;
+        let edited_content = match locked_edited_content.as_ref() {
+            Some(edited_content) if matches!(edited_content.kind, EditedContentKind::Location) => edited_content.clone(),
+            _ => {
+                tracing::error!("Location selected without a content being edited");
+                return;
+            }
+        };
+        *locked_edited_content = None;
+        drop(locked_edited_content);
+
+        let component = interaction.clone().message_component().unwrap();
+        let (location_id, location_name) = match &component.data.kind {
+            ComponentInteractionDataKind::StringSelect { values } => values.first().and_then(|value| value.split_once(':')).unwrap_or_default(),
+            _ => Default::default(),
+        };
+
+        ctx.http.delete_message(channel_id, component.message.id, None).await.unwrap();
+        self.save_location(user_settings, tx, &edited_content.content_info.original_shortcode, location_id, location_name).await;
+    }
+
+    async fn save_location(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction, shortcode: &String, location_id: &str, location_name: &str) {
+        // The content might have been published in the meantime
+        let mut queued_content = match tx.get_queued_content_by_shortcode(shortcode).await {
+            Some(queued_content) => queued_content,
+            None => return,
+        };
+
+        queued_content.location_id = location_id.to_string();
+        queued_content.location_name = location_name.to_string();
+        tx.save_queued_content(&queued_content).await;
+
+        // Refresh the card on the next update
+        let mut content_info = tx.get_content_info_by_shortcode(shortcode).await;
+        content_info.last_updated_at = (now_in_my_timezone(user_settings) - user_settings.interface_update_interval()).to_rfc3339();
+        tx.save_content_info(&content_info).await;
+    }
+}
@@ -1,7 +1,34 @@
+pub(crate) mod announcements;
+pub(crate) mod archival;
+pub(crate) mod blacklist;
 pub(crate) mod bot;
+pub(crate) mod calendar;
+pub(crate) mod category_mix;
+pub(crate) mod challenge;
+pub(crate) mod commands;
+pub(crate) mod duplicates;
+pub(crate) mod hashes;
+pub(crate) mod history;
+pub(crate) mod ingest;
 pub(crate) mod interactions;
+pub(crate) mod location;
 pub(crate) mod macros;
+pub(crate) mod onboarding;
+pub(crate) mod operators;
+pub(crate) mod overrides;
+pub(crate) mod permissions;
+pub(crate) mod previews;
+pub(crate) mod priority;
+pub(crate) mod provenance;
+pub(crate) mod restart;
+pub(crate) mod sla;
 pub(crate) mod state;
+pub(crate) mod stories;
+pub(crate) mod suggestions;
+pub(crate) mod threads;
 pub(crate) mod traits;
+pub(crate) mod updates;
 pub(crate) mod utils;
+pub(crate) mod variety;
 pub(crate) mod view;
+pub(crate) mod warm_up;
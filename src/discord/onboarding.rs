@@ -0,0 +1,136 @@
+use serenity::all::{Context, CreateActionRow, CreateButton, CreateMessage, EditMessage, Interaction, Member, RoleId};
+
+use crate::database::database::DatabaseTransaction;
+use crate::discord::bot::Handler;
+use crate::discord::commands::command_help;
+use crate::discord::utils::now_in_my_timezone;
+
+/// Prefix of the custom ids of the tour buttons, followed by the page they lead to
+pub(crate) const TOUR_BUTTON_PREFIX: &str = "tour_page:";
+
+/// What the buttons do, grouped by the card they are shown on, the labels are taken from ui_definitions
+const BUTTON_PAGES: [(&str, &[(&str, &str)]); 4] = [
+    (
+        "New content shows up in the review channel as a pending card:",
+        &[
+            ("accept", "queues the content, it gets published at the next free slot, in quorum mode it's queued once enough moderators accepted it"),
+            ("accept_anyway", "shown instead of accept when the content is older than the limit set with `!maxage`, or when its source post was deleted right before publishing"),
+            ("reject", "rejects the content, it can still be undone until it expires"),
+            ("edit", "opens the editing buttons"),
+            ("paid_partnership", "marks the content as sponsored, it's then published with the paid partnership label"),
+            ("trial_reel", "publishes the content as a trial reel, shown to non-followers first, the default is set with `!trialreels`"),
+        ],
+    ),
+    (
+        "While editing, the bot waits for your next message in the channel, send `!` to clear the value:",
+        &[
+            ("edit_caption", "replaces the caption"),
+            ("edit_hashtags", "replaces the hashtags"),
+            ("edit_alt_text", "sets the accessibility text read by screen readers"),
+            ("caption_history", "lists the past edits and lets you revert any of them"),
+            ("go_back", "goes back to the pending card"),
+        ],
+    ),
+    (
+        "Queued cards count down to the moment the content gets published:",
+        &[
+            ("publish_now", "publishes the content right away, the rest of the queue moves behind it"),
+            ("remove_from_queue", "takes the content out of the queue, the following posts move up"),
+            ("set_location", "searches for a place to tag on the post"),
+        ],
+    ),
+    (
+        "Rejected and failed cards disappear on their own once they expire:",
+        &[("undo", "puts rejected content back to pending"), ("remove_from_view", "removes the card right away")],
+    ),
+];
+
+/// What the status labels shown at the bottom of the cards mean
+const STATUS_LABELS: [(&str, &str); 5] = [
+    ("queued_caption", "waiting for its slot in the queue"),
+    ("published_caption", "published on the account, shown in the posted channel"),
+    ("rejected_caption", "rejected, deleted for good once the countdown ends"),
+    ("failed_caption", "Instagram refused the upload, check the logs"),
+    ("accepted_caption", "accepted, about to be queued"),
+];
+
+impl Handler {
+    /// Sends the tour to the members that just got the moderator role, each of them gets it only once
+    pub async fn onboard_moderator_if_needed(&self, ctx: &Context, member: &Member) {
+        let moderator_role_id = match self.credentials.get("moderator_role_id").and_then(|role_id| role_id.parse::<u64>().ok()) {
+            Some(role_id) => RoleId::new(role_id),
+            None => return,
+        };
+
+        if member.user.bot || !member.roles.contains(&moderator_role_id) {
+            return;
+        }
+
+        let mut tx = self.database.begin_transaction().await;
+        if tx.is_moderator_onboarded(member.user.id).await {
+            return;
+        }
+
+        let (content, buttons) = self.tour_page(0);
+        let msg = CreateMessage::new().content(content).components(buttons);
+        match member.user.direct_message(ctx, msg).await {
+            Ok(_) => self.record_onboarded_moderator(&mut tx, member).await,
+            Err(e) => tracing::error!("Error sending the onboarding tour to {}: {:?}", member.user.name, e),
+        }
+    }
+
+    async fn record_onboarded_moderator(&self, tx: &mut DatabaseTransaction, member: &Member) {
+        let user_settings = tx.load_user_settings().await;
+        tx.save_onboarded_moderator(member.user.id, &now_in_my_timezone(&user_settings).to_rfc3339()).await;
+        println!(" [{}] Sent the onboarding tour to {}", self.username, member.user.name);
+    }
+
+    /// Shows another page of the tour in place of the current one
+    pub async fn interaction_tour_page(&self, ctx: &Context, interaction: &Interaction, page: usize) {
+        let component = interaction.clone().message_component().unwrap();
+
+        let (content, buttons) = self.tour_page(page);
+        let edited_msg = EditMessage::new().content(content).components(buttons);
+        if let Err(e) = ctx.http.edit_message(component.channel_id, component.message.id, &edited_msg, vec![]).await {
+            tracing::error!("Error showing page {} of the onboarding tour: {:?}", page, e);
+        }
+    }
+
+    /// Builds a page of the tour with the buttons to move between pages, the page after the buttons lists the statuses and the last one the commands
+    fn tour_page(&self, page: usize) -> (String, Vec<CreateActionRow>) {
+        let page_count = BUTTON_PAGES.len() + 3;
+        let page = page.min(page_count - 1);
+
+        let content = if page == 0 {
+            format!("Hi! You can now review the content of {}, here's a quick tour of the interface.", self.username)
+        } else if page <= BUTTON_PAGES.len() {
+            let (description, buttons) = BUTTON_PAGES[page - 1];
+            let mut content = description.to_string();
+            for (button, explanation) in buttons {
+                let label = self.ui_definitions.buttons.get(*button).map(String::as_str).unwrap_or(button);
+                content.push_str(&format!("\n**{}** {}", label, explanation));
+            }
+            content
+        } else if page == BUTTON_PAGES.len() + 1 {
+            let mut content = "The bottom of each card tells you where the content is at:".to_string();
+            for (label, explanation) in STATUS_LABELS {
+                let label = self.ui_definitions.labels.get(label).map(String::as_str).unwrap_or(label);
+                content.push_str(&format!("\n**{}** {}", label, explanation));
+            }
+            content
+        } else {
+            format!("Finally, a few text commands can be sent in the review channel.\n{}", command_help())
+        };
+        let content = format!("{}\n\n*{}/{}*", content, page + 1, page_count);
+
+        let mut buttons = Vec::new();
+        if page > 0 {
+            buttons.push(CreateButton::new(format!("{}{}", TOUR_BUTTON_PREFIX, page - 1)).label("Back"));
+        }
+        if page < page_count - 1 {
+            buttons.push(CreateButton::new(format!("{}{}", TOUR_BUTTON_PREFIX, page + 1)).label("Next"));
+        }
+
+        (content, vec![CreateActionRow::Buttons(buttons)])
+    }
+}
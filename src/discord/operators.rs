@@ -0,0 +1,100 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serenity::all::UserId;
+
+use crate::database::database::{AuditEntry, ContentInfo, DatabaseTransaction, UserSettings};
+use crate::discord::bot::Handler;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::discord::utils::now_in_my_timezone;
+
+/// Actions of the audit log counted as reviews in the operator stats, the edits come from the caption revisions
+pub(crate) const APPROVED_ACTION: &str = "approved";
+pub(crate) const REJECTED_ACTION: &str = "rejected";
+
+#[derive(Default)]
+struct OperatorStats {
+    approvals: u32,
+    rejections: u32,
+    edits: u32,
+    total_review_latency: i64,
+}
+
+impl OperatorStats {
+    fn reviews(&self) -> u32 {
+        self.approvals + self.rejections
+    }
+}
+
+impl Handler {
+    /// Keeps track of who reviewed the content and how long it waited for it
+    pub(crate) async fn record_review(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction, content_info: &ContentInfo, action: &str, operator: UserId) {
+        let now = now_in_my_timezone(user_settings);
+        let review_latency = DateTime::parse_from_rfc3339(&content_info.added_at).map(|added_at| (now - added_at.with_timezone(&Utc)).num_seconds().max(0)).unwrap_or_default();
+
+        let audit_entry = AuditEntry {
+            username: content_info.username.clone(),
+            original_shortcode: content_info.original_shortcode.clone(),
+            action: action.to_string(),
+            operator: operator.to_string(),
+            detail: "".to_string(),
+            created_at: now.to_rfc3339(),
+            review_latency,
+        };
+        tx.save_audit_entry(&audit_entry).await;
+    }
+
+    /// Sums up what each moderator did during a month, e.g. "!operators 2026-09", the current month by default
+    pub(crate) async fn command_operators(&self, arguments: &[&str]) -> String {
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+
+        let month = match arguments.first() {
+            None => now_in_my_timezone(&user_settings).format("%Y-%m").to_string(),
+            Some(month) if NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d").is_ok() => month.to_string(),
+            _ => return format!("Usage: `{}operators [YYYY-MM]`", COMMAND_PREFIX),
+        };
+
+        let mut operators: HashMap<String, OperatorStats> = HashMap::new();
+        for audit_entry in tx.load_audit_entries_for_month(&month).await {
+            let stats = operators.entry(audit_entry.operator).or_default();
+            match audit_entry.action.as_str() {
+                APPROVED_ACTION => stats.approvals += 1,
+                REJECTED_ACTION => stats.rejections += 1,
+                _ => continue,
+            }
+            stats.total_review_latency += audit_entry.review_latency;
+        }
+        for caption_revision in tx.load_caption_revisions_for_month(&month).await {
+            operators.entry(caption_revision.editor).or_default().edits += 1;
+        }
+
+        // The entries of the bot itself only show up for the actions that aren't counted
+        operators.retain(|_, stats| stats.reviews() + stats.edits > 0);
+        if operators.is_empty() {
+            return format!("Nobody reviewed any content in {}.", month);
+        }
+
+        let mut operators: Vec<(String, OperatorStats)> = operators.into_iter().collect();
+        operators.sort_by_key(|(_, stats)| Reverse(stats.reviews() + stats.edits));
+
+        let mut summary = format!("Moderator activity in {}:", month);
+        for (operator, stats) in operators {
+            let average_latency = match stats.reviews() {
+                0 => "no reviews".to_string(),
+                reviews => format!("{} on average to review", format_latency(stats.total_review_latency / reviews as i64)),
+            };
+            summary.push_str(&format!("\n<@{}> {} approved, {} rejected, {} edits, {}", operator, stats.approvals, stats.rejections, stats.edits, average_latency));
+        }
+        summary
+    }
+}
+
+pub(crate) fn format_latency(seconds: i64) -> String {
+    if seconds < 60 * 60 {
+        format!("{} minutes", seconds / 60)
+    } else {
+        format!("{} hours and {} minutes", seconds / 3600, seconds % 3600 / 60)
+    }
+}
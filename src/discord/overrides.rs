@@ -0,0 +1,140 @@
+use chrono::{DateTime, Duration, Utc};
+use serenity::all::{ChannelId, Context, CreateMessage};
+
+use crate::database::database::{DatabaseTransaction, SettingsOverride, UserSettings};
+use crate::database::settings::{POSTING_INTERVAL, QUIET_HOURS_END, QUIET_HOURS_START, RANDOM_INTERVAL_VARIANCE, REJECTED_CONTENT_LIFESPAN, SCRAPE_WINDOW_END, SCRAPE_WINDOW_START};
+use crate::discord::bot::Handler;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::discord::utils::{now_in_my_timezone, send_message_with_retry};
+
+/// Settings that can be temporarily overridden with the override command
+pub(crate) const OVERRIDABLE_SETTINGS: [&str; 8] = ["can_post", "posting_interval", "random_interval_variance", "rejected_content_lifespan", "quiet_hours_start", "quiet_hours_end", "scrape_window_start", "scrape_window_end"];
+/// A year, longer overrides should be a change of the setting itself
+const MAX_OVERRIDE_HOURS: i64 = 24 * 365;
+
+/// Returns the current value of an overridable setting
+pub(crate) fn get_setting_value(user_settings: &UserSettings, setting: &str) -> Option<String> {
+    match setting {
+        "can_post" => Some(user_settings.can_post.to_string()),
+        "posting_interval" => Some(user_settings.posting_interval.to_string()),
+        "random_interval_variance" => Some(user_settings.random_interval_variance.to_string()),
+        "rejected_content_lifespan" => Some(user_settings.rejected_content_lifespan.to_string()),
+        "quiet_hours_start" => Some(user_settings.quiet_hours_start.to_string()),
+        "quiet_hours_end" => Some(user_settings.quiet_hours_end.to_string()),
+        "scrape_window_start" => Some(user_settings.scrape_window_start.to_string()),
+        "scrape_window_end" => Some(user_settings.scrape_window_end.to_string()),
+        _ => None,
+    }
+}
+
+/// Sets an overridable setting from its textual value, returns an error message if the value is invalid or out of range
+pub(crate) fn set_setting_value(user_settings: &mut UserSettings, setting: &str, value: &str) -> Result<(), String> {
+    match setting {
+        "can_post" => user_settings.can_post = value.parse().map_err(|_| format!("can_post has to be true or false, `{}` isn't", value))?,
+        "posting_interval" => user_settings.posting_interval = POSTING_INTERVAL.parse(value)? as i32,
+        "random_interval_variance" => user_settings.random_interval_variance = user_settings.validate_random_interval_variance(RANDOM_INTERVAL_VARIANCE.parse(value)? as i32)?,
+        "rejected_content_lifespan" => user_settings.rejected_content_lifespan = REJECTED_CONTENT_LIFESPAN.parse(value)? as i32,
+        "quiet_hours_start" => user_settings.quiet_hours_start = QUIET_HOURS_START.parse(value)? as i32,
+        "quiet_hours_end" => user_settings.quiet_hours_end = QUIET_HOURS_END.parse(value)? as i32,
+        "scrape_window_start" => user_settings.scrape_window_start = SCRAPE_WINDOW_START.parse(value)? as i32,
+        "scrape_window_end" => user_settings.scrape_window_end = SCRAPE_WINDOW_END.parse(value)? as i32,
+        _ => return Err(format!("{} can't be overridden, try one of: {}", setting, OVERRIDABLE_SETTINGS.join(", "))),
+    }
+    Ok(())
+}
+
+impl Handler {
+    /// Changes a setting for the given amount of hours, e.g. "!override posting_interval 60 24"
+    pub(crate) async fn command_override(&self, arguments: &[&str]) -> String {
+        let mut tx = self.database.begin_transaction().await;
+
+        let (setting, value, hours) = match arguments {
+            [] => return self.list_settings_overrides(&mut tx).await,
+            [setting, "off"] => return self.cancel_settings_override(&mut tx, setting).await,
+            [setting, value, hours] => match hours.parse::<i64>() {
+                Ok(hours) if (1..=MAX_OVERRIDE_HOURS).contains(&hours) => (*setting, *value, hours),
+                _ => return format!("Usage: `{}override <setting> <value> <hours>`, with at most {} hours", COMMAND_PREFIX, MAX_OVERRIDE_HOURS),
+            },
+            _ => return format!("Usage: `{}override <setting> <value> <hours>`", COMMAND_PREFIX),
+        };
+
+        let mut user_settings = tx.load_user_settings().await;
+        let previous_value = match get_setting_value(&user_settings, setting) {
+            Some(previous_value) => previous_value,
+            None => return format!("{} can't be overridden, try one of: {}", setting, OVERRIDABLE_SETTINGS.join(", ")),
+        };
+
+        if let Err(e) = set_setting_value(&mut user_settings, setting, value) {
+            return e;
+        }
+
+        let expires_at = now_in_my_timezone(&user_settings) + Duration::hours(hours);
+        let settings_override = SettingsOverride {
+            username: self.username.clone(),
+            setting: setting.to_string(),
+            value: get_setting_value(&user_settings, setting).unwrap(),
+            previous_value,
+            expires_at: expires_at.to_rfc3339(),
+        };
+
+        // Overriding twice keeps the value from before the first override, so that's what gets restored
+        tx.save_settings_override(&settings_override).await;
+        tx.save_user_settings(&user_settings).await;
+
+        format!("{} set to {} until {}, it will then go back to how it was.", setting, value, expires_at.format("%Y-%m-%d %H:%M"))
+    }
+
+    async fn list_settings_overrides(&self, tx: &mut DatabaseTransaction) -> String {
+        let settings_overrides = tx.load_settings_overrides().await;
+        if settings_overrides.is_empty() {
+            return "No settings are currently overridden.".to_string();
+        }
+
+        let mut list = "Overridden settings:".to_string();
+        for settings_override in settings_overrides {
+            let expires_at = DateTime::parse_from_rfc3339(&settings_override.expires_at).unwrap();
+            list.push_str(&format!("\n- {} is {} until {}, then back to {}", settings_override.setting, settings_override.value, expires_at.format("%Y-%m-%d %H:%M"), settings_override.previous_value));
+        }
+        list
+    }
+
+    async fn cancel_settings_override(&self, tx: &mut DatabaseTransaction, setting: &str) -> String {
+        match tx.load_settings_overrides().await.into_iter().find(|settings_override| settings_override.setting == setting) {
+            Some(settings_override) => {
+                self.revert_settings_override(tx, &settings_override).await;
+                format!("{} is back to {}.", setting, settings_override.previous_value)
+            }
+            None => format!("{} is not overridden.", setting),
+        }
+    }
+
+    /// Restores the settings whose override has expired, letting the user know about it
+    pub(crate) async fn revert_expired_settings_overrides(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, channel_id: ChannelId) {
+        let now = now_in_my_timezone(user_settings);
+        for settings_override in tx.load_settings_overrides().await {
+            if DateTime::parse_from_rfc3339(&settings_override.expires_at).unwrap().with_timezone(&Utc) > now {
+                continue;
+            }
+
+            self.revert_settings_override(tx, &settings_override).await;
+
+            let msg_caption = format!("The override of {} has expired, it's back to {}.", settings_override.setting, settings_override.previous_value);
+            send_message_with_retry(ctx, channel_id, CreateMessage::new().content(msg_caption)).await;
+        }
+    }
+
+    async fn revert_settings_override(&self, tx: &mut DatabaseTransaction, settings_override: &SettingsOverride) {
+        let mut user_settings = tx.load_user_settings().await;
+
+        // A setting changed by hand in the meantime is left alone
+        if get_setting_value(&user_settings, &settings_override.setting).as_deref() == Some(settings_override.value.as_str()) {
+            // The previous value can stop being valid when the other settings changed in the meantime, e.g. a variance above the new posting interval
+            match set_setting_value(&mut user_settings, &settings_override.setting, &settings_override.previous_value) {
+                Ok(()) => tx.save_user_settings(&user_settings).await,
+                Err(e) => tracing::warn!("Couldn't revert the override of {}: {}", settings_override.setting, e),
+            }
+        }
+
+        tx.remove_settings_override(&settings_override.setting).await;
+    }
+}
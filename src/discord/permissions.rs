@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use serenity::all::Message;
+
+use crate::database::database::AuthorPermission;
+use crate::discord::bot::Handler;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::discord::utils::now_in_my_timezone;
+use crate::s3::helper::{delete_from_s3, update_presigned_url, upload_to_s3};
+
+/// The screenshots of the permissions are kept in the bucket in one of these formats
+const SCREENSHOT_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "webp"];
+/// Longer reports don't fit in a Discord message
+const MAX_REPORTED_AUTHORS: usize = 20;
+
+impl Handler {
+    /// Records that an author allowed the account to repost their content, e.g. "!permission @author https://ig.me/..." or with a screenshot of the conversation attached
+    ///
+    /// Without a proof the recorded permission is shown, and "!permission @author revoke" removes it
+    pub(crate) async fn command_permission(&self, msg: &Message, arguments: &[&str]) -> String {
+        let (author, proof_url) = match arguments {
+            [author, "revoke"] => return self.revoke_author_permission(&permission_author(author)).await,
+            [author] if msg.attachments.is_empty() => return self.show_author_permission(&permission_author(author)).await,
+            [author] => (permission_author(author), "".to_string()),
+            [author, proof_url] if proof_url.starts_with("https://") || proof_url.starts_with("http://") => (permission_author(author), proof_url.to_string()),
+            _ => return format!("Usage: `{}permission <author> [link]` with the link to the conversation or a screenshot of it attached, `{}permission <author> revoke` to remove it", COMMAND_PREFIX, COMMAND_PREFIX),
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+        let recorded_at = now_in_my_timezone(&user_settings);
+
+        let proof_s3_key = match msg.attachments.first() {
+            Some(attachment) => {
+                let extension = attachment.filename.rsplit('.').next().unwrap_or_default().to_lowercase();
+                if !SCREENSHOT_EXTENSIONS.contains(&extension.as_str()) {
+                    return format!("The screenshot has to be one of {}, `{}` isn't", SCREENSHOT_EXTENSIONS.join(", "), attachment.filename);
+                }
+                let file = match attachment.download().await {
+                    Ok(file) => file,
+                    Err(e) => return format!("Couldn't download `{}`: {}", attachment.filename, e),
+                };
+
+                let screenshot_file_name = format!("permission_{}_{}.{}", author, recorded_at.timestamp(), extension);
+                if let Err(e) = tokio::fs::write(format!("temp/{screenshot_file_name}"), file).await {
+                    return format!("Couldn't save `{}`: {}", attachment.filename, e);
+                }
+                let proof_s3_key = format!("{}/permissions/{}", self.username, screenshot_file_name);
+                if let Err(e) = upload_to_s3(&self.bucket, screenshot_file_name, proof_s3_key.clone(), true).await {
+                    return format!("Couldn't upload `{}` to the bucket: {}", attachment.filename, e);
+                }
+                proof_s3_key
+            }
+            None => "".to_string(),
+        };
+
+        // The screenshot of the permission it replaces isn't needed anymore
+        if let Some(previous_permission) = tx.get_author_permission(&author).await {
+            if !previous_permission.proof_s3_key.is_empty() && previous_permission.proof_s3_key != proof_s3_key {
+                let _ = delete_from_s3(&self.bucket, previous_permission.proof_s3_key).await;
+            }
+        }
+
+        let author_permission = AuthorPermission {
+            username: self.username.clone(),
+            author: author.clone(),
+            proof_url,
+            proof_s3_key,
+            recorded_by: msg.author.name.clone(),
+            recorded_at: recorded_at.to_rfc3339(),
+        };
+        tx.save_author_permission(&author_permission).await;
+
+        format!("Recorded the permission of @{} to repost their content.", author)
+    }
+
+    async fn show_author_permission(&self, author: &str) -> String {
+        let mut tx = self.database.begin_transaction().await;
+        let Some(author_permission) = tx.get_author_permission(author).await else {
+            return format!("There's no permission on file for @{}.", author);
+        };
+
+        let mut proofs = Vec::new();
+        if !author_permission.proof_url.is_empty() {
+            proofs.push(format!("<{}>", author_permission.proof_url));
+        }
+        // The links of the bucket expire, a new one is made each time
+        if !author_permission.proof_s3_key.is_empty() {
+            match update_presigned_url(&self.bucket, author_permission.proof_s3_key.clone()).await {
+                Ok(screenshot_url) => proofs.push(format!("[screenshot](<{}>)", screenshot_url)),
+                Err(e) => proofs.push(format!("the screenshot at `{}` couldn't be linked: {}", author_permission.proof_s3_key, e)),
+            }
+        }
+
+        format!("@{} gave their permission, recorded by {} on {}: {}", author, author_permission.recorded_by, author_permission.recorded_at, proofs.join(", "))
+    }
+
+    async fn revoke_author_permission(&self, author: &str) -> String {
+        let mut tx = self.database.begin_transaction().await;
+        let Some(author_permission) = tx.remove_author_permission(author).await else {
+            return format!("There's no permission on file for @{}.", author);
+        };
+
+        if !author_permission.proof_s3_key.is_empty() {
+            let _ = delete_from_s3(&self.bucket, author_permission.proof_s3_key).await;
+        }
+        format!("Removed the permission of @{}.", author)
+    }
+
+    /// Lists the authors of the queued and published posts who didn't give their permission, the ones with the most posts first
+    ///
+    /// The content credited to the account itself, e.g. the inbox and the compilations, doesn't need one
+    pub(crate) async fn command_permissions(&self) -> String {
+        let mut tx = self.database.begin_transaction().await;
+        let author_permissions = tx.load_author_permissions().await;
+        let has_permission = |author: &str| author.eq_ignore_ascii_case(&self.username) || author_permissions.iter().any(|author_permission| author_permission.author.eq_ignore_ascii_case(author));
+
+        // (queued, published) posts of each author
+        let mut lacking_permission: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for queued_content in tx.load_content_queue().await.iter().filter(|queued_content| !has_permission(&queued_content.original_author)) {
+            lacking_permission.entry(queued_content.original_author.clone()).or_default().0 += 1;
+        }
+        for published_content in tx.load_posted_content().await.iter().filter(|published_content| !published_content.is_simulated && !has_permission(&published_content.original_author)) {
+            lacking_permission.entry(published_content.original_author.clone()).or_default().1 += 1;
+        }
+
+        if lacking_permission.is_empty() {
+            return format!("Every queued and published post has the permission of its author, {} permissions on file.", author_permissions.len());
+        }
+
+        let mut authors: Vec<(String, (usize, usize))> = lacking_permission.into_iter().collect();
+        authors.sort_by_key(|(_, (queued, published))| std::cmp::Reverse(queued + published));
+        let posts: usize = authors.iter().map(|(_, (queued, published))| queued + published).sum();
+
+        let mut report = format!("{} posts from {} authors lack their permission, `{}permission <author>` records it:", posts, authors.len(), COMMAND_PREFIX);
+        for (author, (queued, published)) in authors.iter().take(MAX_REPORTED_AUTHORS) {
+            report.push_str(&format!("\n@{}: {} queued, {} published", author, queued, published));
+        }
+        if authors.len() > MAX_REPORTED_AUTHORS {
+            report.push_str(&format!("\n...and {} more authors", authors.len() - MAX_REPORTED_AUTHORS));
+        }
+        report
+    }
+}
+
+/// The authors are stored without the "@" and in lowercase, like the blacklisted ones
+fn permission_author(author: &str) -> String {
+    author.trim_start_matches('@').to_lowercase()
+}
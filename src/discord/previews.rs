@@ -0,0 +1,41 @@
+use chrono::Utc;
+
+use crate::discord::bot::Handler;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::s3::cdn::read_cdn_links;
+use crate::video::processing::preview_file_name;
+
+impl Handler {
+    /// Sends the public links to a content through the CDN, e.g. to show it to a collaborator who isn't on the server
+    pub(crate) async fn command_preview(&self, arguments: &[&str]) -> String {
+        let shortcode = match arguments.first() {
+            Some(shortcode) => shortcode.to_string(),
+            None => return format!("Usage: `{}preview <shortcode>`", COMMAND_PREFIX),
+        };
+        let Some(cdn_links) = read_cdn_links(&self.credentials) else {
+            return "The public links need a CDN in front of the bucket, set `cdn_base_url` in the credentials.".to_string();
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        if !tx.get_content_state_by_shortcode(&shortcode).await.is_some_and(|content_state| content_state.deleted_at.is_empty()) {
+            return format!("There's no content `{}`, or its files were already removed.", shortcode);
+        }
+        let content_info = tx.get_content_info_by_shortcode(&shortcode).await;
+
+        let now = Utc::now();
+        let video_path = format!("{}/{}.mp4", self.username, content_info.original_shortcode);
+        let (video_link, expires_at) = cdn_links.link(&video_path, now);
+        let mut links = match expires_at {
+            Some(expires_at) => format!("Public links to `{}`, expiring <t:{}:R>:", shortcode, expires_at.timestamp()),
+            None => format!("Public links to `{}`:", shortcode),
+        };
+        // The content scraped before the previews existed only has the original video
+        if !content_info.preview_url.is_empty() && content_info.preview_url != content_info.url {
+            let (preview_link, _) = cdn_links.link(&preview_file_name(&video_path), now);
+            links.push_str(&format!("\nPreview: {}", preview_link));
+        }
+        // Not embedded, the full video is only there to be downloaded
+        links.push_str(&format!("\nFull video: <{}>", video_link));
+        links
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::database::database::{ContentInfo, DatabaseTransaction, QueuedContent, UserSettings};
+use crate::discord::variety::{movable_queue, reorder_queue};
+
+/// Moves the content that did best on its source account to the front of the queue, returns whether anything was moved
+///
+/// The content the source doesn't tell anything about keeps its order at the back
+pub(crate) async fn prioritize_queue(user_settings: &UserSettings, tx: &mut DatabaseTransaction, content_queue: &[QueuedContent], now: DateTime<Utc>) -> bool {
+    let movable_queue = movable_queue(content_queue, now);
+
+    let content_mapping = tx.load_content_mapping().await;
+    let content_by_shortcode: HashMap<&str, &ContentInfo> = content_mapping.iter().map(|content_info| (content_info.original_shortcode.as_str(), content_info)).collect();
+
+    let shortcodes: Vec<&str> = movable_queue.iter().map(|queued_content| queued_content.original_shortcode.as_str()).collect();
+    let mut prioritized = shortcodes.clone();
+    // The sort is stable, so the content performing the same keeps the order in which it was accepted
+    prioritized.sort_by(|a, b| {
+        let score = |shortcode: &str| content_by_shortcode.get(shortcode).map_or(0.0, |content_info| expected_performance(content_info));
+        score(b).total_cmp(&score(a))
+    });
+    if prioritized == shortcodes {
+        return false;
+    }
+
+    reorder_queue(user_settings, tx, &movable_queue, prioritized, now).await;
+    true
+}
+
+/// The views per day the source post had gathered when it was scraped, the likes stand in for the views when the source doesn't count them
+pub(crate) fn expected_performance(content_info: &ContentInfo) -> f64 {
+    let engagement = if content_info.source_views > 0 { content_info.source_views } else { content_info.source_likes };
+    if engagement == 0 {
+        return 0.0;
+    }
+
+    let (Ok(original_posted_at), Ok(added_at)) = (DateTime::parse_from_rfc3339(&content_info.original_posted_at), DateTime::parse_from_rfc3339(&content_info.added_at)) else {
+        return engagement as f64;
+    };
+    // A post scraped within its first day isn't credited with more than it gathered
+    let age_days = ((added_at - original_posted_at).num_hours() as f64 / 24.0).max(1.0);
+    engagement as f64 / age_days
+}
@@ -0,0 +1,99 @@
+use serde_json::json;
+use serenity::all::{Context, CreateAttachment, CreateMessage, Message};
+
+use crate::discord::bot::Handler;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::video::processing::DUPLICATE_MAX_DISTANCE;
+
+impl Handler {
+    /// Sends where a content came from and what was done to it as a JSON file, e.g. "!provenance C7x9Yb1Nq2w"
+    pub(crate) async fn command_provenance(&self, ctx: &Context, msg: &Message, arguments: &[&str]) {
+        let response = match arguments.first() {
+            Some(shortcode) => self.provenance_message(shortcode).await,
+            None => CreateMessage::new().content(format!("Usage: `{}provenance <shortcode>`", COMMAND_PREFIX)),
+        };
+        if let Err(e) = msg.channel_id.send_message(&ctx.http, response).await {
+            tracing::error!("Error sending the provenance: {:?}", e);
+        }
+    }
+
+    async fn provenance_message(&self, shortcode: &str) -> CreateMessage {
+        let mut tx = self.database.begin_transaction().await;
+        let Some(content_provenance) = tx.get_content_provenance_by_shortcode(shortcode).await else {
+            return CreateMessage::new().content(format!("There's no provenance for `{}`, it was either never sent for review or scraped before the provenance was kept.", shortcode));
+        };
+
+        let published_content = tx.get_published_content_by_shortcode(&shortcode.to_string()).await;
+        let published = published_content.as_ref().map(|published_content| {
+            json!({
+                "original_author": published_content.original_author,
+                "published_at": published_content.published_at,
+                "is_simulated": published_content.is_simulated,
+                "permalink": published_content.permalink,
+                "media_id": published_content.media_id,
+                "caption": published_content.caption_variant,
+            })
+        });
+
+        // Only known once published, the provenance doesn't hold the author
+        let author_permission = match &published_content {
+            Some(published_content) => tx.get_author_permission(&published_content.original_author).await.map(|author_permission| {
+                json!({
+                    "proof_url": author_permission.proof_url,
+                    "proof_s3_key": author_permission.proof_s3_key,
+                    "recorded_by": author_permission.recorded_by,
+                    "recorded_at": author_permission.recorded_at,
+                })
+            }),
+            None => None,
+        };
+
+        let cross_posts: Vec<_> = tx
+            .load_cross_posts_by_shortcode(shortcode)
+            .await
+            .into_iter()
+            .map(|cross_post| {
+                json!({
+                    "platform": cross_post.platform,
+                    "status": cross_post.status,
+                    "post_id": cross_post.post_id,
+                    "error": cross_post.error,
+                    "attempted_at": cross_post.attempted_at,
+                })
+            })
+            .collect();
+
+        let story_share = tx.get_story_share_by_shortcode(shortcode).await.map(|story_share| {
+            json!({
+                "status": story_share.status,
+                "story_id": story_share.story_id,
+                "error": story_share.error,
+                "share_at": story_share.share_at,
+                "attempted_at": story_share.attempted_at,
+            })
+        });
+
+        let provenance = json!({
+            "account": content_provenance.username,
+            "shortcode": content_provenance.original_shortcode,
+            "source_url": content_provenance.source_url,
+            "downloaded_at": content_provenance.downloaded_at,
+            "processing_steps": content_provenance.processing_steps.split(';').filter(|step| !step.is_empty()).collect::<Vec<&str>>(),
+            "dedup": {
+                "compared_videos": content_provenance.dedup_compared_videos,
+                "closest_distance": (content_provenance.dedup_closest_distance >= 0).then_some(content_provenance.dedup_closest_distance),
+                "duplicate_max_distance": DUPLICATE_MAX_DISTANCE,
+            },
+            "s3_key": content_provenance.s3_key,
+            "publish_attempts": content_provenance.publish_attempts.lines().collect::<Vec<&str>>(),
+            "published": published,
+            "author_permission": author_permission,
+            "cross_posts": cross_posts,
+            "story_share": story_share,
+        });
+        let file = serde_json::to_vec_pretty(&provenance).unwrap();
+
+        let attachment = CreateAttachment::bytes(file, format!("provenance_{}_{}.json", self.username, shortcode));
+        CreateMessage::new().content(format!("Here is the provenance of `{}`", shortcode)).add_file(attachment)
+    }
+}
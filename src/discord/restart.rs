@@ -0,0 +1,164 @@
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{DateTime, Duration};
+use serenity::all::{Context, CreateMessage, Message};
+
+use crate::coordination::publishing::{hold_publishing, publishes_in_flight, release_publishing};
+use crate::database::database::DatabaseTransaction;
+use crate::discord::bot::Handler;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::discord::utils::now_in_my_timezone;
+use crate::lease::account_lease::{current_instance_id, INSTANCE_ID_VAR};
+use crate::{crab, MAX_RESTART_DRAIN_HOURS, MY_DISCORD_ID, RESTART_DRAIN_CHECK_INTERVAL, RESTART_DRAIN_TIMEOUT, STATUS_CHANNEL_ID};
+
+/// The restart replaces the whole process, so it's shared by the accounts running in it
+static IS_RESTARTING: AtomicBool = AtomicBool::new(false);
+
+impl Handler {
+    /// Drains the queue for the given hours, applies the update and restarts the process, only the owner of the bot can ask for it
+    ///
+    /// The update is either the "update_script" of the credentials or the binary staged at "staged_binary_path", the script takes precedence
+    pub(crate) async fn command_restart(&self, ctx: &Context, msg: &Message, arguments: &[&str]) -> String {
+        if msg.author.id != MY_DISCORD_ID {
+            return "Only the owner of the bot can restart it.".to_string();
+        }
+
+        let drain_hours = match arguments.first().map(|hours| hours.parse::<i64>()) {
+            None => 0,
+            Some(Ok(hours)) if (0..=MAX_RESTART_DRAIN_HOURS).contains(&hours) => hours,
+            _ => return format!("Usage: `{}restart [hours]`, with at most {} hours", COMMAND_PREFIX, MAX_RESTART_DRAIN_HOURS),
+        };
+
+        if IS_RESTARTING.swap(true, Ordering::SeqCst) {
+            return "A restart is already underway.".to_string();
+        }
+
+        let restart_handler = self.clone();
+        let restart_ctx = ctx.clone();
+        tokio::spawn(async move { restart_handler.restart(restart_ctx, drain_hours).await });
+
+        format!("Restarting once the content scheduled in the next {} hours is published, I'll report back in the status channel.", drain_hours)
+    }
+
+    async fn restart(&self, ctx: Context, drain_hours: i64) {
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+
+        // The drain the user might have set is put back once the update is done, the new process picks it up
+        let mut bot_status = tx.load_bot_status().await;
+        let previous_drain_until = bot_status.drain_until.clone();
+        let drain_until = now_in_my_timezone(&user_settings) + Duration::hours(drain_hours);
+        bot_status.drain_until = drain_until.to_rfc3339();
+        tx.save_bot_status(&bot_status).await;
+
+        // The content doesn't go out while posting is disabled, and the queue could be stuck for other reasons, e.g. the bot being halted
+        let give_up_at = drain_until + Duration::from_std(RESTART_DRAIN_TIMEOUT).unwrap();
+        loop {
+            let remaining = tx.load_content_queue().await.iter().filter(|queued_content| DateTime::parse_from_rfc3339(&queued_content.will_post_at).unwrap() <= drain_until).count();
+            if remaining == 0 {
+                break;
+            }
+            let user_settings = tx.load_user_settings().await;
+            if !user_settings.can_post {
+                let msg_caption = format!("{}: posting is disabled, so the {} posts left to drain would never go out. The restart is cancelled {}", self.username, remaining, crab!("x_x"));
+                self.cancel_restart(&ctx, &mut tx, &previous_drain_until, msg_caption).await;
+                return;
+            }
+            if now_in_my_timezone(&user_settings) > give_up_at {
+                self.cancel_restart(&ctx, &mut tx, &previous_drain_until, format!("{}: {} posts are still left to drain, the restart is cancelled {}", self.username, remaining, crab!("x_x"))).await;
+                return;
+            }
+            tokio::time::sleep(RESTART_DRAIN_CHECK_INTERVAL).await;
+        }
+
+        // The other accounts of the process stop publishing too, the new process picks their queue up again
+        hold_publishing();
+        while publishes_in_flight() > 0 {
+            if now_in_my_timezone(&tx.load_user_settings().await) > give_up_at {
+                self.cancel_restart(&ctx, &mut tx, &previous_drain_until, format!("{}: the other accounts are still publishing, the restart is cancelled {}", self.username, crab!("x_x"))).await;
+                return;
+            }
+            tokio::time::sleep(RESTART_DRAIN_CHECK_INTERVAL).await;
+        }
+
+        // Left empty in the credentials when not used
+        let update_script = self.credentials.get("update_script").filter(|update_script| !update_script.is_empty()).cloned();
+        let staged_binary_path = self.credentials.get("staged_binary_path").filter(|staged_binary_path| !staged_binary_path.is_empty()).cloned();
+        let update_result = tokio::task::spawn_blocking(move || apply_update(update_script.as_ref(), staged_binary_path.as_ref())).await.unwrap_or_else(|e| Err(e.to_string()));
+
+        let update_summary = match update_result {
+            Ok(update_summary) => update_summary,
+            Err(e) => {
+                self.cancel_restart(&ctx, &mut tx, &previous_drain_until, format!("{}: the update failed, the bot keeps running the current version {}\n{}", self.username, crab!("x_x"), e)).await;
+                return;
+            }
+        };
+
+        let current_exe = match std::env::current_exe() {
+            Ok(current_exe) => current_exe,
+            Err(e) => {
+                self.cancel_restart(&ctx, &mut tx, &previous_drain_until, format!("{}: couldn't find the binary to restart, the bot keeps running: {}", self.username, e)).await;
+                return;
+            }
+        };
+
+        let mut bot_status = tx.load_bot_status().await;
+        bot_status.drain_until = previous_drain_until.clone();
+        tx.save_bot_status(&bot_status).await;
+        self.report_restart(&ctx, format!("{}: {}, restarting... the self-check of the new process follows {}", self.username, update_summary, crab!("^_^"))).await;
+
+        let mut command = Command::new(current_exe);
+        command.args(std::env::args().skip(1));
+        // The new process keeps the id of this one, so that it renews the leases of the accounts instead of waiting for them to time out
+        if let Some(instance_id) = current_instance_id() {
+            command.env(INSTANCE_ID_VAR, instance_id);
+        }
+        // Only returns if the new process couldn't be started
+        let e = command.exec();
+        self.cancel_restart(&ctx, &mut tx, &previous_drain_until, format!("{}: couldn't restart, the bot keeps running the current process: {}", self.username, e)).await;
+    }
+
+    /// Puts back the drain the user might have set and lets every account publish again
+    async fn cancel_restart(&self, ctx: &Context, tx: &mut DatabaseTransaction, previous_drain_until: &str, msg_caption: String) {
+        let mut bot_status = tx.load_bot_status().await;
+        bot_status.drain_until = previous_drain_until.to_string();
+        tx.save_bot_status(&bot_status).await;
+
+        release_publishing();
+        IS_RESTARTING.store(false, Ordering::SeqCst);
+        self.report_restart(ctx, msg_caption).await;
+    }
+
+    async fn report_restart(&self, ctx: &Context, msg_caption: String) {
+        tracing::info!("{}", msg_caption);
+        let msg = CreateMessage::new().content(msg_caption);
+        if let Err(e) = STATUS_CHANNEL_ID.send_message(&ctx.http, msg).await {
+            tracing::error!("Error sending the restart report: {:?}", e);
+        }
+    }
+}
+
+/// Runs the update script, or moves the staged binary over the running one, returns what was done
+fn apply_update(update_script: Option<&String>, staged_binary_path: Option<&String>) -> Result<String, String> {
+    if let Some(update_script) = update_script {
+        let output = Command::new("sh").arg("-c").arg(update_script).stdin(Stdio::null()).output().map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("The update script exited with {}: {}", output.status, stderr.trim()));
+        }
+        return Ok("update script done".to_string());
+    }
+
+    let Some(staged_binary_path) = staged_binary_path else {
+        return Ok("nothing to update".to_string());
+    };
+
+    // The running binary can be replaced on Linux, the process keeps the old one open until it restarts
+    let current_exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let previous_exe = current_exe.with_extension("previous");
+    std::fs::copy(&current_exe, &previous_exe).map_err(|e| format!("Couldn't back up the current binary: {}", e))?;
+    std::fs::rename(staged_binary_path, &current_exe).map_err(|e| format!("Couldn't swap in the staged binary {}: {}", staged_binary_path, e))?;
+    Ok(format!("swapped in the staged binary, the previous one is kept at {}", previous_exe.display()))
+}
@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serenity::all::{ChannelId, Context, CreateEmbed, CreateMessage, Mention, MessageId, RoleId};
+
+use crate::database::database::{BotStatus, ContentInfo, DatabaseTransaction, RejectionLogEntry, ScrapeRun, UserSettings};
+use crate::discord::bot::Handler;
+use crate::discord::operators::format_latency;
+use crate::discord::state::ContentStatus;
+use crate::discord::utils::{handle_msg_deletion, is_in_quiet_hours, notification_mention, send_message_with_retry};
+use crate::{crab, MOST_REJECTED_SOURCES_LEN, REVIEW_SLA, REVIEW_SLA_LIST_LEN, STATUS_CHANNEL_ID};
+
+impl Handler {
+    /// Pings the moderators when some pending content has been waiting for a review longer than the threshold,
+    /// the ping is deleted once everything overdue has been reviewed
+    pub(crate) async fn check_review_sla(&self, ctx: &Context, user_settings: &UserSettings, bot_status: &mut BotStatus, content_info_vec: &[ContentInfo], channel_id: ChannelId, now: DateTime<Utc>) {
+        let review_sla = self.review_sla();
+        let overdue_content: Vec<(&ContentInfo, Duration)> = pending_ages(content_info_vec, now).into_iter().filter(|(_, age)| *age >= review_sla).collect();
+
+        if overdue_content.is_empty() {
+            if bot_status.review_sla_message_id.get() != 1 {
+                let delete_msg_result = channel_id.delete_message(&ctx.http, bot_status.review_sla_message_id).await;
+                handle_msg_deletion(delete_msg_result);
+                bot_status.review_sla_message_id = MessageId::new(1);
+            }
+            return;
+        }
+
+        // Waits for the end of the quiet hours, a ping nobody hears doesn't get the content reviewed
+        if bot_status.review_sla_message_id.get() != 1 || is_in_quiet_hours(user_settings) {
+            return;
+        }
+
+        let mention = match self.credentials.get("moderator_role_id").and_then(|role_id| role_id.parse::<u64>().ok()) {
+            Some(role_id) => Mention::Role(RoleId::new(role_id)).to_string(),
+            None => notification_mention(user_settings, false),
+        };
+
+        let mut msg_caption = format!("{mention} {} posts have been waiting for a review for more than {}, the oldest ones are: {}", overdue_content.len(), format_latency(review_sla.num_seconds()), crab!("•̀ - •́"));
+        for (content_info, age) in overdue_content.iter().take(REVIEW_SLA_LIST_LEN) {
+            msg_caption.push_str(&format!("\n{} by {}, waiting for {} {}", content_info.original_shortcode, content_info.original_author, format_latency(age.num_seconds()), content_info.message_id.link(channel_id, None)));
+        }
+
+        let msg = CreateMessage::new().content(msg_caption);
+        bot_status.review_sla_message_id = send_message_with_retry(ctx, channel_id, msg).await.id;
+    }
+
+    /// Sends the summary of the previous day to the status channel, once per day
+    pub(crate) async fn send_daily_digest_if_needed(&self, ctx: &Context, tx: &mut DatabaseTransaction, bot_status: &mut BotStatus, content_info_vec: &[ContentInfo], now: DateTime<Utc>) {
+        let today = now.format("%Y-%m-%d").to_string();
+        if bot_status.last_digest_day == today {
+            return;
+        }
+
+        // The first day the bot runs has nothing to sum up yet
+        if bot_status.last_digest_day.is_empty() {
+            bot_status.last_digest_day = today;
+            return;
+        }
+
+        let yesterday = (now - Duration::days(1)).format("%Y-%m-%d").to_string();
+        let rollup = tx.load_daily_rollups_since(&yesterday).await.into_iter().find(|rollup| rollup.day == yesterday);
+
+        let mut msg_caption = format!("Daily digest of {} for {}:", self.username, yesterday);
+        match rollup {
+            Some(rollup) => msg_caption.push_str(&format!(
+                "\n{} scraped, {} published, {} rejected, {} failed, {} duplicates",
+                rollup.scraped, rollup.published, rollup.rejected, rollup.failed, rollup.duplicates
+            )),
+            None => msg_caption.push_str("\nNothing happened."),
+        }
+
+        let rejection_log: Vec<RejectionLogEntry> = tx.load_rejection_log_since(&yesterday).await.into_iter().filter(|entry| entry.day == yesterday).collect();
+        msg_caption.push_str(&rejection_summary(&rejection_log));
+
+        let mut pending_ages: Vec<i64> = pending_ages(content_info_vec, now).into_iter().map(|(_, age)| age.num_seconds()).collect();
+        if pending_ages.is_empty() {
+            msg_caption.push_str("\nNo content is waiting for a review.");
+        } else {
+            pending_ages.sort_unstable();
+            msg_caption.push_str(&format!(
+                "\n{} pending, waiting for {} (median), {} (90th percentile), {} (oldest)",
+                pending_ages.len(),
+                format_latency(percentile(&pending_ages, 50)),
+                format_latency(percentile(&pending_ages, 90)),
+                format_latency(*pending_ages.last().unwrap())
+            ));
+        }
+
+        let scrape_runs: Vec<ScrapeRun> = tx.load_scrape_runs_since(&yesterday).await.into_iter().filter(|scrape_run| scrape_run.started_at.starts_with(&yesterday)).collect();
+
+        let mut msg = CreateMessage::new().content(msg_caption);
+        if !scrape_runs.is_empty() {
+            msg = msg.embed(scrape_runs_embed(&scrape_runs));
+        }
+        send_message_with_retry(ctx, STATUS_CHANNEL_ID, msg).await;
+        bot_status.last_digest_day = today;
+    }
+
+    /// How long the content can wait for a review before the moderators are pinged
+    fn review_sla(&self) -> Duration {
+        let minutes = self.credentials.get("review_sla_minutes").and_then(|minutes| minutes.parse::<i64>().ok()).unwrap_or(REVIEW_SLA.as_secs() as i64 / 60);
+        Duration::minutes(minutes)
+    }
+}
+
+/// How long each pending content has been waiting for a review, the oldest first
+fn pending_ages(content_info_vec: &[ContentInfo], now: DateTime<Utc>) -> Vec<(&ContentInfo, Duration)> {
+    let mut pending_ages: Vec<(&ContentInfo, Duration)> = content_info_vec
+        .iter()
+        .filter(|content_info| matches!(content_info.status, ContentStatus::Pending { .. }))
+        .filter_map(|content_info| DateTime::parse_from_rfc3339(&content_info.added_at).ok().map(|added_at| (content_info, now - added_at.with_timezone(&Utc))))
+        .collect();
+    pending_ages.sort_by_key(|(_, age)| std::cmp::Reverse(*age));
+    pending_ages
+}
+
+/// Sums up why the content was rejected and which source accounts it came from the most, empty when nothing was rejected
+pub(crate) fn rejection_summary(rejection_log: &[RejectionLogEntry]) -> String {
+    if rejection_log.is_empty() {
+        return "".to_string();
+    }
+
+    let mut reason_counts: HashMap<&str, i32> = HashMap::new();
+    let mut source_counts: HashMap<&str, i32> = HashMap::new();
+    for entry in rejection_log {
+        *reason_counts.entry(&entry.reason).or_default() += entry.count;
+        *source_counts.entry(&entry.original_author).or_default() += entry.count;
+    }
+
+    let mut reason_counts: Vec<(&str, i32)> = reason_counts.into_iter().collect();
+    reason_counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let mut source_counts: Vec<(&str, i32)> = source_counts.into_iter().collect();
+    source_counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+    let reasons: Vec<String> = reason_counts.iter().map(|(reason, count)| format!("{} {}", reason.replace('_', " "), count)).collect();
+    let sources: Vec<String> = source_counts.iter().take(MOST_REJECTED_SOURCES_LEN).map(|(source, count)| format!("{} {}", source, count)).collect();
+    format!("\nRejected for: {}\nMost rejected sources: {}", reasons.join(", "), sources.join(", "))
+}
+
+/// Sums up the iterations of the scraper recorded in the scrape_runs table
+fn scrape_runs_embed(scrape_runs: &[ScrapeRun]) -> CreateEmbed {
+    let mut skip_counts: HashMap<&str, i32> = HashMap::new();
+    for (reason, count) in scrape_runs.iter().flat_map(|scrape_run| scrape_run.skips.split(',')).filter_map(|skip| skip.split_once(':')) {
+        *skip_counts.entry(reason).or_default() += count.parse::<i32>().unwrap_or_default();
+    }
+    let mut skip_counts: Vec<(&str, i32)> = skip_counts.into_iter().collect();
+    skip_counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+    let skips: Vec<String> = skip_counts.iter().map(|(reason, count)| format!("{} {}", reason.replace('_', " "), count)).collect();
+
+    let errors: i32 = scrape_runs.iter().map(|scrape_run| scrape_run.errors).sum();
+    let errors = match scrape_runs.iter().rev().map(|scrape_run| scrape_run.last_error.as_str()).find(|last_error| !last_error.is_empty()) {
+        // The value of a field is limited to 1024 characters
+        Some(last_error) => format!("{}, the last one: {}", errors, last_error.chars().take(900).collect::<String>()),
+        None => errors.to_string(),
+    };
+
+    CreateEmbed::new()
+        .title("Scraper activity")
+        .field("Iterations", scrape_runs.len().to_string(), true)
+        .field("Accounts fetched", scrape_runs.iter().map(|scrape_run| scrape_run.accounts_fetched).sum::<i32>().to_string(), true)
+        .field("Posts seen", scrape_runs.iter().map(|scrape_run| scrape_run.posts_seen).sum::<i32>().to_string(), true)
+        .field("Downloaded", scrape_runs.iter().map(|scrape_run| scrape_run.posts_downloaded).sum::<i32>().to_string(), true)
+        .field("Skipped", if skips.is_empty() { "Nothing".to_string() } else { skips.join(", ") }, false)
+        .field("Errors", errors, false)
+}
+
+/// Nearest-rank percentile of the sorted values
+fn percentile(sorted_values: &[i64], percentile: usize) -> i64 {
+    let rank = (percentile * sorted_values.len()).div_ceil(100).max(1);
+    sorted_values[rank - 1]
+}
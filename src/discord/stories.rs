@@ -0,0 +1,86 @@
+use chrono::DateTime;
+use serenity::all::{ChannelId, Context, CreateMessage, EditMessage, MessageId};
+
+use crate::database::database::{DatabaseTransaction, QueuedStory, UserSettings};
+use crate::discord::bot::Handler;
+use crate::discord::utils::{countdown_until_expiration, get_story_buttons, handle_msg_deletion, now_in_my_timezone, send_message_with_retry};
+use crate::s3::helper::delete_from_s3;
+
+impl Handler {
+    /// Shows the stories waiting for a review, they get a card of their own since they have no caption
+    ///
+    /// The stories are dropped once they expire on the source account, whether they were reshared or not
+    pub(crate) async fn process_story_queue(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, channel_id: ChannelId) {
+        let now = now_in_my_timezone(user_settings);
+
+        for mut story in tx.load_story_queue().await {
+            if DateTime::parse_from_rfc3339(&story.expires_at).unwrap() < now {
+                if story.message_id.get() != 1 {
+                    let delete_msg_result = channel_id.delete_message(&ctx.http, story.message_id).await;
+                    handle_msg_deletion(delete_msg_result);
+                }
+                if let Err(e) = delete_from_s3(&self.bucket, format!("{}/story_{}.mp4", self.username, story.story_id)).await {
+                    tracing::warn!("Couldn't delete the expired story {} from S3: {}", story.story_id, e);
+                }
+                tx.remove_story(&story.story_id).await;
+                continue;
+            }
+
+            match story.status.as_str() {
+                "pending" if story.message_id.get() == 1 => {
+                    let msg_caption = self.story_caption(user_settings, &story, "").await;
+                    let msg = CreateMessage::new().content(msg_caption).components(get_story_buttons(&self.ui_definitions));
+                    story.message_id = send_message_with_retry(ctx, channel_id, msg).await.id;
+                    tx.save_story(&story).await;
+                }
+                // The final state is shown once, then the card is left to the archival
+                "published" | "failed" if story.message_id.get() != 1 => {
+                    let status_caption = if story.status == "published" { "✅  Reshared on the story" } else { "⚠️  Failed to reshare" };
+                    let msg_caption = self.story_caption(user_settings, &story, status_caption).await;
+                    if let Err(e) = channel_id.edit_message(&ctx.http, story.message_id, EditMessage::new().content(msg_caption).components(vec![])).await {
+                        tracing::warn!("Couldn't update the card of the story {}: {}", story.story_id, e);
+                    }
+                    story.message_id = MessageId::new(1);
+                    tx.save_story(&story).await;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub(crate) async fn interaction_story(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, channel_id: ChannelId, interaction_type: &str, mut story: QueuedStory) {
+        match interaction_type {
+            "accept_story" => {
+                story.status = "approved".to_string();
+                let msg_caption = self.story_caption(user_settings, &story, "⏳  Resharing...").await;
+                if let Err(e) = channel_id.edit_message(&ctx.http, story.message_id, EditMessage::new().content(msg_caption).components(vec![])).await {
+                    tracing::warn!("Couldn't update the card of the story {}: {}", story.story_id, e);
+                }
+            }
+            "reject_story" => {
+                // The story is kept until it expires, so that it isn't scraped again
+                story.status = "rejected".to_string();
+                let delete_msg_result = channel_id.delete_message(&ctx.http, story.message_id).await;
+                handle_msg_deletion(delete_msg_result);
+                story.message_id = MessageId::new(1);
+            }
+            _ => {
+                tracing::error!("Unhandled story interaction type: {:?}", interaction_type);
+                return;
+            }
+        }
+        tx.save_story(&story).await;
+    }
+
+    async fn story_caption(&self, user_settings: &UserSettings, story: &QueuedStory, status_caption: &str) -> String {
+        let story_caption = self.ui_definitions.labels.get("story_caption").unwrap();
+        let expires_at = DateTime::parse_from_rfc3339(&story.expires_at).unwrap().to_utc();
+        let countdown = countdown_until_expiration(user_settings, expires_at).await;
+
+        let mut caption = format!("{story_caption} from {}\nExpires in {countdown}\n{}", story.original_author, story.url);
+        if !status_caption.is_empty() {
+            caption = format!("{status_caption}\n{caption}");
+        }
+        caption
+    }
+}
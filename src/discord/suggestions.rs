@@ -0,0 +1,95 @@
+use serenity::all::{ChannelId, Context, CreateMessage, EditMessage, MessageId};
+
+use crate::database::database::{DatabaseTransaction, SourceSuggestion};
+use crate::discord::bot::Handler;
+use crate::discord::utils::{get_source_suggestion_buttons, handle_msg_deletion, send_message_with_retry};
+
+const ACCOUNTS_TO_SCRAPE_PATH: &str = "config/accounts_to_scrape.yaml";
+
+impl Handler {
+    /// Shows the source accounts suggested by the scraper, each one gets a card to add it to accounts_to_scrape.yaml in one click
+    pub(crate) async fn process_source_suggestions(&self, ctx: &Context, tx: &mut DatabaseTransaction, channel_id: ChannelId) {
+        for mut suggestion in tx.load_source_suggestions().await.into_iter().filter(|suggestion| suggestion.status == "pending" && suggestion.message_id.get() == 1) {
+            let msg = CreateMessage::new().content(self.source_suggestion_caption(&suggestion, "")).components(get_source_suggestion_buttons(&self.ui_definitions));
+            suggestion.message_id = send_message_with_retry(ctx, channel_id, msg).await.id;
+            tx.save_source_suggestion(&suggestion).await;
+        }
+    }
+
+    pub(crate) async fn interaction_source_suggestion(&self, ctx: &Context, tx: &mut DatabaseTransaction, channel_id: ChannelId, interaction_type: &str, mut suggestion: SourceSuggestion) {
+        match interaction_type {
+            "add_source_suggestion" => {
+                if let Err(e) = add_to_accounts_to_scrape(&self.username, &suggestion).await {
+                    // The card keeps its buttons, so that it can be tried again
+                    let msg_caption = self.source_suggestion_caption(&suggestion, &format!("⚠️  Couldn't add it to accounts_to_scrape.yaml: {}", e));
+                    if let Err(e) = channel_id.edit_message(&ctx.http, suggestion.message_id, EditMessage::new().content(msg_caption)).await {
+                        tracing::warn!("Couldn't update the card of the suggested source {}: {}", suggestion.profile, e);
+                    }
+                    return;
+                }
+
+                // The final state is shown once, then the card is left to the archival
+                suggestion.status = "added".to_string();
+                let msg_caption = self.source_suggestion_caption(&suggestion, "➕  Added to accounts_to_scrape.yaml, it's scraped once the scraper restarts");
+                if let Err(e) = channel_id.edit_message(&ctx.http, suggestion.message_id, EditMessage::new().content(msg_caption).components(vec![])).await {
+                    tracing::warn!("Couldn't update the card of the suggested source {}: {}", suggestion.profile, e);
+                }
+                suggestion.message_id = MessageId::new(1);
+            }
+            "dismiss_source_suggestion" => {
+                // The suggestion is kept, so that the account isn't suggested again
+                suggestion.status = "dismissed".to_string();
+                let delete_msg_result = channel_id.delete_message(&ctx.http, suggestion.message_id).await;
+                handle_msg_deletion(delete_msg_result);
+                suggestion.message_id = MessageId::new(1);
+            }
+            _ => {
+                tracing::error!("Unhandled source suggestion interaction type: {:?}", interaction_type);
+                return;
+            }
+        }
+        tx.save_source_suggestion(&suggestion).await;
+    }
+
+    fn source_suggestion_caption(&self, suggestion: &SourceSuggestion, status_caption: &str) -> String {
+        let source_suggestion_caption = self.ui_definitions.labels.get("source_suggestion_caption").unwrap();
+
+        let mut caption = format!(
+            "{source_suggestion_caption}: {}\n{} followers, {} like {}\nhttps://www.instagram.com/{}/",
+            suggestion.profile, suggestion.followers, suggestion.category, suggestion.found_through, suggestion.profile
+        );
+        if !status_caption.is_empty() {
+            caption = format!("{status_caption}\n{caption}");
+        }
+        caption
+    }
+}
+
+/// The account is added as the first entry under the given one, the file is edited as text so that its comments and layout are kept
+async fn add_to_accounts_to_scrape(username: &str, suggestion: &SourceSuggestion) -> anyhow::Result<()> {
+    let contents = tokio::fs::read_to_string(ACCOUNTS_TO_SCRAPE_PATH).await?;
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+
+    let is_account_line = |line: &String| line.split('#').next().unwrap_or_default().trim_end() == format!("{}:", username);
+    match lines.iter().position(is_account_line) {
+        Some(account_line) => {
+            // The entries that are already there tell how the file is indented
+            let indentation = lines[account_line + 1..]
+                .iter()
+                .find(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+                .map(|line| &line[..line.len() - line.trim_start().len()])
+                .filter(|indentation| !indentation.is_empty())
+                .unwrap_or("  ")
+                .to_string();
+            lines.insert(account_line + 1, format!("{}{}: {}", indentation, suggestion.profile, suggestion.category));
+        }
+        None => {
+            lines.push(String::new());
+            lines.push(format!("{}:", username));
+            lines.push(format!("  {}: {}", suggestion.profile, suggestion.category));
+        }
+    }
+
+    tokio::fs::write(ACCOUNTS_TO_SCRAPE_PATH, lines.join("\n") + "\n").await?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use serenity::all::{ChannelId, Context, CreateThread, EditThread, MessageId};
+
+use crate::database::database::{AuditEntry, ContentInfo, DatabaseTransaction, FrontendMessage, UserSettings};
+use crate::discord::bot::Handler;
+use crate::discord::utils::now_in_my_timezone;
+use crate::profile::config_profile::CONFIG_PROFILE;
+
+/// Name under which the discussion threads are stored in the frontend_messages table
+pub(crate) const THREAD_FRONTEND_NAME: &str = "discord_thread";
+
+impl Handler {
+    fn are_content_threads_enabled(&self) -> bool {
+        self.credentials.get("content_threads").map(|value| value == "true").unwrap_or(false)
+    }
+
+    /// Opens a discussion thread under the card of the content, unless one already exists
+    pub async fn open_content_thread(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, content_info: &ContentInfo, channel_id: ChannelId, message_id: MessageId) {
+        if !self.are_content_threads_enabled() {
+            return;
+        }
+
+        if tx.get_frontend_message(THREAD_FRONTEND_NAME, &content_info.original_shortcode).await.is_some() {
+            return;
+        }
+
+        let mut thread_name = format!("{} by @{}", content_info.original_shortcode, content_info.original_author);
+        thread_name.truncate(100);
+
+        let thread = match channel_id.create_thread_from_message(&ctx.http, message_id, CreateThread::new(thread_name)).await {
+            Ok(thread) => thread,
+            Err(e) => {
+                tracing::warn!("Couldn't open the discussion thread for {}: {:?}", content_info.original_shortcode, e);
+                return;
+            }
+        };
+
+        let frontend_message = FrontendMessage {
+            username: content_info.username.clone(),
+            frontend: THREAD_FRONTEND_NAME.to_string(),
+            channel_id: thread.id,
+            message_id,
+            original_shortcode: content_info.original_shortcode.clone(),
+        };
+        tx.save_frontend_message(&frontend_message).await;
+
+        save_thread_audit_entry(user_settings, tx, content_info, "thread_opened", thread.id).await;
+    }
+
+    /// Archives the discussion thread of the content, if there is one
+    pub async fn set_content_thread_archived(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, content_info: &ContentInfo, archived: bool) {
+        let thread_message = match tx.get_frontend_message(THREAD_FRONTEND_NAME, &content_info.original_shortcode).await {
+            Some(thread_message) => thread_message,
+            None => return,
+        };
+
+        let edited_thread = EditThread::new().archived(archived).locked(archived);
+        match thread_message.channel_id.edit_thread(&ctx.http, edited_thread).await {
+            Ok(_) => {
+                let action = if archived { "thread_archived" } else { "thread_reopened" };
+                save_thread_audit_entry(user_settings, tx, content_info, action, thread_message.channel_id).await;
+            }
+            Err(e) => {
+                tracing::warn!("Couldn't update the discussion thread for {}: {:?}", content_info.original_shortcode, e);
+            }
+        }
+    }
+}
+
+async fn save_thread_audit_entry(user_settings: &UserSettings, tx: &mut DatabaseTransaction, content_info: &ContentInfo, action: &str, thread_id: ChannelId) {
+    let audit_entry = AuditEntry {
+        username: content_info.username.clone(),
+        original_shortcode: content_info.original_shortcode.clone(),
+        action: action.to_string(),
+        operator: "bot".to_string(),
+        detail: format!("https://discord.com/channels/{}/{}", CONFIG_PROFILE.guild_id(), thread_id),
+        created_at: now_in_my_timezone(user_settings).to_rfc3339(),
+        review_latency: 0,
+    };
+    tx.save_audit_entry(&audit_entry).await;
+}
@@ -113,7 +113,7 @@ impl ProcessableContent for ContentInfo {
 
     async fn generate_buttons(&self, ui_definitions: &UiDefinitions) -> Vec<CreateActionRow> {
         match self.status {
-            ContentStatus::Pending { .. } => get_pending_buttons(ui_definitions),
+            ContentStatus::Pending { .. } => get_pending_buttons(ui_definitions, self),
             ContentStatus::Failed { .. } => get_failed_buttons(ui_definitions),
             ContentStatus::Published { .. } => get_published_buttons(ui_definitions),
             ContentStatus::Queued { .. } => get_queued_buttons(ui_definitions),
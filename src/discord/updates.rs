@@ -0,0 +1,80 @@
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+use serenity::all::{Context, CreateMessage};
+use tokio::time::sleep;
+
+use crate::discord::bot::Handler;
+use crate::discord::utils::now_in_my_timezone;
+use crate::{DEFAULT_RELEASE_FEED_URL, RELEASE_NOTES_EXCERPT_LEN, STATUS_CHANNEL_ID, UPDATE_CHECK_INTERVAL};
+
+/// The latest release, as returned by the GitHub releases API
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    body: Option<String>,
+}
+
+impl Handler {
+    /// Checks the release feed once a day, and lets the status channel know when a newer version is out
+    ///
+    /// The feed can be overridden in the credentials with "release_feed_url", the status channel is shared so only one account announces each version
+    pub async fn run_update_check_loop(&self, ctx: Context) {
+        let release_feed_url = self.credentials.get("release_feed_url").cloned().unwrap_or_else(|| DEFAULT_RELEASE_FEED_URL.to_string());
+        loop {
+            match fetch_latest_release(&release_feed_url).await {
+                Ok(release) => self.announce_release_if_newer(&ctx, &release).await,
+                Err(e) => tracing::warn!("Couldn't check for a newer version at {}: {}", release_feed_url, e),
+            }
+
+            sleep(UPDATE_CHECK_INTERVAL).await;
+        }
+    }
+
+    async fn announce_release_if_newer(&self, ctx: &Context, release: &Release) {
+        let version = release.tag_name.trim_start_matches('v');
+        if !is_newer_version(version, env!("CARGO_PKG_VERSION")) {
+            return;
+        }
+
+        let mut tx = self.database.begin_transaction().await;
+        let notified_at = now_in_my_timezone(&tx.load_user_settings().await).to_rfc3339();
+        if !tx.claim_release_notification(version, &notified_at).await {
+            return;
+        }
+
+        let mut msg_caption = format!("A newer version is out: {} (running {})\n{}", version, env!("CARGO_PKG_VERSION"), release.html_url);
+        let release_notes = release.body.as_deref().unwrap_or_default().trim();
+        if !release_notes.is_empty() {
+            let excerpt: String = release_notes.chars().take(RELEASE_NOTES_EXCERPT_LEN).collect();
+            let ellipsis = if excerpt.len() < release_notes.len() { "\n..." } else { "" };
+            msg_caption.push_str(&format!("\n\n{}{}", excerpt, ellipsis));
+        }
+
+        let msg = CreateMessage::new().content(msg_caption);
+        if let Err(e) = STATUS_CHANNEL_ID.send_message(&ctx.http, msg).await {
+            tracing::error!("Error sending the update notification: {:?}", e);
+        }
+    }
+}
+
+async fn fetch_latest_release(release_feed_url: &str) -> anyhow::Result<Release> {
+    // The GitHub API turns down the requests without a user agent
+    let response = reqwest::Client::new().get(release_feed_url).header(USER_AGENT, env!("CARGO_PKG_NAME")).send().await?.error_for_status()?.text().await?;
+    Ok(serde_json::from_str(&response)?)
+}
+
+/// Compares the numeric parts of the versions, a pre-release suffix like "-beta" is ignored
+fn is_newer_version(candidate: &str, current: &str) -> bool {
+    numeric_parts(candidate) > numeric_parts(current)
+}
+
+/// "1.2.0-beta" becomes [1, 2], so that "1.2" and "1.2.0" are the same version
+fn numeric_parts(version: &str) -> Vec<u64> {
+    let mut parts: Vec<u64> = version.split(['-', '+']).next().unwrap_or_default().split('.').map(|part| part.parse::<u64>().unwrap_or_default()).collect();
+    while parts.last() == Some(&0) {
+        parts.pop();
+    }
+    parts
+}
@@ -1,21 +1,60 @@
 use std::sync::Arc;
 
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, Timelike, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
-use serenity::all::{ChannelId, Context, CreateActionRow, CreateButton, CreateMessage, Http, Message};
+use serenity::all::{ActionRowComponent, ChannelId, Context, CreateActionRow, CreateButton, CreateMessage, Http, Mention, Message, ModalInteraction};
 use serenity::prelude::SerenityError;
 
-use crate::database::database::{BotStatus, ContentInfo, DatabaseTransaction, QueuedContent, UserSettings, DEFAULT_FAILURE_EXPIRATION, DEFAULT_POSTED_EXPIRATION};
+use crate::database::database::{BotStatus, ContentInfo, DailyRollup, DatabaseTransaction, QueuedContent, RejectionReason, UserSettings, DEFAULT_FAILURE_EXPIRATION, DEFAULT_POSTED_EXPIRATION};
 use crate::discord::bot::UiDefinitions;
+use crate::discord::challenge::CHALLENGE_CODE_BUTTON;
+use crate::discord::interactions::REJECTION_REASON_PREFIX;
 use crate::discord::state::ContentStatus;
-use crate::{POSTED_CHANNEL_ID, S3_EXPIRATION_TIME};
+use crate::maintenance::windows::{current_maintenance_window, next_maintenance_window};
+use crate::scraper_poster::compilation::is_compilation_shortcode;
+use crate::{MY_DISCORD_ID, POSTED_CHANNEL_ID, S3_EXPIRATION_TIME};
 
 pub async fn generate_full_caption(user_settings: &UserSettings, tx: &mut DatabaseTransaction, ui_definitions: &UiDefinitions, content_info: &ContentInfo) -> String {
     // let upper_spacer = "^~^~^~^~^~^~^~^~^~^~^~^~^~^~^~^~^~^~^~^~^~^";
     // let upper_spacer = "## nununununununununununununununu";
     let upper_spacer = "### ->->->->->->->->->->->->->->->->->->->->->->";
-    let base_caption = format!("{upper_spacer}\n‎\n{}\n‎\n(from @{})\n‎\n{}\n", content_info.caption, content_info.original_author, content_info.hashtags);
+    let original_posted_caption = original_posted_caption(user_settings, ui_definitions, content_info);
+    let source_metrics_caption = source_metrics_caption(ui_definitions, content_info);
+    let mut base_caption = format!("{upper_spacer}\n‎\n{}\n‎\n(from @{}){}{}\n‎\n{}\n", content_info.caption, content_info.original_author, original_posted_caption, source_metrics_caption, content_info.hashtags);
+    if !content_info.alt_text.is_empty() {
+        let alt_text_caption = ui_definitions.labels.get("alt_text_caption").unwrap();
+        base_caption.push_str(&format!("‎\n{}: {}\n", alt_text_caption, content_info.alt_text));
+    }
+    if content_info.is_paid_partnership {
+        let paid_partnership_caption = ui_definitions.labels.get("paid_partnership_caption").unwrap();
+        base_caption.push_str(&format!("‎\n{}\n", paid_partnership_caption));
+    }
+    if content_info.is_trial_reel {
+        let trial_reel_caption = ui_definitions.labels.get("trial_reel_caption").unwrap();
+        base_caption.push_str(&format!("‎\n{}\n", trial_reel_caption));
+    }
+    if tx.get_author_permission(&content_info.original_author).await.is_some() {
+        let permission_caption = ui_definitions.labels.get("permission_caption").unwrap();
+        base_caption.push_str(&format!("‎\n{}\n", permission_caption));
+    }
+    if is_compilation_shortcode(&content_info.original_shortcode) {
+        let compilation_caption = ui_definitions.labels.get("compilation_caption").unwrap();
+        base_caption.push_str(&format!("‎\n{}\n", compilation_caption));
+    }
+    if content_info.source_deleted {
+        let source_deleted_caption = ui_definitions.labels.get("source_deleted_caption").unwrap();
+        base_caption.push_str(&format!("‎\n{}\n", source_deleted_caption));
+    }
+    if !content_info.caption_language.is_empty() {
+        let label = if content_info.is_caption_translated { "translated_caption" } else { "caption_language_caption" };
+        let caption_language_caption = ui_definitions.labels.get(label).unwrap();
+        base_caption.push_str(&format!("‎\n{} {}\n", caption_language_caption, content_info.caption_language));
+    }
+    if !content_info.profanity.is_empty() {
+        let profanity_caption = ui_definitions.labels.get("profanity_caption").unwrap();
+        base_caption.push_str(&format!("‎\n{}: {}\n", profanity_caption, content_info.profanity));
+    }
 
     match content_info.status {
         ContentStatus::Queued { .. } => {
@@ -27,6 +66,12 @@ pub async fn generate_full_caption(user_settings: &UserSettings, tx: &mut Databa
                     format!("{base_caption}\n{}\n‎\nPosting now...\n\n{}‎", queued_caption, formatted_will_post_at)
                 }
                 Some(queued_content) => {
+                    let mut base_caption = base_caption;
+                    if !queued_content.location_name.is_empty() {
+                        let location_caption = ui_definitions.labels.get("location_caption").unwrap();
+                        base_caption.push_str(&format!("‎\n{}: {}\n", location_caption, queued_content.location_name));
+                    }
+
                     let will_post_at = DateTime::parse_from_rfc3339(&queued_content.will_post_at).unwrap();
                     formatted_will_post_at = will_post_at.format("%Y-%m-%d %H:%M:%S").to_string();
 
@@ -40,7 +85,13 @@ pub async fn generate_full_caption(user_settings: &UserSettings, tx: &mut Databa
             }
         }
         ContentStatus::Pending { .. } => {
-            format!("{base_caption}‎")
+            if user_settings.approvals_required > 1 {
+                let approvals_caption = ui_definitions.labels.get("approvals_caption").unwrap();
+                let approvals = tx.count_content_votes(&content_info.original_shortcode).await;
+                format!("{base_caption}\n{}: {}/{}\n‎", approvals_caption, approvals, user_settings.approvals_required)
+            } else {
+                format!("{base_caption}‎")
+            }
         }
         ContentStatus::Rejected { .. } => {
             let rejected_caption = ui_definitions.labels.get("rejected_caption").unwrap();
@@ -50,11 +101,17 @@ pub async fn generate_full_caption(user_settings: &UserSettings, tx: &mut Databa
                     return format!("{base_caption}\n{}\n‎", rejected_caption);
                 }
             };
-            let will_expire_at = DateTime::parse_from_rfc3339(&rejected_content.rejected_at).unwrap() + Duration::seconds((user_settings.rejected_content_lifespan * 60) as i64);
+            let will_expire_at = DateTime::parse_from_rfc3339(&rejected_content.rejected_at).unwrap() + user_settings.rejected_content_lifespan();
+
+            let removal_caption = removal_countdown(user_settings, will_expire_at.with_timezone(&Utc));
 
-            let countdown_caption = countdown_until_expiration(user_settings, will_expire_at.with_timezone(&Utc)).await;
+            let reason_caption = match (rejected_content.reason.as_str(), rejected_content.reason_note.as_str()) {
+                ("", _) => "".to_string(),
+                (reason, "") => format!(" ({})", reason.replace('_', " ")),
+                (reason, reason_note) => format!(" ({}: {})", reason.replace('_', " "), reason_note),
+            };
 
-            format!("{base_caption}\n{}\n{}\n‎", rejected_caption, countdown_caption)
+            format!("{base_caption}\n{}{}\n{}, until then it can be undone\n‎", rejected_caption, reason_caption, removal_caption)
         }
         ContentStatus::Published { .. } => {
             let published_caption = ui_definitions.labels.get("published_caption").unwrap();
@@ -62,17 +119,25 @@ pub async fn generate_full_caption(user_settings: &UserSettings, tx: &mut Databa
             let published_at = DateTime::parse_from_rfc3339(&published_content.published_at).unwrap().format("%Y-%m-%d %H:%M:%S").to_string();
             let will_expire_at = DateTime::parse_from_rfc3339(&published_content.published_at).unwrap() + DEFAULT_POSTED_EXPIRATION;
 
-            let countdown_caption = countdown_until_expiration(user_settings, will_expire_at.with_timezone(&Utc)).await;
+            let removal_caption = removal_countdown(user_settings, will_expire_at.with_timezone(&Utc));
+
+            let simulated_caption = if published_content.is_simulated { " (dry run, not actually posted)" } else { "" };
+            // Only worth showing when the caption had variations to pick from
+            let variant_caption = if published_content.caption_variant.is_empty() || published_content.caption_variant == published_content.caption {
+                "".to_string()
+            } else {
+                format!("\nPosted as: {}", published_content.caption_variant)
+            };
 
-            format!("{base_caption}\n{} at {}\n{}\n‎", published_caption, published_at, countdown_caption)
+            format!("{base_caption}\n{} at {}{}{}\n{}\n‎", published_caption, published_at, simulated_caption, variant_caption, removal_caption)
         }
         ContentStatus::Failed { .. } => {
             let failed_caption = ui_definitions.labels.get("failed_caption").unwrap();
             let failed_content = tx.get_failed_content_by_shortcode(&content_info.original_shortcode).await.unwrap();
             let will_expire_at = DateTime::parse_from_rfc3339(&failed_content.failed_at).unwrap() + DEFAULT_FAILURE_EXPIRATION;
 
-            let countdown_caption = countdown_until_expiration(user_settings, will_expire_at.with_timezone(&Utc)).await;
-            format!("{base_caption}\n{}\n{}\n‎", failed_caption, countdown_caption)
+            let removal_caption = removal_countdown(user_settings, will_expire_at.with_timezone(&Utc));
+            format!("{base_caption}\n{}\n{}\n‎", failed_caption, removal_caption)
         }
         _ => {
             panic!("Invalid status {}", content_info.status);
@@ -80,12 +145,39 @@ pub async fn generate_full_caption(user_settings: &UserSettings, tx: &mut Databa
     }
 }
 
-pub fn generate_bot_status_caption(user_settings: &UserSettings, bot_status: &BotStatus, content_mapping: Vec<ContentInfo>, content_queue: Vec<QueuedContent>, now: DateTime<Utc>) -> String {
+pub fn generate_bot_status_caption(user_settings: &UserSettings, bot_status: &BotStatus, content_mapping: Vec<ContentInfo>, content_queue: Vec<QueuedContent>, today_rollup: Option<DailyRollup>, now: DateTime<Utc>) -> String {
     let mut full_status_string = bot_status.status_message.clone();
     if !bot_status.is_discord_warmed_up {
         full_status_string = format!("{}, discord is still warming up...", full_status_string);
     }
 
+    if user_settings.dry_run {
+        full_status_string = format!("{}, in dry run mode", full_status_string);
+    }
+
+    if !user_settings.scraping_enabled {
+        full_status_string = format!("{}, scraping paused", full_status_string);
+    }
+
+    if !bot_status.drain_until.is_empty() {
+        let drain_until = DateTime::parse_from_rfc3339(&bot_status.drain_until).unwrap();
+        full_status_string = format!("{}, draining until {}", full_status_string, drain_until.format("%Y-%m-%d %H:%M:%S"));
+    }
+
+    let maintenance_string = match (current_maintenance_window(now), next_maintenance_window(now)) {
+        (Some(window), _) => format!("\n\nPaused for maintenance until {}", window.end.format("%Y-%m-%d %H:%M")),
+        (None, Some(window)) => {
+            let starts_in = window.pauses_at() - now;
+            format!("\n\nNext maintenance window starts on {}, loops pause in {} hours and {} minutes", window.start.format("%Y-%m-%d at %H:%M"), starts_in.num_hours(), starts_in.num_minutes() % 60)
+        }
+        (None, None) => "".to_string(),
+    };
+
+    if bot_status.status == 1 && bot_status.halt_escalation_level > 0 && !bot_status.halted_at.is_empty() {
+        let halted_for = now - DateTime::parse_from_rfc3339(&bot_status.halted_at).unwrap().with_timezone(&Utc);
+        full_status_string = format!("{} for {} minutes!", full_status_string, halted_for.num_minutes());
+    }
+
     //
     let content_mapping_len = content_mapping.len();
     let content_mapping_status_string;
@@ -120,11 +212,19 @@ pub fn generate_bot_status_caption(user_settings: &UserSettings, bot_status: &Bo
         content_queue_string = "Currently there are no queued posts! You should probably add some because, you know, you can :3".to_string();
     }
 
+    let today_string = match today_rollup {
+        Some(rollup) => format!(
+            "Today: {} scraped, {} published, {} rejected, {} failed, {} duplicates skipped",
+            rollup.scraped, rollup.published, rollup.rejected, rollup.failed, rollup.duplicates
+        ),
+        None => "Today: nothing happened yet".to_string(),
+    };
+
     let update_interval = user_settings.interface_update_interval as f64 / 1000.0;
     let update_interval_string = format!("Current interface update interval: {:.2}s", update_interval);
 
     let formatted_now = now.format("%Y-%m-%d %H:%M:%S").to_string();
-    let msg_caption = format!("Bot is {}\n\n{}\n\n{}\n\n{}\n\nLast updated at: {}", full_status_string, update_interval_string, content_mapping_status_string, content_queue_string, formatted_now);
+    let msg_caption = format!("Bot is {}{}\n\n{}\n\n{}\n\n{}\n\n{}\n\nLast updated at: {}", full_status_string, maintenance_string, update_interval_string, content_mapping_status_string, content_queue_string, today_string, formatted_now);
 
     msg_caption
 }
@@ -163,6 +263,98 @@ pub fn now_in_my_timezone(user_settings: &UserSettings) -> DateTime<Utc> {
     utc_now + timezone_offset
 }
 
+/// Whether the current hour falls inside the quiet hours of the user, the window can wrap around midnight
+pub fn is_in_quiet_hours(user_settings: &UserSettings) -> bool {
+    user_settings.quiet_hours_start != user_settings.quiet_hours_end && is_in_hour_window(user_settings, user_settings.quiet_hours_start, user_settings.quiet_hours_end)
+}
+
+/// Whether the scraper is allowed to reach the network at the current hour, it always is when no window is set
+pub fn is_in_scrape_window(user_settings: &UserSettings) -> bool {
+    user_settings.scrape_window_start == user_settings.scrape_window_end || is_in_hour_window(user_settings, user_settings.scrape_window_start, user_settings.scrape_window_end)
+}
+
+fn is_in_hour_window(user_settings: &UserSettings, start: i32, end: i32) -> bool {
+    let hour = now_in_my_timezone(user_settings).hour() as i32;
+    if start < end {
+        hour >= start && hour < end
+    } else {
+        hour >= start || hour < end
+    }
+}
+
+/// Returns the mention to put in a notification, or an empty string during quiet hours
+///
+/// Urgent notifications (e.g. halt alerts) can be configured to mention the user regardless of the quiet hours
+pub fn notification_mention(user_settings: &UserSettings, is_urgent: bool) -> String {
+    if is_in_quiet_hours(user_settings) && !(is_urgent && user_settings.halt_alerts_ignore_quiet_hours) {
+        "".to_string()
+    } else {
+        Mention::from(MY_DISCORD_ID).to_string()
+    }
+}
+
+/// Returns what was typed in the text input of the modal
+pub fn modal_input_value(interaction: &ModalInteraction, custom_id: &str) -> Option<String> {
+    interaction.data.components.iter().flat_map(|row| &row.components).find_map(|component| match component {
+        ActionRowComponent::InputText(input) if input.custom_id == custom_id => input.value.clone(),
+        _ => None,
+    })
+}
+
+/// Tells when the source account posted the content and how long ago, with a warning when it's past the max source age
+fn original_posted_caption(user_settings: &UserSettings, ui_definitions: &UiDefinitions, content_info: &ContentInfo) -> String {
+    let Ok(original_posted_at) = DateTime::parse_from_rfc3339(&content_info.original_posted_at) else {
+        return "".to_string();
+    };
+    let original_posted_label = ui_definitions.labels.get("original_posted_caption").unwrap();
+    let age_days = (now_in_my_timezone(user_settings) - original_posted_at.with_timezone(&Utc)).num_days();
+
+    let mut caption = format!("\n{} {}, {} days ago", original_posted_label, original_posted_at.format("%Y-%m-%d"), age_days);
+    if content_info.needs_age_override {
+        caption.push_str(&format!(" ⚠️ older than the limit of {} days", user_settings.max_source_age_days));
+    }
+    caption
+}
+
+/// How the source post was doing when it was scraped, left out when the source doesn't tell
+fn source_metrics_caption(ui_definitions: &UiDefinitions, content_info: &ContentInfo) -> String {
+    let mut metrics = Vec::new();
+    if content_info.source_views > 0 {
+        metrics.push(format!("{} views", compact_count(content_info.source_views)));
+    }
+    if content_info.source_likes > 0 {
+        metrics.push(format!("{} likes", compact_count(content_info.source_likes)));
+    }
+    // The name is easier to look up than the id, when the source tells it
+    if !content_info.audio_name.is_empty() {
+        metrics.push(format!("audio \"{}\"", content_info.audio_name));
+    } else if !content_info.audio_id.is_empty() {
+        metrics.push(format!("audio {}", content_info.audio_id));
+    }
+    if metrics.is_empty() {
+        return "".to_string();
+    }
+
+    let source_metrics_label = ui_definitions.labels.get("source_metrics_caption").unwrap();
+    format!("\n{} {}", source_metrics_label, metrics.join(", "))
+}
+
+/// 1234567 becomes "1.2M", so that the numbers of the source posts stay short on the cards
+fn compact_count(count: i64) -> String {
+    match count {
+        1_000_000.. => format!("{:.1}M", count as f64 / 1_000_000.0),
+        1_000.. => format!("{:.1}k", count as f64 / 1_000.0),
+        _ => count.to_string(),
+    }
+}
+
+/// Tells when the card will be removed, as a Discord timestamp so that the clients keep the countdown up to date on their own
+pub fn removal_countdown(user_settings: &UserSettings, expiration_datetime: DateTime<Utc>) -> String {
+    // The times are kept in the timezone of the user, Discord expects the actual UNIX time
+    let expiration_datetime = expiration_datetime - Duration::try_hours(user_settings.timezone_offset as i64).unwrap();
+    format!("Will disappear <t:{}:R>", expiration_datetime.timestamp())
+}
+
 pub async fn countdown_until_expiration(user_settings: &UserSettings, expiration_datetime: DateTime<Utc>) -> String {
     let now = now_in_my_timezone(user_settings);
     let duration_until_expiration = expiration_datetime.signed_duration_since(now);
@@ -189,25 +381,72 @@ pub fn get_edit_buttons(ui_definitions: &UiDefinitions) -> Vec<CreateActionRow>
     let go_back = ui_definitions.buttons.get("go_back").unwrap();
     let edit_caption = ui_definitions.buttons.get("edit_caption").unwrap();
     let edit_hashtags = ui_definitions.buttons.get("edit_hashtags").unwrap();
-    vec![CreateActionRow::Buttons(vec![CreateButton::new("go_back").label(go_back), CreateButton::new("edit_caption").label(edit_caption), CreateButton::new("edit_hashtags").label(edit_hashtags)])]
+    let edit_alt_text = ui_definitions.buttons.get("edit_alt_text").unwrap();
+    let caption_history = ui_definitions.buttons.get("caption_history").unwrap();
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new("go_back").label(go_back),
+        CreateButton::new("edit_caption").label(edit_caption),
+        CreateButton::new("edit_hashtags").label(edit_hashtags),
+        CreateButton::new("edit_alt_text").label(edit_alt_text),
+        CreateButton::new("caption_history").label(caption_history),
+    ])]
 }
 
-pub fn get_pending_buttons(ui_definitions: &UiDefinitions) -> Vec<CreateActionRow> {
-    let accept = ui_definitions.buttons.get("accept").unwrap();
+/// The content past the max source age or whose source post was deleted can only be accepted anyway, so that it isn't accepted by mistake
+pub fn get_pending_buttons(ui_definitions: &UiDefinitions, content_info: &ContentInfo) -> Vec<CreateActionRow> {
+    let accept_button = if content_info.needs_age_override || content_info.source_deleted {
+        CreateButton::new("accept_anyway").label(ui_definitions.buttons.get("accept_anyway").unwrap())
+    } else {
+        CreateButton::new("accept").label(ui_definitions.buttons.get("accept").unwrap())
+    };
     let reject = ui_definitions.buttons.get("reject").unwrap();
     let edit = ui_definitions.buttons.get("edit").unwrap();
-    vec![CreateActionRow::Buttons(vec![CreateButton::new("accept").label(accept), CreateButton::new("reject").label(reject), CreateButton::new("edit").label(edit)])]
+    let paid_partnership = ui_definitions.buttons.get("paid_partnership").unwrap();
+    let trial_reel = ui_definitions.buttons.get("trial_reel").unwrap();
+    vec![CreateActionRow::Buttons(vec![
+        accept_button,
+        CreateButton::new("reject").label(reject),
+        CreateButton::new("edit").label(edit),
+        CreateButton::new("toggle_paid_partnership").label(paid_partnership),
+        CreateButton::new("toggle_trial_reel").label(trial_reel),
+    ])]
+}
+
+pub fn get_story_buttons(ui_definitions: &UiDefinitions) -> Vec<CreateActionRow> {
+    let accept = ui_definitions.buttons.get("accept").unwrap();
+    let reject = ui_definitions.buttons.get("reject").unwrap();
+    vec![CreateActionRow::Buttons(vec![CreateButton::new("accept_story").label(accept), CreateButton::new("reject_story").label(reject)])]
+}
+
+pub fn get_source_suggestion_buttons(ui_definitions: &UiDefinitions) -> Vec<CreateActionRow> {
+    let add_source = ui_definitions.buttons.get("add_source").unwrap();
+    let dismiss = ui_definitions.buttons.get("dismiss").unwrap();
+    vec![CreateActionRow::Buttons(vec![CreateButton::new("add_source_suggestion").label(add_source), CreateButton::new("dismiss_source_suggestion").label(dismiss)])]
+}
+
+pub fn get_challenge_buttons(ui_definitions: &UiDefinitions) -> Vec<CreateActionRow> {
+    let enter_challenge_code = ui_definitions.buttons.get("enter_challenge_code").unwrap();
+    vec![CreateActionRow::Buttons(vec![CreateButton::new(CHALLENGE_CODE_BUTTON).label(enter_challenge_code)])]
 }
 
 pub fn get_queued_buttons(ui_definitions: &UiDefinitions) -> Vec<CreateActionRow> {
     let remove_from_queue = ui_definitions.buttons.get("remove_from_queue").unwrap();
     let edit_queued = ui_definitions.buttons.get("edit").unwrap();
     let publish_now = ui_definitions.buttons.get("publish_now").unwrap();
-    vec![CreateActionRow::Buttons(vec![
-        CreateButton::new("remove_from_queue").label(remove_from_queue),
-        CreateButton::new("edit_queued").label(edit_queued),
-        CreateButton::new("publish_now").label(publish_now),
-    ])]
+    let set_location = ui_definitions.buttons.get("set_location").unwrap();
+    let paid_partnership = ui_definitions.buttons.get("paid_partnership").unwrap();
+    let trial_reel = ui_definitions.buttons.get("trial_reel").unwrap();
+    // Discord allows at most 5 buttons per row
+    vec![
+        CreateActionRow::Buttons(vec![
+            CreateButton::new("remove_from_queue").label(remove_from_queue),
+            CreateButton::new("edit_queued").label(edit_queued),
+            CreateButton::new("publish_now").label(publish_now),
+            CreateButton::new("set_location").label(set_location),
+            CreateButton::new("toggle_paid_partnership").label(paid_partnership),
+        ]),
+        CreateActionRow::Buttons(vec![CreateButton::new("toggle_trial_reel").label(trial_reel)]),
+    ]
 }
 
 pub fn get_rejected_buttons(ui_definitions: &UiDefinitions) -> Vec<CreateActionRow> {
@@ -216,6 +455,20 @@ pub fn get_rejected_buttons(ui_definitions: &UiDefinitions) -> Vec<CreateActionR
     vec![CreateActionRow::Buttons(vec![CreateButton::new("undo_rejected").label(undo), CreateButton::new("remove_from_view").label(remove_from_view)])]
 }
 
+pub fn get_rejection_reason_buttons(ui_definitions: &UiDefinitions) -> Vec<CreateActionRow> {
+    let go_back = ui_definitions.buttons.get("go_back").unwrap();
+    let reason_buttons = RejectionReason::ALL
+        .iter()
+        .map(|reason| {
+            let custom_id = format!("{REJECTION_REASON_PREFIX}{}", reason.as_str());
+            let label = ui_definitions.buttons.get(&custom_id).unwrap();
+            CreateButton::new(custom_id).label(label)
+        })
+        .collect();
+    // Discord fits at most 5 buttons in a row
+    vec![CreateActionRow::Buttons(reason_buttons), CreateActionRow::Buttons(vec![CreateButton::new("go_back").label(go_back)])]
+}
+
 pub fn get_failed_buttons(ui_definitions: &UiDefinitions) -> Vec<CreateActionRow> {
     let remove_from_view = ui_definitions.buttons.get("remove_from_view").unwrap();
     vec![CreateActionRow::Buttons(vec![CreateButton::new("remove_from_view_failed").label(remove_from_view)])]
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serenity::all::{ChannelId, Context, CreateMessage, MessageId};
+
+use crate::database::database::{BotStatus, DatabaseTransaction, QueuedContent, UserSettings};
+use crate::discord::bot::Handler;
+use crate::discord::category_mix::balance_queue;
+use crate::discord::commands::COMMAND_PREFIX;
+use crate::discord::priority::prioritize_queue;
+use crate::discord::state::ContentStatus;
+use crate::discord::utils::{handle_msg_deletion, notification_mention, send_message_with_retry};
+use crate::video::similarity::{cluster_embeddings, diversify_order, longest_similar_run};
+use crate::{crab, SIMILAR_CONTENT_THRESHOLD, VARIETY_ALERT_RUN_LEN};
+
+impl Handler {
+    /// Warns when too many similar videos are scheduled back to back, and spreads them out when the variety is enforced
+    pub(crate) async fn check_queue_variety(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, bot_status: &mut BotStatus, channel_id: ChannelId, now: DateTime<Utc>) {
+        let clusters = cluster_embeddings(&tx.load_active_video_embeddings().await, SIMILAR_CONTENT_THRESHOLD);
+
+        let mut content_queue = tx.load_content_queue().await;
+        // Balancing the categories takes the priority of the content into account, so that the two don't keep undoing each other
+        if !self.category_targets.is_empty() {
+            if balance_queue(user_settings, tx, &self.category_targets, &content_queue, now).await {
+                content_queue = tx.load_content_queue().await;
+            }
+        } else if user_settings.prioritize_by_performance && prioritize_queue(user_settings, tx, &content_queue, now).await {
+            content_queue = tx.load_content_queue().await;
+        }
+        if user_settings.enforce_variety && diversify_queue(user_settings, tx, &content_queue, &clusters, now).await {
+            content_queue = tx.load_content_queue().await;
+        }
+
+        let shortcodes: Vec<&str> = content_queue.iter().map(|queued_content| queued_content.original_shortcode.as_str()).collect();
+        let similar_run = longest_similar_run(&shortcodes, &clusters);
+
+        if similar_run.len() < VARIETY_ALERT_RUN_LEN {
+            if bot_status.variety_alert_message_id.get() != 1 {
+                let delete_msg_result = channel_id.delete_message(&ctx.http, bot_status.variety_alert_message_id).await;
+                handle_msg_deletion(delete_msg_result);
+                bot_status.variety_alert_message_id = MessageId::new(1);
+            }
+            return;
+        }
+
+        if bot_status.variety_alert_message_id.get() == 1 {
+            let mention = notification_mention(user_settings, false);
+            let hint = if user_settings.enforce_variety { "there's nothing else in the queue to space them out with".to_string() } else { format!("use `{}variety on` to space them out", COMMAND_PREFIX) };
+            let msg_caption = format!("{mention} the queue is lacking variety, {} similar videos are scheduled back to back: {}, {hint} {}", similar_run.len(), similar_run.join(", "), crab!("-_-"));
+            let msg = CreateMessage::new().content(msg_caption);
+            bot_status.variety_alert_message_id = send_message_with_retry(ctx, channel_id, msg).await.id;
+        }
+    }
+}
+
+/// Swaps the posting times so that no two similar videos follow each other, the set of times stays the same
+///
+/// The content about to be posted is left alone, returns whether anything was moved
+async fn diversify_queue(user_settings: &UserSettings, tx: &mut DatabaseTransaction, content_queue: &[QueuedContent], clusters: &HashMap<String, usize>, now: DateTime<Utc>) -> bool {
+    let movable_queue = movable_queue(content_queue, now);
+
+    let shortcodes: Vec<&str> = movable_queue.iter().map(|queued_content| queued_content.original_shortcode.as_str()).collect();
+    let diversified = diversify_order(&shortcodes, clusters);
+    if diversified == shortcodes {
+        return false;
+    }
+
+    reorder_queue(user_settings, tx, &movable_queue, diversified, now).await;
+    true
+}
+
+/// The content about to be posted can't be moved anymore
+pub(crate) fn movable_queue(content_queue: &[QueuedContent], now: DateTime<Utc>) -> Vec<&QueuedContent> {
+    content_queue.iter().filter(|queued_content| DateTime::parse_from_rfc3339(&queued_content.will_post_at).unwrap() > now + Duration::minutes(1)).collect()
+}
+
+/// Hands the posting times of the movable queue out in the new order, the set of times stays the same
+pub(crate) async fn reorder_queue(user_settings: &UserSettings, tx: &mut DatabaseTransaction, movable_queue: &[&QueuedContent], reordered: Vec<&str>, now: DateTime<Utc>) {
+    let queued_by_shortcode: HashMap<&str, &QueuedContent> = movable_queue.iter().map(|queued_content| (queued_content.original_shortcode.as_str(), *queued_content)).collect();
+    for (slot, shortcode) in movable_queue.iter().zip(reordered) {
+        if slot.original_shortcode == shortcode {
+            continue;
+        }
+
+        let mut queued_content = queued_by_shortcode[shortcode].clone();
+        queued_content.will_post_at = slot.will_post_at.clone();
+        tx.save_queued_content(&queued_content).await;
+
+        // Force the update of the card, so that it shows the new posting time
+        let mut content_info = tx.get_content_info_by_shortcode(&queued_content.original_shortcode).await;
+        if matches!(content_info.status, ContentStatus::Queued { .. }) {
+            content_info.last_updated_at = (now - user_settings.interface_update_interval()).to_rfc3339();
+            tx.save_content_info(&content_info).await;
+        }
+    }
+}
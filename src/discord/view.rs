@@ -1,23 +1,25 @@
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use chrono::{DateTime, Duration, FixedOffset, Utc};
 use lazy_static::lazy_static;
 use regex::Regex;
 use s3::Bucket;
-use serenity::all::{ChannelId, Context, CreateActionRow, CreateAttachment, CreateMessage, EditMessage, Mention, MessageId};
+use serenity::all::{ChannelId, Context, CreateActionRow, CreateAttachment, CreateMessage, EditMessage, Mention, MessageId, RoleId};
 use tokio::sync::Mutex;
 use tokio::time::sleep;
 
-use crate::database::database::{ContentInfo, DatabaseTransaction, UserSettings, DEFAULT_FAILURE_EXPIRATION, DEFAULT_POSTED_EXPIRATION};
-use crate::discord::bot::{ChannelIdMap, Handler};
+use crate::database::database::{BotStatus, ContentInfo, DatabaseTransaction, FrontendMessage, QueuedContent, UserSettings, DEFAULT_FAILURE_EXPIRATION, DEFAULT_POSTED_EXPIRATION};
+use crate::discord::bot::{ChannelIdMap, Handler, FRONTEND_NAME};
+use crate::discord::category_mix::CategoryTargets;
 use crate::discord::state::ContentStatus;
 use crate::discord::state::ContentStatus::RemovedFromView;
 use crate::discord::utils::{
-    generate_bot_status_caption, generate_full_caption, get_bot_status_buttons, get_failed_buttons, get_pending_buttons, get_published_buttons, get_queued_buttons, get_rejected_buttons, handle_msg_deletion, now_in_my_timezone, send_message_with_retry, should_update_buttons, should_update_caption,
+    generate_bot_status_caption, generate_full_caption, get_bot_status_buttons, get_failed_buttons, get_pending_buttons, get_published_buttons, get_queued_buttons, get_rejected_buttons, handle_msg_deletion, is_in_quiet_hours, notification_mention, now_in_my_timezone, send_message_with_retry, should_update_buttons, should_update_caption,
 };
 use crate::s3::helper::delete_from_s3;
-use crate::{crab, DELAY_BETWEEN_MESSAGE_UPDATES, MY_DISCORD_ID, POSTED_CHANNEL_ID, STATUS_CHANNEL_ID};
+use crate::{crab, DELAY_BETWEEN_MESSAGE_UPDATES, HALT_ESCALATION_DM_AFTER, HALT_ESCALATION_EDIT_AFTER, HALT_ESCALATION_ROLE_AFTER, MY_DISCORD_ID, PANIC_REPORT_EXCERPT_LEN, POSTED_CHANNEL_ID, STATUS_CHANNEL_ID};
 
 impl Handler {
     pub async fn process_bot_status(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, global_last_updated_at: Arc<Mutex<DateTime<Utc>>>) {
@@ -25,12 +27,17 @@ impl Handler {
         
         let now = now_in_my_timezone(user_settings);
 
+        self.revert_expired_settings_overrides(ctx, user_settings, tx, channel_id).await;
+
         let mut bot_status = tx.load_bot_status().await;
         let content_queue = tx.load_content_queue().await;
         let content_info_vec = tx.load_content_mapping().await;
         let content_queue_len = content_queue.len();
 
-        let msg_caption = generate_bot_status_caption(&user_settings, &bot_status, content_info_vec.clone(), content_queue, now);
+        let content_queue_snapshot = content_queue.clone();
+        let today = now.format("%Y-%m-%d").to_string();
+        let today_rollup = tx.load_daily_rollups_since(&today).await.into_iter().next();
+        let msg_caption = generate_bot_status_caption(&user_settings, &bot_status, content_info_vec.clone(), content_queue, today_rollup, now);
         let msg_buttons = get_bot_status_buttons(&bot_status);
 
         if bot_status.message_id.get() == 1 {
@@ -39,7 +46,7 @@ impl Handler {
             bot_status.last_updated_at = now_in_my_timezone(user_settings).to_rfc3339();
         } else {
             let last_updated_at = DateTime::parse_from_rfc3339(&bot_status.last_updated_at).unwrap();
-            if now - last_updated_at.with_timezone(&Utc) >= Duration::milliseconds(user_settings.interface_update_interval) {
+            if now - last_updated_at.with_timezone(&Utc) >= user_settings.interface_update_interval() {
                 handle_shown_message_update(ctx, STATUS_CHANNEL_ID, &mut bot_status, user_settings, &msg_caption, msg_buttons, global_last_updated_at).await;
                 bot_status.last_updated_at = now_in_my_timezone(user_settings).to_rfc3339();
             }
@@ -50,7 +57,7 @@ impl Handler {
 
         // Warn the user if the queue is empty
         if content_queue_len == 0 && bot_status.queue_alert_1_message_id.get() == 1 {
-            let mention = Mention::from(MY_DISCORD_ID);
+            let mention = notification_mention(user_settings, false);
             let msg_caption = format!("{mention} the queue is empty! {}", crab!("•̀ - •́"));
             let msg = CreateMessage::new().content(msg_caption);
             bot_status.queue_alert_1_message_id = send_message_with_retry(ctx, channel_id, msg).await.id;
@@ -65,12 +72,12 @@ impl Handler {
         // Warn the user if the queue is about to be empty
         if content_queue_len < bot_status.prev_content_queue_len as usize && queueable_content_count >= 1 {
             if content_queue_len == 1 && bot_status.queue_alert_2_message_id.get() == 1 {
-                let mention = Mention::from(MY_DISCORD_ID);
+                let mention = notification_mention(user_settings, false);
                 let msg_caption = format!("Hello? Are you there {mention}? Queue some content, now! {}", crab!("╥﹏╥"));
                 let msg = CreateMessage::new().content(msg_caption);
                 bot_status.queue_alert_2_message_id = send_message_with_retry(ctx, channel_id, msg).await.id;
             } else if content_queue_len == 3 && bot_status.queue_alert_3_message_id.get() == 1 {
-                let mention = Mention::from(MY_DISCORD_ID);
+                let mention = notification_mention(user_settings, false);
                 let msg_caption = format!("Hey {mention}, remember to add more content to the queue! {}", crab!("¬_¬\""));
                 let msg = CreateMessage::new().content(msg_caption);
                 bot_status.queue_alert_3_message_id = send_message_with_retry(ctx, channel_id, msg).await.id;
@@ -91,12 +98,22 @@ impl Handler {
             }
         }
 
+        self.check_queue_variety(ctx, user_settings, tx, &mut bot_status, channel_id, now).await;
+
+        self.report_duplicates(ctx, tx, channel_id).await;
+
+        self.process_story_queue(ctx, user_settings, tx, channel_id).await;
+
+        self.process_source_suggestions(ctx, tx, channel_id).await;
+
+        self.process_login_challenge(ctx, user_settings, tx, channel_id).await;
+
         // Update prev_content_queue_len
         bot_status.prev_content_queue_len = content_queue_len as i32;
 
         // Notify the user if the bot is halted
         if bot_status.status == 1 && bot_status.halt_alert_message_id.get() == 1 {
-            let mention = Mention::from(MY_DISCORD_ID);
+            let mention = notification_mention(user_settings, true);
             let msg_caption = format!("Hey {mention}, the bot is halted!");
             let msg = CreateMessage::new().content(msg_caption);
             bot_status.halt_alert_message_id = send_message_with_retry(ctx, STATUS_CHANNEL_ID, msg).await.id;
@@ -106,24 +123,133 @@ impl Handler {
             bot_status.halt_alert_message_id = MessageId::new(1);
         }
 
+        self.escalate_halt_if_needed(ctx, user_settings, &mut bot_status, now).await;
+
+        self.report_drain_progress(ctx, &mut bot_status, &content_queue_snapshot).await;
+
+        self.report_panic_if_needed(ctx, user_settings, &mut bot_status).await;
+
+        self.check_review_sla(ctx, user_settings, &mut bot_status, &content_info_vec, channel_id, now).await;
+
+        self.send_daily_digest_if_needed(ctx, tx, &mut bot_status, &content_info_vec, now).await;
+
+        self.announce_upcoming_posts(ctx, tx, &content_queue_snapshot, &content_info_vec, now).await;
+
         tx.save_bot_status(&bot_status).await;
     }
 
+    /// Escalates a halt that has gone unnoticed, first by highlighting it in the status message,
+    /// then by pinging the operator role and finally by messaging the owner directly
+    async fn escalate_halt_if_needed(&self, ctx: &Context, user_settings: &UserSettings, bot_status: &mut BotStatus, now: DateTime<Utc>) {
+        if bot_status.status != 1 {
+            if bot_status.halt_escalation_message_id.get() != 1 {
+                let delete_msg_result = STATUS_CHANNEL_ID.delete_message(&ctx.http, bot_status.halt_escalation_message_id).await;
+                handle_msg_deletion(delete_msg_result);
+                bot_status.halt_escalation_message_id = MessageId::new(1);
+            }
+            bot_status.halted_at = "".to_string();
+            bot_status.halt_escalation_level = 0;
+            return;
+        }
+
+        if bot_status.halted_at.is_empty() {
+            bot_status.halted_at = now.to_rfc3339();
+            return;
+        }
+
+        let halted_for = now - DateTime::parse_from_rfc3339(&bot_status.halted_at).unwrap().with_timezone(&Utc);
+        let can_mention = !is_in_quiet_hours(user_settings) || user_settings.halt_alerts_ignore_quiet_hours;
+
+        if bot_status.halt_escalation_level == 0 && halted_for >= self.halt_escalation_delay("halt_escalation_edit_after_minutes", HALT_ESCALATION_EDIT_AFTER) {
+            bot_status.halt_escalation_level = 1;
+            // Force the update of the status message, so that it shows for how long the bot has been halted
+            bot_status.last_updated_at = (now - user_settings.interface_update_interval()).to_rfc3339();
+        } else if bot_status.halt_escalation_level == 1 && can_mention && halted_for >= self.halt_escalation_delay("halt_escalation_role_after_minutes", HALT_ESCALATION_ROLE_AFTER) {
+            bot_status.halt_escalation_level = 2;
+            match self.credentials.get("operator_role_id").and_then(|role_id| role_id.parse::<u64>().ok()) {
+                Some(role_id) => {
+                    let mention = Mention::Role(RoleId::new(role_id));
+                    let msg_caption = format!("{mention} {} has been halted for {} minutes, can someone take a look? {}", self.username, halted_for.num_minutes(), crab!("°□°"));
+                    let msg = CreateMessage::new().content(msg_caption);
+                    bot_status.halt_escalation_message_id = send_message_with_retry(ctx, STATUS_CHANNEL_ID, msg).await.id;
+                }
+                None => {
+                    tracing::warn!("No operator_role_id in the credentials, skipping the role escalation");
+                }
+            }
+        } else if bot_status.halt_escalation_level == 2 && can_mention && halted_for >= self.halt_escalation_delay("halt_escalation_dm_after_minutes", HALT_ESCALATION_DM_AFTER) {
+            bot_status.halt_escalation_level = 3;
+            let msg_caption = format!("{} has been halted for {} minutes and nobody has resumed it yet! {}", self.username, halted_for.num_minutes(), crab!("╥﹏╥"));
+            let msg = CreateMessage::new().content(msg_caption);
+            match MY_DISCORD_ID.create_dm_channel(&ctx.http).await {
+                Ok(dm_channel) => {
+                    if let Err(e) = dm_channel.send_message(&ctx.http, msg).await {
+                        tracing::error!("Error sending the halt escalation DM: {:?}", e);
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Error opening the DM channel for the halt escalation: {:?}", e);
+                }
+            }
+        }
+    }
+
+    /// Lets the user know when all the content scheduled before the end of the drain has been published
+    async fn report_drain_progress(&self, ctx: &Context, bot_status: &mut BotStatus, content_queue: &[QueuedContent]) {
+        if bot_status.drain_until.is_empty() {
+            if bot_status.drain_report_message_id.get() != 1 {
+                let delete_msg_result = STATUS_CHANNEL_ID.delete_message(&ctx.http, bot_status.drain_report_message_id).await;
+                handle_msg_deletion(delete_msg_result);
+                bot_status.drain_report_message_id = MessageId::new(1);
+            }
+            return;
+        }
+
+        let drain_until = DateTime::parse_from_rfc3339(&bot_status.drain_until).unwrap();
+        let remaining = content_queue.iter().filter(|queued_content| DateTime::parse_from_rfc3339(&queued_content.will_post_at).unwrap() <= drain_until).count();
+
+        if remaining == 0 && bot_status.drain_report_message_id.get() == 1 {
+            let msg_caption = format!("{} is drained, it's now safe to stop the bot {}", self.username, crab!("^_^"));
+            let msg = CreateMessage::new().content(msg_caption);
+            bot_status.drain_report_message_id = send_message_with_retry(ctx, STATUS_CHANNEL_ID, msg).await.id;
+        }
+    }
+
+    /// Lets the status channel know once about each crash of the subsystems, they have already been restarted by then
+    async fn report_panic_if_needed(&self, ctx: &Context, user_settings: &UserSettings, bot_status: &mut BotStatus) {
+        if bot_status.last_panic_at == bot_status.panic_reported_at {
+            return;
+        }
+
+        let mention = notification_mention(user_settings, true);
+        let excerpt: String = bot_status.last_panic.chars().take(PANIC_REPORT_EXCERPT_LEN).collect();
+        let msg_caption = format!("Hey {mention}, a part of {} crashed and was restarted {}\n```\n{}\n```", self.username, crab!("x_x"), excerpt);
+        let msg = CreateMessage::new().content(msg_caption);
+        send_message_with_retry(ctx, STATUS_CHANNEL_ID, msg).await;
+        bot_status.panic_reported_at.clone_from(&bot_status.last_panic_at);
+    }
+
+    fn halt_escalation_delay(&self, key: &str, default: std::time::Duration) -> Duration {
+        let minutes = self.credentials.get(key).and_then(|minutes| minutes.parse::<i64>().ok()).unwrap_or(default.as_secs() as i64 / 60);
+        Duration::minutes(minutes)
+    }
+
     pub async fn process_pending(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction, content_info: &mut ContentInfo, global_last_updated_at: Arc<Mutex<DateTime<Utc>>>) {
         let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
 
         let msg_caption = generate_full_caption(user_settings, tx, &self.ui_definitions, content_info).await;
-        let msg_buttons = get_pending_buttons(&self.ui_definitions);
+        let msg_buttons = get_pending_buttons(&self.ui_definitions, content_info);
 
         if content_info.status == (ContentStatus::Pending { shown: true }) {
             handle_shown_message_update(ctx, channel_id, content_info, user_settings, &msg_caption, msg_buttons, global_last_updated_at).await;
         } else {
             content_info.status = ContentStatus::Pending { shown: true };
 
-            let video_attachment = get_video_attachment(ctx, content_info).await;
+            let video_attachment = if self.is_compact_view() && !content_info.clip_url.is_empty() { get_clip_attachment(ctx, content_info).await } else { get_video_attachment(ctx, content_info).await };
             let video_message = CreateMessage::new().add_file(video_attachment).content(msg_caption).components(msg_buttons);
             let msg = send_message_with_retry(ctx, channel_id, video_message).await;
-            content_info.message_id = msg.id;
+            record_sent_message(tx, content_info, channel_id, msg.id).await;
+            self.open_content_thread(ctx, user_settings, tx, content_info, channel_id, msg.id).await;
             content_info.last_updated_at = now_in_my_timezone(user_settings).to_rfc3339();
         }
     }
@@ -168,7 +294,7 @@ impl Handler {
             let video_attachment = get_video_attachment(ctx, content_info).await;
             let video_message = CreateMessage::new().add_file(video_attachment).content(msg_caption).components(msg_buttons);
             let msg = send_message_with_retry(ctx, channel_id, video_message).await;
-            content_info.message_id = msg.id;
+            record_sent_message(tx, content_info, channel_id, msg.id).await;
             content_info.last_updated_at = now_in_my_timezone(user_settings).to_rfc3339();
         }
     }
@@ -189,7 +315,7 @@ impl Handler {
             }
         };
 
-        let will_expire_at = DateTime::parse_from_rfc3339(&rejected_content.rejected_at).unwrap() + Duration::try_seconds((user_settings.rejected_content_lifespan * 60) as i64).unwrap();
+        let will_expire_at = DateTime::parse_from_rfc3339(&rejected_content.rejected_at).unwrap() + user_settings.rejected_content_lifespan();
 
         if handle_deletion_due_to_expiration(&self.bucket, ctx, content_info, channel_id, now, will_expire_at).await {
             // If the content was deleted, there is no need to process it further
@@ -201,7 +327,7 @@ impl Handler {
             let video_attachment = get_video_attachment(ctx, content_info).await;
             let video_message = CreateMessage::new().add_file(video_attachment).content(msg_caption).components(msg_buttons);
             let msg = send_message_with_retry(ctx, channel_id, video_message).await;
-            content_info.message_id = msg.id;
+            record_sent_message(tx, content_info, channel_id, msg.id).await;
             content_info.last_updated_at = now_in_my_timezone(user_settings).to_rfc3339();
         }
     }
@@ -231,12 +357,14 @@ impl Handler {
         } else {
             content_info.status = ContentStatus::Published { shown: true };
 
+            self.set_content_thread_archived(ctx, user_settings, tx, content_info, true).await;
+
             let video_attachment = get_video_attachment(ctx, content_info).await;
             let video_message = CreateMessage::new().add_file(video_attachment).content(msg_caption).components(msg_buttons);
             let msg = send_message_with_retry(ctx, POSTED_CHANNEL_ID, video_message).await;
             let delete_msg_result = channel_id.delete_message(&ctx.http, content_info.message_id).await;
             handle_msg_deletion(delete_msg_result);
-            content_info.message_id = msg.id;
+            record_sent_message(tx, content_info, POSTED_CHANNEL_ID, msg.id).await;
             content_info.last_updated_at = now_in_my_timezone(user_settings).to_rfc3339();
         }
     }
@@ -271,12 +399,78 @@ impl Handler {
             let msg = send_message_with_retry(ctx, POSTED_CHANNEL_ID, video_message).await;
             let delete_msg_result = channel_id.delete_message(&ctx.http, content_info.message_id).await;
             handle_msg_deletion(delete_msg_result);
-            content_info.message_id = msg.id;
+            record_sent_message(tx, content_info, POSTED_CHANNEL_ID, msg.id).await;
             content_info.last_updated_at = now_in_my_timezone(user_settings).to_rfc3339();
         }
     }
 }
 
+impl Handler {
+    /// Compact cards show the looping clip instead of the full video for pending content
+    fn is_compact_view(&self) -> bool {
+        self.credentials.get("compact_cards").map(|value| value == "true").unwrap_or(false)
+    }
+
+    /// Discord messages can't be moved, so when the preferred order changes the review cards are deleted and sent again
+    pub async fn resort_review_channel_if_needed(&self, ctx: &Context, user_settings: &UserSettings, tx: &mut DatabaseTransaction) {
+        let mut shown_newest_first = self.shown_newest_first.lock().await;
+        match *shown_newest_first {
+            Some(newest_first) if newest_first == user_settings.newest_first => return,
+            // The cards of the first iteration are already sent in the preferred order
+            None => {
+                *shown_newest_first = Some(user_settings.newest_first);
+                return;
+            }
+            Some(_) => *shown_newest_first = Some(user_settings.newest_first),
+        }
+
+        let channel_id = *ctx.data.read().await.get::<ChannelIdMap>().unwrap();
+        for mut content_info in tx.load_content_mapping().await {
+            content_info.status = match content_info.status {
+                ContentStatus::Pending { shown: true } => ContentStatus::Pending { shown: false },
+                ContentStatus::Queued { shown: true } => ContentStatus::Queued { shown: false },
+                ContentStatus::Rejected { shown: true } => ContentStatus::Rejected { shown: false },
+                _ => continue,
+            };
+
+            let delete_msg_result = ctx.http.delete_message(channel_id, content_info.message_id, None).await;
+            handle_msg_deletion(delete_msg_result);
+            tx.save_content_info(&content_info).await;
+        }
+    }
+}
+
+/// Puts the cards that still have to be sent first, in the order preferred by the user, the others keep their order
+///
+/// The content of the categories furthest below their target is sent first, when the account has targets
+pub fn sort_cards_to_send(user_settings: &UserSettings, category_targets: &CategoryTargets, category_counts: &HashMap<String, i64>, content_mapping: &mut [ContentInfo]) {
+    let is_shown = |content_info: &ContentInfo| match content_info.status {
+        ContentStatus::Pending { shown } | ContentStatus::Queued { shown } | ContentStatus::Published { shown } | ContentStatus::Rejected { shown } | ContentStatus::Failed { shown } => shown,
+        ContentStatus::RemovedFromView => true,
+    };
+
+    let deficit = |content_info: &ContentInfo| category_targets.deficit(&content_info.category, category_counts);
+    content_mapping.sort_by(|a, b| match (is_shown(a), is_shown(b)) {
+        (false, false) if user_settings.newest_first => deficit(b).total_cmp(&deficit(a)).then(b.added_at.cmp(&a.added_at)),
+        (false, false) => deficit(b).total_cmp(&deficit(a)).then(a.added_at.cmp(&b.added_at)),
+        (a_shown, b_shown) => a_shown.cmp(&b_shown),
+    });
+}
+
+/// Keeps track of the message currently showing the content, both in the content mapping and in the frontend_messages table
+async fn record_sent_message(tx: &mut DatabaseTransaction, content_info: &mut ContentInfo, channel_id: ChannelId, message_id: MessageId) {
+    content_info.message_id = message_id;
+
+    let frontend_message = FrontendMessage {
+        username: content_info.username.clone(),
+        frontend: FRONTEND_NAME.to_string(),
+        channel_id,
+        message_id,
+        original_shortcode: content_info.original_shortcode.clone(),
+    };
+    tx.save_frontend_message(&frontend_message).await;
+}
+
 async fn update_message_if_needed(ctx: &Context, content_id: MessageId, channel_id: ChannelId, msg_caption: &String, msg_buttons: Vec<CreateActionRow>) {
     let old_msg = match channel_id.message(&ctx.http, content_id).await {
         Ok(msg) => msg,
@@ -324,13 +518,30 @@ pub async fn handle_content_deletion(bucket: &Bucket, ctx: &Context, content_inf
             tracing::error!("Error deleting video from s3: {}", e);
         }
     }
+
+    // Also remove the renditions generated for the cards
+    for rendition_url in [&content_info.preview_url, &content_info.clip_url] {
+        if rendition_url.is_empty() || *rendition_url == content_info.url {
+            continue;
+        }
+        if let Some(captures) = CONTENT_DELETION_REGEX.captures(rendition_url) {
+            let rendition_filename = captures.get(1).unwrap().as_str();
+            match delete_from_s3(bucket, rendition_filename.to_string()).await {
+                Ok(_) => {}
+                Err(e) => {
+                    let e = format!("{:?}", e);
+                    tracing::error!("Error deleting rendition from s3: {}", e);
+                }
+            }
+        }
+    }
 }
 
 async fn handle_shown_message_update<T: crate::discord::traits::Updatable>(ctx: &Context, channel_id: ChannelId, item: &mut T, user_settings: &UserSettings, msg_caption: &String, msg_buttons: Vec<CreateActionRow>, global_last_updated_at: Arc<Mutex<DateTime<Utc>>>) {
     let last_updated_at = DateTime::parse_from_rfc3339(&item.get_last_updated_at()).unwrap();
     let now = now_in_my_timezone(user_settings);
 
-    if now - last_updated_at.with_timezone(&Utc) >= Duration::milliseconds(user_settings.interface_update_interval) {
+    if now - last_updated_at.with_timezone(&Utc) >= user_settings.interface_update_interval() {
 
         // Check if the time difference between now and last_updated_at_last_message is less than half a second
         if (now - *global_last_updated_at.lock().await).num_milliseconds() < DELAY_BETWEEN_MESSAGE_UPDATES.num_milliseconds() {
@@ -355,18 +566,30 @@ async fn handle_deletion_due_to_expiration(bucket: &Bucket, ctx: &Context, conte
     }
 }
 
+/// The cards always show the low resolution preview, falling back to the original for content scraped before previews existed
 async fn get_video_attachment(ctx: &Context, content_info: &ContentInfo) -> CreateAttachment {
-    match CreateAttachment::url(&ctx.http, &content_info.url).await {
+    let url = if content_info.preview_url.is_empty() { &content_info.url } else { &content_info.preview_url };
+    match CreateAttachment::url(&ctx.http, url).await {
         Ok(attachment) => attachment,
         Err(_) => {
             sleep(Duration::seconds(1).to_std().unwrap()).await;
-            match CreateAttachment::url(&ctx.http, &content_info.url).await {
+            match CreateAttachment::url(&ctx.http, url).await {
                 Ok(attachment) => attachment,
                 Err(e) => {
-                    tracing::error!("Error creating attachment for url {} {:?}", content_info.url, e);
-                    panic!("Error creating attachment for url {} {:?}", content_info.url, e);
+                    tracing::error!("Error creating attachment for url {} {:?}", url, e);
+                    panic!("Error creating attachment for url {} {:?}", url, e);
                 }
             }
         }
     }
 }
+
+async fn get_clip_attachment(ctx: &Context, content_info: &ContentInfo) -> CreateAttachment {
+    match CreateAttachment::url(&ctx.http, &content_info.clip_url).await {
+        Ok(attachment) => attachment,
+        Err(e) => {
+            tracing::warn!("Error creating clip attachment for url {} {:?}, falling back to the video", content_info.clip_url, e);
+            get_video_attachment(ctx, content_info).await
+        }
+    }
+}
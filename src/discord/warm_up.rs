@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use serenity::all::{ChannelId, Client, CreateMessage, EditChannel, EditMessage, GetMessages, GuildChannel, Http, Message, MessageId};
+use serenity::prelude::TypeMapKey;
+
+use crate::database::database::Database;
+use crate::discord::commands::{COMMANDS, COMMAND_PREFIX};
+use crate::discord::utils::clear_all_messages;
+use crate::profile::config_profile::CONFIG_PROFILE;
+use crate::self_check::report::SelfCheckReport;
+use crate::{crab, POSTED_CHANNEL_ID, STATUS_CHANNEL_ID};
+
+const WARM_UP_IN_PROGRESS_HEADER: &str = "Warming up the interface of";
+
+/// The progress messages of the other accounts are kept while cleaning up the status channel, as long as they are this recent
+const WARM_UP_PROGRESS_GRACE: Duration = Duration::minutes(10);
+
+/// The warm-up of the account, kept until the first review cards are sent to complete its progress message
+pub(crate) struct WarmUpProgressMap {}
+
+impl TypeMapKey for WarmUpProgressMap {
+    type Value = WarmUpProgress;
+}
+
+/// Reports the steps of the warm-up to the log and to the status channel as they complete
+pub(crate) struct WarmUpProgress {
+    username: String,
+    http: Arc<Http>,
+    steps: Vec<String>,
+    is_posting: bool,
+    message_id: Option<MessageId>,
+}
+
+impl WarmUpProgress {
+    fn new(username: &str, http: &Arc<Http>) -> Self {
+        Self {
+            username: username.to_string(),
+            http: Arc::clone(http),
+            steps: Vec::new(),
+            is_posting: false,
+            message_id: None,
+        }
+    }
+
+    async fn step(&mut self, step: String) {
+        println!(" [{}] Warm-up: {}", self.username, step);
+        self.steps.push(step);
+        if self.is_posting {
+            self.post(false).await;
+        }
+    }
+
+    /// The steps done until then are posted too, the status channel can only be written to once it was cleaned up
+    async fn start_posting(&mut self) {
+        self.is_posting = true;
+        self.post(false).await;
+    }
+
+    /// Called once the first review cards were sent
+    pub(crate) async fn finish(&mut self) {
+        self.steps.push("Sent the review cards".to_string());
+        println!(" [{}] Discord bot finished warming up.", self.username);
+        if self.is_posting {
+            self.post(true).await;
+        }
+    }
+
+    async fn post(&mut self, is_done: bool) {
+        let header = if is_done {
+            format!("The interface of {} is warmed up {}", self.username, crab!("!,!"))
+        } else {
+            format!("{} {}...", WARM_UP_IN_PROGRESS_HEADER, self.username)
+        };
+        let steps = self.steps.iter().map(|step| format!("- {}", step)).collect::<Vec<String>>().join("\n");
+        let content = format!("{}\n{}", header, steps);
+
+        match self.message_id {
+            Some(message_id) => {
+                if let Err(e) = STATUS_CHANNEL_ID.edit_message(&self.http, message_id, EditMessage::new().content(content)).await {
+                    tracing::warn!("Error updating the warm-up progress: {:?}", e);
+                }
+            }
+            None => match STATUS_CHANNEL_ID.send_message(&self.http, CreateMessage::new().content(content)).await {
+                Ok(message) => self.message_id = Some(message.id),
+                Err(e) => tracing::warn!("Error sending the warm-up progress: {:?}", e),
+            },
+        }
+    }
+}
+
+/// Gets the Discord interface of the account ready before the bot starts listening, returns the review channel
+///
+/// The posted and status channels are shared by the accounts, so only the first one cleans them up
+pub(crate) async fn warm_up(client: &Client, database: &Database, username: &str, is_first_run: bool, self_check_report: &SelfCheckReport) -> ChannelId {
+    let mut progress = WarmUpProgress::new(username, &client.http);
+
+    let review_channel = verify_review_channel(client, username, &mut progress).await;
+    let is_posted_channel_reachable = verify_shared_channel(client, POSTED_CHANNEL_ID, "posted", &mut progress).await;
+    let is_status_channel_reachable = verify_shared_channel(client, STATUS_CHANNEL_ID, "status", &mut progress).await;
+
+    register_commands(client, &review_channel, &mut progress).await;
+
+    let mut tx = database.begin_transaction().await;
+    clear_all_messages(&mut tx, &client.http, review_channel.id, true).await;
+    progress.step("Cleared the review channel, the cards are sent again once the bot is ready".to_string()).await;
+
+    let welcome_message = format!("Welcome back! {}", crab!("!,!"));
+
+    if is_first_run && is_posted_channel_reachable {
+        reconcile_posted_channel(client, &welcome_message, &mut progress).await;
+    }
+
+    if is_status_channel_reachable {
+        if is_first_run {
+            reconcile_status_channel(client, database, username, &mut progress).await;
+        }
+        progress.start_posting().await;
+
+        if is_first_run {
+            // Posted after the status channel was cleaned up, it goes away at the next start
+            let msg = CreateMessage::new().content(self_check_report.to_string());
+            let _ = client.http.send_message(STATUS_CHANNEL_ID, vec![], &msg).await;
+            progress.step("Posted the self-check report".to_string()).await;
+        }
+    }
+
+    let msg = CreateMessage::new().content(welcome_message);
+    let _ = client.http.send_message(review_channel.id, vec![], &msg).await;
+
+    client.data.write().await.insert::<WarmUpProgressMap>(progress);
+    review_channel.id
+}
+
+/// Finds the channel named after the account, or creates it
+async fn verify_review_channel(client: &Client, username: &str, progress: &mut WarmUpProgress) -> GuildChannel {
+    let guild = client.http.get_guild(CONFIG_PROFILE.guild_id()).await.unwrap();
+
+    let guild_channels = guild.channels(&client.http).await.unwrap();
+    if let Some(channel) = guild_channels.into_values().find(|channel| channel.name == *username) {
+        progress.step(format!("Found the review channel <#{}>", channel.id)).await;
+        return channel;
+    }
+
+    let mut map = HashMap::new();
+    map.insert("name".to_string(), username.to_string());
+    let channel = client.http.create_channel(guild.id, &map, None).await.unwrap();
+    progress.step(format!("Created the review channel <#{}>", channel.id)).await;
+    channel
+}
+
+/// Returns whether the bot can reach the channel, the steps that need it are skipped otherwise
+async fn verify_shared_channel(client: &Client, channel_id: ChannelId, name: &str, progress: &mut WarmUpProgress) -> bool {
+    match client.http.get_channel(channel_id).await {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::error!("The {} channel {} isn't reachable: {:?}", name, channel_id, e);
+            progress.step(format!("⚠️ The {} channel isn't reachable, skipping its set up", name)).await;
+            false
+        }
+    }
+}
+
+/// The commands are text commands, they are listed in the topic of the review channel so that they show up next to its name
+async fn register_commands(client: &Client, review_channel: &GuildChannel, progress: &mut WarmUpProgress) {
+    let command_names = COMMANDS.iter().map(|(command, _)| command.split(' ').next().unwrap_or_default()).fold(Vec::new(), |mut command_names, command_name| {
+        if !command_names.contains(&command_name) {
+            command_names.push(command_name);
+        }
+        command_names
+    });
+    let topic = format!("Commands: {}", command_names.iter().map(|command_name| format!("{}{}", COMMAND_PREFIX, command_name)).collect::<Vec<String>>().join(" "));
+
+    if review_channel.topic.as_deref() == Some(topic.as_str()) {
+        progress.step(format!("The {} commands are already listed in the review channel", command_names.len())).await;
+        return;
+    }
+
+    match review_channel.id.edit(&client.http, EditChannel::new().topic(topic)).await {
+        Ok(_) => progress.step(format!("Listed the {} commands in the review channel", command_names.len())).await,
+        Err(e) => {
+            tracing::warn!("Error setting the topic of the review channel: {:?}", e);
+            progress.step(format!("⚠️ Couldn't list the commands in the review channel, `{}help` still works", COMMAND_PREFIX)).await;
+        }
+    }
+}
+
+/// Keeps the welcome message if it's the latest one, the rest goes away
+async fn reconcile_posted_channel(client: &Client, welcome_message: &str, progress: &mut WarmUpProgress) {
+    let messages = match POSTED_CHANNEL_ID.messages(&client.http, GetMessages::new()).await {
+        Ok(messages) => messages,
+        Err(e) => {
+            tracing::error!("Error fetching the messages of the posted channel: {:?}", e);
+            progress.step("⚠️ Couldn't clean up the posted channel".to_string()).await;
+            return;
+        }
+    };
+
+    let mut is_message_there = false;
+    let mut deleted = 0;
+    for (i, message) in messages.iter().enumerate() {
+        if i == 0 && message.author.bot && message.content.contains(welcome_message) {
+            is_message_there = true;
+        } else if message.delete(&client.http).await.is_ok() {
+            deleted += 1;
+        }
+    }
+
+    if !is_message_there {
+        let msg = CreateMessage::new().content(welcome_message);
+        let _ = client.http.send_message(POSTED_CHANNEL_ID, vec![], &msg).await;
+    }
+    progress.step(format!("Cleaned up the posted channel, {} messages removed", deleted)).await;
+}
+
+/// Keeps the status message of the account, so that it's edited instead of sent again, and resets the alerts
+async fn reconcile_status_channel(client: &Client, database: &Database, username: &str, progress: &mut WarmUpProgress) {
+    let mut tx = database.begin_transaction().await;
+    tx.clear_all_other_bot_statuses().await;
+
+    let messages = match STATUS_CHANNEL_ID.messages(&client.http, GetMessages::new()).await {
+        Ok(messages) => messages,
+        Err(e) => {
+            tracing::error!("Error fetching the messages of the status channel: {:?}", e);
+            Vec::new()
+        }
+    };
+
+    let mut bot_status = tx.load_bot_status().await;
+    let mut is_message_there = false;
+    for message in messages {
+        if message.author.name == *username && message.author.bot && message.content.contains("Last updated at") {
+            is_message_there = true;
+            bot_status.message_id = message.id;
+        } else if !is_other_warm_up_in_progress(&message, username) {
+            let _ = message.delete(&client.http).await;
+        }
+    }
+
+    // If the message not is there, and the message id is not 1, then reset the message id
+    // so that it will be sent by view.rs
+    if !is_message_there && bot_status.message_id.get() != 1 {
+        bot_status.message_id = MessageId::new(1);
+    }
+
+    // Reset the message ids for the alerts to function properly when restarting the bot
+    bot_status.halt_alert_message_id = MessageId::new(1);
+    bot_status.queue_alert_1_message_id = MessageId::new(1);
+    bot_status.halt_escalation_message_id = MessageId::new(1);
+    bot_status.drain_report_message_id = MessageId::new(1);
+    bot_status.review_sla_message_id = MessageId::new(1);
+    bot_status.variety_alert_message_id = MessageId::new(1);
+
+    tx.save_bot_status(&bot_status).await;
+
+    let status_message = if is_message_there { "found the status message" } else { "the status message will be sent again" };
+    progress.step(format!("Cleaned up the status channel, {}", status_message)).await;
+}
+
+/// The other accounts warm up at the same time, their progress messages are left alone unless they are left over from a previous start
+fn is_other_warm_up_in_progress(message: &Message, username: &str) -> bool {
+    message.author.bot && message.content.starts_with(WARM_UP_IN_PROGRESS_HEADER) && !message.content.starts_with(&format!("{} {}...", WARM_UP_IN_PROGRESS_HEADER, username)) && *message.timestamp > Utc::now() - WARM_UP_PROGRESS_GRACE
+}
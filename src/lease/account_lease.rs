@@ -0,0 +1,91 @@
+use std::env;
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+use crate::database::database::Database;
+use crate::{RunMode, LEASE_HEARTBEAT_INTERVAL, LEASE_TIMEOUT};
+
+pub const INSTANCE_ID_VAR: &str = "REPOST_RUSTY_INSTANCE_ID";
+
+/// Kept so that a restart can pass it on to the new process, which then takes over the leases of this one right away
+static INSTANCE_ID: OnceLock<String> = OnceLock::new();
+
+/// Returns the id used to tell apart the instances running at the same time, it can be pinned with REPOST_RUSTY_INSTANCE_ID
+pub fn generate_instance_id() -> String {
+    let instance_id = match env::var(INSTANCE_ID_VAR) {
+        Ok(instance_id) => instance_id,
+        Err(_) => {
+            let hostname = env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string());
+            let suffix: u32 = rand::thread_rng().gen();
+            format!("{}-{}-{:08x}", hostname, std::process::id(), suffix)
+        }
+    };
+    INSTANCE_ID.get_or_init(|| instance_id).clone()
+}
+
+/// The id of this instance, None until it's generated
+pub fn current_instance_id() -> Option<&'static str> {
+    INSTANCE_ID.get().map(String::as_str)
+}
+
+/// The components of an account that need to be leased for the given mode
+fn leased_components(run_mode: RunMode) -> Vec<&'static str> {
+    let mut components = Vec::new();
+    if run_mode.runs_scraper() {
+        components.push("scraper");
+    }
+    if run_mode.runs_frontend() {
+        components.push("frontend");
+    }
+    components
+}
+
+/// Waits until this instance owns the account for the given mode, then keeps the lease alive in the background
+///
+/// While another instance is heartbeating the account, this one stays on standby and takes over once it stops
+pub async fn acquire_account_lease(database: &Database, username: &str, instance_id: &str, run_mode: RunMode) {
+    let timeout_secs = LEASE_TIMEOUT.as_secs() as i64;
+    let components = leased_components(run_mode);
+
+    let mut is_on_standby = false;
+    loop {
+        let mut tx = database.begin_transaction().await;
+        let mut acquired = true;
+        for component in &components {
+            acquired &= tx.try_acquire_lease(component, instance_id, timeout_secs).await;
+        }
+
+        if acquired {
+            break;
+        }
+
+        if !is_on_standby {
+            println!(" [{}] Account is owned by another instance, waiting on standby...", username);
+            is_on_standby = true;
+        }
+        tokio::time::sleep(LEASE_HEARTBEAT_INTERVAL).await;
+    }
+
+    if is_on_standby {
+        println!(" [{}] Took over the account from the previous instance", username);
+    }
+
+    let database = database.clone();
+    let username = username.to_string();
+    let instance_id = instance_id.to_string();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(LEASE_HEARTBEAT_INTERVAL).await;
+
+            let mut tx = database.begin_transaction().await;
+            for component in &components {
+                if !tx.try_acquire_lease(component, &instance_id, timeout_secs).await {
+                    // Another instance took over while this one wasn't heartbeating, running both would mean double posting
+                    tracing::error!("Lost the {} lease for {}, shutting down", component, username);
+                    std::process::exit(1);
+                }
+            }
+        }
+    });
+}
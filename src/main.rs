@@ -8,18 +8,31 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use serenity::all::{ChannelId, GuildId, UserId};
-use tracing_subscriber::filter::LevelFilter;
 use tracing_subscriber::fmt::format::FmtSpan;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{layer::SubscriberExt, Layer, Registry};
 
+use crate::coordination::post_now::PostNow;
+use crate::coordination::redis_layer::Coordinator;
 use crate::database::database::Database;
+use crate::database::purge::run_purge_loop;
 use crate::discord::bot::DiscordBot;
+use crate::lease::account_lease::{acquire_account_lease, generate_instance_id};
+use crate::profile::config_profile::CONFIG_PROFILE;
+use crate::s3::logs::run_log_shipping_loop;
 use crate::scraper_poster::scraper::ContentManager;
+use crate::self_check::report::SelfCheckReport;
+use crate::supervisor::panics::install_panic_hook;
 
+mod coordination;
 mod discord;
+mod lease;
+mod maintenance;
+mod profile;
 mod s3;
 mod scraper_poster;
+mod self_check;
+mod supervisor;
 mod video;
 
 mod database;
@@ -30,69 +43,285 @@ pub(crate) const GUILD_ID: GuildId = GuildId::new(1090413253592612917);
 pub(crate) const POSTED_CHANNEL_ID: ChannelId = ChannelId::new(1236328603696762891);
 pub(crate) const STATUS_CHANNEL_ID: ChannelId = ChannelId::new(1233547564880498688);
 
-// Internal configuration, don't change the constants below
-const IS_OFFLINE: bool = false;
-
 // Internal scraper configuration
 pub(crate) const SCRAPER_REFRESH_RATE: Duration = Duration::from_millis(5_000);
 const MAX_CONTENT_PER_ITERATION: usize = 8;
+// How many of the latest posts of a source account are fetched, and how far back a new source account is paged the first time
+const DEFAULT_POSTS_PER_FETCH: usize = 5;
+const DEFAULT_BACKFILL_POSTS: usize = 50;
+// The business discovery doesn't return the same posts as the scraper, so a few more are looked at to find the carousels
+const CAROUSEL_LOOKUP_LIMIT: usize = 10;
 pub(crate) const MAX_CONTENT_HANDLED: usize = 50;
 const FETCH_SLEEP_LEN: Duration = Duration::from_secs(60);
+// Each download waits on its own before handing its turn over, so that a few of them can be paced in parallel
 const SCRAPER_DOWNLOAD_SLEEP_LEN: Duration = Duration::from_secs(60 * 20);
+const MAX_CONCURRENT_DOWNLOADS: usize = 3;
+// The temp files left behind by a previous run are removed at startup once they are this old, the more recent downloads can still be resumed
+const ORPHANED_DOWNLOAD_AGE: Duration = Duration::from_secs(60 * 60 * 24);
 const SCRAPER_LOOP_SLEEP_LEN: Duration = Duration::from_secs(60 * 60 * 12);
+const LOGIN_RETRY_INTERVAL: Duration = Duration::from_secs(60 * 30);
+const MAX_AUTOMATIC_LOGIN_RETRIES: u32 = 8;
+// The session is checked in the background, and only halts the bot once logging in again failed a few times in a row
+const SESSION_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 30);
+const MAX_SESSION_FAILURES: u32 = 3;
+// The delays between the requests double for each rate limit hit in a row, and the scraping stops for a cooldown once too many are hit
+// The cooldown can be overridden per account in the credentials
+const RATE_LIMIT_MAX_DOUBLINGS: u32 = 4;
+const RATE_LIMIT_CIRCUIT_BREAKER_THRESHOLD: u32 = 3;
+const RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(60 * 60 * 2);
+// A scraper identity running into a restriction is left out of the rotation for this long
+const IDENTITY_EXCLUSION_LEN: Duration = Duration::from_secs(60 * 60 * 12);
+// The security codes sent by Instagram don't last much longer than this
+pub(crate) const LOGIN_CHALLENGE_TIMEOUT: Duration = Duration::from_secs(60 * 15);
+// Content that can't be found is retried on the following iterations before being written off
+pub(crate) const MAX_MEDIA_NOT_FOUND_ATTEMPTS: i32 = 3;
+// Shared between the scraper and the poster through Redis, ignored when Redis is not configured
+const MAX_INSTAGRAM_REQUESTS_PER_HOUR: u64 = 200;
+// Hashtag discovery goes through the Graph API, which has its own limits
+const HASHTAG_FETCH_SLEEP_LEN: Duration = Duration::from_secs(60 * 2);
+const MAX_HASHTAG_REQUESTS_PER_HOUR: u64 = 30;
+// Instagram only allows 30 different hashtags to be looked up in a week
+const MAX_HASHTAG_SEARCHES_PER_WEEK: u64 = 30;
+// Stories disappear from the source account after a day, the ones that weren't reshared by then are dropped
+pub(crate) const STORY_LIFETIME: Duration = Duration::from_secs(60 * 60 * 24);
+// Source accounts are suggested from the accounts related to a few of the current ones, once per iteration
+const SOURCES_TO_SUGGEST_FROM: usize = 3;
+const MAX_SOURCE_SUGGESTIONS_PER_ITERATION: usize = 5;
+// How long before a maintenance window the loops are paused
+pub(crate) const MAINTENANCE_PAUSE_LEAD: Duration = Duration::from_secs(60 * 5);
+
+// Internal lease configuration, used to make sure that each account is run by a single instance
+pub(crate) const LEASE_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+pub(crate) const LEASE_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Internal database configuration
+// How long removed content can be restored with --restore before being purged
+pub(crate) const SOFT_DELETE_RETENTION: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+pub(crate) const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 // Internal S3 configuration
 pub const S3_EXPIRATION_TIME: u32 = 60 * 60 * 24 * 7;
+// How often the rotated logs are shipped to the bucket when enabled in the credentials, and how long they are kept there by default
+pub(crate) const LOG_SHIPPING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+pub(crate) const DEFAULT_LOG_RETENTION_DAYS: i64 = 30;
 
 // Internal Discord configuration
 pub const DELAY_BETWEEN_MESSAGE_UPDATES: chrono::Duration = chrono::Duration::milliseconds(500);
 pub(crate) const DISCORD_REFRESH_RATE: Duration = Duration::from_millis(1000);
 pub(crate) const INITIAL_INTERFACE_UPDATE_INTERVAL: Duration = Duration::from_millis(60_000);
+pub(crate) const MESSAGE_LOOKUP_CACHE_TTL: Duration = Duration::from_secs(60 * 60);
+// Default halt escalation delays, can be overridden per account in the credentials
+pub(crate) const HALT_ESCALATION_EDIT_AFTER: Duration = Duration::from_secs(60 * 15);
+pub(crate) const HALT_ESCALATION_ROLE_AFTER: Duration = Duration::from_secs(60 * 30);
+pub(crate) const HALT_ESCALATION_DM_AFTER: Duration = Duration::from_secs(60 * 60);
+// Default time pending content can wait for a review before the moderators are pinged, can be overridden per account in the credentials
+pub(crate) const REVIEW_SLA: Duration = Duration::from_secs(60 * 60 * 6);
+pub(crate) const REVIEW_SLA_LIST_LEN: usize = 5;
+// Default time before a content is published at which it's teased in the community channel, can be overridden per account in the credentials
+pub(crate) const ANNOUNCEMENT_LEAD: Duration = Duration::from_secs(60 * 30);
+// How many source accounts the digest and the stats point out for their rejections
+pub(crate) const MOST_REJECTED_SOURCES_LEN: usize = 3;
+// How alike two videos have to look to be considered similar, and how many similar videos in a row make the queue lack variety
+pub(crate) const SIMILAR_CONTENT_THRESHOLD: f32 = 0.9;
+pub(crate) const VARIETY_ALERT_RUN_LEN: usize = 5;
+// How many days of published content make up the current mix of categories, compared to the targets of the account
+pub(crate) const CATEGORY_MIX_DAYS: i64 = 30;
+// Default age after which the bot messages not showing any content are deleted, can be overridden per account in the credentials
+pub(crate) const ARCHIVE_AFTER: Duration = Duration::from_secs(60 * 60 * 24);
+pub(crate) const ARCHIVE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+pub(crate) const ARCHIVE_BATCH_DELAY: Duration = Duration::from_secs(2);
+// Where the newer versions are looked for, can be overridden per account in the credentials
+pub(crate) const DEFAULT_RELEASE_FEED_URL: &str = "https://api.github.com/repos/gramistella/repost_rusty/releases/latest";
+pub(crate) const UPDATE_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60 * 24);
+pub(crate) const RELEASE_NOTES_EXCERPT_LEN: usize = 1500;
+// How often the restart checks whether the queue is drained, and how long past the end of the drain it waits before giving up
+pub(crate) const RESTART_DRAIN_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+pub(crate) const RESTART_DRAIN_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+pub(crate) const MAX_RESTART_DRAIN_HOURS: i64 = 24 * 7;
+// How long a crashed subsystem is left alone before it's started again, so that a crash on startup doesn't spin
+pub(crate) const SUBSYSTEM_RESTART_DELAY: Duration = Duration::from_secs(60);
+// How much of the backtrace of a crash is shown in the status channel, the whole of it is kept in the bot status
+pub(crate) const PANIC_REPORT_EXCERPT_LEN: usize = 1500;
+// How long a video of the inbox has to be left untouched before it's picked up, so that it isn't taken while still being copied
+pub(crate) const INBOX_SETTLE_TIME: Duration = Duration::from_secs(30);
+// How long the operations that could hang are given before being cancelled, the downloads are resumed on the next attempt
+pub(crate) const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60 * 10);
+pub(crate) const UPLOAD_TIMEOUT: Duration = Duration::from_secs(60 * 20);
+pub(crate) const S3_UPLOAD_TIMEOUT: Duration = Duration::from_secs(60 * 5);
+// How often the comments of the latest reels are checked when the replies are enabled, and how long to wait between two replies
+pub(crate) const COMMENT_REPLY_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 10);
+pub(crate) const COMMENT_REPLY_SPACING: Duration = Duration::from_secs(60);
+// How often the weekly compilation is looked at when it's enabled, it's built once the week is over
+pub(crate) const COMPILATION_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 // (V){!,!}(V)
 
+/// Which components run in this process, the scraper and the frontend communicate only through the database,
+/// so they can be started separately, e.g. to scrape from a different host than the one publishing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RunMode {
+    /// Scraper, Discord bot and poster in the same process
+    All,
+    /// Only the scraper and the ingestion of the scraped content
+    Scraper,
+    /// Only the Discord bot and the poster
+    Frontend,
+}
+
+impl RunMode {
+    pub(crate) fn runs_scraper(&self) -> bool {
+        matches!(self, RunMode::All | RunMode::Scraper)
+    }
+
+    pub(crate) fn runs_frontend(&self) -> bool {
+        matches!(self, RunMode::All | RunMode::Frontend)
+    }
+}
+
+/// Parses the "--mode <all|scraper|frontend>" command line argument, defaults to running everything
+fn parse_run_mode() -> RunMode {
+    let args: Vec<String> = env::args().collect();
+    let mode = match args.iter().position(|arg| arg == "--mode") {
+        Some(index) => args.get(index + 1).expect("Missing value for --mode").as_str(),
+        None => return RunMode::All,
+    };
+
+    match mode {
+        "all" => RunMode::All,
+        "scraper" => RunMode::Scraper,
+        "frontend" => RunMode::Frontend,
+        _ => panic!("Invalid mode {}, expected one of: all, scraper, frontend", mode),
+    }
+}
+
+/// Parses the "--dry-run" command line argument, the scraper then only records what it would download in the scrape_audit table
+///
+/// Nothing is downloaded nor reviewed during a dry run, so only the scraper is started
+fn parse_scrape_dry_run() -> bool {
+    env::args().any(|arg| arg == "--dry-run")
+}
+
 fn main() -> anyhow::Result<()> {
     env::set_var("RUST_BACKTRACE", "full");
 
     let (_file_guard, _stdout_guard) = init_logging();
+    install_panic_hook();
+
+    let is_scrape_dry_run = parse_scrape_dry_run();
+    let run_mode = if is_scrape_dry_run { RunMode::Scraper } else { parse_run_mode() };
+    tracing::info!("Using the {} profile", CONFIG_PROFILE.name);
 
     let all_credentials = read_credentials("config/credentials.yaml");
+
+    if let Some((username, shortcode)) = parse_restore_arguments() {
+        restore_content(&all_credentials, username, shortcode);
+        return Ok(());
+    }
+
+    // Printed right away, and posted in the status channel once the Discord bot of the first account is up
+    let enabled_credentials: Vec<(&String, &HashMap<String, String>)> = all_credentials.iter().filter(|(_, credentials)| credentials.get("enabled").expect("No enabled field in credentials") == "true").collect();
+    let self_check_report = tokio::runtime::Runtime::new().unwrap().block_on(SelfCheckReport::run(&enabled_credentials, run_mode));
+    println!("{}", self_check_report);
+    if self_check_report.has_failures() {
+        tracing::error!("The self-check found problems with the environment:\n{}", self_check_report);
+    }
+
     let mut all_handles = Vec::new();
 
+    let instance_id = generate_instance_id();
+
     let mut is_first_run = true;
     for (username, credentials) in all_credentials {
         if credentials.get("enabled").expect("No enabled field in credentials") == "true" {
-            let span = tracing::span!(tracing::Level::INFO, "main", username = username.as_str());
-            let _enter = span.enter();
-            tracing::info!("Starting bot for user: {}", username);
+            // Each account is started on its own thread, since it might have to wait for another instance to release it
+            let instance_id = instance_id.clone();
+            let self_check_report = self_check_report.clone();
+            let account = std::thread::spawn(move || run_account(username, credentials, run_mode, is_scrape_dry_run, instance_id, is_first_run, self_check_report));
+            all_handles.push(account);
+
+            if run_mode.runs_frontend() {
+                is_first_run = false;
+            }
+        }
+    }
 
-            let rt = Arc::new(tokio::runtime::Runtime::new().unwrap());
-            let rt_clone = Arc::clone(&rt);
+    // Wait for all tasks to complete
+    for handle in all_handles {
+        handle.join().expect("Thread panicked");
+    }
 
-            let db = rt.block_on(async { Database::new(username.clone(), credentials.clone()).await.unwrap() });
-            let bucket = init_bucket(credentials.clone());
+    Ok(())
+}
 
-            let mut discord_bot_manager = rt.block_on(async { DiscordBot::new(db.clone(), bucket.clone(), credentials.clone(), is_first_run).await });
+/// Parses the "--restore <username> <shortcode>" command line arguments
+fn parse_restore_arguments() -> Option<(String, String)> {
+    let args: Vec<String> = env::args().collect();
+    let index = args.iter().position(|arg| arg == "--restore")?;
+    let username = args.get(index + 1).expect("Usage: --restore <username> <shortcode>");
+    let shortcode = args.get(index + 2).expect("Usage: --restore <username> <shortcode>");
+    Some((username.clone(), shortcode.clone()))
+}
 
-            // Run the content_manager and the bot concurrently
-            let mut content_manager = ContentManager::new(db, bucket, username, credentials, IS_OFFLINE);
-            let scraper = std::thread::spawn(move || rt.block_on(content_manager.run()));
+/// Restores content that was removed by mistake, the running bot picks it up on its next refresh
+fn restore_content(all_credentials: &HashMap<String, HashMap<String, String>>, username: String, shortcode: String) {
+    let credentials = all_credentials.get(&username).unwrap_or_else(|| panic!("No credentials found for {}", username));
+
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let restored_tables = rt.block_on(async {
+        let db = Database::new(username.clone(), credentials.clone()).await.unwrap();
+        let mut tx = db.begin_transaction().await;
+        tx.restore_content_with_shortcode(&shortcode).await
+    });
+
+    if restored_tables.is_empty() {
+        println!("Nothing to restore for {} on {}", shortcode, username);
+    } else {
+        println!("Restored {} on {} in: {}", shortcode, username, restored_tables.join(", "));
+    }
+}
 
-            let discord = std::thread::spawn(move || rt_clone.block_on(async { discord_bot_manager.run().await }));
+fn run_account(username: String, credentials: HashMap<String, String>, run_mode: RunMode, is_scrape_dry_run: bool, instance_id: String, is_first_run: bool, self_check_report: SelfCheckReport) {
+    let span = tracing::span!(tracing::Level::INFO, "main", username = username.as_str());
+    let _enter = span.enter();
+    tracing::info!("Starting bot for user: {} ({:?} mode{})", username, run_mode, if is_scrape_dry_run { ", dry run" } else { "" });
 
-            all_handles.push(scraper);
-            all_handles.push(discord);
+    let rt = Arc::new(tokio::runtime::Runtime::new().unwrap());
+    let rt_clone = Arc::clone(&rt);
 
-            is_first_run = false;
-        }
+    let db = rt.block_on(async { Database::new(username.clone(), credentials.clone()).await.unwrap() });
+
+    // Make sure that no other instance is running this account
+    rt.block_on(acquire_account_lease(&db, &username, &instance_id, run_mode));
+
+    let bucket = init_bucket(credentials.clone());
+    let coordinator = rt.block_on(Coordinator::new(&username, &credentials));
+
+    if credentials.get("ship_logs").is_some_and(|ship_logs| ship_logs == "true") {
+        let retention_days = credentials.get("log_retention_days").and_then(|days| days.parse().ok()).unwrap_or(DEFAULT_LOG_RETENTION_DAYS);
+        rt.spawn(run_log_shipping_loop(bucket.clone(), retention_days));
     }
 
-    // Wait for all tasks to complete
-    for handle in all_handles {
-        handle.join().expect("Thread panicked");
+    // The publications asked for from Discord wake the poster up right away
+    let post_now = Arc::new(PostNow::default());
+
+    let mut handles = Vec::new();
+    if run_mode.runs_frontend() {
+        // Only the frontend removes content, so it's also in charge of purging it
+        rt.spawn(run_purge_loop(db.clone(), username.clone()));
+
+        let mut discord_bot_manager = rt.block_on(async { DiscordBot::new(db.clone(), bucket.clone(), coordinator.clone(), Arc::clone(&post_now), credentials.clone(), is_first_run, &self_check_report).await });
+        let discord = std::thread::spawn(move || rt_clone.block_on(async { discord_bot_manager.run().await }));
+        handles.push(discord);
     }
 
-    Ok(())
+    // Run the content_manager and the bot concurrently
+    let mut content_manager = ContentManager::new(db, bucket, coordinator, post_now, username, credentials, CONFIG_PROFILE.is_offline, is_scrape_dry_run);
+    let scraper = std::thread::spawn(move || rt.block_on(content_manager.run(run_mode)));
+    handles.push(scraper);
+
+    for handle in handles {
+        handle.join().expect("Thread panicked");
+    }
 }
 
 fn init_logging() -> (tracing_appender::non_blocking::WorkerGuard, tracing_appender::non_blocking::WorkerGuard) {
@@ -107,7 +336,7 @@ fn init_logging() -> (tracing_appender::non_blocking::WorkerGuard, tracing_appen
         .with_target(false)
         .with_span_events(FmtSpan::ENTER | FmtSpan::CLOSE)
         .with_writer(non_blocking)
-        .with_filter(LevelFilter::WARN);
+        .with_filter(CONFIG_PROFILE.log_level());
 
     let (non_blocking, stdout_guard) = tracing_appender::non_blocking(std::io::stdout());
     let layer2 = tracing_subscriber::fmt::Layer::new()
@@ -118,7 +347,7 @@ fn init_logging() -> (tracing_appender::non_blocking::WorkerGuard, tracing_appen
         .with_target(false)
         .with_span_events(FmtSpan::ENTER | FmtSpan::CLOSE)
         .with_writer(non_blocking)
-        .with_filter(LevelFilter::WARN);
+        .with_filter(CONFIG_PROFILE.log_level());
 
     //let logger = Registry::default().with(file_layer).with(layer2);
     //LogWrapper::new(multi.clone(), logger).try_init().unwrap();
@@ -132,7 +361,7 @@ fn init_bucket(credentials: HashMap<String, String>) -> Bucket {
     let secret_key = Some(credentials.get("s3_secret_key").unwrap().as_str());
 
     let creds = Credentials::new(access_key, secret_key, None, None, None).unwrap();
-    let bucket = Bucket::new("repostrusty", Region::EuNorth1, creds).unwrap();
+    let bucket = Bucket::new(&CONFIG_PROFILE.bucket, Region::EuNorth1, creds).unwrap();
 
     bucket
 }
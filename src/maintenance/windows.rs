@@ -0,0 +1,70 @@
+use std::fs::File;
+use std::io::Read;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::MAINTENANCE_PAUSE_LEAD;
+
+const MAINTENANCE_WINDOWS_PATH: &str = "config/maintenance_windows.yaml";
+const MAINTENANCE_WINDOW_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+lazy_static! {
+    static ref MAINTENANCE_WINDOWS: Vec<MaintenanceWindow> = read_maintenance_windows(MAINTENANCE_WINDOWS_PATH);
+}
+
+#[derive(Debug, Deserialize)]
+struct InnerMaintenanceWindow {
+    start: String,
+    end: String,
+    #[serde(default)]
+    reason: String,
+}
+
+/// A period during which the scraper and the poster are paused, the times are in the timezone of the user
+#[derive(Debug, Clone)]
+pub struct MaintenanceWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub reason: String,
+}
+
+impl MaintenanceWindow {
+    /// The loops are paused a bit before the window starts, so that nothing is left half done
+    pub fn pauses_at(&self) -> DateTime<Utc> {
+        self.start - chrono::Duration::from_std(MAINTENANCE_PAUSE_LEAD).unwrap()
+    }
+}
+
+/// Returns the maintenance window the loops should currently be paused for, if any
+pub fn current_maintenance_window(now: DateTime<Utc>) -> Option<MaintenanceWindow> {
+    MAINTENANCE_WINDOWS.iter().find(|window| window.pauses_at() <= now && now < window.end).cloned()
+}
+
+/// Returns the closest maintenance window that hasn't started yet, if any
+pub fn next_maintenance_window(now: DateTime<Utc>) -> Option<MaintenanceWindow> {
+    MAINTENANCE_WINDOWS.iter().filter(|window| window.pauses_at() > now).min_by_key(|window| window.start).cloned()
+}
+
+/// Reads the maintenance windows from the config, the file is optional
+fn read_maintenance_windows(path: &str) -> Vec<MaintenanceWindow> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return vec![],
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).expect("Unable to read the maintenance windows file");
+    let inner_windows: Vec<InnerMaintenanceWindow> = serde_yaml::from_str(&contents).expect("Error parsing maintenance windows file");
+
+    inner_windows
+        .into_iter()
+        .map(|window| {
+            let start = NaiveDateTime::parse_from_str(&window.start, MAINTENANCE_WINDOW_FORMAT).expect("Invalid maintenance window start").and_utc();
+            let end = NaiveDateTime::parse_from_str(&window.end, MAINTENANCE_WINDOW_FORMAT).expect("Invalid maintenance window end").and_utc();
+            assert!(start < end, "Maintenance window ends before it starts: {} - {}", window.start, window.end);
+            MaintenanceWindow { start, end, reason: window.reason }
+        })
+        .collect()
+}
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use serenity::all::GuildId;
+use tracing_subscriber::filter::LevelFilter;
+
+use crate::GUILD_ID;
+
+const CONFIG_PROFILES_PATH: &str = "config/profiles.yaml";
+const DEFAULT_PROFILE: &str = "prod";
+
+lazy_static! {
+    pub static ref CONFIG_PROFILE: ConfigProfile = load_config_profile(CONFIG_PROFILES_PATH, &parse_profile_name());
+}
+
+/// The environment the bot runs against, selected with "--profile <name>"
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConfigProfile {
+    #[serde(skip)]
+    pub name: String,
+    /// Name of the Postgres database on the configured host
+    pub database: String,
+    pub bucket: String,
+    /// Prepended to every S3 key, so that several profiles can share the same bucket
+    #[serde(default)]
+    pub bucket_prefix: String,
+    pub guild_id: u64,
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+    /// Replaces the Instagram scraper and poster with local test data
+    #[serde(default)]
+    pub is_offline: bool,
+}
+
+impl ConfigProfile {
+    pub fn guild_id(&self) -> GuildId {
+        GuildId::new(self.guild_id)
+    }
+
+    pub fn log_level(&self) -> LevelFilter {
+        LevelFilter::from_str(&self.log_level).unwrap_or_else(|_| panic!("Invalid log level {} in profile {}", self.log_level, self.name))
+    }
+}
+
+fn default_log_level() -> String {
+    "warn".to_string()
+}
+
+/// Parses the "--profile <name>" command line argument, defaults to prod
+fn parse_profile_name() -> String {
+    let args: Vec<String> = env::args().collect();
+    match args.iter().position(|arg| arg == "--profile") {
+        Some(index) => args.get(index + 1).expect("Missing value for --profile").clone(),
+        None => DEFAULT_PROFILE.to_string(),
+    }
+}
+
+/// The profiles available without a profiles file, they can be overridden in it
+fn built_in_profiles() -> HashMap<String, ConfigProfile> {
+    let profile = |database: &str, bucket_prefix: &str, log_level: &str, is_offline: bool| ConfigProfile {
+        name: "".to_string(),
+        database: database.to_string(),
+        bucket: "repostrusty".to_string(),
+        bucket_prefix: bucket_prefix.to_string(),
+        guild_id: GUILD_ID.get(),
+        log_level: log_level.to_string(),
+        is_offline,
+    };
+
+    HashMap::from([
+        ("dev".to_string(), profile("dev", "dev/", "info", true)),
+        ("staging".to_string(), profile("staging", "staging/", "info", false)),
+        ("prod".to_string(), profile("prod", "", "warn", false)),
+    ])
+}
+
+/// Reads the requested profile from the config, falling back on the built-in ones, the file is optional
+fn load_config_profile(path: &str, name: &str) -> ConfigProfile {
+    let mut profiles = built_in_profiles();
+
+    if let Ok(mut file) = File::open(path) {
+        let mut contents = String::new();
+        file.read_to_string(&mut contents).expect("Unable to read the profiles file");
+        let configured_profiles: HashMap<String, ConfigProfile> = serde_yaml::from_str(&contents).expect("Error parsing profiles file");
+        profiles.extend(configured_profiles);
+    }
+
+    let mut profile = profiles.remove(name).unwrap_or_else(|| panic!("Unknown profile {}, check {}", name, path));
+    profile.name = name.to_string();
+    profile
+}
@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+
+use crate::profile::config_profile::CONFIG_PROFILE;
+
+/// How long the links stay valid when cdn_link_minutes isn't set
+const DEFAULT_LINK_MINUTES: i64 = 60;
+
+/// A CDN pulling from the bucket, its links can be opened outside Discord and don't go through the presigned urls of S3
+///
+/// The links are signed like the token authentication of BunnyCDN, the token being the url safe base64 of sha256(signing key + path + expiration)
+#[derive(Debug, Clone)]
+pub struct CdnLinks {
+    base_url: String,
+    signing_key: String,
+    link_lifetime: Duration,
+}
+
+/// Configured as "cdn_base_url", "cdn_signing_key" and "cdn_link_minutes" in the credentials, None when there's no base url
+///
+/// Without a signing key the links never expire, so the CDN has to be kept private some other way
+pub fn read_cdn_links(credentials: &HashMap<String, String>) -> Option<CdnLinks> {
+    let base_url = credentials.get("cdn_base_url").filter(|base_url| !base_url.is_empty())?;
+    let link_minutes = credentials.get("cdn_link_minutes").and_then(|minutes| minutes.parse::<i64>().ok()).filter(|minutes| *minutes > 0).unwrap_or(DEFAULT_LINK_MINUTES);
+
+    Some(CdnLinks {
+        base_url: base_url.trim_end_matches('/').to_string(),
+        signing_key: credentials.get("cdn_signing_key").cloned().unwrap_or_default(),
+        link_lifetime: Duration::minutes(link_minutes),
+    })
+}
+
+impl CdnLinks {
+    /// Returns the public link to a file of the bucket, e.g. "repostrusty/C7x9Yb1Nq2w.mp4", along with when it expires if it's signed
+    pub fn link(&self, path_to_file: &str, now: DateTime<Utc>) -> (String, Option<DateTime<Utc>>) {
+        let path = format!("/{}{}", CONFIG_PROFILE.bucket_prefix, path_to_file);
+        if self.signing_key.is_empty() {
+            return (format!("{}{}", self.base_url, path), None);
+        }
+
+        let expires_at = now + self.link_lifetime;
+        let expires = expires_at.timestamp();
+        let digest = Sha256::digest(format!("{}{}{}", self.signing_key, path, expires).as_bytes());
+        let token = URL_SAFE_NO_PAD.encode(digest);
+        (format!("{}{}?token={}&expires={}", self.base_url, path, token, expires), Some(expires_at))
+    }
+}
@@ -2,7 +2,9 @@ use s3::bucket::Bucket;
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 
-use crate::{IS_OFFLINE, S3_EXPIRATION_TIME};
+use crate::profile::config_profile::CONFIG_PROFILE;
+use crate::supervisor::watchdog::with_watchdog;
+use crate::{S3_EXPIRATION_TIME, S3_UPLOAD_TIMEOUT};
 
 //noinspection ALL
 pub async fn upload_to_s3(bucket: &Bucket, video_path: String, path_to_file: String, delete_from_local_storage: bool) -> Result<String, Box<dyn std::error::Error>> {
@@ -12,17 +14,16 @@ pub async fn upload_to_s3(bucket: &Bucket, video_path: String, path_to_file: Str
     let mut file_content = Vec::new();
     file.read_to_end(&mut file_content).await.unwrap();
 
-    let mut final_path = path_to_file;
-    if IS_OFFLINE {
-        final_path = format!("dev/{}", final_path);
-    }
+    let final_path = format!("{}{}", CONFIG_PROFILE.bucket_prefix, path_to_file);
 
-    match bucket.put_object_with_content_type(final_path.clone(), &file_content, "video/mp4").await {
+    let content_type = content_type_for(&final_path);
+    let operation = format!("The upload of {} to s3", video_path);
+    match with_watchdog(&operation, S3_UPLOAD_TIMEOUT, bucket.put_object_with_content_type(final_path.clone(), &file_content, content_type)).await? {
         Ok(_) => {}
         Err(e) => {
             tracing::warn!("Error uploading file to s3, retrying...\n{}", e);
             tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-            match bucket.put_object_with_content_type(final_path.clone(), &file_content, "video/mp4").await {
+            match with_watchdog(&operation, S3_UPLOAD_TIMEOUT, bucket.put_object_with_content_type(final_path.clone(), &file_content, content_type)).await? {
                 Ok(_) => {}
                 Err(e) => {
                     tracing::error!("Error uploading file to s3: {}", e);
@@ -41,22 +42,30 @@ pub async fn upload_to_s3(bucket: &Bucket, video_path: String, path_to_file: Str
 }
 
 pub async fn delete_from_s3(bucket: &Bucket, path_to_file: String) -> Result<(), Box<dyn std::error::Error>> {
-    let mut final_path = path_to_file;
-    if IS_OFFLINE {
-        final_path = format!("dev/{}", final_path);
-    }
+    let final_path = format!("{}{}", CONFIG_PROFILE.bucket_prefix, path_to_file);
     bucket.delete_object(final_path).await.unwrap();
 
     Ok(())
 }
 
 pub async fn update_presigned_url(bucket: &Bucket, path_to_file: String) -> Result<String, Box<dyn std::error::Error>> {
-    let mut final_path = path_to_file;
-    if IS_OFFLINE {
-        final_path = format!("dev/{}", final_path);
-    }
+    let final_path = format!("{}{}", CONFIG_PROFILE.bucket_prefix, path_to_file);
 
     let url = bucket.presign_get(final_path.clone(), S3_EXPIRATION_TIME, None).await.unwrap();
 
     Ok(url)
 }
+
+fn content_type_for(path: &str) -> &'static str {
+    if path.ends_with(".webp") {
+        "image/webp"
+    } else if path.ends_with(".gif") {
+        "image/gif"
+    } else if path.ends_with(".png") {
+        "image/png"
+    } else if path.ends_with(".jpg") || path.ends_with(".jpeg") {
+        "image/jpeg"
+    } else {
+        "video/mp4"
+    }
+}
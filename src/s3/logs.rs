@@ -0,0 +1,93 @@
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::{DateTime, Duration, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use s3::bucket::Bucket;
+
+use crate::profile::config_profile::CONFIG_PROFILE;
+use crate::supervisor::watchdog::with_watchdog;
+use crate::{LOG_SHIPPING_INTERVAL, S3_UPLOAD_TIMEOUT};
+
+/// Where the rolling appender writes, see init_logging
+const LOGS_DIR: &str = "logs";
+/// Prefix of the shipped logs in the bucket, under the prefix of the profile
+const LOGS_PREFIX: &str = "logs/";
+
+/// The logs are shared by all the accounts of the process, so they are shipped by a single one of them
+static IS_SHIPPING_LOGS: AtomicBool = AtomicBool::new(false);
+
+/// Moves the rotated log files to the bucket every hour, gzipped, so that they don't fill up the disk of small servers
+///
+/// The shipped logs older than the retention are removed from the bucket
+pub async fn run_log_shipping_loop(bucket: Bucket, retention_days: i64) {
+    if IS_SHIPPING_LOGS.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    loop {
+        match ship_rotated_logs(&bucket).await {
+            Ok(0) => {}
+            Ok(shipped_logs) => tracing::info!("Shipped {} log files to s3", shipped_logs),
+            Err(e) => tracing::error!("Error while shipping the logs to s3: {}", e),
+        }
+        if let Err(e) = remove_expired_logs(&bucket, retention_days).await {
+            tracing::error!("Error while removing the expired logs from s3: {}", e);
+        }
+
+        tokio::time::sleep(LOG_SHIPPING_INTERVAL).await;
+    }
+}
+
+/// Returns how many files were shipped, the latest file is left alone since it's still being written to
+async fn ship_rotated_logs(bucket: &Bucket) -> anyhow::Result<usize> {
+    let mut log_files = Vec::new();
+    let mut entries = tokio::fs::read_dir(LOGS_DIR).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() {
+            log_files.push(entry.file_name().to_string_lossy().to_string());
+        }
+    }
+    // The files are suffixed with their hour, e.g. rolling.log.2024-05-12-17
+    log_files.sort();
+    log_files.pop();
+
+    for log_file in &log_files {
+        let path = format!("{LOGS_DIR}/{log_file}");
+        let compressed = tokio::task::spawn_blocking({
+            let path = path.clone();
+            move || gzip_file(&path)
+        })
+        .await??;
+
+        let key = format!("{}{LOGS_PREFIX}{log_file}.gz", CONFIG_PROFILE.bucket_prefix);
+        with_watchdog(&format!("The upload of {} to s3", path), S3_UPLOAD_TIMEOUT, bucket.put_object_with_content_type(key, &compressed, "application/gzip")).await??;
+        tokio::fs::remove_file(&path).await?;
+    }
+
+    Ok(log_files.len())
+}
+
+fn gzip_file(path: &str) -> std::io::Result<Vec<u8>> {
+    let contents = std::fs::read(path)?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&contents)?;
+    encoder.finish()
+}
+
+async fn remove_expired_logs(bucket: &Bucket, retention_days: i64) -> anyhow::Result<()> {
+    let expired_before = Utc::now() - Duration::days(retention_days);
+
+    let prefix = format!("{}{LOGS_PREFIX}", CONFIG_PROFILE.bucket_prefix);
+    for list_result in bucket.list(prefix, None).await? {
+        for object in list_result.contents {
+            let is_expired = DateTime::parse_from_rfc3339(&object.last_modified).is_ok_and(|last_modified| last_modified < expired_before);
+            if is_expired {
+                bucket.delete_object(&object.key).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
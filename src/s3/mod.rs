@@ -1 +1,3 @@
+pub mod cdn;
 pub mod helper;
+pub mod logs;
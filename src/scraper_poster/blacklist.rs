@@ -0,0 +1,16 @@
+use crate::database::database::BlacklistEntry;
+
+pub(crate) const BLACKLIST_KINDS: [&str; 3] = ["author", "shortcode", "keyword"];
+
+/// Returns the entry of the blacklist the content falls under, the authors and keywords are matched regardless of the case
+///
+/// The caption isn't known before the content is downloaded, the keywords are only checked once it is
+pub(crate) fn find_blacklist_match<'a>(blacklist: &'a [BlacklistEntry], authors: &[&str], shortcode: &str, caption: Option<&str>) -> Option<&'a BlacklistEntry> {
+    let caption = caption.map(|caption| caption.to_lowercase());
+    blacklist.iter().find(|blacklist_entry| match blacklist_entry.kind.as_str() {
+        "author" => authors.iter().any(|author| author.eq_ignore_ascii_case(&blacklist_entry.value)),
+        "shortcode" => blacklist_entry.value == shortcode,
+        "keyword" => caption.as_ref().is_some_and(|caption| caption.contains(&blacklist_entry.value.to_lowercase())),
+        _ => false,
+    })
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::scraper_poster::emoji_pack::SIGNATURE_STEP;
+
+/// The processing steps that check whether they're enabled for the post, the other ones can't be canaried
+const CANARY_CAPABLE_STEPS: [&str; 1] = [SIGNATURE_STEP];
+
+/// A processing step that is only applied to every Nth post until the given date, after which it applies to every post
+///
+/// Configured in the credentials as "canary_steps", e.g. "signature:5:2026-11-01"
+#[derive(Debug, Clone)]
+pub struct CanaryStep {
+    pub name: String,
+    pub every_nth: i64,
+    pub until: DateTime<Utc>,
+}
+
+pub fn read_canary_steps(credentials: &HashMap<String, String>) -> Vec<CanaryStep> {
+    configured_canary_steps(credentials)
+        .filter_map(|step| match parse_canary_step(step) {
+            Ok(canary_step) => Some(canary_step),
+            Err(e) => {
+                tracing::warn!("Ignoring the canary step \"{}\", it {}", step, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// Checked at startup, so that a step that would never be canaried stands out in the self-check instead of being silently ignored
+pub fn validate_canary_steps(credentials: &HashMap<String, String>) -> Result<(), String> {
+    configured_canary_steps(credentials).try_for_each(|step| parse_canary_step(step).map(|_| ()).map_err(|e| format!("\"{}\" {}", step, e)))
+}
+
+fn configured_canary_steps(credentials: &HashMap<String, String>) -> impl Iterator<Item = &str> {
+    credentials.get("canary_steps").into_iter().flat_map(|canary_steps| canary_steps.split(',')).map(str::trim).filter(|step| !step.is_empty())
+}
+
+fn parse_canary_step(step: &str) -> Result<CanaryStep, String> {
+    let parts: Vec<&str> = step.split(':').collect();
+    let canary_step = match parts.as_slice() {
+        [name, every_nth, until] => every_nth.parse::<i64>().ok().filter(|every_nth| *every_nth > 0).zip(NaiveDate::parse_from_str(until, "%Y-%m-%d").ok()).map(|(every_nth, until)| CanaryStep {
+            name: name.to_string(),
+            every_nth,
+            until: until.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+        }),
+        _ => None,
+    };
+
+    match canary_step {
+        None => Err("is invalid, expected name:every_nth:YYYY-MM-DD".to_string()),
+        Some(canary_step) if !CANARY_CAPABLE_STEPS.contains(&canary_step.name.as_str()) => Err(format!("can't be canaried, only {} can", CANARY_CAPABLE_STEPS.join(", "))),
+        Some(canary_step) => Ok(canary_step),
+    }
+}
+
+/// Whether a processing step should be applied to the given post, steps that aren't being canaried always apply
+pub fn is_step_enabled(canary_steps: &[CanaryStep], step_name: &str, post_number: i64, now: DateTime<Utc>) -> bool {
+    match canary_steps.iter().find(|step| step.name == step_name) {
+        Some(step) if now < step.until => post_number % step.every_nth == 0,
+        _ => true,
+    }
+}
+
+/// Whether a processing step is still being rolled out, the posts it was applied to are then tagged in the post metrics
+pub fn is_step_canaried(canary_steps: &[CanaryStep], step_name: &str, now: DateTime<Utc>) -> bool {
+    canary_steps.iter().any(|step| step.name == step_name && now < step.until)
+}
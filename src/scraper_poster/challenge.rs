@@ -0,0 +1,166 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use instagram_scraper_rs::InstagramScraper;
+use lazy_static::lazy_static;
+use regex::Regex;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Client, RequestBuilder};
+use reqwest_cookie_store::CookieStoreMutex;
+use serde_json::Value;
+use serenity::all::MessageId;
+use tokio::time::sleep;
+
+use crate::database::database::LoginChallenge;
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::stories::INSTAGRAM_APP_ID;
+use crate::scraper_poster::utils::{save_cookie_store_to_json, set_bot_status_halted, set_bot_status_operational};
+use crate::{LOGIN_CHALLENGE_TIMEOUT, SCRAPER_REFRESH_RATE};
+
+/// The checkpoints are answered through the private API, the library has no support for them
+const CHALLENGE_API_URL: &str = "https://i.instagram.com/api/v1";
+/// Picked when Instagram lets choose where to send the code, "0" would be by SMS
+const EMAIL_CHOICE: &str = "1";
+
+lazy_static! {
+    static ref CHECKPOINT_URL_REGEX: Regex = Regex::new(r"/challenge/[\w\-/]+").unwrap();
+}
+
+/// Returns the url of the checkpoint the login ran into, if that's why it failed
+fn checkpoint_url(login_error: &str) -> Option<String> {
+    CHECKPOINT_URL_REGEX.find(login_error).map(|checkpoint_url| checkpoint_url.as_str().to_string())
+}
+
+impl ContentManager {
+    /// Goes through the checkpoint Instagram put on the login, with the security code the moderators enter from Discord
+    ///
+    /// Returns whether the account is logged in afterwards, the login errors that aren't a checkpoint are left to the caller
+    pub(crate) async fn resolve_login_challenge(&self, scraper: &mut InstagramScraper, login_error: &str) -> bool {
+        let Some(checkpoint_url) = checkpoint_url(login_error) else {
+            return false;
+        };
+
+        let client = match self.challenge_client(Arc::clone(&scraper.session.cookie_store)).await {
+            Ok(client) => client,
+            Err(e) => {
+                self.println(&format!("Couldn't set up the checkpoint requests: {}", e));
+                return false;
+            }
+        };
+
+        let (challenge_type, contact_point) = match request_challenge_code(&client, &checkpoint_url).await {
+            Ok(sent_to) => sent_to,
+            Err(e) => {
+                self.println(&format!("Couldn't ask for the security code of the checkpoint: {}", e));
+                return false;
+            }
+        };
+        self.println(&format!("Instagram sent a security code by {} to {}, waiting for it on Discord...", challenge_type, contact_point));
+
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+        let challenge = LoginChallenge {
+            username: self.username.clone(),
+            challenge_type,
+            contact_point,
+            checkpoint_url: checkpoint_url.clone(),
+            status: "awaiting_code".to_string(),
+            security_code: "".to_string(),
+            message_id: MessageId::new(1),
+            requested_at: now_in_my_timezone(&user_settings).to_rfc3339(),
+        };
+        tx.save_login_challenge(&challenge).await;
+        set_bot_status_halted(&mut tx).await;
+
+        let expires_at = Instant::now() + LOGIN_CHALLENGE_TIMEOUT;
+        loop {
+            sleep(SCRAPER_REFRESH_RATE).await;
+
+            let Some(challenge) = tx.load_login_challenge().await else {
+                return false;
+            };
+            if Instant::now() >= expires_at {
+                self.println("Nobody entered the security code in time");
+                tx.update_login_challenge_status("failed").await;
+                return false;
+            }
+            if challenge.status != "code_submitted" {
+                continue;
+            }
+
+            match submit_challenge_code(&client, &checkpoint_url, &challenge.security_code).await {
+                Ok(_) => break,
+                Err(e) => {
+                    self.println(&format!("The security code was rejected: {}", e));
+                    tx.update_login_challenge_status("code_rejected").await;
+                }
+            }
+        }
+
+        // With the checkpoint cleared the login goes through as usual
+        let username = self.credentials.get("username").unwrap().clone();
+        let password = self.credentials.get("password").unwrap().clone();
+        scraper.authenticate_with_login(username, password);
+        match scraper.login().await {
+            Ok(_) => {
+                self.println("Logged in successfully");
+                tx.update_login_challenge_status("resolved").await;
+                set_bot_status_operational(&mut tx).await;
+                save_cookie_store_to_json(&self.cookie_store_path, Arc::clone(&scraper.session.cookie_store)).await;
+                true
+            }
+            Err(e) => {
+                self.println(&format!(" Login failed after the checkpoint: {}", e));
+                tx.update_login_challenge_status("failed").await;
+                false
+            }
+        }
+    }
+
+    /// Returns a client sharing the session of the scraper, through its proxy if there's one
+    async fn challenge_client(&self, cookie_store: Arc<CookieStoreMutex>) -> anyhow::Result<Client> {
+        let csrf_token = cookie_store.lock().unwrap().iter_unexpired().find(|cookie| cookie.name() == "csrftoken").map(|cookie| cookie.value().to_string()).unwrap_or_default();
+
+        let mut headers = HeaderMap::new();
+        headers.insert("X-IG-App-ID", HeaderValue::from_static(INSTAGRAM_APP_ID));
+        headers.insert("X-CSRFToken", HeaderValue::from_str(&csrf_token)?);
+
+        let mut client = Client::builder().cookie_provider(cookie_store).default_headers(headers);
+        if let Some(proxy) = self.proxies.get(*self.active_proxy.lock().await) {
+            client = client.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        Ok(client.build()?)
+    }
+}
+
+/// Asks Instagram to send the security code, returns how and where it was sent
+async fn request_challenge_code(client: &Client, checkpoint_url: &str) -> anyhow::Result<(String, String)> {
+    let url = format!("{CHALLENGE_API_URL}{checkpoint_url}");
+
+    // Some checkpoints send the code right away, the others first let choose between SMS and email
+    let mut step = send_challenge_request(client.get(&url)).await?;
+    if step["step_name"] == "select_verify_method" {
+        let choice = step["step_data"]["choice"].as_str().unwrap_or(EMAIL_CHOICE).to_string();
+        step = send_challenge_request(client.post(&url).form(&[("choice", choice)])).await?;
+    }
+
+    let challenge_type = if step["step_name"].as_str().unwrap_or_default().contains("email") { "email" } else { "sms" };
+    let contact_point = step["step_data"]["contact_point"].as_str().unwrap_or("the account").to_string();
+    Ok((challenge_type.to_string(), contact_point))
+}
+
+async fn submit_challenge_code(client: &Client, checkpoint_url: &str, security_code: &str) -> anyhow::Result<()> {
+    send_challenge_request(client.post(format!("{CHALLENGE_API_URL}{checkpoint_url}")).form(&[("security_code", security_code)])).await?;
+    Ok(())
+}
+
+/// Returns the response of the challenge API, the refusals come with a message from Instagram
+async fn send_challenge_request(request: RequestBuilder) -> anyhow::Result<Value> {
+    let response = request.send().await?.text().await?;
+    let response: Value = serde_json::from_str(&response)?;
+    if response["status"] == "fail" {
+        anyhow::bail!("{}", response["message"].as_str().unwrap_or("unknown error"));
+    }
+    Ok(response)
+}
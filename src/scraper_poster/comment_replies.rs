@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use rand::prelude::{SliceRandom, StdRng};
+use rand::SeedableRng;
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::database::database::CommentReply;
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::graph_api::{media_comments, reply_to_comment};
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::utils::jittered_duration;
+use crate::{COMMENT_REPLY_CHECK_INTERVAL, COMMENT_REPLY_SPACING};
+
+const COMMENT_REPLIES_PATH: &str = "config/comment_replies.yaml";
+
+/// Replies left on the first comments of the new reels of an account, e.g. "Follow for more!"
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CommentReplies {
+    #[serde(default)]
+    pub enabled: bool,
+    /// One of them is picked at random for each reply, "{commenter}" is replaced with a mention of the author of the comment
+    pub templates: Vec<String>,
+    /// How many of the first comments of each reel are replied to
+    #[serde(default = "default_first_comments")]
+    pub first_comments: usize,
+    /// How long after being published the comments of a reel are still looked at
+    #[serde(default = "default_watch_hours")]
+    pub watch_hours: i64,
+}
+
+fn default_first_comments() -> usize {
+    5
+}
+
+fn default_watch_hours() -> i64 {
+    24
+}
+
+/// Reads the comment replies of the given account, the file is optional
+pub async fn read_comment_replies(username: &str) -> CommentReplies {
+    let mut file = match File::open(COMMENT_REPLIES_PATH).await {
+        Ok(file) => file,
+        Err(_) => return CommentReplies::default(),
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.expect("Unable to read the comment replies file");
+    let mut comment_replies: HashMap<String, CommentReplies> = serde_yaml::from_str(&contents).expect("Error parsing the comment replies file");
+    comment_replies.remove(username).unwrap_or_default()
+}
+
+impl ContentManager {
+    /// Replies to the first comments of the reels published recently, the file is read again on each pass so that the replies can be toggled without a restart
+    pub fn comment_reply_loop(&self) -> JoinHandle<anyhow::Result<()>> {
+        let cloned_self = self.clone();
+        tokio::spawn(async move {
+            loop {
+                sleep(jittered_duration(COMMENT_REPLY_CHECK_INTERVAL)).await;
+
+                let comment_replies = read_comment_replies(&cloned_self.username).await;
+                if comment_replies.enabled && !comment_replies.templates.is_empty() {
+                    cloned_self.reply_to_first_comments(&comment_replies).await;
+                }
+            }
+        })
+    }
+
+    async fn reply_to_first_comments(&self, comment_replies: &CommentReplies) {
+        let access_token = self.credentials.get("fb_access_token").unwrap();
+        let own_username = self.credentials.get("username").unwrap();
+        let mut rng = StdRng::from_entropy();
+
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+        let watched_since = now_in_my_timezone(&user_settings) - Duration::hours(comment_replies.watch_hours);
+        let recent_reels = tx
+            .load_posted_content()
+            .await
+            .into_iter()
+            .filter(|published_content| !published_content.media_id.is_empty())
+            .filter(|published_content| DateTime::parse_from_rfc3339(&published_content.published_at).is_ok_and(|published_at| published_at.with_timezone(&Utc) >= watched_since));
+
+        for published_content in recent_reels {
+            let replied_comment_ids = tx.load_replied_comment_ids(&published_content.media_id).await;
+            if replied_comment_ids.len() >= comment_replies.first_comments {
+                continue;
+            }
+
+            let mut comments = match media_comments(&published_content.media_id, access_token).await {
+                Ok(comments) => comments,
+                Err(e) => {
+                    self.println(&format!("Couldn't fetch the comments of {}: {}", published_content.original_shortcode, e));
+                    continue;
+                }
+            };
+            // The comment left by the poster right after publishing isn't one of them
+            comments.retain(|comment| &comment.username != own_username);
+            comments.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+            for comment in comments.iter().take(comment_replies.first_comments).filter(|comment| !replied_comment_ids.contains(&comment.id)) {
+                let template = comment_replies.templates.choose(&mut rng).unwrap();
+                let message = template.replace("{commenter}", &format!("@{}", comment.username));
+
+                if let Err(e) = reply_to_comment(&comment.id, access_token, &message).await {
+                    self.println(&format!("Couldn't reply to a comment on {}: {}", published_content.original_shortcode, e));
+                    return;
+                }
+                self.println(&format!("Replied to the comment of {} on {}", comment.username, published_content.original_shortcode));
+
+                let comment_reply = CommentReply {
+                    username: self.username.clone(),
+                    media_id: published_content.media_id.clone(),
+                    comment_id: comment.id.clone(),
+                    replied_at: now_in_my_timezone(&user_settings).to_rfc3339(),
+                };
+                tx.save_comment_reply(&comment_reply).await;
+
+                // Replying too fast is what gets an account flagged for spam
+                sleep(jittered_duration(COMMENT_REPLY_SPACING)).await;
+            }
+        }
+    }
+}
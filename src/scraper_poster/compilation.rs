@@ -0,0 +1,126 @@
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Datelike, Duration, IsoWeek};
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::download_manager::download_media;
+use crate::scraper_poster::graph_api::media_engagement;
+use crate::scraper_poster::metadata::SourcePostMetadata;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::utils::jittered_duration;
+use crate::video::processing::{build_compilation, COMPILATION_STEP};
+use crate::COMPILATION_CHECK_INTERVAL;
+
+/// The compilations go through the review under a shortcode of their own, e.g. "compilation_2026-W41"
+const COMPILATION_SHORTCODE_PREFIX: &str = "compilation_";
+const DEFAULT_COMPILATION_CLIPS: usize = 5;
+/// A compilation of a single reel would just be the reel again
+const MIN_COMPILATION_CLIPS: usize = 2;
+
+pub(crate) fn is_compilation_shortcode(shortcode: &str) -> bool {
+    shortcode.starts_with(COMPILATION_SHORTCODE_PREFIX)
+}
+
+fn compilation_shortcode(week: IsoWeek) -> String {
+    format!("{}{}-W{:02}", COMPILATION_SHORTCODE_PREFIX, week.year(), week.week())
+}
+
+impl ContentManager {
+    /// Stitches the best reels of the previous week together once it's over, the compilation then waits in the review channel like the scraped content
+    pub fn compilation_loop(&self) -> JoinHandle<anyhow::Result<()>> {
+        let cloned_self = self.clone();
+        tokio::spawn(async move {
+            loop {
+                sleep(jittered_duration(COMPILATION_CHECK_INTERVAL)).await;
+                cloned_self.compile_previous_week().await;
+            }
+        })
+    }
+
+    /// The reels are ranked by their likes and comments, the compilations of the previous weeks are left out
+    async fn compile_previous_week(&self) {
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+        let week = (now_in_my_timezone(&user_settings) - Duration::weeks(1)).iso_week();
+        let shortcode = compilation_shortcode(week);
+
+        // Already waiting for the sender loop, e.g. while the bot is halted
+        let is_handed_over = self.latest_content_mutex.lock().await.as_ref().is_some_and(|(_, _, _, latest_shortcode, _)| *latest_shortcode == shortcode);
+        if is_handed_over || tx.does_content_exist_with_shortcode(&shortcode).await {
+            return;
+        }
+
+        let published_content: Vec<_> = tx
+            .load_posted_content()
+            .await
+            .into_iter()
+            .filter(|published_content| !published_content.is_simulated && !published_content.media_id.is_empty() && !is_compilation_shortcode(&published_content.original_shortcode))
+            .filter(|published_content| DateTime::parse_from_rfc3339(&published_content.published_at).is_ok_and(|published_at| published_at.iso_week() == week))
+            .collect();
+        if published_content.len() < MIN_COMPILATION_CLIPS {
+            return;
+        }
+
+        let access_token = self.credentials.get("fb_access_token").unwrap();
+        let mut clips = Vec::new();
+        for published_content in published_content {
+            self.coordinator.increment_counter("instagram_requests", StdDuration::from_secs(60 * 60)).await;
+            match media_engagement(&published_content.media_id, access_token).await {
+                Ok(engagement) if !engagement.media_url.is_empty() => clips.push((published_content, engagement)),
+                Ok(_) => self.println(&format!("The video of {} can't be downloaded, leaving it out of the compilation", published_content.original_shortcode)),
+                Err(e) => self.println(&format!("Couldn't look up how {} did, leaving it out of the compilation: {}", published_content.original_shortcode, e)),
+            }
+        }
+        if clips.len() < MIN_COMPILATION_CLIPS {
+            return;
+        }
+
+        let clip_count = self.credentials.get("compilation_clips").and_then(|clips| clips.parse::<usize>().ok()).unwrap_or(DEFAULT_COMPILATION_CLIPS).max(MIN_COMPILATION_CLIPS);
+        clips.sort_by_key(|(_, engagement)| -(engagement.likes + engagement.comments));
+        clips.truncate(clip_count);
+
+        let mut clip_paths = Vec::new();
+        for (i, (published_content, engagement)) in clips.iter().enumerate() {
+            let clip_path = format!("{}_{}.mp4", shortcode, i);
+            if let Err(e) = download_media(&engagement.media_url, &clip_path).await {
+                self.println(&format!("Error while downloading {} for the compilation, trying again later: {}", published_content.original_shortcode, e));
+                remove_clips(&clip_paths).await;
+                return;
+            }
+            clip_paths.push(clip_path);
+        }
+
+        let filename = format!("{}.mp4", shortcode);
+        let compilation = build_compilation(&clip_paths, &filename);
+        remove_clips(&clip_paths).await;
+        if let Err(e) = compilation {
+            self.println(&format!("Error while building the compilation of {}-W{:02}, trying again later: {}", week.year(), week.week(), e));
+            return;
+        }
+
+        let mut authors: Vec<String> = Vec::new();
+        for (published_content, _) in &clips {
+            if !authors.contains(&published_content.original_author) {
+                authors.push(published_content.original_author.clone());
+            }
+        }
+        let caption = format!("Best of the week 🏆\n\nClips by {}", authors.iter().map(|author| format!("@{}", author)).collect::<Vec<String>>().join(", "));
+
+        let shortcodes = clips.iter().map(|(published_content, _)| published_content.original_shortcode.as_str()).collect::<Vec<&str>>().join(",");
+        let source_post_metadata = SourcePostMetadata {
+            processing_steps: vec![format!("{} {}", COMPILATION_STEP, shortcodes)],
+            ..SourcePostMetadata::default()
+        };
+        self.println(&format!("Built the compilation of {}-W{:02} out of {}", week.year(), week.week(), shortcodes));
+
+        self.hand_over_content(&mut tx, (filename, caption, self.username.clone(), shortcode, source_post_metadata)).await;
+    }
+}
+
+async fn remove_clips(clip_paths: &[String]) {
+    for clip_path in clip_paths {
+        let _ = tokio::fs::remove_file(format!("temp/{clip_path}")).await;
+    }
+}
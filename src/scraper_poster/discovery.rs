@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use tokio::time::sleep;
+
+use crate::database::database::{SkipReason, SourceAccount};
+use crate::scraper_poster::graph_api::{hashtag_media, search_hashtag};
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::utils::pause_scraper_if_needed;
+use crate::{FETCH_SLEEP_LEN, HASHTAG_FETCH_SLEEP_LEN, MAX_HASHTAG_REQUESTS_PER_HOUR, MAX_HASHTAG_SEARCHES_PER_WEEK};
+
+const HASHTAGS_TO_DISCOVER_PATH: &str = "config/hashtags_to_discover.yaml";
+
+lazy_static! {
+    /// The budgets of each account when Redis isn't configured, with the count and the end of the current window
+    static ref LOCAL_BUDGETS: Mutex<HashMap<String, (u64, Instant)>> = Mutex::new(HashMap::new());
+}
+
+/// Which of the two lists Instagram keeps for each hashtag the posts are taken from
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashtagMedia {
+    #[default]
+    Top,
+    Recent,
+}
+
+impl HashtagMedia {
+    pub fn as_edge(&self) -> &'static str {
+        match self {
+            HashtagMedia::Top => "top_media",
+            HashtagMedia::Recent => "recent_media",
+        }
+    }
+}
+
+/// A hashtag whose posts are scraped along with the ones of the accounts in accounts_to_scrape.yaml
+#[derive(Debug, Clone, Deserialize)]
+pub struct HashtagToDiscover {
+    pub hashtag: String,
+    #[serde(default)]
+    pub media: HashtagMedia,
+    /// How many videos are taken from the hashtag at each iteration
+    pub max_posts: usize,
+    /// The category of hashtags.yaml used when the caption has none, like in accounts_to_scrape.yaml
+    pub hashtags: String,
+}
+
+impl HashtagToDiscover {
+    /// The content found through the hashtag is credited to it, the "#" keeps it apart from the accounts
+    pub fn profile(&self) -> String {
+        format!("#{}", self.hashtag.trim_start_matches('#'))
+    }
+}
+
+/// Reads the hashtags to discover for the given account, the file is optional
+pub async fn read_hashtags_to_discover(username: &str) -> Vec<HashtagToDiscover> {
+    let mut file = match File::open(HASHTAGS_TO_DISCOVER_PATH).await {
+        Ok(file) => file,
+        Err(_) => return vec![],
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.expect("Unable to read the hashtags to discover file");
+    let mut hashtags: HashMap<String, Vec<HashtagToDiscover>> = serde_yaml::from_str(&contents).expect("Error parsing the hashtags to discover file");
+    hashtags.remove(username).unwrap_or_default()
+}
+
+impl ContentManager {
+    /// Looks up the ids of the hashtags, they are kept with the source accounts so that each hashtag is only searched once
+    pub(crate) async fn resolve_hashtags(&mut self, hashtags_to_discover: &[HashtagToDiscover], hashtags_being_scraped: &mut Vec<(HashtagToDiscover, SourceAccount)>) {
+        if hashtags_to_discover.is_empty() {
+            return;
+        }
+
+        let mut tx = self.database.begin_transaction().await;
+        let user_id = self.credentials.get("instagram_business_account_id").unwrap().clone();
+        let access_token = self.credentials.get("fb_access_token").unwrap().clone();
+
+        for hashtag_to_discover in hashtags_to_discover {
+            let profile = hashtag_to_discover.profile();
+            if let Some(source_account) = tx.get_source_account(&profile).await {
+                hashtags_being_scraped.push((hashtag_to_discover.clone(), source_account));
+                continue;
+            }
+
+            pause_scraper_if_needed(&mut tx).await;
+            self.wait_for_hashtag_search_budget().await;
+
+            match search_hashtag(&user_id, &access_token, profile.trim_start_matches('#')).await {
+                Ok(Some(hashtag_id)) => {
+                    let source_account = SourceAccount {
+                        username: self.username.clone(),
+                        profile: profile.clone(),
+                        user_id: hashtag_id,
+                        display_name: profile.clone(),
+                        updated_at: Utc::now().to_rfc3339(),
+                    };
+                    tx.save_source_account(&source_account).await;
+                    hashtags_being_scraped.push((hashtag_to_discover.clone(), source_account));
+                    self.println(&format!("Resolved hashtag {}", profile));
+                }
+                Ok(None) => self.println(&format!("Hashtag {} doesn't exist, skipping it", profile)),
+                Err(e) => self.println(&format!("Error looking up hashtag {}: {}", profile, e)),
+            }
+        }
+    }
+
+    /// Fetches the latest videos of each hashtag, up to its cap
+    pub(crate) async fn fetch_hashtag_posts(&mut self, hashtags_being_scraped: &[(HashtagToDiscover, SourceAccount)], discovered_posts: &mut Vec<(SourceAccount, String)>) {
+        if hashtags_being_scraped.is_empty() {
+            return;
+        }
+
+        let mut tx = self.database.begin_transaction().await;
+        let user_id = self.credentials.get("instagram_business_account_id").unwrap().clone();
+        let access_token = self.credentials.get("fb_access_token").unwrap().clone();
+
+        self.println("Fetching hashtag posts...");
+        for (hashtags_scraped, (hashtag_to_discover, source_account)) in hashtags_being_scraped.iter().enumerate() {
+            pause_scraper_if_needed(&mut tx).await;
+            self.wait_for_hashtag_budget().await;
+
+            self.println(&format!("{}/{} Retrieving posts from hashtag {}", hashtags_scraped + 1, hashtags_being_scraped.len(), source_account.display_name));
+            match hashtag_media(&user_id, &access_token, &source_account.user_id, hashtag_to_discover.media.as_edge()).await {
+                Ok(posts) => {
+                    let posts_len = posts.len();
+                    let videos: Vec<String> = posts.into_iter().filter(|post| post.is_video).map(|post| post.shortcode).collect();
+                    tx.record_skipped_content(SkipReason::NotAVideo, (posts_len - videos.len()) as i32).await;
+
+                    discovered_posts.extend(videos.into_iter().take(hashtag_to_discover.max_posts).map(|shortcode| (source_account.clone(), shortcode)));
+                }
+                // The hashtags don't go through the scraper session, so an error here doesn't halt the bot
+                Err(e) => self.println(&format!("Error fetching posts from hashtag {}: {}", source_account.display_name, e)),
+            }
+
+            self.randomized_sleep(HASHTAG_FETCH_SLEEP_LEN.as_secs()).await;
+        }
+    }
+
+    /// Counts a request to the hashtag endpoints and waits while their hourly budget is exhausted
+    ///
+    /// Shared between the processes when Redis is configured, otherwise only counted within this one
+    async fn wait_for_hashtag_budget(&self) {
+        self.wait_for_budget("hashtag_requests", MAX_HASHTAG_REQUESTS_PER_HOUR, Duration::from_secs(60 * 60)).await;
+    }
+
+    /// Instagram only allows a limited amount of different hashtags to be looked up per week
+    async fn wait_for_hashtag_search_budget(&self) {
+        self.wait_for_budget("hashtag_searches", MAX_HASHTAG_SEARCHES_PER_WEEK, Duration::from_secs(60 * 60 * 24 * 7)).await;
+    }
+
+    async fn wait_for_budget(&self, counter: &str, budget: u64, window: Duration) {
+        let mut is_waiting = false;
+        while !self.try_spend(counter, budget, window).await {
+            if !is_waiting {
+                self.println(&format!("Reached the {} budget, waiting...", counter.replace('_', " ")));
+                is_waiting = true;
+            }
+            sleep(FETCH_SLEEP_LEN).await;
+        }
+    }
+
+    /// Returns whether the request was still within the budget, in which case it's counted
+    async fn try_spend(&self, counter: &str, budget: u64, window: Duration) -> bool {
+        if self.coordinator.is_enabled() {
+            return self.coordinator.try_spend(counter, budget, window).await;
+        }
+
+        let mut local_budgets = LOCAL_BUDGETS.lock().unwrap();
+        let now = Instant::now();
+        let (count, window_end) = local_budgets.entry(format!("{}:{}", self.username, counter)).or_insert((0, now + window));
+        if now >= *window_end {
+            *count = 0;
+            *window_end = now + window;
+        }
+        if *count >= budget {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
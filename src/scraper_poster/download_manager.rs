@@ -0,0 +1,107 @@
+use std::time::SystemTime;
+
+use anyhow::bail;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use tokio::fs::OpenOptions;
+use tokio::io::AsyncWriteExt;
+
+use crate::supervisor::watchdog::with_watchdog;
+use crate::video::processing::get_video_duration;
+use crate::{DOWNLOAD_TIMEOUT, ORPHANED_DOWNLOAD_AGE};
+
+const TEMP_DIR: &str = "temp";
+
+/// Downloads a media from its url into the temp folder, next to the reels downloaded by the library
+///
+/// The bytes are written to a .part file first, which is resumed with a range request when a previous download was cut short
+pub async fn download_media(url: &str, filename: &str) -> anyhow::Result<()> {
+    // The .part file is kept when the download hangs, the next attempt picks up from there
+    with_watchdog(&format!("The download of {}", filename), DOWNLOAD_TIMEOUT, resume_download(url, filename)).await?
+}
+
+async fn resume_download(url: &str, filename: &str) -> anyhow::Result<()> {
+    let path = format!("{TEMP_DIR}/{filename}");
+    let part_path = format!("{path}.part");
+    let downloaded = tokio::fs::metadata(&part_path).await.map(|metadata| metadata.len()).unwrap_or(0);
+
+    let mut request = reqwest::Client::new().get(url);
+    if downloaded > 0 {
+        request = request.header(RANGE, format!("bytes={}-", downloaded));
+    }
+    let response = request.send().await?;
+
+    // The part doesn't match what the server has anymore, the next attempt starts from scratch
+    if response.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        let _ = tokio::fs::remove_file(&part_path).await;
+        bail!("the partial download of {} couldn't be resumed", filename);
+    }
+    let mut response = response.error_for_status()?;
+
+    // Servers that don't support ranges send the whole file again
+    let is_resumed = response.status() == StatusCode::PARTIAL_CONTENT;
+    let expected_size = if is_resumed {
+        response.headers().get(CONTENT_RANGE).and_then(|content_range| content_range.to_str().ok()).and_then(|content_range| content_range.rsplit('/').next()).and_then(|total| total.parse::<u64>().ok())
+    } else {
+        response.content_length()
+    };
+
+    let mut file = if is_resumed {
+        OpenOptions::new().append(true).open(&part_path).await?
+    } else {
+        OpenOptions::new().create(true).write(true).truncate(true).open(&part_path).await?
+    };
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+
+    if let Err(e) = verify_download(&part_path, expected_size).await {
+        let _ = tokio::fs::remove_file(&part_path).await;
+        return Err(e);
+    }
+    tokio::fs::rename(&part_path, &path).await?;
+    Ok(())
+}
+
+/// Checks that a media downloaded in the temp folder can be read, the reels downloaded by the library can't be resumed but they can at least be checked
+pub async fn verify_media(filename: &str) -> anyhow::Result<()> {
+    verify_download(&format!("{TEMP_DIR}/{filename}"), None).await
+}
+
+async fn verify_download(path: &str, expected_size: Option<u64>) -> anyhow::Result<()> {
+    let size = tokio::fs::metadata(path).await?.len();
+    if let Some(expected_size) = expected_size {
+        if size != expected_size {
+            bail!("{} is {} bytes instead of {}", path, size, expected_size);
+        }
+    }
+
+    match get_video_duration(path) {
+        Ok(duration) if duration > 0.0 => Ok(()),
+        Ok(_) => bail!("{} has no duration", path),
+        Err(e) => bail!("{} can't be read: {}", path, e),
+    }
+}
+
+/// Removes what was left in the temp folder by the previous runs, the downloads cut short long ago aren't worth resuming
+pub async fn clean_up_orphaned_downloads() {
+    let Ok(mut entries) = tokio::fs::read_dir(TEMP_DIR).await else {
+        return;
+    };
+
+    let mut removed = 0;
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(metadata) = entry.metadata().await else {
+            continue;
+        };
+        let age = metadata.modified().ok().and_then(|modified| SystemTime::now().duration_since(modified).ok()).unwrap_or_default();
+        if metadata.is_file() && age > ORPHANED_DOWNLOAD_AGE && tokio::fs::remove_file(entry.path()).await.is_ok() {
+            removed += 1;
+        }
+    }
+
+    if removed > 0 {
+        tracing::info!("Removed {} orphaned files from the temp folder", removed);
+    }
+}
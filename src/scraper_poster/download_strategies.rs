@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::scraper_poster::download_manager::download_media;
+use crate::scraper_poster::downloads::DownloadOutcome;
+use crate::scraper_poster::scraper::ContentManager;
+
+/// The page Instagram serves to embed a post on other websites, it's reachable without a session
+const EMBED_URL: &str = "https://www.instagram.com/p";
+
+lazy_static! {
+    /// The url of the video in the data of the embed page, where the slashes and the quotes are escaped once or twice
+    static ref EMBED_VIDEO_URL: Regex = Regex::new(r#"video_url\\*":\\*"(https:[^"]+?)\\*""#).unwrap();
+}
+
+/// A way of downloading the reels, the ones of an account are tried in turn until one of them gets past the errors of the session
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DownloadStrategy {
+    /// The web session of the library, through the scraper identities first
+    Web,
+    /// The url of the video given by the private API, which goes through the same session but not through the same endpoints
+    MobileApi,
+    /// The url of the video found in the embed page of the post, the caption isn't kept
+    Embed,
+}
+
+impl DownloadStrategy {
+    fn parse(download_strategy: &str) -> Option<Self> {
+        match download_strategy {
+            "web" => Some(DownloadStrategy::Web),
+            "mobile_api" => Some(DownloadStrategy::MobileApi),
+            "embed" => Some(DownloadStrategy::Embed),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            DownloadStrategy::Web => "web",
+            DownloadStrategy::MobileApi => "mobile_api",
+            DownloadStrategy::Embed => "embed",
+        }
+    }
+}
+
+/// Reads the download strategies of the account from the credentials, all of them in the default order when not set
+///
+/// Configured as "download_strategies", e.g. "web,mobile_api,embed"
+pub fn read_download_strategies(credentials: &HashMap<String, String>) -> Vec<DownloadStrategy> {
+    let download_strategies = match credentials.get("download_strategies") {
+        Some(download_strategies) => download_strategies,
+        None => return vec![DownloadStrategy::Web, DownloadStrategy::MobileApi, DownloadStrategy::Embed],
+    };
+
+    download_strategies
+        .split(',')
+        .map(str::trim)
+        .filter(|download_strategy| !download_strategy.is_empty())
+        .filter_map(|download_strategy| {
+            let parsed = DownloadStrategy::parse(download_strategy);
+            if parsed.is_none() {
+                tracing::warn!("Ignoring the unknown download strategy {}", download_strategy);
+            }
+            parsed
+        })
+        .collect()
+}
+
+impl ContentManager {
+    /// Returns None when the strategy couldn't download the reel either, so that the next one is tried
+    pub(crate) async fn download_reel_with_fallback(&self, download_strategy: DownloadStrategy, shortcode: &str, filename: &str) -> Option<DownloadOutcome> {
+        let download = match download_strategy {
+            DownloadStrategy::MobileApi => self.download_reel_with_mobile_api(shortcode, filename).await,
+            DownloadStrategy::Embed => download_reel_with_embed(shortcode, filename).await,
+            DownloadStrategy::Web => return None,
+        };
+
+        match download {
+            Ok(caption) => {
+                self.println(&format!("Downloaded the reel {} through the {} fallback", shortcode, download_strategy.as_str()));
+                Some(DownloadOutcome::Downloaded(caption))
+            }
+            Err(e) => {
+                self.println(&format!("The {} fallback couldn't download the reel {} either | {}", download_strategy.as_str(), shortcode, e));
+                let _ = tokio::fs::remove_file(format!("temp/{filename}")).await;
+                None
+            }
+        }
+    }
+
+    async fn download_reel_with_mobile_api(&self, shortcode: &str, filename: &str) -> anyhow::Result<String> {
+        self.wait_for_instagram_budget().await;
+        let item = self.fetch_media_item(shortcode).await?;
+
+        // The versions are sorted from the best quality
+        let Some(video_url) = item["video_versions"][0]["url"].as_str() else {
+            anyhow::bail!("The media {} has no video", shortcode);
+        };
+        download_media(video_url, filename).await?;
+
+        Ok(item["caption"]["text"].as_str().unwrap_or_default().to_string())
+    }
+}
+
+/// The embed page doesn't hold the caption in a usable form, the content goes through with an empty one
+async fn download_reel_with_embed(shortcode: &str, filename: &str) -> anyhow::Result<String> {
+    let page = reqwest::Client::new().get(format!("{}/{}/embed/captioned/", EMBED_URL, shortcode)).send().await?.error_for_status()?.text().await?;
+
+    let Some(captures) = EMBED_VIDEO_URL.captures(&page) else {
+        anyhow::bail!("The embed page of {} has no video", shortcode);
+    };
+    // The escaped ampersands are left as "u0026" once the backslashes are gone
+    let video_url = captures[1].replace('\\', "").replace("u0026", "&");
+    download_media(&video_url, filename).await?;
+
+    Ok(String::new())
+}
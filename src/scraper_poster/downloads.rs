@@ -0,0 +1,209 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use instagram_scraper_rs::InstagramScraperError;
+
+use crate::database::database::DatabaseTransaction;
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::download_manager::{download_media, verify_media};
+use crate::scraper_poster::download_strategies::DownloadStrategy;
+use crate::scraper_poster::graph_api::CarouselVideo;
+use crate::scraper_poster::metadata::{instagram_post_url, is_instagram_shortcode, SourcePostMetadata};
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::utils::{is_scraper_paused, save_cookie_store_to_json, set_bot_status_halted, set_bot_status_operational};
+use crate::supervisor::watchdog::with_watchdog;
+use crate::{DOWNLOAD_TIMEOUT, MAX_CONTENT_PER_ITERATION, SCRAPER_REFRESH_RATE};
+
+/// What came out of a download of the parallel stage
+#[derive(Debug)]
+pub(crate) enum DownloadOutcome {
+    /// Holds the caption of the content
+    Downloaded(String),
+    MediaNotFound,
+    Failed,
+    /// Not attempted because of the rate limit or the cap of the iteration, the content is left for the next iteration
+    Postponed,
+}
+
+/// State shared by the downloads of an iteration
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DownloadStage {
+    /// Downloads started or done, the failed ones give their slot back
+    reserved: Arc<AtomicUsize>,
+    rate_limited: Arc<AtomicBool>,
+}
+
+impl ContentManager {
+    /// Downloads a content of the parallel stage, the reels go through the scraper session while the videos of the carousels are fetched from their media url
+    ///
+    /// The downloads only take a connection for the queries they make, so that they don't hold most of the pool while they're running
+    pub(crate) async fn download_content(&self, download_stage: &DownloadStage, shortcode: &str, carousel_video: Option<&CarouselVideo>) -> DownloadOutcome {
+        if download_stage.rate_limited.load(Ordering::SeqCst) {
+            return DownloadOutcome::Postponed;
+        }
+        if download_stage.reserved.fetch_add(1, Ordering::SeqCst) >= MAX_CONTENT_PER_ITERATION {
+            download_stage.reserved.fetch_sub(1, Ordering::SeqCst);
+            return DownloadOutcome::Postponed;
+        }
+
+        while is_scraper_paused(&mut self.database.begin_transaction().await).await {
+            tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
+        }
+
+        let filename = format!("{}.mp4", shortcode);
+        let download_outcome = match carousel_video {
+            Some(carousel_video) => match download_media(&carousel_video.media_url, &filename).await {
+                Ok(_) => DownloadOutcome::Downloaded(carousel_video.caption.clone()),
+                Err(e) => {
+                    self.println(&format!("Error while downloading carousel video {} | {}", shortcode, e));
+                    DownloadOutcome::Failed
+                }
+            },
+            None => self.download_reel(download_stage, shortcode, &filename).await,
+        };
+
+        if !matches!(download_outcome, DownloadOutcome::Downloaded(_)) {
+            download_stage.reserved.fetch_sub(1, Ordering::SeqCst);
+        }
+        download_outcome
+    }
+
+    /// The download strategies of the account are tried in turn, until one of them gets past the errors of the session
+    ///
+    /// When none of them does, the bot is halted until the session is fixed, like before the fallbacks existed
+    async fn download_reel(&self, download_stage: &DownloadStage, shortcode: &str, filename: &str) -> DownloadOutcome {
+        self.rate_limiter.wait_for_circuit().await;
+        let mut download_outcome = None;
+        for download_strategy in &self.download_strategies {
+            download_outcome = match download_strategy {
+                DownloadStrategy::Web => self.download_reel_through_web(download_stage, shortcode, filename).await,
+                DownloadStrategy::MobileApi | DownloadStrategy::Embed => self.download_reel_with_fallback(*download_strategy, shortcode, filename).await,
+            };
+            if download_outcome.is_some() {
+                break;
+            }
+        }
+        let download_outcome = match download_outcome {
+            Some(download_outcome) => download_outcome,
+            None => self.retry_reel_until_resumed(shortcode, filename).await,
+        };
+
+        // A reel cut short is retried like the content that couldn't be found, up to the same amount of attempts
+        match download_outcome {
+            DownloadOutcome::Downloaded(caption) => match verify_media(filename).await {
+                Ok(_) => DownloadOutcome::Downloaded(caption),
+                Err(e) => {
+                    self.println(&format!("The downloaded reel {} is incomplete | {}", shortcode, e));
+                    let _ = tokio::fs::remove_file(format!("temp/{filename}")).await;
+                    DownloadOutcome::MediaNotFound
+                }
+            },
+            download_outcome => download_outcome,
+        }
+    }
+
+    /// The sessions of the scraper identities take the downloads first, the one of the account is the fallback
+    ///
+    /// Returns None when the session of the account ran into an error it can't get past on its own
+    async fn download_reel_through_web(&self, download_stage: &DownloadStage, shortcode: &str, filename: &str) -> Option<DownloadOutcome> {
+        match self.download_reel_with_identity(shortcode, filename).await {
+            Some(download_outcome) => Some(download_outcome),
+            None => self.download_reel_with_own_session(download_stage, shortcode, filename).await,
+        }
+    }
+
+    /// The scraper is locked for the whole download, so the reels themselves are still downloaded one at a time
+    async fn download_reel_with_own_session(&self, download_stage: &DownloadStage, shortcode: &str, filename: &str) -> Option<DownloadOutcome> {
+        let mut scraper_guard = self.scraper.lock().await;
+        let operation = format!("The download of the reel {}", shortcode);
+        let download_outcome = match with_watchdog(&operation, DOWNLOAD_TIMEOUT, scraper_guard.download_reel(shortcode, filename)).await {
+            // The session itself isn't at fault, the content is left for the next iterations
+            Err(e) => {
+                self.println(&format!("{}, moving on", e));
+                let _ = tokio::fs::remove_file(format!("temp/{filename}")).await;
+                Some(DownloadOutcome::Failed)
+            }
+            Ok(Ok(caption)) => {
+                self.rate_limiter.record_success().await;
+                set_bot_status_operational(&mut self.database.begin_transaction().await).await;
+                Some(DownloadOutcome::Downloaded(caption))
+            }
+            Ok(Err(e)) => {
+                self.println(&format!("Error while downloading reel | {}", e));
+
+                match e {
+                    // This is often temporary, so the content is retried on the next iterations before being written off
+                    InstagramScraperError::MediaNotFound { .. } => Some(DownloadOutcome::MediaNotFound),
+                    InstagramScraperError::RateLimitExceeded { .. } => {
+                        self.handle_rate_limited(&mut self.database.begin_transaction().await).await;
+                        download_stage.rate_limited.store(true, Ordering::SeqCst);
+                        Some(DownloadOutcome::Postponed)
+                    }
+                    _ => None,
+                }
+            }
+        };
+
+        let cookie_store = Arc::clone(&scraper_guard.session.cookie_store);
+        save_cookie_store_to_json(&self.cookie_store_path, cookie_store).await;
+        download_outcome
+    }
+
+    /// Halts the bot and tries the session of the account again each time it's resumed from Discord
+    async fn retry_reel_until_resumed(&self, shortcode: &str, filename: &str) -> DownloadOutcome {
+        let mut scraper_guard = self.scraper.lock().await;
+        let operation = format!("The download of the reel {}", shortcode);
+
+        set_bot_status_halted(&mut self.database.begin_transaction().await).await;
+        let download_outcome = loop {
+            let bot_status = self.database.begin_transaction().await.load_bot_status().await;
+            if bot_status.status == 0 {
+                self.println("Retrying to download reel...");
+                match with_watchdog(&operation, DOWNLOAD_TIMEOUT, scraper_guard.download_reel(shortcode, filename)).await {
+                    Ok(Ok(caption)) => {
+                        set_bot_status_operational(&mut self.database.begin_transaction().await).await;
+                        break DownloadOutcome::Downloaded(caption);
+                    }
+                    Ok(Err(e)) => {
+                        self.println(&format!("Error while downloading reel | {}", e));
+                        set_bot_status_halted(&mut self.database.begin_transaction().await).await;
+                    }
+                    Err(e) => {
+                        self.println(&e.to_string());
+                        let _ = tokio::fs::remove_file(format!("temp/{filename}")).await;
+                        set_bot_status_halted(&mut self.database.begin_transaction().await).await;
+                    }
+                }
+            } else {
+                tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
+            }
+        };
+
+        let cookie_store = Arc::clone(&scraper_guard.session.cookie_store);
+        save_cookie_store_to_json(&self.cookie_store_path, cookie_store).await;
+        download_outcome
+    }
+
+    /// Hands a downloaded content over to the sender loop, once it's done with the previous one
+    ///
+    /// The downloads can now complete close to each other, the previous content would otherwise be replaced before being picked up
+    pub(crate) async fn hand_over_content(&self, tx: &mut DatabaseTransaction, mut content: (String, String, String, String, SourcePostMetadata)) {
+        // Recorded in the provenance of the content
+        let (_, _, _, shortcode, source_post_metadata) = &mut content;
+        source_post_metadata.downloaded_at = now_in_my_timezone(&tx.load_user_settings().await).to_rfc3339();
+        if source_post_metadata.source_url.is_empty() && is_instagram_shortcode(shortcode) {
+            source_post_metadata.source_url = instagram_post_url(shortcode);
+        }
+
+        loop {
+            let previous_shortcode = self.latest_content_mutex.lock().await.as_ref().map(|(_, _, _, shortcode, _)| shortcode.clone());
+            match previous_shortcode {
+                Some(shortcode) if shortcode != "halted" && !tx.does_content_exist_with_shortcode(&shortcode).await => tokio::time::sleep(SCRAPER_REFRESH_RATE).await,
+                _ => break,
+            }
+        }
+
+        let mut lock = self.latest_content_mutex.lock().await;
+        *lock = Some(content);
+    }
+}
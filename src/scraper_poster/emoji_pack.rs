@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+const EMOJI_PACKS_PATH: &str = "config/emoji_packs.yaml";
+/// Name of the signature in the canary steps
+pub const SIGNATURE_STEP: &str = "signature";
+
+/// Emojis appended as a signature to the captions of an account, rotated so that the same one doesn't come back too soon
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EmojiPack {
+    pub emojis: Vec<String>,
+    /// How many of the latest posts can't share their signature
+    #[serde(default = "default_no_repeat_within")]
+    pub no_repeat_within: usize,
+}
+
+impl EmojiPack {
+    /// Picks an emoji that none of the recent captions ends with, or the one used the longest ago when the pack is too small for that
+    ///
+    /// The recent captions are expected from the most recent one
+    pub fn pick_signature(&self, recent_captions: &[String], rng: &mut impl Rng) -> Option<String> {
+        let last_used = |emoji: &String| recent_captions.iter().take(self.no_repeat_within).position(|caption| caption.ends_with(emoji.as_str()));
+
+        let unused_emojis = self.emojis.iter().filter(|emoji| last_used(emoji).is_none()).collect::<Vec<&String>>();
+        match unused_emojis.choose(rng) {
+            Some(emoji) => Some(emoji.to_string()),
+            None => self.emojis.iter().max_by_key(|emoji| last_used(emoji)).cloned(),
+        }
+    }
+}
+
+/// Appends the signature at the end of the caption, on the same line
+pub fn sign_caption(caption: &str, signature: &str) -> String {
+    if caption.trim().is_empty() {
+        signature.to_string()
+    } else {
+        format!("{} {}", caption.trim_end(), signature)
+    }
+}
+
+fn default_no_repeat_within() -> usize {
+    3
+}
+
+/// Reads the emoji pack of the given account, the file is optional
+pub async fn read_emoji_pack(username: &str) -> EmojiPack {
+    let mut file = match File::open(EMOJI_PACKS_PATH).await {
+        Ok(file) => file,
+        Err(_) => return EmojiPack::default(),
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.expect("Unable to read the emoji packs file");
+    let mut emoji_packs: HashMap<String, EmojiPack> = serde_yaml::from_str(&contents).expect("Error parsing the emoji packs file");
+    emoji_packs.remove(username).unwrap_or_default()
+}
@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use instagram_scraper_rs::Post;
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+const ENGAGEMENT_FILTERS_PATH: &str = "config/engagement_filters.yaml";
+/// The entry of engagement_filters.yaml applied to the source accounts without one of their own
+const DEFAULT_FILTER: &str = "default";
+
+/// The least a post of a source account has to reach to be downloaded, every threshold is optional
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct EngagementFilter {
+    pub min_likes: Option<u64>,
+    pub min_views: Option<u64>,
+    pub min_comments: Option<u64>,
+    pub max_age_days: Option<u64>,
+}
+
+impl EngagementFilter {
+    /// The thresholds left out fall back to the ones of the other filter
+    fn or(self, fallback: EngagementFilter) -> EngagementFilter {
+        EngagementFilter {
+            min_likes: self.min_likes.or(fallback.min_likes),
+            min_views: self.min_views.or(fallback.min_views),
+            min_comments: self.min_comments.or(fallback.min_comments),
+            max_age_days: self.max_age_days.or(fallback.max_age_days),
+        }
+    }
+
+    /// Returns why the post falls short of the thresholds, if it does
+    pub fn check(&self, post: &Post, now_timestamp: i64) -> Option<String> {
+        let likes = post.likes as u64;
+        let views = post.video_view_count.unwrap_or_default() as u64;
+        let comments = post.comments as u64;
+        let age_days = (now_timestamp - post.taken_at_timestamp as i64).max(0) as u64 / (60 * 60 * 24);
+
+        match *self {
+            EngagementFilter { min_likes: Some(min_likes), .. } if likes < min_likes => Some(format!("{} likes out of {}", likes, min_likes)),
+            EngagementFilter { min_views: Some(min_views), .. } if views < min_views => Some(format!("{} views out of {}", views, min_views)),
+            EngagementFilter { min_comments: Some(min_comments), .. } if comments < min_comments => Some(format!("{} comments out of {}", comments, min_comments)),
+            EngagementFilter { max_age_days: Some(max_age_days), .. } if age_days > max_age_days => Some(format!("{} days old, at most {}", age_days, max_age_days)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EngagementFilters {
+    default: EngagementFilter,
+    per_source: HashMap<String, EngagementFilter>,
+}
+
+impl EngagementFilters {
+    pub fn for_source(&self, profile: &str) -> EngagementFilter {
+        self.per_source.get(profile).copied().unwrap_or_default().or(self.default)
+    }
+}
+
+/// Reads the engagement thresholds of the given account, the file is optional
+pub async fn read_engagement_filters(username: &str) -> EngagementFilters {
+    let mut file = match File::open(ENGAGEMENT_FILTERS_PATH).await {
+        Ok(file) => file,
+        Err(_) => return EngagementFilters::default(),
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.expect("Unable to read the engagement filters file");
+    let mut engagement_filters: HashMap<String, HashMap<String, EngagementFilter>> = serde_yaml::from_str(&contents).expect("Error parsing the engagement filters file");
+
+    let mut per_source = engagement_filters.remove(username).unwrap_or_default();
+    let default = per_source.remove(DEFAULT_FILTER).unwrap_or_default();
+    EngagementFilters { default, per_source }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+
+use crate::database::database::ExpiredPending;
+use crate::discord::state::ContentStatus;
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::{MAX_CONTENT_HANDLED, MAX_CONTENT_PER_ITERATION};
+
+/// What the scraper does once the backlog reaches MAX_CONTENT_HANDLED
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BacklogPolicy {
+    /// Waits for the moderators to go through the backlog
+    Sleep,
+    /// Evicts the oldest pending content, so that the fresh content keeps flowing
+    EvictOldestPending,
+}
+
+/// Configured as "backlog_policy" in the credentials, either "sleep" or "evict_oldest_pending", "sleep" by default
+pub fn read_backlog_policy(credentials: &HashMap<String, String>) -> BacklogPolicy {
+    match credentials.get("backlog_policy").map(String::as_str) {
+        Some("evict_oldest_pending") => BacklogPolicy::EvictOldestPending,
+        Some("sleep") | None => BacklogPolicy::Sleep,
+        Some(backlog_policy) => {
+            tracing::warn!("Unknown backlog policy {}, sleeping instead", backlog_policy);
+            BacklogPolicy::Sleep
+        }
+    }
+}
+
+impl ContentManager {
+    /// Moves the oldest pending content to the expired_pending table, until a whole iteration of fresh content fits under MAX_CONTENT_HANDLED
+    ///
+    /// The content some moderators already voted for is kept, since it's halfway through its review. Returns how many were evicted
+    pub(crate) async fn evict_oldest_pending(&self, content_mapping_len: usize) -> usize {
+        let mut tx = self.database.begin_transaction().await;
+        let user_settings = tx.load_user_settings().await;
+        let expired_at = now_in_my_timezone(&user_settings).to_rfc3339();
+
+        let mut pending_content: Vec<_> = tx.load_content_mapping().await.into_iter().filter(|content_info| matches!(content_info.status, ContentStatus::Pending { .. })).collect();
+        pending_content.sort_by(|a, b| a.added_at.cmp(&b.added_at));
+
+        let to_evict = (content_mapping_len + MAX_CONTENT_PER_ITERATION).saturating_sub(MAX_CONTENT_HANDLED);
+        let mut evicted = 0;
+        for content_info in pending_content {
+            if evicted >= to_evict {
+                break;
+            }
+            if tx.count_content_votes(&content_info.original_shortcode).await > 0 {
+                continue;
+            }
+
+            let expired_pending = ExpiredPending {
+                username: content_info.username.clone(),
+                original_shortcode: content_info.original_shortcode.clone(),
+                url: content_info.url.clone(),
+                caption: content_info.caption.clone(),
+                hashtags: content_info.hashtags.clone(),
+                original_author: content_info.original_author.clone(),
+                added_at: content_info.added_at.clone(),
+                expired_at: expired_at.clone(),
+            };
+            tx.save_expired_pending(&expired_pending).await;
+            // The card is left to the archival, like the cards of the rest of the removed content
+            tx.remove_content_info_with_shortcode(&content_info.original_shortcode).await;
+            evicted += 1;
+        }
+        evicted
+    }
+}
@@ -0,0 +1,124 @@
+use lazy_static::lazy_static;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use regex::Regex;
+
+/// Screen readers cut longer alt texts, so the generated one is kept short
+const MAX_ALT_TEXT_LENGTH: usize = 250;
+
+lazy_static! {
+    /// An innermost group of variations, braces without a `|` are left as they are
+    static ref SPINTAX_GROUP_REGEX: Regex = Regex::new(r"\{([^{}]*\|[^{}]*)\}").unwrap();
+}
+
+/// Where a content gets published, the same content is rendered differently for each destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    Reel,
+    Story,
+    YouTubeShort,
+}
+
+/// How the caption of a content is rendered for a destination
+#[derive(Debug, Clone, Copy)]
+pub struct FormattingProfile {
+    /// Limit enforced by the platform, in characters
+    pub max_caption_length: usize,
+    pub max_hashtags: usize,
+    pub includes_disclaimer: bool,
+}
+
+impl Destination {
+    pub fn formatting_profile(&self) -> FormattingProfile {
+        match self {
+            Destination::Reel => FormattingProfile {
+                max_caption_length: 2200,
+                max_hashtags: 30,
+                includes_disclaimer: true,
+            },
+            // Stories don't show a full caption, the text has to stay short
+            Destination::Story => FormattingProfile {
+                max_caption_length: 250,
+                max_hashtags: 10,
+                includes_disclaimer: false,
+            },
+            // The caption ends up in the description, YouTube ignores every hashtag of a video that has more than 15
+            Destination::YouTubeShort => FormattingProfile {
+                max_caption_length: 5000,
+                max_hashtags: 15,
+                includes_disclaimer: true,
+            },
+        }
+    }
+}
+
+/// Renders the caption and the hashtags of a content for the given destination
+pub fn format_caption(caption: &str, hashtags: &str, destination: Destination) -> String {
+    // Example of a reel caption:
+    // "This is a cool caption!"
+    // "•"
+    // "•"
+    // "•"
+    // "•"
+    // "•"
+    // "(We don’t own this reel. All rights are reserved & belong to their respective owners, no copyright infringement intended. DM for credit/removal.)"
+    // "•"
+    // "#cool #caption #hashtags"
+    let formatting_profile = destination.formatting_profile();
+
+    let big_spacer = "\n\n\n•\n•\n•\n•\n•\n";
+    let small_spacer = "\n•\n";
+    let disclaimer = "(We don’t own this content. All rights are reserved & belong to their respective owners, no copyright infringement intended. DM for credit/removal.)";
+
+    let hashtags = hashtags.split_whitespace().take(formatting_profile.max_hashtags).collect::<Vec<&str>>().join(" ");
+
+    let suffix = if hashtags.is_empty() {
+        "".to_string()
+    } else if caption.is_empty() {
+        hashtags
+    } else if formatting_profile.includes_disclaimer {
+        format!("{}{}{}{}", big_spacer, disclaimer, small_spacer, hashtags)
+    } else {
+        format!("{}{}", small_spacer, hashtags)
+    };
+
+    // The caption itself gets cut rather than the hashtags, the ellipsis counts towards the limit
+    let available_length = formatting_profile.max_caption_length.saturating_sub(suffix.chars().count());
+    let caption = if caption.chars().count() > available_length {
+        let truncated_caption: String = caption.chars().take(available_length.saturating_sub(1)).collect();
+        format!("{}…", truncated_caption.trim_end())
+    } else {
+        caption.to_string()
+    };
+
+    format!("{}{}", caption, suffix)
+}
+
+/// Picks one of the variations of every `{a|b|c}` group of the caption, the groups can be nested
+///
+/// Resolved at publish time, so that sister accounts posting the same content don't end up with the exact same caption
+pub fn resolve_spintax(caption: &str, rng: &mut impl Rng) -> String {
+    let mut resolved = caption.to_string();
+    // The innermost groups are resolved first, until none is left
+    while SPINTAX_GROUP_REGEX.is_match(&resolved) {
+        resolved = SPINTAX_GROUP_REGEX
+            .replace_all(&resolved, |captures: &regex::Captures| {
+                let variations = captures[1].split('|').collect::<Vec<&str>>();
+                variations.choose(rng).unwrap().to_string()
+            })
+            .to_string();
+    }
+    resolved
+}
+
+/// Alt text generated from the caption, used when no alt text was set on the content
+pub fn default_alt_text(caption: &str) -> String {
+    let alt_text = caption.split_whitespace().filter(|word| !word.starts_with('#') && !word.starts_with('@')).collect::<Vec<&str>>().join(" ");
+
+    if alt_text.chars().count() > MAX_ALT_TEXT_LENGTH {
+        let truncated_alt_text: String = alt_text.chars().take(MAX_ALT_TEXT_LENGTH - 1).collect();
+        format!("{}…", truncated_alt_text.trim_end())
+    } else {
+        alt_text
+    }
+}
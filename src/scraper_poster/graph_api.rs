@@ -0,0 +1,358 @@
+use std::time::Duration;
+
+use instagram_scraper_rs::InstagramUploaderError;
+use serde_json::Value;
+use thiserror::Error;
+use tokio::time::sleep;
+
+/// The uploader of instagram_scraper_rs doesn't take the optional fields of the media container,
+/// so the content that needs them is published by calling the Graph API directly
+pub(crate) const GRAPH_API_URL: &str = "https://graph.facebook.com/v19.0";
+const CONTAINER_STATUS_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const MAX_CONTAINER_STATUS_POLLS: u32 = 60;
+const MEDIA_VERIFICATION_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const MAX_MEDIA_VERIFICATION_POLLS: u32 = 6;
+/// The hashtag endpoints return at most 50 posts per page
+const HASHTAG_MEDIA_PAGE_SIZE: &str = "50";
+/// The trial reels graduate to the followers on their own if they perform well with the non-followers
+const TRIAL_REEL_PARAMS: &str = r#"{"graduation_strategy":"SS_PERFORMANCE"}"#;
+
+/// Optional fields of the media container, the library uploader is used when none of them are set
+#[derive(Debug, Clone, Default)]
+pub struct MediaContainerOptions {
+    pub alt_text: String,
+    pub location_id: String,
+    pub is_paid_partnership: bool,
+    /// Shown to non-followers first
+    pub is_trial_reel: bool,
+    /// The name given to the audio of the reel, it can only be set once
+    pub audio_name: String,
+}
+
+impl MediaContainerOptions {
+    pub fn is_empty(&self) -> bool {
+        self.alt_text.is_empty() && self.location_id.is_empty() && !self.is_paid_partnership && !self.is_trial_reel && self.audio_name.is_empty()
+    }
+
+    fn as_form(&self) -> Vec<(&'static str, String)> {
+        let mut form = Vec::new();
+        if !self.alt_text.is_empty() {
+            form.push(("alt_text", self.alt_text.clone()));
+        }
+        if !self.location_id.is_empty() {
+            form.push(("location_id", self.location_id.clone()));
+        }
+        if self.is_paid_partnership {
+            form.push(("is_paid_partnership", "true".to_string()));
+        }
+        if self.is_trial_reel {
+            form.push(("trial_params", TRIAL_REEL_PARAMS.to_string()));
+        }
+        if !self.audio_name.is_empty() {
+            form.push(("audio_name", self.audio_name.clone()));
+        }
+        form
+    }
+}
+
+/// A place that can be tagged on a post
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub id: String,
+    pub name: String,
+}
+
+/// A post found through a hashtag, the author is not exposed by the Graph API
+#[derive(Debug, Clone)]
+pub struct HashtagPost {
+    pub shortcode: String,
+    pub is_video: bool,
+}
+
+/// A video inside a carousel of another account, the library only downloads reels so it's fetched from its media url
+#[derive(Debug, Clone)]
+pub struct CarouselVideo {
+    /// Shortcode of the carousel followed by the position of the video in it, e.g. "C7x9Yb1Nq2w_2"
+    pub shortcode: String,
+    pub carousel_shortcode: String,
+    pub media_url: String,
+    pub caption: String,
+}
+
+/// A comment left on a reel of the account
+#[derive(Debug, Clone)]
+pub struct MediaComment {
+    pub id: String,
+    pub username: String,
+    /// e.g. "2024-05-01T12:00:00+0000", sorts in chronological order
+    pub timestamp: String,
+}
+
+/// How a reel of the account did, along with where its video can be downloaded from
+#[derive(Debug, Clone)]
+pub struct MediaEngagement {
+    pub likes: i64,
+    pub comments: i64,
+    /// Empty when Instagram doesn't hand out the video, e.g. when it has copyrighted music
+    pub media_url: String,
+}
+
+#[derive(Error, Debug)]
+pub enum GraphApiError {
+    #[error("{0}")]
+    UploadFailedRecoverable(String),
+    #[error("{0}")]
+    UploadFailedNonRecoverable(String),
+    #[error("{0}")]
+    UploadSucceededButFailedToRetrieveId(String),
+}
+
+impl From<InstagramUploaderError> for GraphApiError {
+    fn from(err: InstagramUploaderError) -> Self {
+        match err {
+            InstagramUploaderError::UploadFailedRecoverable(err) => GraphApiError::UploadFailedRecoverable(err.to_string()),
+            InstagramUploaderError::UploadFailedNonRecoverable(err) => GraphApiError::UploadFailedNonRecoverable(err.to_string()),
+            InstagramUploaderError::UploadSucceededButFailedToRetrieveId(err) => GraphApiError::UploadSucceededButFailedToRetrieveId(err.to_string()),
+        }
+    }
+}
+
+/// Creates a reel container with the given options, waits for Instagram to process it and publishes it, returns the id of the reel
+pub async fn upload_reel_with_options(user_id: &str, access_token: &str, video_url: &str, caption: &str, options: &MediaContainerOptions) -> Result<String, GraphApiError> {
+    let client = reqwest::Client::new();
+
+    let mut form = vec![("media_type", "REELS".to_string()), ("video_url", video_url.to_string()), ("caption", caption.to_string()), ("access_token", access_token.to_string())];
+    form.extend(options.as_form());
+
+    let response = send(client.post(format!("{GRAPH_API_URL}/{user_id}/media")).form(&form)).await?;
+    let container_id = match response["id"].as_str() {
+        Some(container_id) => container_id.to_string(),
+        None => return Err(GraphApiError::UploadFailedNonRecoverable(format!("No container id returned: {}", response))),
+    };
+
+    wait_for_container(&client, access_token, &container_id).await?;
+
+    let form = [("creation_id", container_id), ("access_token", access_token.to_string())];
+    let response = send(client.post(format!("{GRAPH_API_URL}/{user_id}/media_publish")).form(&form)).await?;
+    match response["id"].as_str() {
+        Some(reel_id) => Ok(reel_id.to_string()),
+        None => Err(GraphApiError::UploadSucceededButFailedToRetrieveId(format!("No media id returned: {}", response))),
+    }
+}
+
+/// Publishes the video on the story of the account with the caption rendered for stories, returns the id of the story
+pub async fn upload_story(user_id: &str, access_token: &str, video_url: &str, caption: &str) -> Result<String, GraphApiError> {
+    let client = reqwest::Client::new();
+
+    let mut form = vec![("media_type", "STORIES".to_string()), ("video_url", video_url.to_string()), ("access_token", access_token.to_string())];
+    if !caption.is_empty() {
+        form.push(("caption", caption.to_string()));
+    }
+    let response = send(client.post(format!("{GRAPH_API_URL}/{user_id}/media")).form(&form)).await?;
+    let container_id = match response["id"].as_str() {
+        Some(container_id) => container_id.to_string(),
+        None => return Err(GraphApiError::UploadFailedNonRecoverable(format!("No container id returned: {}", response))),
+    };
+
+    wait_for_container(&client, access_token, &container_id).await?;
+
+    let form = [("creation_id", container_id), ("access_token", access_token.to_string())];
+    let response = send(client.post(format!("{GRAPH_API_URL}/{user_id}/media_publish")).form(&form)).await?;
+    match response["id"].as_str() {
+        Some(story_id) => Ok(story_id.to_string()),
+        None => Err(GraphApiError::UploadSucceededButFailedToRetrieveId(format!("No media id returned: {}", response))),
+    }
+}
+
+/// Looks up the places matching the query, only the ones with a location can be tagged
+pub async fn search_locations(access_token: &str, query: &str) -> Result<Vec<Location>, GraphApiError> {
+    let request = reqwest::Client::new().get(format!("{GRAPH_API_URL}/pages/search")).query(&[("q", query), ("fields", "id,name,location"), ("access_token", access_token)]);
+    let response = send(request).await?;
+
+    let locations = response["data"]
+        .as_array()
+        .map(|pages| {
+            pages
+                .iter()
+                .filter(|page| page.get("location").is_some())
+                .filter_map(|page| Some(Location { id: page["id"].as_str()?.to_string(), name: page["name"].as_str()?.to_string() }))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(locations)
+}
+
+/// Checks that a just published media went through the processing on Instagram's side, returns its permalink, e.g. https://www.instagram.com/reel/C7x9Yb1Nq2w/
+///
+/// The media that failed the processing stops existing, which comes back as a non recoverable error.
+/// The one that is still processing has no permalink yet, it's reported as recoverable once the polls run out
+pub async fn verify_published_media(media_id: &str, access_token: &str) -> Result<String, GraphApiError> {
+    let client = reqwest::Client::new();
+    for _ in 0..MAX_MEDIA_VERIFICATION_POLLS {
+        let request = client.get(format!("{GRAPH_API_URL}/{media_id}")).query(&[("fields", "id,permalink"), ("access_token", access_token)]);
+        match send(request).await {
+            Ok(response) => {
+                if let Some(permalink) = response["permalink"].as_str().filter(|permalink| !permalink.is_empty()) {
+                    return Ok(permalink.to_string());
+                }
+            }
+            Err(GraphApiError::UploadFailedNonRecoverable(e)) => return Err(GraphApiError::UploadFailedNonRecoverable(format!("Media {} is gone: {}", media_id, e))),
+            Err(_) => {}
+        }
+        sleep(MEDIA_VERIFICATION_POLL_INTERVAL).await;
+    }
+
+    Err(GraphApiError::UploadFailedRecoverable(format!("Media {} is still processing", media_id)))
+}
+
+/// Returns the first page of the top level comments of a reel
+pub async fn media_comments(media_id: &str, access_token: &str) -> Result<Vec<MediaComment>, GraphApiError> {
+    let request = reqwest::Client::new().get(format!("{GRAPH_API_URL}/{media_id}/comments")).query(&[("fields", "id,username,timestamp"), ("access_token", access_token)]);
+    let response = send(request).await?;
+
+    let comments = response["data"]
+        .as_array()
+        .map(|comments| {
+            comments
+                .iter()
+                .filter_map(|comment| {
+                    Some(MediaComment {
+                        id: comment["id"].as_str()?.to_string(),
+                        username: comment["username"].as_str().unwrap_or_default().to_string(),
+                        timestamp: comment["timestamp"].as_str().unwrap_or_default().to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(comments)
+}
+
+/// Returns the likes and the comments of a reel of the account, and the url of its video
+pub async fn media_engagement(media_id: &str, access_token: &str) -> Result<MediaEngagement, GraphApiError> {
+    let request = reqwest::Client::new().get(format!("{GRAPH_API_URL}/{media_id}")).query(&[("fields", "like_count,comments_count,media_url"), ("access_token", access_token)]);
+    let response = send(request).await?;
+
+    Ok(MediaEngagement {
+        likes: response["like_count"].as_i64().unwrap_or_default(),
+        comments: response["comments_count"].as_i64().unwrap_or_default(),
+        media_url: response["media_url"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+/// Replies to a comment as the account, returns the id of the reply
+pub async fn reply_to_comment(comment_id: &str, access_token: &str, message: &str) -> Result<String, GraphApiError> {
+    let form = [("message", message), ("access_token", access_token)];
+    let response = send(reqwest::Client::new().post(format!("{GRAPH_API_URL}/{comment_id}/replies")).form(&form)).await?;
+
+    Ok(response["id"].as_str().unwrap_or_default().to_string())
+}
+
+/// Returns the id of the hashtag, or None if nobody ever used it
+pub async fn search_hashtag(user_id: &str, access_token: &str, hashtag: &str) -> Result<Option<String>, GraphApiError> {
+    let request = reqwest::Client::new().get(format!("{GRAPH_API_URL}/ig_hashtag_search")).query(&[("user_id", user_id), ("q", hashtag), ("access_token", access_token)]);
+    let response = send(request).await?;
+
+    Ok(response["data"].as_array().and_then(|hashtags| hashtags.first()).and_then(|hashtag| hashtag["id"].as_str()).map(str::to_string))
+}
+
+/// Returns the first page of the top or recent posts of a hashtag, the edge is either "top_media" or "recent_media"
+pub async fn hashtag_media(user_id: &str, access_token: &str, hashtag_id: &str, edge: &str) -> Result<Vec<HashtagPost>, GraphApiError> {
+    let request = reqwest::Client::new()
+        .get(format!("{GRAPH_API_URL}/{hashtag_id}/{edge}"))
+        .query(&[("user_id", user_id), ("fields", "id,media_type,permalink"), ("limit", HASHTAG_MEDIA_PAGE_SIZE), ("access_token", access_token)]);
+    let response = send(request).await?;
+
+    let posts = response["data"]
+        .as_array()
+        .map(|posts| {
+            posts
+                .iter()
+                .filter_map(|post| {
+                    let shortcode = shortcode_from_permalink(post["permalink"].as_str()?)?;
+                    Some(HashtagPost { shortcode, is_video: post["media_type"].as_str() == Some("VIDEO") })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(posts)
+}
+
+/// Returns the videos in the latest carousels of another business or creator account, looked up through the business discovery
+pub async fn carousel_videos(user_id: &str, access_token: &str, username: &str, limit: usize) -> Result<Vec<CarouselVideo>, GraphApiError> {
+    let fields = format!("business_discovery.username({username}){{media.limit({limit}){{permalink,media_type,caption,children{{media_type,media_url}}}}}}");
+    let request = reqwest::Client::new().get(format!("{GRAPH_API_URL}/{user_id}")).query(&[("fields", fields.as_str()), ("access_token", access_token)]);
+    let response = send(request).await?;
+
+    let mut videos = Vec::new();
+    for media in response["business_discovery"]["media"]["data"].as_array().into_iter().flatten() {
+        if media["media_type"].as_str() != Some("CAROUSEL_ALBUM") {
+            continue;
+        }
+
+        let carousel_shortcode = match media["permalink"].as_str().and_then(shortcode_from_permalink) {
+            Some(carousel_shortcode) => carousel_shortcode,
+            None => continue,
+        };
+        let caption = media["caption"].as_str().unwrap_or_default().to_string();
+
+        for (position, child) in media["children"]["data"].as_array().into_iter().flatten().enumerate() {
+            if child["media_type"].as_str() != Some("VIDEO") {
+                continue;
+            }
+
+            if let Some(media_url) = child["media_url"].as_str() {
+                videos.push(CarouselVideo {
+                    shortcode: format!("{}_{}", carousel_shortcode, position + 1),
+                    carousel_shortcode: carousel_shortcode.clone(),
+                    media_url: media_url.to_string(),
+                    caption: caption.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(videos)
+}
+
+/// e.g. https://www.instagram.com/reel/C7x9Yb1Nq2w/
+pub(crate) fn shortcode_from_permalink(permalink: &str) -> Option<String> {
+    permalink.trim_end_matches('/').rsplit('/').next().filter(|shortcode| !shortcode.is_empty()).map(str::to_string)
+}
+
+/// Instagram processes the video asynchronously, the container can only be published once it's finished
+async fn wait_for_container(client: &reqwest::Client, access_token: &str, container_id: &str) -> Result<(), GraphApiError> {
+    for _ in 0..MAX_CONTAINER_STATUS_POLLS {
+        let request = client.get(format!("{GRAPH_API_URL}/{container_id}")).query(&[("fields", "status_code,status"), ("access_token", access_token)]);
+        let response = send(request).await?;
+        match response["status_code"].as_str() {
+            Some("FINISHED") => return Ok(()),
+            Some("ERROR") | Some("EXPIRED") => return Err(GraphApiError::UploadFailedNonRecoverable(format!("Container {} failed: {}", container_id, response["status"]))),
+            _ => sleep(CONTAINER_STATUS_POLL_INTERVAL).await,
+        }
+    }
+
+    Err(GraphApiError::UploadFailedRecoverable(format!("Container {} is still processing", container_id)))
+}
+
+/// Sends the request and parses the response, transient errors are reported as recoverable
+pub(crate) async fn send(request: reqwest::RequestBuilder) -> Result<Value, GraphApiError> {
+    let response = request.send().await.map_err(|e| GraphApiError::UploadFailedRecoverable(e.to_string()))?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| GraphApiError::UploadFailedRecoverable(e.to_string()))?;
+    let body: Value = serde_json::from_str(&body).map_err(|_| GraphApiError::UploadFailedRecoverable(format!("Invalid response ({}): {}", status, body)))?;
+
+    if let Some(error) = body.get("error") {
+        let message = error["message"].as_str().unwrap_or_default().to_string();
+        return if status.is_server_error() || error["is_transient"].as_bool().unwrap_or(false) {
+            Err(GraphApiError::UploadFailedRecoverable(message))
+        } else {
+            Err(GraphApiError::UploadFailedNonRecoverable(message))
+        };
+    }
+
+    Ok(body)
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use instagram_scraper_rs::{InstagramScraper, InstagramScraperError};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::scraper_poster::downloads::DownloadOutcome;
+use crate::scraper_poster::proxy::new_scraper;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::utils::save_cookie_store_to_json;
+use crate::supervisor::watchdog::with_watchdog;
+use crate::{DOWNLOAD_TIMEOUT, IDENTITY_EXCLUSION_LEN};
+
+/// A secondary Instagram session the downloads are spread across, so that the session of the account itself is spared
+struct ScraperIdentity {
+    username: String,
+    password: String,
+    /// Derived from the one of the account, each session keeps its own cookies
+    cookie_store_path: String,
+    scraper: Mutex<InstagramScraper>,
+    /// Set once the session runs into a restriction, it's left out of the rotation until then
+    excluded_until: Mutex<Option<Instant>>,
+}
+
+/// The pool of sessions of a posting account, picked in turn for each download
+#[derive(Clone)]
+pub(crate) struct ScraperIdentities {
+    identities: Arc<Vec<ScraperIdentity>>,
+    next_identity: Arc<AtomicUsize>,
+}
+
+impl ScraperIdentities {
+    /// Reads the sessions from the credentials, configured as "scraper_identities", e.g. "burner_one:password,burner_two:password"
+    pub(crate) fn new(credentials: &HashMap<String, String>, cookie_store_path: &str, proxy: Option<&String>) -> Self {
+        let identities = credentials
+            .get("scraper_identities")
+            .map(|scraper_identities| {
+                scraper_identities
+                    .split(',')
+                    .filter_map(|identity| {
+                        let Some((username, password)) = identity.trim().split_once(':') else {
+                            tracing::warn!("Ignoring a scraper identity without a password");
+                            return None;
+                        };
+                        let cookie_store_path = identity_cookie_store_path(cookie_store_path, username);
+                        Some(ScraperIdentity {
+                            username: username.to_string(),
+                            password: password.to_string(),
+                            scraper: Mutex::new(new_scraper(&cookie_store_path, proxy)),
+                            cookie_store_path,
+                            excluded_until: Mutex::new(None),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Self {
+            identities: Arc::new(identities),
+            next_identity: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Round-robin over the sessions that aren't excluded, there's none left when they all ran into restrictions
+    async fn next(&self) -> Option<&ScraperIdentity> {
+        for _ in 0..self.identities.len() {
+            let identity = &self.identities[self.next_identity.fetch_add(1, Ordering::SeqCst) % self.identities.len()];
+            if !identity.is_excluded().await {
+                return Some(identity);
+            }
+        }
+        None
+    }
+}
+
+impl ScraperIdentity {
+    async fn is_excluded(&self) -> bool {
+        self.excluded_until.lock().await.is_some_and(|excluded_until| excluded_until > Instant::now())
+    }
+
+    async fn exclude(&self) {
+        *self.excluded_until.lock().await = Some(Instant::now() + IDENTITY_EXCLUSION_LEN);
+    }
+}
+
+/// "cookies/cookies_account.json" becomes "cookies/cookies_account_identity.json"
+fn identity_cookie_store_path(cookie_store_path: &str, identity_username: &str) -> String {
+    match cookie_store_path.strip_suffix(".json") {
+        Some(cookie_store_path) => format!("{}_{}.json", cookie_store_path, identity_username),
+        None => format!("{}_{}", cookie_store_path, identity_username),
+    }
+}
+
+impl ContentManager {
+    /// Logs the sessions of the pool in, the ones that can't are excluded for a while instead of halting the bot
+    pub(crate) async fn log_in_identities(&self) {
+        for identity in self.scraper_identities.identities.iter() {
+            let mut scraper_guard = identity.scraper.lock().await;
+            scraper_guard.authenticate_with_login(identity.username.clone(), identity.password.clone());
+            match scraper_guard.login().await {
+                Ok(_) => {
+                    self.println(&format!("Logged in as the scraper identity {}", identity.username));
+                    save_cookie_store_to_json(&identity.cookie_store_path, Arc::clone(&scraper_guard.session.cookie_store)).await;
+                }
+                Err(e) => {
+                    self.println(&format!("Couldn't log in as the scraper identity {}, leaving it out for now: {}", identity.username, e));
+                    identity.exclude().await;
+                }
+            }
+        }
+    }
+
+    /// Downloads the reel through the next session of the pool, returns None when it has to go through the session of the account instead
+    ///
+    /// A session running into anything but a missing media is excluded, it's most likely restricted
+    pub(crate) async fn download_reel_with_identity(&self, shortcode: &str, filename: &str) -> Option<DownloadOutcome> {
+        let identity = self.scraper_identities.next().await?;
+
+        let mut scraper_guard = identity.scraper.lock().await;
+        let operation = format!("The download of the reel {} as {}", shortcode, identity.username);
+        let download_outcome = match with_watchdog(&operation, DOWNLOAD_TIMEOUT, scraper_guard.download_reel(shortcode, filename)).await {
+            Ok(Ok(caption)) => Some(DownloadOutcome::Downloaded(caption)),
+            Ok(Err(InstagramScraperError::MediaNotFound { .. })) => Some(DownloadOutcome::MediaNotFound),
+            // The other sessions would most likely hang as well, the content is left for the next iterations
+            Err(e) => {
+                self.println(&format!("{}, moving on", e));
+                let _ = tokio::fs::remove_file(format!("temp/{filename}")).await;
+                Some(DownloadOutcome::Failed)
+            }
+            Ok(Err(e)) => {
+                self.println(&format!("The scraper identity {} ran into an error, leaving it out for {} hours | {}", identity.username, IDENTITY_EXCLUSION_LEN.as_secs() / 3600, e));
+                identity.exclude().await;
+                None
+            }
+        };
+
+        save_cookie_store_to_json(&identity.cookie_store_path, Arc::clone(&scraper_guard.session.cookie_store)).await;
+        download_outcome
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::scraper_poster::metadata::SourcePostMetadata;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::INBOX_SETTLE_TIME;
+
+/// The videos dropped in inbox/<username>/ are picked up, along with their caption in a text file of the same name
+const INBOX_DIR: &str = "inbox";
+/// Given to the videos that were already handled, so that they stay in the inbox without being picked up again
+const SKIPPED_EXTENSION: &str = "skipped";
+
+impl ContentManager {
+    /// Hands the videos of the inbox over to the sender loop, they are checked for duplicates and reviewed like the scraped content
+    ///
+    /// The original content is credited to the account itself
+    pub(crate) async fn ingest_inbox(&self) {
+        let inbox_path = Path::new(INBOX_DIR).join(&self.username);
+        let Ok(mut entries) = tokio::fs::read_dir(&inbox_path).await else {
+            return;
+        };
+
+        let mut tx = self.database.begin_transaction().await;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            let is_video = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("mp4"));
+            if !is_video || !is_settled(&entry).await {
+                continue;
+            }
+
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let shortcode = format!("inbox_{}", stem.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect::<String>());
+            if tx.does_content_exist_with_shortcode(&shortcode).await {
+                self.println(&format!("The inbox video {} was already handled, skipping it", path.display()));
+                let mut skipped_path = path.clone().into_os_string();
+                skipped_path.push(format!(".{SKIPPED_EXTENSION}"));
+                let _ = tokio::fs::rename(&path, skipped_path).await;
+                continue;
+            }
+
+            let caption_path = path.with_extension("txt");
+            let caption = tokio::fs::read_to_string(&caption_path).await.unwrap_or_default();
+
+            let filename = format!("{}.mp4", shortcode);
+            if let Err(e) = move_file(&path, &Path::new("temp").join(&filename)).await {
+                self.println(&format!("Error while moving the inbox video {} | {}", path.display(), e));
+                continue;
+            }
+            let _ = tokio::fs::remove_file(&caption_path).await;
+            self.println(&format!("Picked up {} from the inbox", shortcode));
+
+            self.hand_over_content(&mut tx, (filename, caption.trim().to_string(), self.username.clone(), shortcode, SourcePostMetadata::default())).await;
+        }
+    }
+}
+
+/// A video still being copied into the inbox is left for the next pass
+async fn is_settled(entry: &tokio::fs::DirEntry) -> bool {
+    let Ok(modified) = entry.metadata().await.and_then(|metadata| metadata.modified()) else {
+        return false;
+    };
+    SystemTime::now().duration_since(modified).is_ok_and(|age| age >= INBOX_SETTLE_TIME)
+}
+
+/// The inbox might be mounted from another filesystem, where the video can't just be renamed
+async fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if tokio::fs::rename(from, to).await.is_ok() {
+        return Ok(());
+    }
+    tokio::fs::copy(from, to).await?;
+    tokio::fs::remove_file(from).await
+}
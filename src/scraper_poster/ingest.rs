@@ -0,0 +1,50 @@
+use crate::scraper_poster::downloads::{DownloadOutcome, DownloadStage};
+use crate::scraper_poster::metadata::SourcePostMetadata;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::utils::pause_scraper_if_needed;
+use crate::SCRAPER_REFRESH_RATE;
+
+impl ContentManager {
+    /// Downloads the reels asked for from Discord and picks up the videos of the inbox as they come, they then go through the sender loop like the scraped content
+    pub(crate) async fn run_ingest_loop(&self) {
+        loop {
+            self.ingest_inbox().await;
+
+            let mut tx = self.database.begin_transaction().await;
+            for ingest_request in tx.load_ingest_requests().await {
+                let shortcode = ingest_request.original_shortcode;
+                if tx.does_content_exist_with_shortcode(&shortcode).await {
+                    tx.remove_ingest_request(&shortcode).await;
+                    continue;
+                }
+
+                pause_scraper_if_needed(&mut tx).await;
+                self.rate_limiter.wait_for_circuit().await;
+
+                // The reel is still worth reviewing without its author, it just can't be credited
+                let (author, source_post_metadata) = self.fetch_source_post(&shortcode).await.unwrap_or_else(|e| {
+                    self.println(&format!("Couldn't look up the reel {} asked for by {}: {}", shortcode, ingest_request.requested_by, e));
+                    ("unknown".to_string(), SourcePostMetadata::default())
+                });
+
+                let caption = match self.download_content(&DownloadStage::default(), &shortcode, None).await {
+                    DownloadOutcome::Downloaded(caption) => caption,
+                    // Tried again once the rate limit is over
+                    DownloadOutcome::Postponed => continue,
+                    DownloadOutcome::MediaNotFound | DownloadOutcome::Failed => {
+                        self.println(&format!("Couldn't download the reel {} asked for by {}, dropping it", shortcode, ingest_request.requested_by));
+                        tx.remove_ingest_request(&shortcode).await;
+                        continue;
+                    }
+                };
+                self.println(&format!("Ingested content from {} asked for by {}: {}", author, ingest_request.requested_by, shortcode));
+
+                let filename = format!("{}.mp4", shortcode);
+                self.hand_over_content(&mut tx, (filename, caption, author, shortcode.clone(), source_post_metadata)).await;
+                tx.remove_ingest_request(&shortcode).await;
+            }
+
+            tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::database::database::{ScrapeRun, SkipReason};
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::scraper::ContentManager;
+
+/// Counts what the scraper does during an iteration, shared by the downloads running in parallel
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ScrapeJournal {
+    entries: Arc<Mutex<JournalEntries>>,
+}
+
+#[derive(Debug, Default)]
+struct JournalEntries {
+    started_at: String,
+    accounts_fetched: i32,
+    posts_seen: i32,
+    posts_downloaded: i32,
+    skips: BTreeMap<&'static str, i32>,
+    errors: i32,
+    last_error: String,
+}
+
+impl ScrapeJournal {
+    fn update(&self, update: impl FnOnce(&mut JournalEntries)) {
+        if let Ok(mut entries) = self.entries.lock() {
+            update(&mut entries);
+        }
+    }
+
+    pub(crate) fn record_accounts_fetched(&self, count: usize) {
+        self.update(|entries| entries.accounts_fetched += count as i32);
+    }
+
+    pub(crate) fn record_posts_seen(&self, count: usize) {
+        self.update(|entries| entries.posts_seen += count as i32);
+    }
+
+    pub(crate) fn record_download(&self) {
+        self.update(|entries| entries.posts_downloaded += 1);
+    }
+
+    pub(crate) fn record_skips(&self, reason: SkipReason, count: usize) {
+        self.update(|entries| *entries.skips.entry(reason.as_str()).or_default() += count as i32);
+    }
+
+    pub(crate) fn record_error(&self, error: &str) {
+        self.update(|entries| {
+            entries.errors += 1;
+            entries.last_error = error.to_string();
+        });
+    }
+}
+
+impl ContentManager {
+    /// Starts counting a new iteration of the scraper
+    pub(crate) async fn start_scrape_run(&self) {
+        let user_settings = self.database.begin_transaction().await.load_user_settings().await;
+        let started_at = now_in_my_timezone(&user_settings).to_rfc3339();
+        self.scrape_journal.update(|entries| *entries = JournalEntries { started_at, ..Default::default() });
+    }
+
+    /// Records the iteration in the scrape_runs table, for the daily digest
+    pub(crate) async fn finish_scrape_run(&self) {
+        let mut tx = self.database.begin_transaction().await;
+        let finished_at = now_in_my_timezone(&tx.load_user_settings().await).to_rfc3339();
+
+        let Some(scrape_run) = self.scrape_journal.entries.lock().ok().map(|entries| ScrapeRun {
+            username: self.username.clone(),
+            started_at: entries.started_at.clone(),
+            finished_at,
+            accounts_fetched: entries.accounts_fetched,
+            posts_seen: entries.posts_seen,
+            posts_downloaded: entries.posts_downloaded,
+            skips: entries.skips.iter().map(|(reason, count)| format!("{reason}:{count}")).collect::<Vec<_>>().join(","),
+            errors: entries.errors,
+            last_error: entries.last_error.clone(),
+        }) else {
+            return;
+        };
+        tx.save_scrape_run(&scrape_run).await;
+    }
+}
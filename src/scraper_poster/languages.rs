@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use whatlang::Lang;
+
+const CAPTION_LANGUAGES_PATH: &str = "config/caption_languages.yaml";
+
+/// What happens to the content whose caption isn't in one of the allowed languages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LanguageAction {
+    /// The language is shown on the card, the content is reviewed as usual
+    #[default]
+    Tag,
+    /// The caption is translated before the review, and tagged as well
+    Translate,
+    /// The content is dropped before the review
+    Skip,
+}
+
+/// The languages the captions of an account are expected in
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CaptionLanguages {
+    /// ISO 639-3 codes, e.g. "eng", no filtering when empty
+    #[serde(default)]
+    pub allowed_languages: Vec<String>,
+    #[serde(default)]
+    pub action: LanguageAction,
+    /// The ISO 639-1 code the captions are translated to, e.g. "en"
+    #[serde(default)]
+    pub translate_to: String,
+    /// The translate endpoint of a LibreTranslate instance
+    #[serde(default)]
+    pub translate_url: String,
+    #[serde(default)]
+    pub translate_api_key: String,
+}
+
+impl CaptionLanguages {
+    /// Returns the language of the caption when it isn't one of the allowed ones
+    ///
+    /// The short captions and the ones made of hashtags and emojis can't be told apart reliably, so they are always let through
+    pub fn foreign_language(&self, caption: &str) -> Option<Lang> {
+        if self.allowed_languages.is_empty() {
+            return None;
+        }
+
+        // The hashtags and the mentions aren't written in any language
+        let text = caption.split_whitespace().filter(|word| !word.starts_with('#') && !word.starts_with('@')).collect::<Vec<&str>>().join(" ");
+        let info = whatlang::detect(&text).filter(|info| info.is_reliable())?;
+        let is_allowed = self.allowed_languages.iter().any(|allowed_language| allowed_language.eq_ignore_ascii_case(info.lang().code()));
+        (!is_allowed).then_some(info.lang())
+    }
+
+    /// Translates the caption through LibreTranslate, the source language is detected again on its side since it doesn't use the same codes
+    pub async fn translate(&self, caption: &str) -> anyhow::Result<String> {
+        let body = serde_json::json!({
+            "q": caption,
+            "source": "auto",
+            "target": self.translate_to,
+            "format": "text",
+            "api_key": self.translate_api_key,
+        });
+        let response = reqwest::Client::new().post(&self.translate_url).header("Content-Type", "application/json").body(body.to_string()).send().await?.error_for_status()?.text().await?;
+        let response: Value = serde_json::from_str(&response)?;
+
+        let Some(translated_text) = response["translatedText"].as_str() else {
+            anyhow::bail!("No translation in the response: {}", response);
+        };
+        Ok(translated_text.to_string())
+    }
+}
+
+/// Reads the languages allowed for the captions of the given account, the file is optional
+pub async fn read_caption_languages(username: &str) -> CaptionLanguages {
+    let mut file = match File::open(CAPTION_LANGUAGES_PATH).await {
+        Ok(file) => file,
+        Err(_) => return CaptionLanguages::default(),
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.expect("Unable to read the caption languages file");
+    let mut caption_languages: HashMap<String, CaptionLanguages> = serde_yaml::from_str(&contents).expect("Error parsing the caption languages file");
+    caption_languages.remove(username).unwrap_or_default()
+}
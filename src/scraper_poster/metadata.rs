@@ -0,0 +1,141 @@
+use instagram_scraper_rs::Post;
+use reqwest::StatusCode;
+use serde_json::Value;
+
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::stories::INSTAGRAM_APP_ID;
+
+/// The posts scraped by the library don't hold their audio, it's looked up on the private API
+const MEDIA_INFO_URL: &str = "https://i.instagram.com/api/v1/media";
+/// The alphabet of the shortcodes, which are the media ids written in base 64
+const SHORTCODE_ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+/// The content that doesn't come from Instagram is given a shortcode of its own, starting with the name of its source
+const OTHER_SOURCE_PREFIXES: [&str; 5] = ["compilation_", "inbox_", "reddit_", "tiktok_", "youtube_"];
+
+/// What the source post looked like when it was scraped, handed over to the sender loop along with the video
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SourcePostMetadata {
+    /// 0 when the source doesn't tell
+    pub(crate) taken_at_timestamp: i64,
+    pub(crate) views: i64,
+    pub(crate) likes: i64,
+    /// Empty when the audio couldn't be found
+    pub(crate) audio_id: String,
+    /// The title and the artist of the music, or the title of the original sound, empty when unknown
+    pub(crate) audio_name: String,
+    /// The category of hashtags.yaml of the source account, empty when it has none
+    pub(crate) category: String,
+    /// The post the video was taken from, empty for the videos sent to the inbox
+    pub(crate) source_url: String,
+    /// Set when the content is handed over to the sender loop
+    pub(crate) downloaded_at: String,
+    /// The processing steps applied to the video right after its download, e.g. the re-encoding of the other sources
+    pub(crate) processing_steps: Vec<String>,
+}
+
+impl From<&Post> for SourcePostMetadata {
+    fn from(post: &Post) -> Self {
+        Self {
+            taken_at_timestamp: post.taken_at_timestamp as i64,
+            views: post.video_view_count.unwrap_or_default() as i64,
+            likes: post.likes as i64,
+            audio_id: String::new(),
+            audio_name: String::new(),
+            category: String::new(),
+            source_url: String::new(),
+            downloaded_at: String::new(),
+            processing_steps: vec![],
+        }
+    }
+}
+
+/// Returns None when the shortcode holds a character outside of the alphabet
+fn media_id_from_shortcode(shortcode: &str) -> Option<u128> {
+    shortcode.chars().try_fold(0u128, |media_id, c| SHORTCODE_ALPHABET.find(c).map(|position| media_id * 64 + position as u128))
+}
+
+/// e.g. https://www.instagram.com/p/C7x9Yb1Nq2w/, which also leads to the reels
+pub(crate) fn instagram_post_url(shortcode: &str) -> String {
+    format!("https://www.instagram.com/p/{}/", shortcode)
+}
+
+pub(crate) fn is_instagram_shortcode(shortcode: &str) -> bool {
+    !OTHER_SOURCE_PREFIXES.iter().any(|prefix| shortcode.starts_with(prefix)) && media_id_from_shortcode(shortcode).is_some()
+}
+
+/// The original sounds and the licensed music are found under different keys, and their ids come either as numbers or as strings
+fn audio_id_of_item(item: &Value) -> Option<String> {
+    let clips_metadata = &item["clips_metadata"];
+    [&clips_metadata["music_info"]["music_asset_info"]["audio_asset_id"], &clips_metadata["original_sound_info"]["audio_asset_id"]]
+        .into_iter()
+        .find_map(|audio_asset_id| audio_asset_id.as_str().map(str::to_string).or_else(|| audio_asset_id.as_u64().map(|audio_asset_id| audio_asset_id.to_string())))
+}
+
+/// e.g. "Espresso - Sabrina Carpenter" for the licensed music, the original sounds only have a title
+fn audio_name_of_item(item: &Value) -> Option<String> {
+    let clips_metadata = &item["clips_metadata"];
+    let music_asset_info = &clips_metadata["music_info"]["music_asset_info"];
+    match (music_asset_info["title"].as_str(), music_asset_info["display_artist"].as_str()) {
+        (Some(title), Some(artist)) if !artist.is_empty() => Some(format!("{} - {}", title, artist)),
+        (Some(title), _) => Some(title.to_string()),
+        _ => clips_metadata["original_sound_info"]["original_audio_title"].as_str().map(str::to_string),
+    }
+}
+
+impl ContentManager {
+    /// Returns the id and the name of the audio used by the reel, empty when it has none
+    pub(crate) async fn fetch_audio(&self, shortcode: &str) -> anyhow::Result<(String, String)> {
+        let item = self.fetch_media_item(shortcode).await?;
+        Ok((audio_id_of_item(&item).unwrap_or_default(), audio_name_of_item(&item).unwrap_or_default()))
+    }
+
+    /// Returns the author of the reel along with its metadata, for the reels that weren't found through a source account
+    pub(crate) async fn fetch_source_post(&self, shortcode: &str) -> anyhow::Result<(String, SourcePostMetadata)> {
+        let item = self.fetch_media_item(shortcode).await?;
+        let Some(author) = item["user"]["username"].as_str() else {
+            anyhow::bail!("The media {} has no author", shortcode);
+        };
+
+        let source_post_metadata = SourcePostMetadata {
+            taken_at_timestamp: item["taken_at"].as_i64().unwrap_or_default(),
+            views: item["play_count"].as_i64().or_else(|| item["view_count"].as_i64()).unwrap_or_default(),
+            likes: item["like_count"].as_i64().unwrap_or_default(),
+            audio_id: audio_id_of_item(&item).unwrap_or_default(),
+            audio_name: audio_name_of_item(&item).unwrap_or_default(),
+            category: String::new(),
+            source_url: String::new(),
+            downloaded_at: String::new(),
+            processing_steps: vec![],
+        };
+        Ok((author.to_string(), source_post_metadata))
+    }
+
+    /// Whether the author deleted the source post, the private API turns down the lookup of a media that isn't there anymore
+    pub(crate) async fn is_source_post_deleted(&self, shortcode: &str) -> anyhow::Result<bool> {
+        let Some(media_id) = media_id_from_shortcode(shortcode) else {
+            anyhow::bail!("{} is not a valid shortcode", shortcode);
+        };
+
+        let response = self.private_api_client().await?.get(format!("{}/{}/info/", MEDIA_INFO_URL, media_id)).header("X-IG-App-ID", INSTAGRAM_APP_ID).send().await?;
+        match response.status() {
+            StatusCode::NOT_FOUND => Ok(true),
+            // Also what a rate limit or an expired session looks like, so the error has to say the media is gone
+            StatusCode::BAD_REQUEST => Ok(response.text().await?.to_lowercase().contains("not found")),
+            _ => {
+                let response: Value = serde_json::from_str(&response.error_for_status()?.text().await?)?;
+                Ok(response["items"].as_array().is_some_and(|items| items.is_empty()))
+            }
+        }
+    }
+
+    pub(crate) async fn fetch_media_item(&self, shortcode: &str) -> anyhow::Result<Value> {
+        let Some(media_id) = media_id_from_shortcode(shortcode) else {
+            anyhow::bail!("{} is not a valid shortcode", shortcode);
+        };
+
+        let response = self.private_api_client().await?.get(format!("{}/{}/info/", MEDIA_INFO_URL, media_id)).header("X-IG-App-ID", INSTAGRAM_APP_ID).send().await?.error_for_status()?.text().await?;
+        let mut response: Value = serde_json::from_str(&response)?;
+
+        Ok(response["items"][0].take())
+    }
+}
@@ -1,3 +1,33 @@
+pub(crate) mod blacklist;
+pub(crate) mod canary;
+mod challenge;
+mod comment_replies;
+pub(crate) mod compilation;
+mod discovery;
+mod download_manager;
+mod download_strategies;
+mod downloads;
+mod emoji_pack;
+mod engagement;
+mod eviction;
+mod formatting;
+pub(crate) mod graph_api;
+mod identities;
+mod inbox;
+mod ingest;
+mod journal;
+mod languages;
+mod metadata;
 mod poster;
+mod profanity;
+mod proxy;
+mod publishers;
+mod rate_limiter;
+mod rules;
+mod session;
 pub(crate) mod scraper;
+mod sources;
+mod stories;
+mod story_shares;
+mod suggestions;
 mod utils;
@@ -1,19 +1,29 @@
 use std::time::Duration;
 
-use chrono::{DateTime, Utc};
-use instagram_scraper_rs::{InstagramScraper, InstagramUploaderError};
+use chrono::{DateTime, Datelike, Utc};
+use instagram_scraper_rs::InstagramScraper;
 use rand::prelude::{SliceRandom, StdRng};
 use rand::rngs::OsRng;
 use rand::{Rng, SeedableRng};
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
 
-use crate::database::database::{DatabaseTransaction, FailedContent, PublishedContent, QueuedContent, UserSettings};
+use crate::coordination::publishing::PublishingGuard;
+use crate::database::database::{ContentInfo, DatabaseTransaction, FailedContent, PostMetrics, PublishedContent, QueuedContent, UserSettings};
 use crate::discord::state::ContentStatus;
 use crate::discord::utils::now_in_my_timezone;
+use crate::maintenance::windows::current_maintenance_window;
+use crate::scraper_poster::canary::{is_step_canaried, is_step_enabled, read_canary_steps};
+use crate::scraper_poster::emoji_pack::{read_emoji_pack, sign_caption, SIGNATURE_STEP};
+use crate::scraper_poster::formatting::{default_alt_text, format_caption, resolve_spintax, Destination};
+use crate::scraper_poster::graph_api::{shortcode_from_permalink, verify_published_media, GraphApiError, MediaContainerOptions};
+use crate::scraper_poster::metadata::is_instagram_shortcode;
+use crate::scraper_poster::publishers::instagram::InstagramReels;
+use crate::scraper_poster::publishers::{read_cross_post_publishers, Publisher};
 use crate::scraper_poster::scraper::ContentManager;
 use crate::scraper_poster::utils::{set_bot_status_halted};
-use crate::SCRAPER_REFRESH_RATE;
+use crate::supervisor::watchdog::with_watchdog;
+use crate::{SCRAPER_REFRESH_RATE, UPLOAD_TIMEOUT};
 
 impl ContentManager {
     pub fn poster_loop(&mut self) -> JoinHandle<anyhow::Result<()>> {
@@ -33,37 +43,124 @@ impl ContentManager {
             // Sleep
             sleep(Duration::from_millis(sleep_duration_millis)).await;
 
+            let emoji_pack = read_emoji_pack(&cloned_self.username).await;
+            let cross_post_publishers = read_cross_post_publishers(&cloned_self.credentials);
+
             cloned_self.println("Starting poster loop...");
 
+            let mut is_paused_for_maintenance = false;
             loop {
                 let mut tx = cloned_self.database.begin_transaction().await;
                 let content_mapping = tx.load_content_mapping().await;
                 let user_settings = tx.load_user_settings().await;
 
                 let queued_posts = tx.load_content_queue().await;
+                let bot_status = tx.load_bot_status().await;
+
+                if let Some(maintenance_window) = current_maintenance_window(now_in_my_timezone(&user_settings)) {
+                    if !is_paused_for_maintenance {
+                        cloned_self.println(&format!("Pausing poster for maintenance until {}", maintenance_window.end.format("%Y-%m-%d %H:%M")));
+                        is_paused_for_maintenance = true;
+                    }
+                    sleep(SCRAPER_REFRESH_RATE).await;
+                    continue;
+                } else if is_paused_for_maintenance {
+                    cloned_self.println("Maintenance is over, resuming poster...");
+                    is_paused_for_maintenance = false;
+                }
+
+                // Held while the process is about to restart, the new process picks the queue up again
+                let Some(publishing) = PublishingGuard::start() else {
+                    sleep(SCRAPER_REFRESH_RATE).await;
+                    continue;
+                };
 
                 'outer: for content_info in content_mapping {
                     if content_info.status.to_string().contains("queued_") {
                         for queued_post in queued_posts.iter() {
+                            // While draining, only the content scheduled before the end of the drain is published
+                            if !bot_status.drain_until.is_empty() && DateTime::parse_from_rfc3339(&queued_post.will_post_at).unwrap() > DateTime::parse_from_rfc3339(&bot_status.drain_until).unwrap() {
+                                continue;
+                            }
+
                             if DateTime::parse_from_rfc3339(&queued_post.will_post_at).unwrap() < now_in_my_timezone(&user_settings) {
                                 if user_settings.can_post {
-                                    if !cloned_self.is_offline {
-                                        let full_caption = Self::prepare_caption_for_post(queued_post);
+                                    // Asked for from Discord, it goes out even outside of the posting days and past the daily cap
+                                    let is_post_now = cloned_self.post_now.is_requested(&queued_post.original_shortcode);
+                                    cloned_self.post_now.clear(&queued_post.original_shortcode);
+                                    if !is_post_now && cloned_self.hold_until_posting_day(&user_settings, &mut tx, queued_post).await {
+                                        break 'outer;
+                                    }
+                                    if cloned_self.hold_if_source_deleted(&user_settings, &mut tx, queued_post).await {
+                                        break 'outer;
+                                    }
 
+                                    // The processing steps being canaried only apply to every Nth post, which gets tagged with the ones it went through
+                                    let post_number = tx.count_post_metrics().await + 1;
+                                    let canary_steps = read_canary_steps(&cloned_self.credentials);
+                                    let mut applied_canary_steps = Vec::new();
+
+                                    // The variation picked for the caption is recorded along with the published content
+                                    let mut caption_variant = resolve_spintax(&queued_post.caption, &mut rng);
+                                    if is_step_enabled(&canary_steps, SIGNATURE_STEP, post_number, now_in_my_timezone(&user_settings)) {
+                                        let recent_caption_variants = tx.load_recent_caption_variants(emoji_pack.no_repeat_within).await;
+                                        if let Some(signature) = emoji_pack.pick_signature(&recent_caption_variants, &mut rng) {
+                                            caption_variant = sign_caption(&caption_variant, &signature);
+                                            if is_step_canaried(&canary_steps, SIGNATURE_STEP, now_in_my_timezone(&user_settings)) {
+                                                applied_canary_steps.push(SIGNATURE_STEP);
+                                            }
+                                        }
+                                    }
+
+                                    let mut permalink = String::new();
+                                    let mut media_id = String::new();
+                                    if user_settings.dry_run {
+                                        // Go through everything except the upload, so that config changes can be tested on real data
+                                        let full_caption = format_caption(&caption_variant, &queued_post.hashtags, Destination::Reel);
+                                        cloned_self.println(&format!("[dry run] Would publish {} ({}) with caption:\n{}", queued_post.original_shortcode, queued_post.url, full_caption));
+                                    } else if !cloned_self.is_offline {
                                         let user_id = cloned_self.credentials.get("instagram_business_account_id").unwrap();
                                         let access_token = cloned_self.credentials.get("fb_access_token").unwrap();
 
                                         // We want to lock the scraper for the entire duration of the publishing process
                                         let mut scraper_guard = cloned_self.scraper.lock().await;
 
+                                        // Publishing is never delayed, but it still counts towards the budget shared with the scraper
+                                        cloned_self.coordinator.increment_counter("instagram_requests", Duration::from_secs(60 * 60)).await;
+
                                         // Publish the content
-                                        let reel_id = match cloned_self.publish_content(&mut scraper_guard, &user_settings, &mut tx, queued_post, &full_caption, user_id, access_token).await {
+                                        let reel_id = match cloned_self.publish_content(&mut scraper_guard, &user_settings, &mut tx, queued_post, &caption_variant, user_id, access_token).await {
                                             Some(value) => value,
                                             None => break 'outer,
                                         };
 
+                                        // The processing on Instagram's side can still fail after the publishing, which would otherwise leave a silently missing post
+                                        // The permalink is shared in the community channel once the reel is live
+                                        permalink = match verify_published_media(&reel_id, access_token).await {
+                                            Ok(permalink) => permalink,
+                                            Err(GraphApiError::UploadFailedNonRecoverable(e)) => {
+                                                cloned_self.println(&format!("[!] Instagram failed to process the published content!\n [ERROR] {}\n{}", e, queued_post.url));
+                                                tx.record_publish_attempt(&queued_post.original_shortcode, &format!("{} {} failed processing: {}", now_in_my_timezone(&user_settings).to_rfc3339(), reel_id, e)).await;
+                                                cloned_self.handle_failed_content(&user_settings, &mut tx, queued_post).await;
+                                                break 'outer;
+                                            }
+                                            Err(e) => {
+                                                cloned_self.println(&format!("Couldn't verify the published content, keeping it as published: {}", e));
+                                                "".to_string()
+                                            }
+                                        };
+
                                         // Try to comment on the post
                                         cloned_self.comment_on_published_content(&mut scraper_guard, access_token, &reel_id).await;
+                                        media_id = reel_id;
+                                        // The other destinations don't go through the scraper, it can get back to work in the meantime
+                                        drop(scraper_guard);
+
+                                        cloned_self.cross_post(&cross_post_publishers, &user_settings, &mut tx, queued_post, &caption_variant).await;
+
+                                        if user_settings.story_share_enabled {
+                                            cloned_self.schedule_story_share(&user_settings, &mut tx, queued_post, &media_id).await;
+                                        }
                                     } else if queued_post.caption.contains("will_fail") {
                                         cloned_self.println(&format!("[!] Failed to upload content offline: {}", queued_post.url));
                                         cloned_self.handle_failed_content(&user_settings, &mut tx, queued_post).await;
@@ -85,15 +182,32 @@ impl ContentManager {
                                         original_author: queued_post.original_author.clone(),
                                         original_shortcode: queued_post.original_shortcode.clone(),
                                         published_at: now_in_my_timezone(&user_settings).to_rfc3339(),
+                                        is_simulated: user_settings.dry_run,
+                                        caption_variant,
+                                        is_trial_reel: content_info.is_trial_reel,
+                                        permalink,
+                                        media_id,
                                     };
 
                                     tx.save_published_content(&published_content).await;
+
+                                    if !user_settings.dry_run {
+                                        let post_metrics = PostMetrics {
+                                            username: queued_post.username.clone(),
+                                            original_shortcode: queued_post.original_shortcode.clone(),
+                                            published_at: published_content.published_at.clone(),
+                                            canary_steps: applied_canary_steps.join(","),
+                                        };
+                                        tx.save_post_metrics(&post_metrics).await;
+                                        tx.record_published_category(&content_info.category, &published_content.published_at).await;
+                                    }
+                                    cloned_self.coordinator.publish_event(&format!("published:{}", queued_post.original_shortcode)).await;
                                 } else {
                                     for content in queued_posts.clone().iter_mut() {
-                                        content.will_post_at = (DateTime::parse_from_rfc3339(&content.will_post_at).unwrap() + Duration::from_secs((user_settings.posting_interval * 60) as u64)).to_rfc3339();
+                                        content.will_post_at = (DateTime::parse_from_rfc3339(&content.will_post_at).unwrap() + user_settings.posting_interval()).to_rfc3339();
                                         tx.save_queued_content(queued_post).await;
                                         let mut content_info = tx.get_content_info_by_shortcode(&queued_post.original_shortcode).await;
-                                        content_info.last_updated_at = (now_in_my_timezone(&user_settings) - chrono::Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+                                        content_info.last_updated_at = (now_in_my_timezone(&user_settings) - user_settings.interface_update_interval()).to_rfc3339();
                                         tx.save_content_info(&content_info).await;
                                     }
                                     // Since we have just altered the whole queue, and we are also iterating over the queue in the outer loop, we need to break here
@@ -104,8 +218,12 @@ impl ContentManager {
                         }
                     }
                 }
+                cloned_self.publish_approved_stories(&user_settings, &mut tx).await;
+                cloned_self.share_due_reels_on_story(&user_settings, &mut tx).await;
+                drop(publishing);
+
                 // Don't remove this sleep, without it the bot becomes completely unresponsive
-                sleep(SCRAPER_REFRESH_RATE).await;
+                cloned_self.post_now.wait(SCRAPER_REFRESH_RATE).await;
             }
         })
     }
@@ -154,10 +272,37 @@ impl ContentManager {
         }
     }
 
-    async fn publish_content(&self, scraper: &mut InstagramScraper, user_settings: &UserSettings, tx: &mut DatabaseTransaction, queued_post: &QueuedContent, full_caption: &str, user_id: &str, access_token: &str) -> Option<String> {
+    async fn publish_content(&self, scraper: &mut InstagramScraper, user_settings: &UserSettings, tx: &mut DatabaseTransaction, queued_post: &QueuedContent, caption_variant: &str, user_id: &str, access_token: &str) -> Option<String> {
         self.println(&format!("[+] Publishing content to instagram: {}", queued_post.original_shortcode));
         let timer = std::time::Instant::now();
-        let result = scraper.upload_reel(user_id, access_token, &queued_post.url, full_caption).await;
+
+        let full_caption = format_caption(caption_variant, &queued_post.hashtags, Destination::Reel);
+        let content_info = tx.get_content_info_by_shortcode(&queued_post.original_shortcode).await;
+        let options = MediaContainerOptions {
+            alt_text: self.alt_text_for_post(&content_info, caption_variant),
+            location_id: queued_post.location_id.clone(),
+            is_paid_partnership: content_info.is_paid_partnership,
+            is_trial_reel: content_info.is_trial_reel,
+            audio_name: self.audio_name_for_post(&content_info),
+        };
+        let upload = async {
+            if options.is_empty() {
+                scraper.upload_reel(user_id, access_token, &queued_post.url, &full_caption).await.map_err(GraphApiError::from)
+            } else {
+                let instagram_reels = InstagramReels { user_id: user_id.to_string(), access_token: access_token.to_string(), options: options.clone() };
+                instagram_reels.publish(&queued_post.url, &full_caption).await
+            }
+        };
+        // A hanging upload is tried again later like the other recoverable failures
+        let operation = format!("The upload of {}", queued_post.original_shortcode);
+        let result = with_watchdog(&operation, UPLOAD_TIMEOUT, upload).await.unwrap_or_else(|e| Err(GraphApiError::UploadFailedRecoverable(e.to_string())));
+
+        let attempt = match &result {
+            Ok(reel_id) => reel_id.clone(),
+            Err(err) => format!("failed: {}", err),
+        };
+        tx.record_publish_attempt(&queued_post.original_shortcode, &format!("{} {}", now_in_my_timezone(user_settings).to_rfc3339(), attempt)).await;
+
         match result {
             Ok(reel_id) => {
                 let duration = timer.elapsed(); // End timer
@@ -172,10 +317,112 @@ impl ContentManager {
         }
     }
 
-    async fn handle_upload_error(&self, err: InstagramUploaderError, user_settings: &UserSettings, tx: &mut DatabaseTransaction, queued_post: &QueuedContent) -> Option<String> {
+    /// The alt text set from Discord, otherwise the one generated from the published caption if enabled in the credentials
+    fn alt_text_for_post(&self, content_info: &ContentInfo, caption_variant: &str) -> String {
+        if !content_info.alt_text.is_empty() {
+            return content_info.alt_text.clone();
+        }
+
+        if self.credentials.get("auto_alt_text").map(|value| value == "true").unwrap_or(false) {
+            default_alt_text(caption_variant)
+        } else {
+            "".to_string()
+        }
+    }
+
+    /// The Graph API can't attach an audio of the library to the reel, but the audio of the video can be named after the one of the source post
+    fn audio_name_for_post(&self, content_info: &ContentInfo) -> String {
+        if self.credentials.get("reuse_audio_name").is_some_and(|value| value == "true") {
+            content_info.audio_name.clone()
+        } else {
+            "".to_string()
+        }
+    }
+
+    /// The queue can have been scheduled before the daily cap or the posting days were changed, so they are checked again right before publishing
+    ///
+    /// The content is then moved to the next day with room. Returns whether it was held back
+    async fn hold_until_posting_day(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction, queued_post: &QueuedContent) -> bool {
+        let now = now_in_my_timezone(user_settings);
+        let published_times: Vec<DateTime<Utc>> = tx.load_posted_content().await.iter().map(|published_content| DateTime::parse_from_rfc3339(&published_content.published_at).unwrap().with_timezone(&Utc)).collect();
+        if user_settings.allows_post_on(&published_times, &now) {
+            return false;
+        }
+
+        let mut queued_post = queued_post.clone();
+        queued_post.will_post_at = tx.get_new_post_time().await;
+        let reason = if user_settings.is_posting_day(now.weekday()) {
+            format!("the daily cap of {} posts is reached", user_settings.max_posts_per_day)
+        } else {
+            "today isn't a posting day".to_string()
+        };
+        self.println(&format!("Not publishing {} since {}, moving it to {}", queued_post.original_shortcode, reason, queued_post.will_post_at));
+        tx.save_queued_content(&queued_post).await;
+
+        let mut content_info = tx.get_content_info_by_shortcode(&queued_post.original_shortcode).await;
+        // Force the update of the card
+        content_info.last_updated_at = (now - user_settings.interface_update_interval()).to_rfc3339();
+        tx.save_content_info(&content_info).await;
+        true
+    }
+
+    /// The author deleting the source post often means they want it taken down, so it's checked right before publishing
+    ///
+    /// The content is then sent back for a review, unless deleted_source_action is set to "publish" in the credentials.
+    /// Returns whether the content was held back
+    async fn hold_if_source_deleted(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction, queued_post: &QueuedContent) -> bool {
+        if self.is_offline {
+            return false;
+        }
+        // The videos of a carousel don't have a post of their own, the one they come from is checked instead
+        let source_shortcode = match tx.get_content_provenance_by_shortcode(&queued_post.original_shortcode).await.and_then(|content_provenance| shortcode_from_permalink(&content_provenance.source_url)) {
+            Some(source_shortcode) => source_shortcode,
+            None => queued_post.original_shortcode.clone(),
+        };
+        if !is_instagram_shortcode(&queued_post.original_shortcode) || !is_instagram_shortcode(&source_shortcode) {
+            return false;
+        }
+
+        let mut content_info = tx.get_content_info_by_shortcode(&queued_post.original_shortcode).await;
+        // Already accepted anyway
+        if content_info.source_deleted {
+            return false;
+        }
+
+        // Like publishing, the check is never delayed but it still counts towards the budget shared with the scraper
+        self.coordinator.increment_counter("instagram_requests", Duration::from_secs(60 * 60)).await;
+        match self.is_source_post_deleted(&source_shortcode).await {
+            Ok(true) => {}
+            Ok(false) => return false,
+            Err(e) => {
+                self.println(&format!("Couldn't check whether the source post of {} is still up, publishing it anyway: {}", queued_post.original_shortcode, e));
+                return false;
+            }
+        }
+
+        content_info.source_deleted = true;
+        if self.credentials.get("deleted_source_action").is_some_and(|action| action == "publish") {
+            self.println(&format!("The source post of {} was deleted by {}, publishing it anyway", queued_post.original_shortcode, queued_post.original_author));
+            tx.save_content_info(&content_info).await;
+            return false;
+        }
+
+        self.println(&format!("The source post of {} was deleted by {}, holding it until it's accepted again", queued_post.original_shortcode, queued_post.original_author));
+        tx.remove_post_from_queue_with_shortcode(&queued_post.original_shortcode).await;
+        content_info.status = match content_info.status {
+            ContentStatus::Queued { shown } => ContentStatus::Pending { shown },
+            status => status,
+        };
+        // Force the update of the card
+        content_info.last_updated_at = (now_in_my_timezone(user_settings) - user_settings.interface_update_interval()).to_rfc3339();
+        tx.save_content_info(&content_info).await;
+        true
+    }
+
+    async fn handle_upload_error(&self, err: GraphApiError, user_settings: &UserSettings, tx: &mut DatabaseTransaction, queued_post: &QueuedContent) -> Option<String> {
         match err {
-            InstagramUploaderError::UploadFailedRecoverable(err) => {
-                if err.to_string().contains("The app user's Instagram Professional account is inactive, checkpointed, or restricted.") {
+            GraphApiError::UploadFailedRecoverable(err) => {
+                if err.contains("The app user's Instagram Professional account is inactive, checkpointed, or restricted.") {
                     self.println("[!] Couldn't upload content to instagram! The app user's Instagram Professional account is inactive, checkpointed, or restricted.");
                     set_bot_status_halted(tx).await;
                     None
@@ -185,12 +432,12 @@ impl ContentManager {
                     None
                 }
             }
-            InstagramUploaderError::UploadFailedNonRecoverable(err) => {
+            GraphApiError::UploadFailedNonRecoverable(err) => {
                 self.println(&format!("[!] Couldn't upload content to instagram!\n [ERROR] {}\n{}", err, queued_post.url));
                 self.handle_failed_content(user_settings, tx, queued_post).await;
                 None
             }
-            InstagramUploaderError::UploadSucceededButFailedToRetrieveId(e) => {
+            GraphApiError::UploadSucceededButFailedToRetrieveId(e) => {
                 self.println(&format!("[!] Uploaded content to instagram, but failed to retrieve media id!\n [WARNING] {}\n{}", e, queued_post.url));
                 self.handle_posted_but_failed_content(user_settings, tx, queued_post).await;
                 None
@@ -198,34 +445,6 @@ impl ContentManager {
         }
     }
 
-    fn prepare_caption_for_post(queued_post: &QueuedContent) -> String {
-        // Example of a caption:
-        // "This is a cool caption!"
-        // "•"
-        // "•"
-        // "•"
-        // "•"
-        // "•"
-        // "(We don’t own this reel. All rights are reserved & belong to their respective owners, no copyright infringement intended. DM for credit/removal.)"
-        // "•"
-        // "#cool #caption #hashtags"
-
-        let full_caption;
-        let big_spacer = "\n\n\n•\n•\n•\n•\n•\n";
-        let small_spacer = "\n•\n";
-        let disclaimer = "(We don’t own this content. All rights are reserved & belong to their respective owners, no copyright infringement intended. DM for credit/removal.)";
-        if queued_post.caption.is_empty() && queued_post.hashtags.is_empty() {
-            full_caption = "".to_string();
-        } else if queued_post.caption.is_empty() {
-            full_caption = format!("{}", queued_post.hashtags);
-        } else if queued_post.hashtags.is_empty() {
-            full_caption = format!("{}", queued_post.caption);
-        } else {
-            full_caption = format!("{}{}{}{}{}", queued_post.caption, big_spacer, disclaimer, small_spacer, queued_post.hashtags);
-        }
-        full_caption
-    }
-
     async fn handle_failed_content(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction, queued_post: &QueuedContent) {
         let span = tracing::span!(tracing::Level::INFO, "handle_failed_content");
         let _enter = span.enter();
@@ -254,7 +473,7 @@ impl ContentManager {
         let _enter = span.enter();
 
         for mut queued_post in tx.load_content_queue().await {
-            let new_will_post_at = DateTime::parse_from_rfc3339(&queued_post.will_post_at).unwrap() + Duration::from_secs((user_settings.posting_interval * 60) as u64);
+            let new_will_post_at = DateTime::parse_from_rfc3339(&queued_post.will_post_at).unwrap() + user_settings.posting_interval();
             queued_post.will_post_at = new_will_post_at.to_rfc3339();
             tx.save_queued_content(&queued_post).await;
         }
@@ -277,9 +496,16 @@ impl ContentManager {
             original_author: queued_post.original_author.clone(),
             original_shortcode: queued_post.original_shortcode.clone(),
             published_at: now_in_my_timezone(&user_settings).to_rfc3339(),
+            is_simulated: false,
+            // The caption is known to have been posted, but not which of its variations
+            caption_variant: "".to_string(),
+            is_trial_reel: content_info.is_trial_reel,
+            permalink: "".to_string(),
+            media_id: "".to_string(),
         };
 
         tx.save_published_content(&published_content).await;
+        tx.record_published_category(&content_info.category, &published_content.published_at).await;
     }
 
     /// This function will amend the queue to ensure that only one post is posted at a time,
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+use crate::database::database::{AuditEntry, ContentInfo, DatabaseTransaction, RejectedContent, RejectionReason, UserSettings};
+use crate::discord::state::ContentStatus;
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::rules::AutomatedDecision;
+
+const PROFANITY_FILTERS_PATH: &str = "config/profanity_filters.yaml";
+
+/// The words of each severity that are always filtered, the accounts can add their own
+const MILD_WORDS: [&str; 6] = ["crap", "damn", "hell", "piss", "bloody", "sucks"];
+const MODERATE_WORDS: [&str; 6] = ["ass", "asshole", "bastard", "bitch", "dick", "shit"];
+const SEVERE_WORDS: [&str; 5] = ["cock", "cunt", "fuck", "motherfucker", "pussy"];
+
+/// How offensive a word is, the filter catches the words of its severity and the ones above
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Mild,
+    #[default]
+    Moderate,
+    Severe,
+}
+
+/// What happens to the content whose caption has profanity in it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfanityAction {
+    /// The words are shown on the card, the content is reviewed as usual
+    #[default]
+    Flag,
+    /// The words are replaced with asterisks, past the first letter
+    Mask,
+    /// Rejected right away, it can still be undone from the card
+    Reject,
+}
+
+impl ProfanityAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ProfanityAction::Flag => "flag",
+            ProfanityAction::Mask => "mask",
+            ProfanityAction::Reject => "reject",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ProfanityFilterConfig {
+    #[serde(default)]
+    severity: Severity,
+    #[serde(default)]
+    action: ProfanityAction,
+    #[serde(default)]
+    extra_words: HashMap<Severity, Vec<String>>,
+}
+
+/// The profanity filter of an account, disabled when there's no entry for it
+#[derive(Debug, Clone)]
+pub struct ProfanityFilter {
+    pub action: ProfanityAction,
+    pattern: Regex,
+}
+
+impl ProfanityFilter {
+    fn new(config: ProfanityFilterConfig) -> Self {
+        let mut words: Vec<String> = [(Severity::Mild, &MILD_WORDS[..]), (Severity::Moderate, &MODERATE_WORDS[..]), (Severity::Severe, &SEVERE_WORDS[..])]
+            .into_iter()
+            .filter(|(severity, _)| *severity >= config.severity)
+            .flat_map(|(_, words)| words.iter().map(|word| word.to_string()))
+            .collect();
+        words.extend(config.extra_words.into_iter().filter(|(severity, _)| *severity >= config.severity).flat_map(|(_, words)| words));
+
+        // The common endings are matched too, but not the longer words that happen to start the same way, like "hello"
+        let alternatives = words.iter().map(|word| regex::escape(&word.to_lowercase())).collect::<Vec<String>>().join("|");
+        let pattern = Regex::new(&format!(r"(?i)\b(?:{})(?:s|es|ed|er|ers|ing|in)?\b", alternatives)).unwrap();
+        Self { action: config.action, pattern }
+    }
+
+    /// Returns the profanity found in the caption, lowercased and without repetitions
+    pub fn find(&self, caption: &str) -> Vec<String> {
+        let mut hits: Vec<String> = self.pattern.find_iter(caption).map(|hit| hit.as_str().to_lowercase()).collect();
+        hits.sort();
+        hits.dedup();
+        hits
+    }
+
+    /// e.g. "what the f***"
+    pub fn mask(&self, caption: &str) -> String {
+        self.pattern
+            .replace_all(caption, |captures: &regex::Captures| {
+                let mut chars = captures[0].chars();
+                let first_char = chars.next().unwrap_or_default();
+                format!("{}{}", first_char, "*".repeat(chars.count()))
+            })
+            .to_string()
+    }
+}
+
+/// Reads the profanity filter of the account, the file is optional
+pub async fn read_profanity_filter(username: &str) -> Option<ProfanityFilter> {
+    let mut file = File::open(PROFANITY_FILTERS_PATH).await.ok()?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.expect("Unable to read the profanity filters file");
+    let mut profanity_filters: HashMap<String, ProfanityFilterConfig> = serde_yaml::from_str(&contents).expect("Error parsing the profanity filters file");
+    profanity_filters.remove(username).map(ProfanityFilter::new)
+}
+
+/// Flags, masks or rejects the content before it is saved, the hits are counted along with the ones of the automation rules
+///
+/// Returns the row to save in the rejections once the content is saved, when the content was rejected
+pub async fn apply_profanity_filter(tx: &mut DatabaseTransaction, user_settings: &UserSettings, profanity_filter: &ProfanityFilter, content_info: &mut ContentInfo) -> Option<AutomatedDecision> {
+    let hits = profanity_filter.find(&content_info.caption);
+    if hits.is_empty() {
+        return None;
+    }
+
+    content_info.profanity = hits.join(", ");
+    let now = now_in_my_timezone(user_settings);
+    let automated_decision = match profanity_filter.action {
+        ProfanityAction::Flag => None,
+        ProfanityAction::Mask => {
+            content_info.caption = profanity_filter.mask(&content_info.caption);
+            None
+        }
+        ProfanityAction::Reject => {
+            content_info.status = ContentStatus::Rejected { shown: false };
+            let rejected_content = RejectedContent {
+                username: content_info.username.clone(),
+                url: content_info.url.clone(),
+                caption: content_info.caption.clone(),
+                hashtags: content_info.hashtags.clone(),
+                original_author: content_info.original_author.clone(),
+                original_shortcode: content_info.original_shortcode.clone(),
+                rejected_at: now.to_rfc3339(),
+                reason: RejectionReason::Other.as_str().to_string(),
+                reason_note: format!("profanity: {}", content_info.profanity),
+            };
+            Some(AutomatedDecision::Rejected(rejected_content))
+        }
+    };
+
+    let audit_entry = AuditEntry {
+        username: content_info.username.clone(),
+        original_shortcode: content_info.original_shortcode.clone(),
+        action: format!("profanity_{}", profanity_filter.action.as_str()),
+        operator: "profanity filter".to_string(),
+        detail: content_info.profanity.clone(),
+        created_at: now.to_rfc3339(),
+        review_latency: 0,
+    };
+    tx.save_audit_entry(&audit_entry).await;
+    tx.record_rule_hit(&format!("profanity filter ({})", profanity_filter.action.as_str())).await;
+
+    automated_decision
+}
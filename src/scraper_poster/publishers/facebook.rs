@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::scraper_poster::graph_api::{send, GraphApiError, GRAPH_API_URL};
+use crate::scraper_poster::publishers::Publisher;
+
+/// Where the video of a Facebook reel is uploaded to, between the start and the finish of the upload
+const FACEBOOK_UPLOAD_URL: &str = "https://rupload.facebook.com/video-upload/v19.0";
+
+/// The reels of a Facebook page, which needs its own access token rather than the one of the user
+pub struct FacebookReels {
+    page_id: String,
+    access_token: String,
+}
+
+impl FacebookReels {
+    /// Configured as "facebook_page_id" and "facebook_page_access_token" in the credentials
+    pub fn from_credentials(credentials: &HashMap<String, String>) -> Option<Self> {
+        Some(Self {
+            page_id: credentials.get("facebook_page_id").filter(|page_id| !page_id.is_empty())?.clone(),
+            access_token: credentials.get("facebook_page_access_token").filter(|access_token| !access_token.is_empty())?.clone(),
+        })
+    }
+}
+
+#[async_trait]
+impl Publisher for FacebookReels {
+    fn platform(&self) -> &'static str {
+        "facebook_reels"
+    }
+
+    async fn publish(&self, video_url: &str, caption: &str) -> Result<String, GraphApiError> {
+        let client = reqwest::Client::new();
+        let reels_url = format!("{GRAPH_API_URL}/{}/video_reels", self.page_id);
+
+        let form = [("upload_phase", "start"), ("access_token", self.access_token.as_str())];
+        let response = send(client.post(&reels_url).form(&form)).await?;
+        let video_id = match response["video_id"].as_str() {
+            Some(video_id) => video_id.to_string(),
+            None => return Err(GraphApiError::UploadFailedNonRecoverable(format!("No video id returned: {}", response))),
+        };
+
+        // Facebook fetches the video from the url on its side, like Instagram does
+        let upload = client.post(format!("{FACEBOOK_UPLOAD_URL}/{video_id}")).header("Authorization", format!("OAuth {}", self.access_token)).header("file_url", video_url);
+        send(upload).await?;
+
+        let form = [("upload_phase", "finish"), ("video_id", video_id.as_str()), ("video_state", "PUBLISHED"), ("description", caption), ("access_token", self.access_token.as_str())];
+        let response = send(client.post(&reels_url).form(&form)).await?;
+        if response["success"].as_bool() != Some(true) {
+            return Err(GraphApiError::UploadFailedNonRecoverable(format!("The reel {} wasn't published: {}", video_id, response)));
+        }
+        Ok(video_id)
+    }
+}
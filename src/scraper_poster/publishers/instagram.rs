@@ -0,0 +1,22 @@
+use async_trait::async_trait;
+
+use crate::scraper_poster::graph_api::{upload_reel_with_options, GraphApiError, MediaContainerOptions};
+use crate::scraper_poster::publishers::Publisher;
+
+/// The reels of the account itself, published through the Graph API with the optional fields of the media container
+pub struct InstagramReels {
+    pub user_id: String,
+    pub access_token: String,
+    pub options: MediaContainerOptions,
+}
+
+#[async_trait]
+impl Publisher for InstagramReels {
+    fn platform(&self) -> &'static str {
+        "instagram_reels"
+    }
+
+    async fn publish(&self, video_url: &str, caption: &str) -> Result<String, GraphApiError> {
+        upload_reel_with_options(&self.user_id, &self.access_token, video_url, caption, &self.options).await
+    }
+}
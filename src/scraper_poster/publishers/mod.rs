@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::database::database::{CrossPost, DatabaseTransaction, QueuedContent, UserSettings};
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::formatting::{format_caption, Destination};
+use crate::scraper_poster::graph_api::GraphApiError;
+use crate::scraper_poster::publishers::facebook::FacebookReels;
+use crate::scraper_poster::publishers::youtube::YouTubeShorts;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::supervisor::watchdog::with_watchdog;
+use crate::UPLOAD_TIMEOUT;
+
+pub(crate) mod facebook;
+pub(crate) mod instagram;
+pub(crate) mod youtube;
+
+/// A platform the approved content gets published to
+#[async_trait]
+pub trait Publisher: Send + Sync {
+    /// Recorded along with the outcome of each publication, e.g. "facebook_reels"
+    fn platform(&self) -> &'static str;
+
+    /// How the caption is rendered for the platform, the reel one unless the platform has its own limits
+    fn destination(&self) -> Destination {
+        Destination::Reel
+    }
+
+    /// Publishes the video found at the url with the caption, returns the id of the post on the platform
+    async fn publish(&self, video_url: &str, caption: &str) -> Result<String, GraphApiError>;
+}
+
+/// Reads the destinations the reels are cross-posted to once they're published on Instagram, none by default
+///
+/// Configured as "cross_post_destinations", e.g. "facebook_reels,youtube_shorts". The destinations whose credentials are missing are left out
+pub fn read_cross_post_publishers(credentials: &HashMap<String, String>) -> Vec<Box<dyn Publisher>> {
+    let Some(destinations) = credentials.get("cross_post_destinations") else {
+        return vec![];
+    };
+
+    let mut publishers: Vec<Box<dyn Publisher>> = Vec::new();
+    for destination in destinations.split(',').map(str::trim).filter(|destination| !destination.is_empty()) {
+        let publisher: Option<Box<dyn Publisher>> = match destination {
+            "facebook_reels" => FacebookReels::from_credentials(credentials).map(|publisher| Box::new(publisher) as Box<dyn Publisher>),
+            "youtube_shorts" => YouTubeShorts::from_credentials(credentials).map(|publisher| Box::new(publisher) as Box<dyn Publisher>),
+            _ => {
+                tracing::warn!("Ignoring the unknown cross-post destination {}", destination);
+                continue;
+            }
+        };
+        match publisher {
+            Some(publisher) => publishers.push(publisher),
+            None => tracing::warn!("Not cross-posting to {}, its credentials are missing", destination),
+        }
+    }
+    publishers
+}
+
+impl ContentManager {
+    /// Publishes the content to the other destinations, the reel on Instagram stands regardless of how it goes there
+    ///
+    /// Takes the caption variant picked for Instagram, so that every destination gets the same one
+    pub(crate) async fn cross_post(&self, publishers: &[Box<dyn Publisher>], user_settings: &UserSettings, tx: &mut DatabaseTransaction, queued_post: &QueuedContent, caption_variant: &str) {
+        for publisher in publishers {
+            let caption = format_caption(caption_variant, &queued_post.hashtags, publisher.destination());
+            // A hanging upload is recorded as failed like the other errors, the reel on Instagram is already up
+            let operation = format!("The cross-post of {} to {}", queued_post.original_shortcode, publisher.platform());
+            let result = with_watchdog(&operation, UPLOAD_TIMEOUT, publisher.publish(&queued_post.url, &caption)).await.unwrap_or_else(|e| Err(GraphApiError::UploadFailedRecoverable(e.to_string())));
+            let (status, post_id, error) = match result {
+                Ok(post_id) => {
+                    self.println(&format!("[+] Cross-posted {} to {}", queued_post.original_shortcode, publisher.platform()));
+                    ("published", post_id, "".to_string())
+                }
+                Err(e) => {
+                    self.println(&format!("[!] Couldn't cross-post {} to {}: {}", queued_post.original_shortcode, publisher.platform(), e));
+                    ("failed", "".to_string(), e.to_string())
+                }
+            };
+
+            let cross_post = CrossPost {
+                username: queued_post.username.clone(),
+                original_shortcode: queued_post.original_shortcode.clone(),
+                platform: publisher.platform().to_string(),
+                status: status.to_string(),
+                post_id,
+                error,
+                attempted_at: now_in_my_timezone(user_settings).to_rfc3339(),
+            };
+            tx.save_cross_post(&cross_post).await;
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::scraper_poster::formatting::Destination;
+use crate::scraper_poster::graph_api::GraphApiError;
+use crate::scraper_poster::publishers::Publisher;
+
+const GOOGLE_TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const YOUTUBE_UPLOAD_URL: &str = "https://www.googleapis.com/upload/youtube/v3/videos";
+/// Enforced by YouTube, in characters
+const MAX_TITLE_LENGTH: usize = 100;
+/// The vertical videos under a minute are classified as shorts on their own, the tag only helps them get found
+const SHORTS_TAG: &str = "#Shorts";
+
+/// The shorts of a YouTube channel, the access tokens are obtained from the refresh token of an OAuth client
+pub struct YouTubeShorts {
+    client_id: String,
+    client_secret: String,
+    refresh_token: String,
+}
+
+impl YouTubeShorts {
+    /// Configured as "youtube_client_id", "youtube_client_secret" and "youtube_refresh_token" in the credentials
+    pub fn from_credentials(credentials: &HashMap<String, String>) -> Option<Self> {
+        let credential = |key: &str| credentials.get(key).filter(|value| !value.is_empty()).cloned();
+        Some(Self {
+            client_id: credential("youtube_client_id")?,
+            client_secret: credential("youtube_client_secret")?,
+            refresh_token: credential("youtube_refresh_token")?,
+        })
+    }
+
+    async fn access_token(&self, client: &reqwest::Client) -> Result<String, GraphApiError> {
+        let form = [("client_id", self.client_id.as_str()), ("client_secret", self.client_secret.as_str()), ("refresh_token", self.refresh_token.as_str()), ("grant_type", "refresh_token")];
+        let response = read_response(client.post(GOOGLE_TOKEN_URL).form(&form).send().await).await?;
+
+        match response["access_token"].as_str() {
+            Some(access_token) => Ok(access_token.to_string()),
+            None => Err(GraphApiError::UploadFailedNonRecoverable(format!("No access token returned: {}", response))),
+        }
+    }
+}
+
+#[async_trait]
+impl Publisher for YouTubeShorts {
+    fn platform(&self) -> &'static str {
+        "youtube_shorts"
+    }
+
+    fn destination(&self) -> Destination {
+        Destination::YouTubeShort
+    }
+
+    async fn publish(&self, video_url: &str, caption: &str) -> Result<String, GraphApiError> {
+        let client = reqwest::Client::new();
+        let access_token = self.access_token(&client).await?;
+
+        // Unlike the Graph API, the Data API doesn't fetch the video on its own
+        let video = client.get(video_url).send().await.and_then(|response| response.error_for_status()).map_err(|e| GraphApiError::UploadFailedRecoverable(e.to_string()))?;
+        let video = video.bytes().await.map_err(|e| GraphApiError::UploadFailedRecoverable(e.to_string()))?;
+
+        let metadata = json!({
+            "snippet": { "title": shorts_title(caption), "description": caption },
+            "status": { "privacyStatus": "public", "selfDeclaredMadeForKids": false },
+        });
+        let session = client
+            .post(YOUTUBE_UPLOAD_URL)
+            .query(&[("uploadType", "resumable"), ("part", "snippet,status")])
+            .bearer_auth(&access_token)
+            .header("Content-Type", "application/json; charset=UTF-8")
+            .header("X-Upload-Content-Type", "video/mp4")
+            .body(metadata.to_string())
+            .send()
+            .await
+            .map_err(|e| GraphApiError::UploadFailedRecoverable(e.to_string()))?;
+        let upload_url = match session.headers().get("Location").and_then(|location| location.to_str().ok()) {
+            Some(upload_url) => upload_url.to_string(),
+            None => return Err(response_error(session.status(), &session.text().await.unwrap_or_default())),
+        };
+
+        let response = read_response(client.put(upload_url).bearer_auth(&access_token).header("Content-Type", "video/mp4").body(video).send().await).await?;
+        match response["id"].as_str() {
+            Some(video_id) => Ok(video_id.to_string()),
+            None => Err(GraphApiError::UploadSucceededButFailedToRetrieveId(format!("No video id returned: {}", response))),
+        }
+    }
+}
+
+/// The first line of the caption without its hashtags, followed by the shorts tag
+fn shorts_title(caption: &str) -> String {
+    let first_line = caption.lines().next().unwrap_or_default();
+    let title = first_line.split_whitespace().filter(|word| !word.starts_with('#')).collect::<Vec<&str>>().join(" ");
+    let title: String = title.chars().take(MAX_TITLE_LENGTH - SHORTS_TAG.len() - 1).collect();
+    format!("{} {}", title, SHORTS_TAG).trim_start().to_string()
+}
+
+async fn read_response(response: reqwest::Result<reqwest::Response>) -> Result<Value, GraphApiError> {
+    let response = response.map_err(|e| GraphApiError::UploadFailedRecoverable(e.to_string()))?;
+    let status = response.status();
+    let body = response.text().await.map_err(|e| GraphApiError::UploadFailedRecoverable(e.to_string()))?;
+    if !status.is_success() {
+        return Err(response_error(status, &body));
+    }
+    serde_json::from_str(&body).map_err(|_| GraphApiError::UploadFailedRecoverable(format!("Invalid response ({}): {}", status, body)))
+}
+
+/// The quota running out and the errors on Google's side go away on their own
+fn response_error(status: reqwest::StatusCode, body: &str) -> GraphApiError {
+    let message = format!("{}: {}", status, body);
+    if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS || body.contains("quotaExceeded") {
+        GraphApiError::UploadFailedRecoverable(message)
+    } else {
+        GraphApiError::UploadFailedNonRecoverable(message)
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+use tokio::time::{sleep, sleep_until, Instant};
+
+use crate::database::database::DatabaseTransaction;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::utils::jittered_duration;
+use crate::RATE_LIMIT_CIRCUIT_BREAKER_THRESHOLD;
+
+/// How much longer than usual the scraper waits between its requests to Instagram, once it starts getting rate limited
+pub(crate) trait RateLimitStrategy: Send + Sync {
+    /// The consecutive limits are the rate limits hit in a row since the last request that went through
+    fn delay(&self, base_delay: Duration, consecutive_limits: u32) -> Duration;
+}
+
+/// Doubles the delay for each rate limit hit in a row, up to the given amount of times
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ExponentialBackoff {
+    pub(crate) max_doublings: u32,
+}
+
+impl RateLimitStrategy for ExponentialBackoff {
+    fn delay(&self, base_delay: Duration, consecutive_limits: u32) -> Duration {
+        base_delay.saturating_mul(1 << consecutive_limits.min(self.max_doublings))
+    }
+}
+
+#[derive(Debug, Default)]
+struct RateLimiterState {
+    consecutive_limits: u32,
+    circuit_open_until: Option<Instant>,
+}
+
+/// Paces the requests of the scraper to Instagram, shared between the fetches and the downloads
+///
+/// Once too many rate limits are hit in a row the circuit opens, and the scraping stops until the cooldown is over
+#[derive(Clone)]
+pub(crate) struct RateLimiter {
+    strategy: Arc<dyn RateLimitStrategy>,
+    cooldown: Duration,
+    state: Arc<Mutex<RateLimiterState>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(strategy: Arc<dyn RateLimitStrategy>, cooldown: Duration) -> Self {
+        Self {
+            strategy,
+            cooldown,
+            state: Arc::new(Mutex::new(RateLimiterState::default())),
+        }
+    }
+
+    /// Waits the jittered base delay, stretched by the strategy while the requests are being rate limited
+    pub(crate) async fn pace(&self, base_delay: Duration) {
+        self.wait_for_circuit().await;
+        let consecutive_limits = self.state.lock().await.consecutive_limits;
+        sleep(jittered_duration(self.strategy.delay(base_delay, consecutive_limits))).await;
+    }
+
+    /// Only waits while the circuit is open
+    pub(crate) async fn wait_for_circuit(&self) {
+        let circuit_open_until = self.state.lock().await.circuit_open_until;
+        if let Some(circuit_open_until) = circuit_open_until {
+            sleep_until(circuit_open_until).await;
+        }
+    }
+
+    pub(crate) async fn record_success(&self) {
+        let mut state = self.state.lock().await;
+        state.consecutive_limits = 0;
+        state.circuit_open_until = None;
+    }
+
+    /// Returns whether this rate limit opened the circuit
+    pub(crate) async fn record_rate_limited(&self) -> bool {
+        let mut state = self.state.lock().await;
+        state.consecutive_limits += 1;
+
+        let is_circuit_open = state.circuit_open_until.is_some_and(|circuit_open_until| circuit_open_until > Instant::now());
+        if state.consecutive_limits >= RATE_LIMIT_CIRCUIT_BREAKER_THRESHOLD && !is_circuit_open {
+            state.circuit_open_until = Some(Instant::now() + self.cooldown);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn cooldown(&self) -> Duration {
+        self.cooldown
+    }
+}
+
+impl ContentManager {
+    /// Backs off after a request was rate limited, and keeps track of how often it happens
+    pub(crate) async fn handle_rate_limited(&self, tx: &mut DatabaseTransaction) {
+        tx.record_rate_limit_event("rate_limited").await;
+        if self.rate_limiter.record_rate_limited().await {
+            self.println(&format!("Rate limited too many times in a row, the scraping stops for {} minutes", self.rate_limiter.cooldown().as_secs() / 60));
+            tx.record_rate_limit_event("circuit_opened").await;
+        } else {
+            self.println("Rate limited, backing off...");
+        }
+    }
+}
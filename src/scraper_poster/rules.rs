@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+use crate::database::database::{AuditEntry, ContentInfo, DatabaseTransaction, QueuedContent, RejectedContent, RejectionReason, UserSettings};
+use crate::discord::state::ContentStatus;
+use crate::discord::utils::now_in_my_timezone;
+use crate::video::processing::get_video_duration;
+
+const RULES_PATH: &str = "config/rules.yaml";
+/// How many hashtags of the category replace the ones of the caption
+const RULE_CATEGORY_HASHTAGS: usize = 3;
+
+/// What happens to the content matched by a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleAction {
+    /// Queued right away, as if a moderator accepted it
+    Accept,
+    Reject,
+    /// Still reviewed by hand, only the category is applied
+    Review,
+}
+
+impl RuleAction {
+    fn audit_action(&self) -> &'static str {
+        match self {
+            RuleAction::Accept => "auto_accepted",
+            RuleAction::Reject => "auto_rejected",
+            RuleAction::Review => "auto_categorized",
+        }
+    }
+}
+
+/// The row a content decided automatically gets in the queue or in the rejections
+///
+/// It references the content, so it's only saved once the content itself is
+pub enum AutomatedDecision {
+    Queued(QueuedContent),
+    Rejected(RejectedContent),
+}
+
+impl AutomatedDecision {
+    pub async fn save(&self, tx: &mut DatabaseTransaction) {
+        match self {
+            AutomatedDecision::Queued(queued_content) => tx.save_queued_content(queued_content).await,
+            AutomatedDecision::Rejected(rejected_content) => {
+                tx.save_rejected_content(rejected_content).await;
+                tx.record_rejection(rejected_content, 1).await;
+            }
+        }
+    }
+}
+
+/// All the conditions that are set have to match, a rule without conditions matches everything
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RuleConditions {
+    /// The author of the content as shown on the cards, e.g. "memes_daily" or "u/someone"
+    pub source: Option<String>,
+    /// In seconds
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+    /// Case insensitive
+    pub caption_contains: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleOutcome {
+    pub action: RuleAction,
+    /// The category of hashtags.yaml whose hashtags replace the ones of the caption
+    pub category: Option<String>,
+}
+
+/// A decision taken automatically when the content is scraped, instead of waiting for a moderator
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    /// Shown in the stats and in the history of the content
+    pub name: String,
+    #[serde(rename = "if", default)]
+    pub conditions: RuleConditions,
+    #[serde(rename = "then")]
+    pub outcome: RuleOutcome,
+}
+
+impl Rule {
+    fn needs_duration(&self) -> bool {
+        self.conditions.min_duration.is_some() || self.conditions.max_duration.is_some()
+    }
+
+    fn matches(&self, author: &str, caption: &str, duration: f64) -> bool {
+        let conditions = &self.conditions;
+        conditions.source.as_ref().map_or(true, |source| source.eq_ignore_ascii_case(author))
+            && conditions.min_duration.map_or(true, |min_duration| duration >= min_duration)
+            && conditions.max_duration.map_or(true, |max_duration| duration < max_duration)
+            && conditions.caption_contains.as_ref().map_or(true, |text| caption.to_lowercase().contains(&text.to_lowercase()))
+    }
+}
+
+/// Reads the automation rules of the account, the file is optional
+pub async fn read_rules(username: &str) -> Vec<Rule> {
+    let mut file = match File::open(RULES_PATH).await {
+        Ok(file) => file,
+        Err(_) => return vec![],
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.expect("Unable to read the rules file");
+    let mut rules: HashMap<String, Vec<Rule>> = serde_yaml::from_str(&contents).expect("Error parsing the rules file");
+    rules.remove(username).unwrap_or_default()
+}
+
+/// Returns the first rule matching the content, the video is only probed when a rule depends on its duration
+pub fn find_matching_rule<'a>(rules: &'a [Rule], video_file_name: &str, author: &str, caption: &str) -> Option<&'a Rule> {
+    if rules.is_empty() {
+        return None;
+    }
+
+    let duration = if rules.iter().any(Rule::needs_duration) {
+        match get_video_duration(&format!("temp/{video_file_name}")) {
+            Ok(duration) => duration,
+            Err(e) => {
+                tracing::warn!("Couldn't read the duration of {}, skipping the rules: {}", video_file_name, e);
+                return None;
+            }
+        }
+    } else {
+        0.0
+    };
+
+    rules.iter().find(|rule| rule.matches(author, caption, duration))
+}
+
+/// Applies the outcome of the rule to the content before it is saved, the queue and the rejections are updated like the buttons would
+///
+/// Returns the row to save in the queue or in the rejections once the content is saved
+pub async fn apply_rule(tx: &mut DatabaseTransaction, user_settings: &UserSettings, hashtag_mapping: &HashMap<String, String>, rule: &Rule, content_info: &mut ContentInfo) -> Option<AutomatedDecision> {
+    if let Some(category) = &rule.outcome.category {
+        match hashtag_mapping.get(category) {
+            Some(category_hashtags) => {
+                let category_hashtags: Vec<&str> = category_hashtags.split(',').map(str::trim).filter(|hashtag| !hashtag.is_empty()).collect();
+                let mut rng = StdRng::from_entropy();
+                content_info.hashtags = category_hashtags.choose_multiple(&mut rng, RULE_CATEGORY_HASHTAGS).copied().collect::<Vec<&str>>().join(" ");
+                content_info.category = category.clone();
+            }
+            None => tracing::warn!("Rule \"{}\" uses the unknown category {}", rule.name, category),
+        }
+    }
+
+    let now = now_in_my_timezone(user_settings);
+    let automated_decision = match rule.outcome.action {
+        RuleAction::Accept => {
+            content_info.status = ContentStatus::Queued { shown: false };
+            let queued_content = QueuedContent {
+                username: content_info.username.clone(),
+                url: content_info.url.clone(),
+                caption: content_info.caption.clone(),
+                hashtags: content_info.hashtags.clone(),
+                original_author: content_info.original_author.clone(),
+                original_shortcode: content_info.original_shortcode.clone(),
+                will_post_at: tx.get_new_post_time().await,
+                location_id: "".to_string(),
+                location_name: "".to_string(),
+            };
+            Some(AutomatedDecision::Queued(queued_content))
+        }
+        RuleAction::Reject => {
+            content_info.status = ContentStatus::Rejected { shown: false };
+            let rejected_content = RejectedContent {
+                username: content_info.username.clone(),
+                url: content_info.url.clone(),
+                caption: content_info.caption.clone(),
+                hashtags: content_info.hashtags.clone(),
+                original_author: content_info.original_author.clone(),
+                original_shortcode: content_info.original_shortcode.clone(),
+                rejected_at: now.to_rfc3339(),
+                reason: RejectionReason::Other.as_str().to_string(),
+                reason_note: format!("rule {}", rule.name),
+            };
+            Some(AutomatedDecision::Rejected(rejected_content))
+        }
+        RuleAction::Review => None,
+    };
+
+    let audit_entry = AuditEntry {
+        username: content_info.username.clone(),
+        original_shortcode: content_info.original_shortcode.clone(),
+        action: rule.outcome.action.audit_action().to_string(),
+        operator: "rules".to_string(),
+        detail: rule.name.clone(),
+        created_at: now.to_rfc3339(),
+        review_latency: 0,
+    };
+    tx.save_audit_entry(&audit_entry).await;
+    tx.record_rule_hit(&rule.name).await;
+
+    automated_decision
+}
@@ -1,86 +1,222 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use futures::FutureExt;
 use instagram_scraper_rs::{InstagramScraper, InstagramScraperError, Post, User};
-use rand::prelude::SliceRandom;
 use rand::rngs::{OsRng, StdRng};
 use rand::{Rng, SeedableRng};
 use s3::Bucket;
+use serde::Deserialize;
 use serenity::all::MessageId;
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex, Semaphore};
 use tokio::task::JoinHandle;
 use tokio::time::sleep;
 use tracing::Instrument;
 
-use crate::database::database::{ContentInfo, Database, DatabaseTransaction, DuplicateContent};
+use crate::coordination::post_now::PostNow;
+use crate::coordination::redis_layer::Coordinator;
+use crate::database::database::{ContentInfo, ContentProvenance, Database, DatabaseTransaction, DuplicateContent, DuplicateReport, ScrapeAuditEntry, SkipReason, SourceAccount};
 use crate::discord::state::ContentStatus;
 use crate::discord::utils::now_in_my_timezone;
+use crate::maintenance::windows::current_maintenance_window;
 use crate::s3::helper::upload_to_s3;
-use crate::scraper_poster::utils::{pause_scraper_if_needed, process_caption, save_cookie_store_to_json, set_bot_status_halted, set_bot_status_operational};
-use crate::video::processing::process_video;
-use crate::{FETCH_SLEEP_LEN, MAX_CONTENT_PER_ITERATION, SCRAPER_DOWNLOAD_SLEEP_LEN, SCRAPER_LOOP_SLEEP_LEN};
-use crate::{MAX_CONTENT_HANDLED, SCRAPER_REFRESH_RATE};
+use crate::scraper_poster::blacklist::find_blacklist_match;
+use crate::scraper_poster::discovery::read_hashtags_to_discover;
+use crate::scraper_poster::download_manager::clean_up_orphaned_downloads;
+use crate::scraper_poster::download_strategies::{read_download_strategies, DownloadStrategy};
+use crate::scraper_poster::downloads::{DownloadOutcome, DownloadStage};
+use crate::scraper_poster::engagement::{read_engagement_filters, EngagementFilters};
+use crate::scraper_poster::eviction::{read_backlog_policy, BacklogPolicy};
+use crate::scraper_poster::graph_api::{carousel_videos, CarouselVideo};
+use crate::scraper_poster::identities::ScraperIdentities;
+use crate::scraper_poster::journal::ScrapeJournal;
+use crate::scraper_poster::languages::{read_caption_languages, LanguageAction};
+use crate::scraper_poster::metadata::{instagram_post_url, SourcePostMetadata};
+use crate::scraper_poster::profanity::{apply_profanity_filter, read_profanity_filter};
+use crate::scraper_poster::proxy::{new_scraper, read_scraper_proxies};
+use crate::scraper_poster::rate_limiter::{ExponentialBackoff, RateLimiter};
+use crate::scraper_poster::rules::{apply_rule, find_matching_rule, read_rules};
+use crate::scraper_poster::sources::read_content_sources;
+use crate::scraper_poster::stories::read_stories_to_scrape;
+use crate::scraper_poster::utils::{jittered_duration, pause_scraper_if_needed, process_caption, save_cookie_store_to_json, set_bot_status_halted, set_bot_status_operational};
+use crate::supervisor::tasks::supervise;
+use crate::video::hash_index::HashIndex;
+use crate::video::processing::{generate_preview, generate_preview_clip, process_video};
+use crate::{CAROUSEL_LOOKUP_LIMIT, DEFAULT_BACKFILL_POSTS, DEFAULT_POSTS_PER_FETCH, FETCH_SLEEP_LEN, LOGIN_RETRY_INTERVAL, MAX_AUTOMATIC_LOGIN_RETRIES, MAX_CONCURRENT_DOWNLOADS, MAX_CONTENT_PER_ITERATION, MAX_INSTAGRAM_REQUESTS_PER_HOUR, MAX_MEDIA_NOT_FOUND_ATTEMPTS, RATE_LIMIT_COOLDOWN, RATE_LIMIT_MAX_DOUBLINGS, SCRAPER_DOWNLOAD_SLEEP_LEN, SCRAPER_LOOP_SLEEP_LEN};
+use crate::{RunMode, MAX_CONTENT_HANDLED, SCRAPER_REFRESH_RATE};
 
 #[derive(Clone)]
 pub struct ContentManager {
     pub(crate) username: String,
     pub(crate) scraper: Arc<Mutex<InstagramScraper>>,
     pub(crate) database: Database,
-    bucket: Bucket,
+    pub(crate) bucket: Bucket,
+    pub(crate) coordinator: Coordinator,
+    /// Wakes the poster up when a content is asked to be published right away
+    pub(crate) post_now: Arc<PostNow>,
     pub(crate) is_offline: bool,
-    cookie_store_path: String,
+    /// Set with --dry-run, the posts that would be downloaded are only recorded in the scrape_audit table
+    pub(crate) is_scrape_dry_run: bool,
+    pub(crate) cookie_store_path: String,
     pub(crate) credentials: HashMap<String, String>,
-    latest_content_mutex: Arc<Mutex<Option<(String, String, String, String)>>>,
+    /// The scraper goes through the active one, the next ones are failovers
+    pub(crate) proxies: Vec<String>,
+    pub(crate) active_proxy: Arc<Mutex<usize>>,
+    /// Failed attempts at recovering the session in a row
+    pub(crate) session_failures: Arc<AtomicU32>,
+    pub(crate) rate_limiter: RateLimiter,
+    pub(crate) scraper_identities: ScraperIdentities,
+    /// Tried in turn when a reel can't be downloaded, before halting the bot
+    pub(crate) download_strategies: Vec<DownloadStrategy>,
+    /// The file, caption, author and shortcode of the latest content handed over to the sender loop, along with when it was posted (0 when unknown)
+    pub(crate) latest_content_mutex: Arc<Mutex<Option<(String, String, String, String, SourcePostMetadata)>>>,
+    /// What the current iteration of the scraper did so far
+    pub(crate) scrape_journal: ScrapeJournal,
 }
 
 impl ContentManager {
-    pub fn new(database: Database, bucket: Bucket, username: String, credentials: HashMap<String, String>, is_offline: bool) -> Self {
+    pub fn new(database: Database, bucket: Bucket, coordinator: Coordinator, post_now: Arc<PostNow>, username: String, credentials: HashMap<String, String>, is_offline: bool, is_scrape_dry_run: bool) -> Self {
         let cookie_store_path = format!("cookies/cookies_{}.json", username);
-        let scraper = Arc::new(Mutex::new(InstagramScraper::with_cookie_store(&cookie_store_path)));
+        let proxies = read_scraper_proxies(&credentials);
+        let scraper = Arc::new(Mutex::new(new_scraper(&cookie_store_path, proxies.first())));
+        let scraper_identities = ScraperIdentities::new(&credentials, &cookie_store_path, proxies.first());
+        let download_strategies = read_download_strategies(&credentials);
 
         let latest_content_mutex = Arc::new(Mutex::new(None));
 
+        let cooldown_minutes = credentials.get("rate_limit_cooldown_minutes").and_then(|minutes| minutes.parse::<u64>().ok()).unwrap_or(RATE_LIMIT_COOLDOWN.as_secs() / 60);
+        let rate_limiter = RateLimiter::new(Arc::new(ExponentialBackoff { max_doublings: RATE_LIMIT_MAX_DOUBLINGS }), Duration::from_secs(cooldown_minutes * 60));
+
         Self {
             username,
             scraper,
             database,
             bucket,
+            coordinator,
+            post_now,
             is_offline,
+            is_scrape_dry_run,
             cookie_store_path,
             credentials,
+            proxies,
+            active_proxy: Arc::new(Mutex::new(0)),
+            session_failures: Arc::new(AtomicU32::new(0)),
+            rate_limiter,
+            scraper_identities,
+            download_strategies,
             latest_content_mutex,
+            scrape_journal: ScrapeJournal::default(),
         }
     }
 
-    pub async fn run(&mut self) {
-        let (sender_loop, scraper_loop) = self.scraper_loop().await;
+    /// Runs the loops needed by the given mode, the scraper and the poster only share state through the database
+    ///
+    /// Each subsystem is started again when one of its loops crashes
+    pub async fn run(&mut self, run_mode: RunMode) {
+        let mut subsystems = Vec::new();
+
+        if run_mode.runs_scraper() {
+            clean_up_orphaned_downloads().await;
+
+            // The sender and the scraper share the content being handed over, so they are restarted together
+            let content_manager = self.clone();
+            let scraper_span = tracing::span!(tracing::Level::INFO, "scraper_poster");
+            let scraper = supervise(self.database.clone(), "scraper", move || {
+                let mut content_manager = content_manager.clone();
+                async move {
+                    let (sender_loop, scraper_loop) = content_manager.scraper_loop().await;
+                    vec![sender_loop, scraper_loop]
+                }
+            });
+            subsystems.push(scraper.instrument(scraper_span).boxed_local());
+
+            if !self.is_offline {
+                let content_manager = self.clone();
+                let session_span = tracing::span!(tracing::Level::INFO, "session_health");
+                let session_health = supervise(self.database.clone(), "session health", move || {
+                    let session_health_loop = content_manager.session_health_loop();
+                    async move { vec![session_health_loop] }
+                });
+                subsystems.push(session_health.instrument(session_span).boxed_local());
+            }
 
-        let poster_loop = self.poster_loop();
+            // The compilation is handed over to the sender loop, so it's built where the scraper runs
+            if !self.is_offline && self.credentials.get("weekly_compilation").is_some_and(|value| value == "true") {
+                let content_manager = self.clone();
+                let compilation_span = tracing::span!(tracing::Level::INFO, "compilation");
+                let compilation = supervise(self.database.clone(), "weekly compilation", move || {
+                    let compilation_loop = content_manager.compilation_loop();
+                    async move { vec![compilation_loop] }
+                });
+                subsystems.push(compilation.instrument(compilation_span).boxed_local());
+            }
+        }
 
-        let sender_span = tracing::span!(tracing::Level::INFO, "sender");
-        let scraper_span = tracing::span!(tracing::Level::INFO, "scraper_poster");
-        let poster_span = tracing::span!(tracing::Level::INFO, "poster");
+        if run_mode.runs_frontend() {
+            let content_manager = self.clone();
+            let poster_span = tracing::span!(tracing::Level::INFO, "poster");
+            let poster = supervise(self.database.clone(), "poster", move || {
+                let poster_loop = content_manager.clone().poster_loop();
+                async move { vec![poster_loop] }
+            });
+            subsystems.push(poster.instrument(poster_span).boxed_local());
+
+            if !self.is_offline {
+                let content_manager = self.clone();
+                let comment_replies_span = tracing::span!(tracing::Level::INFO, "comment_replies");
+                let comment_replies = supervise(self.database.clone(), "comment replies", move || {
+                    let comment_reply_loop = content_manager.comment_reply_loop();
+                    async move { vec![comment_reply_loop] }
+                });
+                subsystems.push(comment_replies.instrument(comment_replies_span).boxed_local());
+            }
+        }
 
-        let _ = tokio::try_join!(sender_loop.instrument(sender_span), scraper_loop.instrument(scraper_span), poster_loop.instrument(poster_span));
+        futures::future::join_all(subsystems).await;
     }
 
     async fn scraper_loop(&mut self) -> (JoinHandle<anyhow::Result<()>>, JoinHandle<anyhow::Result<()>>) {
         let span = tracing::span!(tracing::Level::INFO, "outer_scraper_loop");
         let _enter = span.enter();
         let scraper_loop: JoinHandle<anyhow::Result<()>>;
-        let mut accounts_to_scrape: HashMap<String, String> = read_accounts_to_scrape("config/accounts_to_scrape.yaml", self.username.as_str()).await;
+        let (mut accounts_to_scrape, scrape_quotas) = read_accounts_to_scrape("config/accounts_to_scrape.yaml", self.username.as_str()).await;
         let hashtag_mapping: HashMap<String, String> = read_hashtag_mapping("config/hashtags.yaml").await;
+        let hashtags_to_discover = read_hashtags_to_discover(self.username.as_str()).await;
+        let content_sources = read_content_sources(self.username.as_str()).await;
+        let rules = read_rules(self.username.as_str()).await;
+        let caption_languages = read_caption_languages(self.username.as_str()).await;
+        let profanity_filter = read_profanity_filter(self.username.as_str()).await;
+        let stories_to_scrape = read_stories_to_scrape(self.username.as_str()).await;
+        let engagement_filters = read_engagement_filters(self.username.as_str()).await;
+        let sender_hashtag_mapping = hashtag_mapping.clone();
 
         let mut transaction = self.database.begin_transaction().await;
+        // Listening before loading the hashes, so that an import in the meantime isn't missed
+        let mut imported_hashes_listener = self.database.listen_for_imported_hashes().await;
+        // The hashes imported from the other accounts go first, so that the ones of this account take precedence
+        let mut hashed_videos = transaction.load_imported_hashed_videos().await;
+        hashed_videos.extend(transaction.load_hashed_videos().await);
+        let mut hash_index = HashIndex::new(hashed_videos);
         let username = self.username.clone();
         let bucket = self.bucket.clone();
+        let coordinator = self.coordinator.clone();
+        let database = self.database.clone();
         let sender_latest_content = Arc::clone(&self.latest_content_mutex);
         let sender_loop = tokio::spawn(async move {
             loop {
                 {
+                    // The hashes imported with `!importhashes` while the scraper runs
+                    for imported_at in database.take_imported_hashes(&mut imported_hashes_listener).await {
+                        for hashed_video in transaction.load_imported_hashed_videos_at(&imported_at).await {
+                            hash_index.insert(hashed_video);
+                        }
+                    }
+
                     // Use a scoped block to avoid sleeping while the mutex is locked
                     let content_tuple = {
                         let lock = sender_latest_content.lock().await;
@@ -91,42 +227,138 @@ impl ContentManager {
 
                     let bot_status = transaction.load_bot_status().await;
 
-                    if bot_status.status != 0 {
+                    let is_in_maintenance = current_maintenance_window(now_in_my_timezone(&user_settings)).is_some();
+                    if bot_status.status != 0 || !bot_status.drain_until.is_empty() || is_in_maintenance {
                         tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
                         continue;
                     }
 
-                    if let Some((video_file_name, caption, author, shortcode)) = content_tuple {
+                    if let Some((video_file_name, caption, author, shortcode, source_post_metadata)) = content_tuple {
                         if !transaction.does_content_exist_with_shortcode(&shortcode).await && shortcode != "halted" {
-                            // Process video to check if it already exists
-                            let video_exists = process_video(&mut transaction, &video_file_name, author.clone(), shortcode.clone()).await.unwrap();
+                            // The blacklist may have changed since the content was scraped, and the other sources don't look at it
+                            let blacklist = transaction.load_blacklist().await;
+                            // The captions in a language the account doesn't post in are tagged, translated or skipped
+                            let foreign_language = caption_languages.foreign_language(&caption);
+                            let skip = if let Some(blacklist_entry) = find_blacklist_match(&blacklist, &[author.as_str()], &shortcode, Some(&caption)) {
+                                Some((SkipReason::Blacklisted, format!("The {} {} is blacklisted", blacklist_entry.kind, blacklist_entry.value)))
+                            } else if let Some(language) = foreign_language.filter(|_| caption_languages.action == LanguageAction::Skip) {
+                                Some((SkipReason::ForeignLanguage, format!("The caption is in {}", language.eng_name())))
+                            } else {
+                                None
+                            };
+                            if let Some((skip_reason, explanation)) = skip {
+                                println!("{}, skipping {}", explanation, shortcode);
+                                transaction.record_skipped_content(skip_reason, 1).await;
+                                let _ = tokio::fs::remove_file(format!("temp/{video_file_name}")).await;
+
+                                // Handled once, unless the scraper has already moved on to the next content
+                                let mut latest_content = sender_latest_content.lock().await;
+                                if latest_content.as_ref().is_some_and(|(_, _, _, latest_shortcode, _)| *latest_shortcode == shortcode) {
+                                    *latest_content = None;
+                                }
+                                continue;
+                            }
 
-                            if video_exists {
+                            // Process video to check if it already exists, the hashes are kept under the account so that they can be found again
+                            let duplicate_check = process_video(&mut transaction, &mut hash_index, &video_file_name, username.clone(), shortcode.clone()).await.unwrap();
+
+                            if let Some(duplicate_match) = duplicate_check.duplicate_match {
                                 println!("The same video is already in the database with a different shortcode, skipping! :)");
 
                                 let duplicate_content = DuplicateContent {
                                     username: username.clone(),
                                     original_shortcode: shortcode.clone(),
+                                    matched_shortcode: duplicate_match.matched_shortcode.clone(),
+                                    distance: duplicate_match.distance as i32,
+                                    detected_at: now_in_my_timezone(&user_settings).to_rfc3339(),
                                 };
-
                                 transaction.save_duplicate_content(&duplicate_content).await;
+
+                                // Shown in Discord by the frontend, so that the operator can check the match
+                                let duplicate_report = DuplicateReport {
+                                    username: username.clone(),
+                                    original_shortcode: shortcode.clone(),
+                                    original_author: author.clone(),
+                                    matched_shortcode: duplicate_match.matched_shortcode,
+                                    matched_account: duplicate_match.matched_account,
+                                    distance: duplicate_match.distance as i32,
+                                    frame_distances: duplicate_match.frame_distances.map(|frame_distance| frame_distance.to_string()).join(","),
+                                    composite: duplicate_match.composite.unwrap_or_default(),
+                                };
+                                transaction.save_duplicate_report(&duplicate_report).await;
                                 continue;
                             }
 
+                            // The rules can depend on the duration, so they are evaluated on the original video
+                            let matched_rule = find_matching_rule(&rules, &video_file_name, &author, &caption);
+
+                            // Generate the low resolution rendition before the original is removed from local storage
+                            let preview_file_name = match generate_preview(&video_file_name) {
+                                Ok(preview_file_name) => Some(preview_file_name),
+                                Err(e) => {
+                                    tracing::warn!("Falling back to the original video for the preview: {}", e);
+                                    None
+                                }
+                            };
+
+                            let clip_file_name = match generate_preview_clip(&video_file_name) {
+                                Ok(clip_file_name) => Some(clip_file_name),
+                                Err(e) => {
+                                    tracing::warn!("Couldn't generate the preview clip: {}", e);
+                                    None
+                                }
+                            };
+
                             // Upload the video to S3
                             let s3_filename = format!("{}/{}", username, video_file_name);
-                            let url = upload_to_s3(&bucket, video_file_name, s3_filename, true).await.unwrap();
+                            let url = upload_to_s3(&bucket, video_file_name, s3_filename.clone(), true).await.unwrap();
+
+                            let preview_url = match preview_file_name {
+                                Some(preview_file_name) => {
+                                    let s3_preview_filename = format!("{}/{}", username, preview_file_name);
+                                    upload_to_s3(&bucket, preview_file_name, s3_preview_filename, true).await.unwrap_or_else(|_| url.clone())
+                                }
+                                None => url.clone(),
+                            };
+
+                            let clip_url = match clip_file_name {
+                                Some(clip_file_name) => {
+                                    let s3_clip_filename = format!("{}/{}", username, clip_file_name);
+                                    upload_to_s3(&bucket, clip_file_name, s3_clip_filename, true).await.unwrap_or_default()
+                                }
+                                None => "".to_string(),
+                            };
 
                             let re = regex::Regex::new(r"#\w+").unwrap();
                             let cloned_caption = caption.clone();
                             let hashtags: Vec<&str> = re.find_iter(&cloned_caption).map(|mat| mat.as_str()).collect();
                             let hashtags = hashtags.join(" ");
                             let caption = re.replace_all(&caption.clone(), "").to_string();
-                            let now_string = now_in_my_timezone(&user_settings).to_rfc3339();
+                            // Translated without its hashtags, which are kept as they are
+                            let (caption, is_caption_translated) = match foreign_language {
+                                Some(language) if caption_languages.action == LanguageAction::Translate => match caption_languages.translate(&caption).await {
+                                    Ok(translated_caption) => (translated_caption, true),
+                                    Err(e) => {
+                                        tracing::warn!("Couldn't translate the caption of {} from {}: {}", shortcode, language.eng_name(), e);
+                                        (caption, false)
+                                    }
+                                },
+                                _ => (caption, false),
+                            };
+                            let now = now_in_my_timezone(&user_settings);
+                            let now_string = now.to_rfc3339();
+
+                            // The times of the database are in the timezone of the user
+                            let taken_at_timestamp = source_post_metadata.taken_at_timestamp;
+                            let original_posted_at = DateTime::from_timestamp(taken_at_timestamp, 0).filter(|_| taken_at_timestamp > 0).map(|taken_at| now + (taken_at - Utc::now()));
+                            let needs_age_override = match original_posted_at {
+                                Some(original_posted_at) if user_settings.max_source_age_days > 0 => now - original_posted_at > ChronoDuration::days(user_settings.max_source_age_days as i64),
+                                _ => false,
+                            };
 
                             let message_id = transaction.get_temp_message_id(&user_settings).await;
 
-                            let video = ContentInfo {
+                            let mut video = ContentInfo {
                                 username: user_settings.username.clone(),
                                 message_id: MessageId::new(message_id),
                                 url: url.clone(),
@@ -138,9 +370,53 @@ impl ContentManager {
                                 last_updated_at: now_string.clone(),
                                 added_at: now_string,
                                 encountered_errors: 0,
+                                preview_url,
+                                clip_url,
+                                alt_text: "".to_string(),
+                                is_paid_partnership: false,
+                                is_trial_reel: user_settings.trial_reels_by_default,
+                                original_posted_at: original_posted_at.map(|original_posted_at| original_posted_at.to_rfc3339()).unwrap_or_default(),
+                                needs_age_override,
+                                source_views: source_post_metadata.views,
+                                source_likes: source_post_metadata.likes,
+                                audio_id: source_post_metadata.audio_id,
+                                audio_name: source_post_metadata.audio_name,
+                                category: source_post_metadata.category,
+                                source_deleted: false,
+                                caption_language: foreign_language.map(|language| language.eng_name().to_string()).unwrap_or_default(),
+                                is_caption_translated,
+                                profanity: "".to_string(),
+                            };
+
+                            // The content rejected for its profanity isn't accepted by a rule afterwards
+                            let mut automated_decision = match &profanity_filter {
+                                Some(profanity_filter) => apply_profanity_filter(&mut transaction, &user_settings, profanity_filter, &mut video).await,
+                                None => None,
+                            };
+                            if let Some(rule) = matched_rule.filter(|_| automated_decision.is_none()) {
+                                automated_decision = apply_rule(&mut transaction, &user_settings, &sender_hashtag_mapping, rule, &mut video).await;
+                            }
+
+                            // Kept for when the origin of the content is disputed, the poster adds its attempts at publishing it
+                            let content_provenance = ContentProvenance {
+                                username: user_settings.username.clone(),
+                                original_shortcode: shortcode.clone(),
+                                source_url: source_post_metadata.source_url,
+                                downloaded_at: source_post_metadata.downloaded_at,
+                                processing_steps: source_post_metadata.processing_steps.join(";"),
+                                dedup_compared_videos: duplicate_check.compared_videos as i64,
+                                dedup_closest_distance: duplicate_check.closest_distance.map_or(-1, |closest_distance| closest_distance as i32),
+                                s3_key: s3_filename,
+                                publish_attempts: "".to_string(),
                             };
+                            transaction.save_content_provenance(&content_provenance).await;
 
                             transaction.save_content_info(&video).await;
+                            if let Some(automated_decision) = automated_decision {
+                                automated_decision.save(&mut transaction).await;
+                            }
+                            transaction.notify_new_content().await;
+                            coordinator.publish_event(&format!("new_content:{}", shortcode)).await;
                         }
                     } else {
                         //tx.send(("".to_string(), "".to_string(), "".to_string(), "ignore".to_string())).await.unwrap();
@@ -181,7 +457,7 @@ impl ContentManager {
                         file.write_all(&bytes).await.unwrap();
 
                         let mut latest_content_guard = scraper_latest_content.lock().await;
-                        *latest_content_guard = Some((format!("../{path}").to_string(), caption_string.clone(), "local".to_string(), format!("shortcode{}", inner_loop_iterations)));
+                        *latest_content_guard = Some((format!("../{path}").to_string(), caption_string.clone(), "local".to_string(), format!("shortcode{}", inner_loop_iterations), SourcePostMetadata::default()));
                         sleep(Duration::from_secs(10)).await;
                     }
                 }
@@ -193,34 +469,89 @@ impl ContentManager {
                 let span = tracing::span!(tracing::Level::INFO, "online_scraper_loop");
                 let _enter = span.enter();
 
+                cloned_self.ensure_healthy_proxy().await;
                 cloned_self.login_scraper().await;
+                cloned_self.log_in_identities().await;
 
-                let mut accounts_being_scraped = Vec::new();
+                // The reels asked for from Discord and the inbox don't wait for the next iteration, and go away along with the scraper when it's restarted
+                let ingest_self = cloned_self.clone();
+                let ingest_loop = async move {
+                    if !ingest_self.is_scrape_dry_run {
+                        ingest_self.run_ingest_loop().await;
+                    }
+                };
 
-                cloned_self.fetch_user_info(&mut accounts_to_scrape, &mut accounts_being_scraped).await;
+                let scraping_loop = async move {
+                    let mut accounts_being_scraped = Vec::new();
 
-                loop {
-                    let content_mapping_len = cloned_self.database.begin_transaction().await.load_content_mapping().await.len();
+                    cloned_self.fetch_user_info(&mut accounts_to_scrape, &mut accounts_being_scraped).await;
 
-                    if content_mapping_len >= MAX_CONTENT_HANDLED {
-                        cloned_self.println("Reached the maximum amount of handled content");
-                        cloned_self.println(&format!("Starting long sleep ({} minutes)", SCRAPER_LOOP_SLEEP_LEN.as_secs() / 60));
-                        cloned_self.randomized_sleep(SCRAPER_LOOP_SLEEP_LEN.as_secs()).await;
+                    let mut hashtags_being_scraped = Vec::new();
+                    cloned_self.resolve_hashtags(&hashtags_to_discover, &mut hashtags_being_scraped).await;
 
-                        continue;
+                    // The captions of the content found through a hashtag or another source fall back to the hashtags configured for it
+                    for hashtag_to_discover in &hashtags_to_discover {
+                        accounts_to_scrape.insert(hashtag_to_discover.profile(), hashtag_to_discover.hashtags.clone());
+                    }
+                    for content_source in &content_sources {
+                        accounts_to_scrape.insert(content_source.name(), content_source.hashtags());
                     }
 
-                    let mut posts: HashMap<User, Vec<Post>> = HashMap::new();
-                    cloned_self.fetch_posts(accounts_being_scraped.clone(), &mut posts).await;
+                    loop {
+                        let content_mapping_len = cloned_self.database.begin_transaction().await.load_content_mapping().await.len();
 
-                    // Scrape the posts
-                    cloned_self.scrape_posts(&accounts_to_scrape, &hashtag_mapping, &mut posts).await;
+                        if content_mapping_len >= MAX_CONTENT_HANDLED {
+                            cloned_self.println("Reached the maximum amount of handled content");
+                            let evicted = match read_backlog_policy(&cloned_self.credentials) {
+                                BacklogPolicy::EvictOldestPending => cloned_self.evict_oldest_pending(content_mapping_len).await,
+                                BacklogPolicy::Sleep => 0,
+                            };
 
-                    // Wait for a while before the next iteration
+                            if content_mapping_len - evicted >= MAX_CONTENT_HANDLED {
+                                cloned_self.println(&format!("Starting long sleep ({} minutes)", SCRAPER_LOOP_SLEEP_LEN.as_secs() / 60));
+                                cloned_self.randomized_sleep(SCRAPER_LOOP_SLEEP_LEN.as_secs()).await;
 
-                    cloned_self.println(&format!("Starting long sleep ({} minutes)", SCRAPER_LOOP_SLEEP_LEN.as_secs() / 60));
-                    cloned_self.randomized_sleep(SCRAPER_LOOP_SLEEP_LEN.as_secs()).await;
-                }
+                                continue;
+                            }
+                            cloned_self.println(&format!("Evicted the {} oldest pending content to make room for fresh content", evicted));
+                        }
+
+                        if cloned_self.ensure_healthy_proxy().await {
+                            cloned_self.login_scraper().await;
+                        }
+
+                        cloned_self.start_scrape_run().await;
+                        let mut posts: HashMap<SourceAccount, Vec<Post>> = HashMap::new();
+                        cloned_self.fetch_posts(accounts_being_scraped.clone(), &scrape_quotas, &mut posts).await;
+
+                        let mut discovered_posts: Vec<(SourceAccount, String)> = Vec::new();
+                        cloned_self.fetch_hashtag_posts(&hashtags_being_scraped, &mut discovered_posts).await;
+
+                        // Scrape the posts
+                        cloned_self.scrape_posts(&accounts_to_scrape, &scrape_quotas, &engagement_filters, &hashtag_mapping, &mut posts, discovered_posts).await;
+
+                        // The other sources and the stories are downloaded as soon as they are found, so they are left out of a dry run
+                        if !cloned_self.is_scrape_dry_run {
+                            // Then the other sources
+                            cloned_self.scrape_sources(&content_sources, &accounts_to_scrape, &hashtag_mapping).await;
+
+                            // The stories skip the content pipeline, they only wait for a review
+                            cloned_self.scrape_stories(&stories_to_scrape, &accounts_being_scraped).await;
+                        }
+
+                        // Nothing is downloaded here, the suggestions only wait for a review
+                        cloned_self.suggest_sources(&accounts_to_scrape, &accounts_being_scraped).await;
+                        cloned_self.finish_scrape_run().await;
+
+                        // Wait for a while before the next iteration
+
+                        cloned_self.println(&format!("Starting long sleep ({} minutes)", SCRAPER_LOOP_SLEEP_LEN.as_secs() / 60));
+                        cloned_self.randomized_sleep(SCRAPER_LOOP_SLEEP_LEN.as_secs()).await;
+                    }
+                };
+
+                tokio::join!(scraping_loop, ingest_loop);
+                Ok(())
             });
         }
         (sender_loop, scraper_loop)
@@ -242,28 +573,43 @@ impl ContentManager {
                 }
                 Err(e) => {
                     self.println(&format!(" Login failed: {}", e));
-                    let mut tx = self.database.begin_transaction().await;
-                    set_bot_status_halted(&mut tx).await;
+                    // Instagram may put a checkpoint on the login, the moderators can then answer it from Discord
+                    if !self.resolve_login_challenge(&mut scraper_guard, &e.to_string()).await {
+                        let mut tx = self.database.begin_transaction().await;
+                        set_bot_status_halted(&mut tx).await;
 
-                    loop {
-                        let bot_status = tx.load_bot_status().await;
-                        if bot_status.status == 0 {
-                            self.println("Retrying to log in...");
-                            scraper_guard.authenticate_with_login(username.clone(), password.clone());
-                            let result = scraper_guard.login().await;
-                            match result {
-                                Ok(_) => {
-                                    self.println("Logged in successfully");
-                                    set_bot_status_operational(&mut tx).await;
-                                    break;
+                        // Transient login failures usually go away on their own, so we periodically retry
+                        // without waiting for the user, up to MAX_AUTOMATIC_LOGIN_RETRIES times
+                        let mut automatic_retries = 0;
+                        let mut next_automatic_retry = Instant::now() + jittered_duration(LOGIN_RETRY_INTERVAL);
+
+                        loop {
+                            let bot_status = tx.load_bot_status().await;
+                            let is_automatic_retry = bot_status.status != 0 && automatic_retries < MAX_AUTOMATIC_LOGIN_RETRIES && Instant::now() >= next_automatic_retry;
+                            if bot_status.status == 0 || is_automatic_retry {
+                                if is_automatic_retry {
+                                    automatic_retries += 1;
+                                    self.println(&format!("Automatically retrying to log in ({}/{})...", automatic_retries, MAX_AUTOMATIC_LOGIN_RETRIES));
+                                } else {
+                                    self.println("Retrying to log in...");
                                 }
-                                Err(e) => {
-                                    self.println(&format!(" Login failed: {}", e));
-                                    set_bot_status_halted(&mut tx).await;
+                                scraper_guard.authenticate_with_login(username.clone(), password.clone());
+                                let result = scraper_guard.login().await;
+                                match result {
+                                    Ok(_) => {
+                                        self.println("Logged in successfully");
+                                        set_bot_status_operational(&mut tx).await;
+                                        break;
+                                    }
+                                    Err(e) => {
+                                        self.println(&format!(" Login failed: {}", e));
+                                        set_bot_status_halted(&mut tx).await;
+                                        next_automatic_retry = Instant::now() + jittered_duration(LOGIN_RETRY_INTERVAL);
+                                    }
                                 }
+                            } else {
+                                tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
                             }
-                        } else {
-                            tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
                         }
                     }
                 }
@@ -274,7 +620,7 @@ impl ContentManager {
         }
     }
 
-    async fn fetch_user_info(&mut self, accounts_to_scrape: &mut HashMap<String, String>, accounts_being_scraped: &mut Vec<User>) {
+    async fn fetch_user_info(&mut self, accounts_to_scrape: &mut HashMap<String, String>, accounts_being_scraped: &mut Vec<SourceAccount>) {
         let mut tx = self.database.begin_transaction().await;
 
         pause_scraper_if_needed(&mut tx).await;
@@ -285,13 +631,25 @@ impl ContentManager {
             {
                 pause_scraper_if_needed(&mut tx).await;
 
+                self.wait_for_instagram_budget().await;
+
                 accounts_scraped += 1;
                 let mut scraper_guard = self.scraper.lock().await;
-                let result = scraper_guard.scrape_userinfo(&profile).await;
+                let result = loop {
+                    match scraper_guard.scrape_userinfo(&profile).await {
+                        Err(InstagramScraperError::RateLimitExceeded { .. }) => {
+                            self.handle_rate_limited(&mut tx).await;
+                            self.rate_limiter.pace(FETCH_SLEEP_LEN).await;
+                        }
+                        result => break result,
+                    }
+                };
 
                 match result {
                     Ok(user) => {
-                        accounts_being_scraped.push(user);
+                        self.rate_limiter.record_success().await;
+                        let source_account = self.track_source_account(&mut tx, &profile, &user).await;
+                        accounts_being_scraped.push(source_account);
                         self.println(&format!("{}/{} Fetched user info for {}", accounts_scraped, accounts_to_scrape_len, profile));
                         set_bot_status_operational(&mut tx).await;
                     }
@@ -299,7 +657,16 @@ impl ContentManager {
                         self.println(&format!("{}/{} Error fetching user info for {}: {}", accounts_scraped, accounts_to_scrape_len, profile, e));
                         match e {
                             InstagramScraperError::UserNotFound(profile) => {
-                                accounts_to_scrape.remove(&profile);
+                                // The account was most likely renamed, its user id still points to it
+                                match tx.get_source_account(&profile).await {
+                                    Some(source_account) => {
+                                        self.println(&format!("{} seems to have been renamed, following it by id as {}", profile, source_account.display_name));
+                                        accounts_being_scraped.push(source_account);
+                                    }
+                                    None => {
+                                        accounts_to_scrape.remove(&profile);
+                                    }
+                                }
                             }
                             InstagramScraperError::Http(error) => {
                                 let error = format!("{}", error);
@@ -309,7 +676,8 @@ impl ContentManager {
                                     let result = scraper_guard.scrape_userinfo(&profile).await;
                                     match result {
                                         Ok(user) => {
-                                            accounts_being_scraped.push(user);
+                                            let source_account = self.track_source_account(&mut tx, &profile, &user).await;
+                                            accounts_being_scraped.push(source_account);
                                             self.println(&format!("{}/{} Fetched user info for {}", accounts_scraped, accounts_to_scrape_len, profile));
                                             set_bot_status_operational(&mut tx).await;
                                         }
@@ -330,11 +698,11 @@ impl ContentManager {
                 };
             }
 
-            self.randomized_sleep(FETCH_SLEEP_LEN.as_secs()).await;
+            self.rate_limiter.pace(FETCH_SLEEP_LEN).await;
         }
     }
 
-    async fn fetch_user_info_halted_loop(&self, accounts_being_scraped: &mut Vec<User>, mut tx: &mut DatabaseTransaction, accounts_scraped: &mut i32, accounts_to_scrape_len: &usize, profile: &String, scraper_guard: &mut InstagramScraper) {
+    async fn fetch_user_info_halted_loop(&self, accounts_being_scraped: &mut Vec<SourceAccount>, mut tx: &mut DatabaseTransaction, accounts_scraped: &mut i32, accounts_to_scrape_len: &usize, profile: &String, scraper_guard: &mut InstagramScraper) {
         loop {
             let bot_status = tx.load_bot_status().await;
             if bot_status.status == 0 {
@@ -342,7 +710,8 @@ impl ContentManager {
                 let result = scraper_guard.scrape_userinfo(&profile).await;
                 match result {
                     Ok(user) => {
-                        accounts_being_scraped.push(user);
+                        let source_account = self.track_source_account(&mut tx, &profile, &user).await;
+                        accounts_being_scraped.push(source_account);
                         self.println(&format!("{}/{} Fetched user info for {}", accounts_scraped, accounts_to_scrape_len, profile));
                         set_bot_status_operational(&mut tx).await;
                         break;
@@ -358,61 +727,119 @@ impl ContentManager {
         }
     }
 
-    async fn fetch_posts(&mut self, accounts_being_scraped: Vec<User>, posts: &mut HashMap<User, Vec<Post>>) {
+    /// Records the user id behind a configured profile, so that the account can be followed across renames
+    async fn track_source_account(&self, tx: &mut DatabaseTransaction, profile: &str, user: &User) -> SourceAccount {
+        let source_accounts = tx.load_source_accounts().await;
+
+        // The profile was renamed in the config as well, the old entry is replaced by the new one
+        for previous_account in source_accounts.iter().filter(|account| account.user_id == user.id && account.profile != profile) {
+            self.println(&format!("{} was previously tracked as {}", profile, previous_account.profile));
+            tx.remove_source_account(&previous_account.profile).await;
+        }
+
+        if let Some(existing_account) = source_accounts.iter().find(|account| account.profile == profile) {
+            if existing_account.user_id != user.id {
+                self.println(&format!("{} now belongs to a different account, the previous one was last seen as {}", profile, existing_account.display_name));
+            } else if existing_account.display_name != user.username {
+                self.println(&format!("{} is now known as {}", existing_account.display_name, user.username));
+            }
+        }
+
+        let source_account = SourceAccount {
+            username: self.username.clone(),
+            profile: profile.to_string(),
+            user_id: user.id.clone(),
+            display_name: user.username.clone(),
+            updated_at: Utc::now().to_rfc3339(),
+        };
+        tx.save_source_account(&source_account).await;
+        source_account
+    }
+
+    async fn fetch_posts(&mut self, accounts_being_scraped: Vec<SourceAccount>, scrape_quotas: &HashMap<String, ScrapeQuota>, posts: &mut HashMap<SourceAccount, Vec<Post>>) {
         let mut tx = self.database.begin_transaction().await;
         pause_scraper_if_needed(&mut tx).await;
         let mut accounts_scraped = 0;
         let accounts_being_scraped_len = accounts_being_scraped.len();
+        let scrape_cursors = tx.load_scrape_cursors().await;
         self.println("Fetching posts...");
         for user in accounts_being_scraped.iter() {
             // get posts
             {
                 pause_scraper_if_needed(&mut tx).await;
+                self.wait_for_instagram_budget().await;
 
                 let mut scraper_guard = self.scraper.lock().await;
                 accounts_scraped += 1;
-                self.println(&format!("{}/{} Retrieving posts from user {}", accounts_scraped, accounts_being_scraped_len, user.username));
+                self.println(&format!("{}/{} Retrieving posts from user {}", accounts_scraped, accounts_being_scraped_len, user.display_name));
 
-                match scraper_guard.scrape_posts(&user.id, 5).await {
+                let posts_to_fetch = scrape_quotas.get(&user.profile).copied().unwrap_or_default().posts_to_fetch(scrape_cursors.contains_key(&user.profile));
+
+                let result = loop {
+                    match scraper_guard.scrape_posts(&user.user_id, posts_to_fetch).await {
+                        Err(InstagramScraperError::RateLimitExceeded { .. }) => {
+                            self.handle_rate_limited(&mut tx).await;
+                            self.rate_limiter.pace(FETCH_SLEEP_LEN).await;
+                        }
+                        result => break result,
+                    }
+                };
+
+                match result {
                     Ok(scraped_posts) => {
+                        self.rate_limiter.record_success().await;
                         set_bot_status_operational(&mut tx).await;
-                        posts.insert(user.clone(), scraped_posts);
+                        posts.insert(user.clone(), posts_newer_than_cursor(scraped_posts, scrape_cursors.get(&user.profile)));
                     }
                     Err(e) => {
                         self.println(&format!("Error scraping posts: {}", e));
-                        let mut bot_status = tx.load_bot_status().await;
-                        bot_status.status = 1;
-                        tx.save_bot_status(&bot_status).await;
-                        loop {
-                            let bot_status = tx.load_bot_status().await;
-                            if bot_status.status == 0 {
-                                self.println("Retrying to fetch posts...");
-                                let result = scraper_guard.scrape_posts(&user.id, 5).await;
-                                match result {
-                                    Ok(scraped_posts) => {
-                                        posts.insert(user.clone(), scraped_posts);
-                                        set_bot_status_operational(&mut tx).await;
-                                        break;
-                                    }
-                                    Err(e) => {
-                                        self.println(&format!("Error scraping posts: {}", e));
-                                        set_bot_status_halted(&mut tx).await;
+                        self.scrape_journal.record_error(&format!("Couldn't fetch the posts of {}: {}", user.display_name, e));
+
+                        // An expired session is the usual culprit, the bot is only halted when logging in again keeps failing
+                        if self.recover_session(&mut scraper_guard, &mut tx).await {
+                            match scraper_guard.scrape_posts(&user.user_id, posts_to_fetch).await {
+                                Ok(scraped_posts) => {
+                                    posts.insert(user.clone(), posts_newer_than_cursor(scraped_posts, scrape_cursors.get(&user.profile)));
+                                }
+                                Err(e) => self.println(&format!("Error scraping posts after logging in again: {}", e)),
+                            }
+                        } else if self.has_too_many_session_failures() {
+                            let mut bot_status = tx.load_bot_status().await;
+                            bot_status.status = 1;
+                            tx.save_bot_status(&bot_status).await;
+                            loop {
+                                let bot_status = tx.load_bot_status().await;
+                                if bot_status.status == 0 {
+                                    self.println("Retrying to fetch posts...");
+                                    let result = scraper_guard.scrape_posts(&user.user_id, posts_to_fetch).await;
+                                    match result {
+                                        Ok(scraped_posts) => {
+                                            posts.insert(user.clone(), posts_newer_than_cursor(scraped_posts, scrape_cursors.get(&user.profile)));
+                                            self.session_failures.store(0, Ordering::SeqCst);
+                                            set_bot_status_operational(&mut tx).await;
+                                            break;
+                                        }
+                                        Err(e) => {
+                                            self.println(&format!("Error scraping posts: {}", e));
+                                            set_bot_status_halted(&mut tx).await;
+                                        }
                                     }
+                                } else {
+                                    tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
                                 }
-                            } else {
-                                tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
                             }
+                            break;
                         }
-                        break;
                     }
                 };
             }
 
-            self.randomized_sleep(FETCH_SLEEP_LEN.as_secs()).await;
+            self.rate_limiter.pace(FETCH_SLEEP_LEN).await;
         }
+        self.scrape_journal.record_accounts_fetched(posts.len());
     }
 
-    async fn scrape_posts(&mut self, accounts_to_scrape: &HashMap<String, String>, hashtag_mapping: &HashMap<String, String>, posts: &mut HashMap<User, Vec<Post>>) {
+    async fn scrape_posts(&mut self, accounts_to_scrape: &HashMap<String, String>, scrape_quotas: &HashMap<String, ScrapeQuota>, engagement_filters: &EngagementFilters, hashtag_mapping: &HashMap<String, String>, posts: &mut HashMap<SourceAccount, Vec<Post>>, discovered_posts: Vec<(SourceAccount, String)>) {
         let mut transaction = self.database.begin_transaction().await;
 
         pause_scraper_if_needed(&mut transaction).await;
@@ -420,137 +847,336 @@ impl ContentManager {
 
         self.println("Scraping posts...");
 
-        let mut flattened_posts: Vec<(User, Post)> = Vec::new();
-        for (user, user_posts) in posts {
+        let mut flattened_posts: Vec<(SourceAccount, Post)> = Vec::new();
+        for (user, user_posts) in posts.iter() {
             for post in user_posts {
                 flattened_posts.push((user.clone(), post.clone()));
             }
         }
+        self.scrape_journal.record_posts_seen(flattened_posts.len() + discovered_posts.len());
 
-        flattened_posts.shuffle(&mut rng);
+        // Carousels aren't videos themselves, but the videos inside them can be used
+        let carousel_videos = self.fetch_carousel_videos(&flattened_posts).await;
+
+        // Shown on the cards, the videos of a carousel were posted along with it and share its numbers
+        let mut post_metadata: HashMap<String, SourcePostMetadata> = flattened_posts.iter().map(|(_, post)| (post.shortcode.clone(), SourcePostMetadata::from(post))).collect();
+        for (_, carousel_video) in carousel_videos.iter() {
+            if let Some(carousel_metadata) = post_metadata.get(&carousel_video.carousel_shortcode).cloned() {
+                post_metadata.insert(carousel_video.shortcode.clone(), carousel_metadata);
+            }
+        }
+        let carousels_with_videos = carousel_videos.iter().map(|(_, carousel_video)| &carousel_video.carousel_shortcode).collect::<HashSet<_>>();
 
         // remove everything that is not a video
-        flattened_posts.retain(|(_, post)| post.is_video);
+        let (videos, not_videos): (Vec<_>, Vec<_>) = flattened_posts.into_iter().partition(|(_, post)| post.is_video);
+        let not_videos: Vec<(&SourceAccount, &str, String)> = not_videos.iter().filter(|(_, post)| !carousels_with_videos.contains(&post.shortcode)).map(|(author, post)| (author, post.shortcode.as_str(), String::new())).collect();
+        self.record_skipped_posts(&mut transaction, SkipReason::NotAVideo, &not_videos).await;
+        let mut flattened_posts = videos;
+
+        // The posts performing poorly aren't worth downloading, nor reviewing
+        let now_timestamp = Utc::now().timestamp();
+        let mut low_engagement_posts = Vec::new();
+        flattened_posts.retain(|(author, post)| match engagement_filters.for_source(&author.profile).check(post, now_timestamp) {
+            Some(shortfall) => {
+                self.println(&format!("Skipping {} from {}, not enough engagement: {}", post.shortcode, author.display_name, shortfall));
+                low_engagement_posts.push((author.clone(), post.shortcode.clone(), shortfall));
+                false
+            }
+            None => true,
+        });
+        let low_engagement_posts: Vec<(&SourceAccount, &str, String)> = low_engagement_posts.iter().map(|(author, shortcode, shortfall)| (author, shortcode.as_str(), shortfall.clone())).collect();
+        self.record_skipped_posts(&mut transaction, SkipReason::LowEngagement, &low_engagement_posts).await;
+
+        let mut flattened_posts: Vec<(SourceAccount, String)> = flattened_posts.into_iter().map(|(author, post)| (author, post.shortcode)).collect();
+        flattened_posts.extend(carousel_videos.iter().map(|(author, carousel_video)| (author.clone(), carousel_video.shortcode.clone())));
+        let carousel_videos: HashMap<String, CarouselVideo> = carousel_videos.into_iter().map(|(_, carousel_video)| (carousel_video.shortcode.clone(), carousel_video)).collect();
+
+        // The content found through the hashtags goes through the same pipeline, a post can show up both under an account and a hashtag
+        let mut seen_shortcodes: HashSet<String> = HashSet::new();
+        flattened_posts.extend(discovered_posts);
+        flattened_posts.retain(|(_, shortcode)| seen_shortcodes.insert(shortcode.clone()));
+
+        // Weighted shuffle, the posts of the accounts with a higher weight tend to come first
+        let mut weighted_posts: Vec<(f64, (SourceAccount, String))> = flattened_posts
+            .into_iter()
+            .map(|(author, shortcode)| {
+                let weight = scrape_quotas.get(&author.profile).copied().unwrap_or_default().weight;
+                (rng.gen::<f64>().powf(1.0 / weight), (author, shortcode))
+            })
+            .collect();
+        weighted_posts.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        let mut flattened_posts: Vec<(SourceAccount, String)> = weighted_posts.into_iter().map(|(_, post)| post).collect();
+
+        // Content that couldn't be found last time gets a second chance before the new posts
+        let media_retries = transaction.load_media_retries().await;
+        for media_retry in media_retries.iter().filter(|media_retry| media_retry.attempts < MAX_MEDIA_NOT_FOUND_ATTEMPTS) {
+            let author = match posts.keys().find(|author| author.profile == media_retry.profile) {
+                Some(author) => author.clone(),
+                None => match transaction.get_source_account(&media_retry.profile).await {
+                    Some(author) => author,
+                    None => continue,
+                },
+            };
+            flattened_posts.retain(|(_, shortcode)| *shortcode != media_retry.original_shortcode);
+            flattened_posts.insert(0, (author, media_retry.original_shortcode.clone()));
+        }
+
+        let mut scraped_today = transaction.load_scraped_today_by_profile().await;
+        let blacklist = transaction.load_blacklist().await;
 
         let mut flattened_posts_processed = 0;
         let flattened_posts_len = flattened_posts.len();
 
-        let mut actually_scraped = 0;
-        for (author, post) in flattened_posts {
+        // The posts left for the next iteration keep the cursor of their account behind them
+        let mut unhandled_shortcodes: HashSet<String> = flattened_posts.iter().map(|(_, shortcode)| shortcode.clone()).collect();
+
+        // The downloads run in the background with their own pacing, while the posts are still being checked against the database
+        let download_stage = DownloadStage::default();
+        let download_permits = Arc::new(Semaphore::new(MAX_CONCURRENT_DOWNLOADS));
+        let (outcome_sender, mut outcome_receiver) = mpsc::unbounded_channel();
+        let mut would_download = 0;
+        for (author, shortcode) in flattened_posts {
             pause_scraper_if_needed(&mut transaction).await;
 
             flattened_posts_processed += 1;
+            unhandled_shortcodes.remove(&shortcode);
 
-            if actually_scraped >= MAX_CONTENT_PER_ITERATION {
-                self.println("Reached the maximum amount of scraped content per iteration");
-                set_bot_status_operational(&mut transaction).await;
-                break;
-            }
+            let base_print = format!("{flattened_posts_processed}/{flattened_posts_len}");
 
-            let base_print = format!("{flattened_posts_processed}/{flattened_posts_len} - {actually_scraped}/{MAX_CONTENT_PER_ITERATION}");
-
-            // Send the URL through the channel
-            if post.is_video {
-                if !transaction.does_content_exist_with_shortcode(&post.shortcode).await {
-                    let filename;
-                    let caption;
-                    {
-                        filename = format!("{}.mp4", post.shortcode);
-                        let mut scraper_guard = self.scraper.lock().await;
-                        caption = match scraper_guard.download_reel(&post.shortcode, &filename).await {
-                            Ok(caption) => {
-                                actually_scraped += 1;
-                                let base_print = format!("{flattened_posts_processed}/{flattened_posts_len} - {actually_scraped}/{MAX_CONTENT_PER_ITERATION}");
-                                self.println(&format!("{base_print} Scraped content from {}: {}", author.username, post.shortcode));
-                                set_bot_status_operational(&mut transaction).await;
-                                caption
-                            }
-                            Err(e) => {
-                                self.println(&format!("Error while downloading reel | {}", e));
-
-                                match e {
-                                    InstagramScraperError::MediaNotFound { .. } => continue,
-                                    InstagramScraperError::RateLimitExceeded { .. } => break,
-                                    _ => {
-                                        set_bot_status_halted(&mut transaction).await;
-                                        loop {
-                                            let bot_status = transaction.load_bot_status().await;
-                                            if bot_status.status == 0 {
-                                                self.println("Retrying to download reel...");
-                                                let result = scraper_guard.download_reel(&post.shortcode, &filename).await;
-                                                match result {
-                                                    Ok(caption) => {
-                                                        actually_scraped += 1;
-                                                        let base_print = format!("{flattened_posts_processed}/{flattened_posts_len} - {actually_scraped}/{MAX_CONTENT_PER_ITERATION}");
-                                                        self.println(&format!("{base_print} Scraped content from {}: {}", author.username, post.shortcode));
-                                                        set_bot_status_operational(&mut transaction).await;
-                                                        break caption;
-                                                    }
-                                                    Err(e) => {
-                                                        self.println(&format!("Error while downloading reel | {}", e));
-                                                        set_bot_status_halted(&mut transaction).await;
-                                                    }
-                                                }
-                                            } else {
-                                                tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        };
+            if media_retries.iter().any(|media_retry| media_retry.original_shortcode == shortcode && media_retry.attempts >= MAX_MEDIA_NOT_FOUND_ATTEMPTS) {
+                self.println(&format!("{base_print} Content could not be found after {} attempts: {}", MAX_MEDIA_NOT_FOUND_ATTEMPTS, shortcode));
+                self.record_skipped_posts(&mut transaction, SkipReason::MediaNotFound, &[(&author, shortcode.as_str(), format!("{} attempts", MAX_MEDIA_NOT_FOUND_ATTEMPTS))]).await;
+                continue;
+            }
 
-                        let cookie_store = Arc::clone(&scraper_guard.session.cookie_store);
-                        save_cookie_store_to_json(&self.cookie_store_path, cookie_store).await;
+            if !transaction.does_content_exist_with_shortcode(&shortcode).await {
+                if let Some(max_posts_per_day) = scrape_quotas.get(&author.profile).and_then(|scrape_quota| scrape_quota.max_posts_per_day) {
+                    if scraped_today.get(&author.profile).copied().unwrap_or_default() >= max_posts_per_day {
+                        self.println(&format!("{base_print} Reached the daily quota of {}, skipping: {}", author.display_name, shortcode));
+                        self.record_skipped_posts(&mut transaction, SkipReason::QuotaReached, &[(&author, shortcode.as_str(), format!("{} per day", max_posts_per_day))]).await;
+                        continue;
                     }
+                }
 
-                    let caption = process_caption(accounts_to_scrape, hashtag_mapping, &mut rng, &author, caption);
+                if let Some(blacklist_entry) = find_blacklist_match(&blacklist, &[author.profile.as_str(), author.display_name.as_str()], &shortcode, None) {
+                    self.println(&format!("{base_print} The {} {} is blacklisted, skipping: {}", blacklist_entry.kind, blacklist_entry.value, shortcode));
+                    self.record_skipped_posts(&mut transaction, SkipReason::Blacklisted, &[(&author, shortcode.as_str(), format!("{} {}", blacklist_entry.kind, blacklist_entry.value))]).await;
+                    continue;
+                }
 
-                    // Use a scoped block to immediately drop the lock
-                    {
-                        // Store the new URL in the shared variable
-                        let mut lock = self.latest_content_mutex.lock().await;
-                        //println!("Storing URL: {}", url);
-                        *lock = Some((filename, caption, author.username.clone(), post.shortcode.clone()));
+                // Counted right away so that the downloads in flight can't go over the quota, given back if the download doesn't go through
+                *scraped_today.entry(author.profile.clone()).or_default() += 1;
+
+                // The keywords of the caption can't be checked without downloading, the rest of the filters have been gone through at this point
+                if self.is_scrape_dry_run {
+                    would_download += 1;
+                    let decision = if would_download <= MAX_CONTENT_PER_ITERATION { "download" } else { "postponed" };
+                    self.println(&format!("{base_print} [dry run] {} from {}: {}", decision, author.display_name, shortcode));
+                    self.record_scrape_decision(&mut transaction, &author, &shortcode, decision, "").await;
+                    continue;
+                }
+
+                let cloned_self = self.clone();
+                let download_stage = download_stage.clone();
+                let download_permits = Arc::clone(&download_permits);
+                let outcome_sender = outcome_sender.clone();
+                let carousel_video = carousel_videos.get(&shortcode).cloned();
+                tokio::spawn(async move {
+                    let _permit = download_permits.acquire_owned().await.unwrap();
+                    let download_outcome = cloned_self.download_content(&download_stage, &shortcode, carousel_video.as_ref()).await;
+                    let is_postponed = matches!(download_outcome, DownloadOutcome::Postponed);
+                    let _ = outcome_sender.send((author, shortcode, download_outcome));
+
+                    // The pacing is per connection, the permit is only given to the next download once this one has waited
+                    if !is_postponed {
+                        cloned_self.rate_limiter.pace(SCRAPER_DOWNLOAD_SLEEP_LEN).await;
                     }
-                } else {
-                    let existing_content_shortcodes: Vec<String> = transaction.load_content_mapping().await.iter().map(|content_info| content_info.original_shortcode.clone()).collect();
-                    let existing_posted_shortcodes: Vec<String> = transaction.load_posted_content().await.iter().map(|existing_posted| existing_posted.original_shortcode.clone()).collect();
-                    let existing_failed_shortcodes: Vec<String> = transaction.load_failed_content().await.iter().map(|existing_posted| existing_posted.original_shortcode.clone()).collect();
-                    let existing_rejected_shortcodes: Vec<String> = transaction.load_rejected_content().await.iter().map(|existing_posted| existing_posted.original_shortcode.clone()).collect();
-                    let existing_duplicate_shortcodes: Vec<String> = transaction.load_duplicate_content().await.iter().map(|existing_posted| existing_posted.original_shortcode.clone()).collect();
-
-                    match existing_content_shortcodes.iter().position(|x| x == &post.shortcode) {
-                        Some(_) => {
-                            self.println(&format!("{base_print} Content already scraped: {}", post.shortcode));
-                        }
-                        None => {
-                            // Check if the shortcode is in the posted, failed or rejected content
-                            if existing_posted_shortcodes.contains(&post.shortcode) {
-                                self.println(&format!("{base_print} Content already posted: {}", post.shortcode));
-                            } else if existing_failed_shortcodes.contains(&post.shortcode) {
-                                self.println(&format!("{base_print} Content already failed: {}", post.shortcode));
-                            } else if existing_rejected_shortcodes.contains(&post.shortcode) {
-                                self.println(&format!("{base_print} Content already rejected: {}", post.shortcode));
-                            } else if existing_duplicate_shortcodes.contains(&post.shortcode) {
-                                self.println(&format!("{base_print} Content already scraped (dupe): {}", post.shortcode));
-                            } else {
-                                let error_message = format!("{base_print} Content not found in any mapping: {}", post.shortcode);
-                                tracing::error!(error_message);
-                                panic!("{}", error_message);
-                            }
+                });
+            } else {
+                let existing_content_shortcodes: Vec<String> = transaction.load_content_mapping().await.iter().map(|content_info| content_info.original_shortcode.clone()).collect();
+                let existing_posted_shortcodes: Vec<String> = transaction.load_posted_content().await.iter().map(|existing_posted| existing_posted.original_shortcode.clone()).collect();
+                let existing_failed_shortcodes: Vec<String> = transaction.load_failed_content().await.iter().map(|existing_posted| existing_posted.original_shortcode.clone()).collect();
+                let existing_rejected_shortcodes: Vec<String> = transaction.load_rejected_content().await.iter().map(|existing_posted| existing_posted.original_shortcode.clone()).collect();
+                let existing_duplicate_shortcodes: Vec<String> = transaction.load_duplicate_content().await.iter().map(|existing_posted| existing_posted.original_shortcode.clone()).collect();
+                let existing_evicted_shortcodes: Vec<String> = transaction.load_expired_pending().await.iter().map(|expired_pending| expired_pending.original_shortcode.clone()).collect();
+                let existing_removed_shortcodes = transaction.load_removed_shortcodes().await;
+
+                match existing_content_shortcodes.iter().position(|x| x == &shortcode) {
+                    Some(_) => {
+                        self.println(&format!("{base_print} Content already scraped: {}", shortcode));
+                        self.record_skipped_posts(&mut transaction, SkipReason::AlreadyScraped, &[(&author, shortcode.as_str(), String::new())]).await;
+                    }
+                    None => {
+                        // Check if the shortcode is in the posted, failed or rejected content
+                        if existing_posted_shortcodes.contains(&shortcode) {
+                            self.println(&format!("{base_print} Content already posted: {}", shortcode));
+                            self.record_skipped_posts(&mut transaction, SkipReason::AlreadyPosted, &[(&author, shortcode.as_str(), String::new())]).await;
+                        } else if existing_failed_shortcodes.contains(&shortcode) {
+                            self.println(&format!("{base_print} Content already failed: {}", shortcode));
+                            self.record_skipped_posts(&mut transaction, SkipReason::AlreadyFailed, &[(&author, shortcode.as_str(), String::new())]).await;
+                        } else if existing_rejected_shortcodes.contains(&shortcode) {
+                            self.println(&format!("{base_print} Content already rejected: {}", shortcode));
+                            self.record_skipped_posts(&mut transaction, SkipReason::AlreadyRejected, &[(&author, shortcode.as_str(), String::new())]).await;
+                        } else if existing_duplicate_shortcodes.contains(&shortcode) {
+                            self.println(&format!("{base_print} Content already scraped (dupe): {}", shortcode));
+                            self.record_skipped_posts(&mut transaction, SkipReason::Duplicate, &[(&author, shortcode.as_str(), String::new())]).await;
+                        } else if existing_evicted_shortcodes.contains(&shortcode) {
+                            // Evicted from the backlog, it stays out even once its removal is purged
+                            self.println(&format!("{base_print} Content already evicted: {}", shortcode));
+                            self.record_skipped_posts(&mut transaction, SkipReason::Evicted, &[(&author, shortcode.as_str(), String::new())]).await;
+                        } else if existing_removed_shortcodes.contains(&shortcode) {
+                            // Removed content stays known until it's purged, so that it can still be restored
+                            self.println(&format!("{base_print} Content already removed: {}", shortcode));
+                            self.record_skipped_posts(&mut transaction, SkipReason::AlreadyRemoved, &[(&author, shortcode.as_str(), String::new())]).await;
+                        } else {
+                            let error_message = format!("{base_print} Content not found in any mapping: {}", shortcode);
+                            tracing::error!(error_message);
+                            panic!("{}", error_message);
                         }
-                    };
+                    }
+                };
+            }
+        }
+        // The receiver is done once every download has sent its outcome
+        drop(outcome_sender);
+
+        let mut actually_scraped = 0;
+        while let Some((author, shortcode, download_outcome)) = outcome_receiver.recv().await {
+            let caption = match download_outcome {
+                DownloadOutcome::Downloaded(caption) => caption,
+                DownloadOutcome::MediaNotFound => {
+                    let attempts = transaction.record_media_not_found(&shortcode, &author.profile).await;
+                    if attempts < MAX_MEDIA_NOT_FOUND_ATTEMPTS {
+                        self.println(&format!("Content not found, retrying on the next iteration ({}/{}): {}", attempts, MAX_MEDIA_NOT_FOUND_ATTEMPTS, shortcode));
+                    }
+                    *scraped_today.entry(author.profile.clone()).or_default() -= 1;
+                    continue;
                 }
-                self.randomized_sleep(SCRAPER_DOWNLOAD_SLEEP_LEN.as_secs()).await;
-            } else {
-                self.println(&format!("{base_print} Content is not a video: {}", post.shortcode));
+                DownloadOutcome::Failed => {
+                    self.scrape_journal.record_error(&format!("Couldn't download {} from {}", shortcode, author.display_name));
+                    *scraped_today.entry(author.profile.clone()).or_default() -= 1;
+                    continue;
+                }
+                DownloadOutcome::Postponed => {
+                    unhandled_shortcodes.insert(shortcode);
+                    *scraped_today.entry(author.profile.clone()).or_default() -= 1;
+                    continue;
+                }
+            };
+
+            actually_scraped += 1;
+            self.scrape_journal.record_download();
+            let base_print = format!("{actually_scraped}/{MAX_CONTENT_PER_ITERATION}");
+            let kind = if carousel_videos.contains_key(&shortcode) { "carousel video" } else { "content" };
+            self.println(&format!("{base_print} Scraped {} from {}: {}", kind, author.display_name, shortcode));
+
+            if media_retries.iter().any(|media_retry| media_retry.original_shortcode == shortcode) {
+                transaction.remove_media_retry(&shortcode).await;
+            }
+
+            let filename = format!("{}.mp4", shortcode);
+
+            // The keywords can only be looked for once the caption is known
+            if let Some(blacklist_entry) = find_blacklist_match(&blacklist, &[], &shortcode, Some(&caption)) {
+                self.println(&format!("{base_print} The caption contains the blacklisted keyword {}, skipping: {}", blacklist_entry.value, shortcode));
+                transaction.record_skipped_content(SkipReason::Blacklisted, 1).await;
+                self.scrape_journal.record_skips(SkipReason::Blacklisted, 1);
+                let _ = tokio::fs::remove_file(format!("temp/{filename}")).await;
+                continue;
+            }
+
+            let caption = process_caption(accounts_to_scrape, hashtag_mapping, &mut rng, &author, caption);
+
+            transaction.record_scraped_from_profile(&author.profile).await;
+
+            let mut source_post_metadata = post_metadata.get(&shortcode).cloned().unwrap_or_default();
+            source_post_metadata.category = accounts_to_scrape.get(&author.profile).cloned().unwrap_or_default();
+            source_post_metadata.source_url = instagram_post_url(carousel_videos.get(&shortcode).map_or(shortcode.as_str(), |carousel_video| carousel_video.carousel_shortcode.as_str()));
+            // The videos of the carousels have no audio of their own
+            if !carousel_videos.contains_key(&shortcode) {
+                self.rate_limiter.wait_for_circuit().await;
+                (source_post_metadata.audio_id, source_post_metadata.audio_name) = self.fetch_audio(&shortcode).await.unwrap_or_else(|e| {
+                    self.println(&format!("{base_print} Couldn't find the audio of {}: {}", shortcode, e));
+                    (String::new(), String::new())
+                });
+            }
+            self.hand_over_content(&mut transaction, (filename, caption, author.display_name.clone(), shortcode.clone(), source_post_metadata)).await;
+        }
+
+        if actually_scraped >= MAX_CONTENT_PER_ITERATION {
+            self.println("Reached the maximum amount of scraped content per iteration");
+            set_bot_status_operational(&mut transaction).await;
+        }
+
+        // The cursors stay where they are during a dry run, so that the same posts can be looked at again once the filters are tuned
+        if self.is_scrape_dry_run {
+            return;
+        }
+
+        // The videos of a carousel are handled along with the post they come from
+        let unhandled_posts: HashSet<&str> = unhandled_shortcodes.iter().map(|shortcode| carousel_videos.get(shortcode).map_or(shortcode.as_str(), |carousel_video| carousel_video.carousel_shortcode.as_str())).collect();
+        for (author, author_posts) in posts.iter() {
+            let Some(newest_taken_at) = author_posts.iter().map(|post| post.taken_at_timestamp as i64).max() else {
+                continue;
+            };
+            // Stop right before the oldest post left behind, so that it's fetched again at the next iteration
+            let oldest_unhandled_taken_at = author_posts.iter().filter(|post| unhandled_posts.contains(post.shortcode.as_str())).map(|post| post.taken_at_timestamp as i64).min();
+            let scrape_cursor = oldest_unhandled_taken_at.map_or(newest_taken_at, |taken_at| taken_at - 1);
+            transaction.save_scrape_cursor(&author.profile, scrape_cursor).await;
+        }
+    }
+
+    /// Counts the posts skipped for the given reason, during a dry run each of them is recorded in the scrape_audit table instead
+    ///
+    /// The skipped posts are given with their author and the detail of why they were skipped
+    async fn record_skipped_posts(&self, tx: &mut DatabaseTransaction, reason: SkipReason, skipped_posts: &[(&SourceAccount, &str, String)]) {
+        self.scrape_journal.record_skips(reason, skipped_posts.len());
+        if !self.is_scrape_dry_run {
+            tx.record_skipped_content(reason, skipped_posts.len() as i32).await;
+            return;
+        }
+
+        for (author, shortcode, detail) in skipped_posts {
+            self.record_scrape_decision(tx, author, shortcode, reason.as_str(), detail).await;
+        }
+    }
+
+    async fn record_scrape_decision(&self, tx: &mut DatabaseTransaction, author: &SourceAccount, shortcode: &str, decision: &str, detail: &str) {
+        let user_settings = tx.load_user_settings().await;
+        let scrape_audit_entry = ScrapeAuditEntry {
+            username: self.username.clone(),
+            original_shortcode: shortcode.to_string(),
+            profile: author.profile.clone(),
+            decision: decision.to_string(),
+            detail: detail.to_string(),
+            audited_at: now_in_my_timezone(&user_settings).to_rfc3339(),
+        };
+        tx.save_scrape_audit_entry(&scrape_audit_entry).await;
+    }
+
+    /// Looks up the videos of the carousels among the posts, through the business discovery of the Graph API
+    async fn fetch_carousel_videos(&self, posts: &[(SourceAccount, Post)]) -> Vec<(SourceAccount, CarouselVideo)> {
+        let mut carousels: HashMap<&SourceAccount, HashSet<&String>> = HashMap::new();
+        for (author, post) in posts.iter().filter(|(_, post)| !post.is_video) {
+            carousels.entry(author).or_default().insert(&post.shortcode);
+        }
+
+        let user_id = self.credentials.get("instagram_business_account_id").unwrap();
+        let access_token = self.credentials.get("fb_access_token").unwrap();
+
+        let mut videos = Vec::new();
+        for (author, shortcodes) in carousels {
+            match carousel_videos(user_id, access_token, &author.display_name, CAROUSEL_LOOKUP_LIMIT).await {
+                Ok(carousel_videos) => videos.extend(carousel_videos.into_iter().filter(|carousel_video| shortcodes.contains(&carousel_video.carousel_shortcode)).map(|carousel_video| (author.clone(), carousel_video))),
+                // Only business and creator accounts can be looked up, the carousels of the others are skipped
+                Err(e) => self.println(&format!("Couldn't look up the carousels of {}: {}", author.display_name, e)),
             }
         }
+        videos
     }
 
     /// Randomized sleep function, will randomize the sleep duration by up to 30% of the original duration
-    async fn randomized_sleep(&mut self, original_duration: u64) {
+    pub(crate) async fn randomized_sleep(&mut self, original_duration: u64) {
         let span = tracing::span!(tracing::Level::INFO, "randomized_sleep");
         let mut rng = StdRng::from_rng(OsRng).unwrap();
         let variance: u64 = rng.gen_range(0..=1); // generates a number between 0 and 1
@@ -562,17 +1188,132 @@ impl ContentManager {
         sleep(Duration::from_secs(sleep_duration)).await;
     }
 
+    /// Counts a request to Instagram and waits while the hourly budget shared with the poster is exhausted
+    ///
+    /// Only enforced when Redis is configured, since the budget needs to be shared between processes
+    pub(crate) async fn wait_for_instagram_budget(&self) {
+        let window = Duration::from_secs(60 * 60);
+        let mut is_waiting = false;
+        while !self.coordinator.try_spend("instagram_requests", MAX_INSTAGRAM_REQUESTS_PER_HOUR, window).await {
+            if !is_waiting {
+                self.println("Reached the hourly Instagram request budget, waiting...");
+                is_waiting = true;
+            }
+            sleep(FETCH_SLEEP_LEN).await;
+        }
+    }
+
     pub(crate) fn println(&self, message: &str) {
         println!(" [{}] - {}", self.username, message);
     }
 }
 
-async fn read_accounts_to_scrape(path: &str, username: &str) -> HashMap<String, String> {
+/// How often the posts of a source account are picked, how much of its content can be scraped per day and how far back its posts are fetched
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrapeQuota {
+    pub weight: f64,
+    pub max_posts_per_day: Option<i64>,
+    pub posts_per_fetch: usize,
+    /// Used instead of posts_per_fetch the first time the account is scraped, before it has a scrape cursor
+    pub backfill_posts: usize,
+}
+
+impl Default for ScrapeQuota {
+    fn default() -> Self {
+        ScrapeQuota {
+            weight: 1.0,
+            max_posts_per_day: None,
+            posts_per_fetch: DEFAULT_POSTS_PER_FETCH,
+            backfill_posts: DEFAULT_BACKFILL_POSTS,
+        }
+    }
+}
+
+impl ScrapeQuota {
+    /// A new source account is paged much further back, afterwards only its latest posts are looked at
+    fn posts_to_fetch(&self, has_scrape_cursor: bool) -> usize {
+        if has_scrape_cursor {
+            self.posts_per_fetch
+        } else {
+            self.backfill_posts.max(self.posts_per_fetch)
+        }
+    }
+}
+
+/// An entry of accounts_to_scrape.yaml, either just the category of hashtags.yaml or the category along with the quota
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum AccountToScrape {
+    Category(String),
+    WithQuota {
+        hashtags: String,
+        #[serde(default = "default_scrape_weight")]
+        weight: f64,
+        max_posts_per_day: Option<i64>,
+        #[serde(default = "default_posts_per_fetch")]
+        posts_per_fetch: usize,
+        #[serde(default = "default_backfill_posts")]
+        backfill_posts: usize,
+    },
+}
+
+/// Keeps the posts published after the cursor of the account, the ones before it were already handled
+///
+/// The scraper still fetches the first page of the account, but the older posts aren't checked one by one against the database anymore
+fn posts_newer_than_cursor(posts: Vec<Post>, scrape_cursor: Option<&i64>) -> Vec<Post> {
+    match scrape_cursor {
+        Some(scrape_cursor) => posts.into_iter().filter(|post| post.taken_at_timestamp as i64 > *scrape_cursor).collect(),
+        None => posts,
+    }
+}
+
+fn default_scrape_weight() -> f64 {
+    ScrapeQuota::default().weight
+}
+
+fn default_posts_per_fetch() -> usize {
+    ScrapeQuota::default().posts_per_fetch
+}
+
+fn default_backfill_posts() -> usize {
+    ScrapeQuota::default().backfill_posts
+}
+
+/// Returns the category of hashtags of each account to scrape, and the quotas of the accounts that have one
+async fn read_accounts_to_scrape(path: &str, username: &str) -> (HashMap<String, String>, HashMap<String, ScrapeQuota>) {
     let mut file = File::open(path).await.expect("Unable to open credentials file");
     let mut contents = String::new();
     file.read_to_string(&mut contents).await.expect("Unable to read the credentials file");
-    let accounts: HashMap<String, HashMap<String, String>> = serde_yaml::from_str(&contents).expect("Error parsing credentials file");
-    accounts.get(username).unwrap().clone()
+    let mut accounts: HashMap<String, HashMap<String, AccountToScrape>> = serde_yaml::from_str(&contents).expect("Error parsing credentials file");
+
+    let mut categories = HashMap::new();
+    let mut scrape_quotas = HashMap::new();
+    for (profile, account_to_scrape) in accounts.remove(username).unwrap() {
+        match account_to_scrape {
+            AccountToScrape::Category(hashtags) => {
+                categories.insert(profile, hashtags);
+            }
+            AccountToScrape::WithQuota {
+                hashtags,
+                weight,
+                max_posts_per_day,
+                posts_per_fetch,
+                backfill_posts,
+            } => {
+                scrape_quotas.insert(
+                    profile.clone(),
+                    ScrapeQuota {
+                        weight,
+                        max_posts_per_day,
+                        posts_per_fetch,
+                        backfill_posts,
+                    },
+                );
+                categories.insert(profile, hashtags);
+            }
+        }
+    }
+    (categories, scrape_quotas)
 }
 
 async fn read_hashtag_mapping(path: &str) -> HashMap<String, String> {
@@ -0,0 +1,107 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use instagram_scraper_rs::{InstagramScraper, InstagramScraperError};
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::database::database::DatabaseTransaction;
+use crate::discord::utils::is_in_scrape_window;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::utils::{jittered_duration, save_cookie_store_to_json, set_bot_status_halted};
+use crate::{FETCH_SLEEP_LEN, MAX_SESSION_FAILURES, SESSION_CHECK_INTERVAL};
+
+/// What Instagram answers with once the session isn't valid anymore, logging in again is only worth the risk then
+const SESSION_ERROR_MARKERS: [&str; 5] = ["login_required", "checkpoint_required", "401 unauthorized", "unauthenticated", "authentication failed"];
+
+impl ContentManager {
+    /// Periodically checks that the session of the scraper is still valid, by fetching the profile of the account itself
+    ///
+    /// An invalid session is recovered by logging in again, before the scraper runs into it
+    pub fn session_health_loop(&self) -> JoinHandle<anyhow::Result<()>> {
+        let cloned_self = self.clone();
+        tokio::spawn(async move {
+            let own_username = cloned_self.credentials.get("username").unwrap().clone();
+            loop {
+                sleep(jittered_duration(SESSION_CHECK_INTERVAL)).await;
+
+                // While halted or in manual mode the session is left alone, the user is already on it
+                let mut tx = cloned_self.database.begin_transaction().await;
+                let bot_status = tx.load_bot_status().await;
+                if bot_status.status != 0 || bot_status.manual_mode {
+                    continue;
+                }
+                // Outside the scrape window or while the scraping is paused the account isn't supposed to be active at all
+                let user_settings = tx.load_user_settings().await;
+                if !user_settings.scraping_enabled || !is_in_scrape_window(&user_settings) {
+                    continue;
+                }
+
+                cloned_self.rate_limiter.wait_for_circuit().await;
+                cloned_self.wait_for_instagram_budget().await;
+                let mut scraper_guard = cloned_self.scraper.lock().await;
+                match scraper_guard.scrape_userinfo(&own_username).await {
+                    Ok(_) => cloned_self.session_failures.store(0, Ordering::SeqCst),
+                    Err(e) if is_session_error(&e) => {
+                        cloned_self.println(&format!("Session check failed: {}", e));
+                        cloned_self.recover_session(&mut scraper_guard, &mut tx).await;
+                    }
+                    // Logging in again after a rate limit or a network error would only look more suspicious
+                    Err(InstagramScraperError::RateLimitExceeded { .. }) => {
+                        drop(scraper_guard);
+                        cloned_self.handle_rate_limited(&mut tx).await;
+                    }
+                    Err(e) => {
+                        drop(scraper_guard);
+                        cloned_self.println(&format!("Session check failed, keeping the session: {}", e));
+                        cloned_self.rate_limiter.pace(FETCH_SLEEP_LEN).await;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Logs in again after a request failed, returns whether the session was recovered
+    ///
+    /// The bot is only halted once logging in again failed MAX_SESSION_FAILURES times in a row
+    pub(crate) async fn recover_session(&self, scraper: &mut InstagramScraper, tx: &mut DatabaseTransaction) -> bool {
+        let username = self.credentials.get("username").unwrap().clone();
+        let password = self.credentials.get("password").unwrap().clone();
+
+        self.println("The session looks invalid, logging in again...");
+        scraper.authenticate_with_login(username, password);
+        match scraper.login().await {
+            Ok(_) => {
+                self.println("Logged in successfully");
+                self.session_failures.store(0, Ordering::SeqCst);
+                save_cookie_store_to_json(&self.cookie_store_path, Arc::clone(&scraper.session.cookie_store)).await;
+                true
+            }
+            Err(e) if self.resolve_login_challenge(scraper, &e.to_string()).await => {
+                self.session_failures.store(0, Ordering::SeqCst);
+                true
+            }
+            Err(e) => {
+                let session_failures = self.session_failures.fetch_add(1, Ordering::SeqCst) + 1;
+                self.println(&format!("Login failed ({}/{}): {}", session_failures, MAX_SESSION_FAILURES, e));
+                if session_failures >= MAX_SESSION_FAILURES {
+                    set_bot_status_halted(tx).await;
+                }
+                false
+            }
+        }
+    }
+
+    pub(crate) fn has_too_many_session_failures(&self) -> bool {
+        self.session_failures.load(Ordering::SeqCst) >= MAX_SESSION_FAILURES
+    }
+}
+
+/// Whether the error means that the session itself was turned down, rather than the request
+fn is_session_error(e: &InstagramScraperError) -> bool {
+    if matches!(e, InstagramScraperError::RateLimitExceeded { .. }) {
+        return false;
+    }
+    let e = e.to_string().to_lowercase();
+    SESSION_ERROR_MARKERS.iter().any(|marker| e.contains(marker))
+}
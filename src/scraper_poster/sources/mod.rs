@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Deserialize;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+use crate::database::database::SourceAccount;
+use crate::scraper_poster::metadata::SourcePostMetadata;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::sources::reddit::Subreddit;
+use crate::scraper_poster::sources::tiktok::TikTokAccount;
+use crate::scraper_poster::sources::youtube::YouTubeChannel;
+use crate::scraper_poster::utils::{pause_scraper_if_needed, process_caption};
+
+pub(crate) mod reddit;
+pub(crate) mod tiktok;
+pub(crate) mod youtube;
+pub(crate) mod yt_dlp;
+
+const CONTENT_SOURCES_PATH: &str = "config/content_sources.yaml";
+
+/// A video found by a content source, with what the scraper needs to hand it over to the sender loop
+#[derive(Debug, Clone)]
+pub struct SourcedVideo {
+    /// Prefixed with the source, so that it can't collide with the Instagram shortcodes, e.g. "reddit_1c2d3e4"
+    pub shortcode: String,
+    pub author: String,
+    pub caption: String,
+    pub url: String,
+}
+
+/// A place other than Instagram the content can be pulled from
+///
+/// The videos go through the same pipeline as the scraped reels, so they are checked for duplicates by the video hashing as well
+#[async_trait]
+pub trait ContentSource: Send + Sync {
+    /// The profile the content is credited to in the logs and in accounts_to_scrape, e.g. "r/catvideos"
+    fn name(&self) -> String;
+
+    /// The category of hashtags.yaml used when the caption has none, like in accounts_to_scrape.yaml
+    fn hashtags(&self) -> String;
+
+    /// Returns the latest videos of the source, up to its cap
+    async fn fetch_videos(&self) -> anyhow::Result<Vec<SourcedVideo>>;
+
+    /// Downloads the video into the temp folder, where the sender loop expects it
+    ///
+    /// Returns the processing steps applied to the video after the download, recorded in its provenance
+    async fn download_video(&self, video: &SourcedVideo, filename: &str) -> anyhow::Result<Vec<String>>;
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ContentSourcesConfig {
+    #[serde(default)]
+    reddit: Vec<Subreddit>,
+    #[serde(default)]
+    tiktok: Vec<TikTokAccount>,
+    #[serde(default)]
+    youtube: Vec<YouTubeChannel>,
+}
+
+/// Reads the content sources configured for the account, the file is optional
+pub async fn read_content_sources(username: &str) -> Vec<Box<dyn ContentSource>> {
+    let mut file = match File::open(CONTENT_SOURCES_PATH).await {
+        Ok(file) => file,
+        Err(_) => return vec![],
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.expect("Unable to read the content sources file");
+    let mut content_sources: HashMap<String, ContentSourcesConfig> = serde_yaml::from_str(&contents).expect("Error parsing the content sources file");
+    let config = content_sources.remove(username).unwrap_or_default();
+
+    let mut sources: Vec<Box<dyn ContentSource>> = Vec::new();
+    sources.extend(config.reddit.into_iter().map(|subreddit| Box::new(subreddit) as Box<dyn ContentSource>));
+    sources.extend(config.tiktok.into_iter().map(|tiktok_account| Box::new(tiktok_account) as Box<dyn ContentSource>));
+    sources.extend(config.youtube.into_iter().map(|youtube_channel| Box::new(youtube_channel) as Box<dyn ContentSource>));
+    sources
+}
+
+impl ContentManager {
+    /// Scrapes the latest videos of the other content sources and hands them over to the sender loop one at a time
+    ///
+    /// The other content sources aren't as strict as Instagram, each video only waits for the sender loop to pick up the previous one
+    pub(crate) async fn scrape_sources(&mut self, content_sources: &[Box<dyn ContentSource>], accounts_to_scrape: &HashMap<String, String>, hashtag_mapping: &HashMap<String, String>) {
+        if content_sources.is_empty() {
+            return;
+        }
+
+        let mut tx = self.database.begin_transaction().await;
+        let mut rng = StdRng::from_entropy();
+
+        for content_source in content_sources {
+            pause_scraper_if_needed(&mut tx).await;
+
+            self.println(&format!("Fetching videos from {}...", content_source.name()));
+            let videos = match content_source.fetch_videos().await {
+                Ok(videos) => videos,
+                Err(e) => {
+                    self.println(&format!("Error fetching videos from {}: {}", content_source.name(), e));
+                    self.scrape_journal.record_error(&format!("Couldn't fetch the videos from {}: {}", content_source.name(), e));
+                    continue;
+                }
+            };
+            self.scrape_journal.record_posts_seen(videos.len());
+
+            for video in videos {
+                if tx.does_content_exist_with_shortcode(&video.shortcode).await {
+                    continue;
+                }
+
+                pause_scraper_if_needed(&mut tx).await;
+
+                let filename = format!("{}.mp4", video.shortcode);
+                let processing_steps = match content_source.download_video(&video, &filename).await {
+                    Ok(processing_steps) => processing_steps,
+                    Err(e) => {
+                        self.println(&format!("Error while downloading {} from {} | {}", video.shortcode, content_source.name(), e));
+                        self.scrape_journal.record_error(&format!("Couldn't download {} from {}: {}", video.shortcode, content_source.name(), e));
+                        continue;
+                    }
+                };
+                self.scrape_journal.record_download();
+                self.println(&format!("Scraped content from {} on {}: {}", video.author, content_source.name(), video.shortcode));
+
+                let author = SourceAccount {
+                    username: self.username.clone(),
+                    profile: content_source.name(),
+                    user_id: "".to_string(),
+                    display_name: video.author.clone(),
+                    updated_at: Utc::now().to_rfc3339(),
+                };
+                let caption = process_caption(accounts_to_scrape, hashtag_mapping, &mut rng, &author, video.caption.clone());
+
+                let source_post_metadata = SourcePostMetadata {
+                    category: content_source.hashtags(),
+                    source_url: video.url.clone(),
+                    processing_steps,
+                    ..Default::default()
+                };
+                self.hand_over_content(&mut tx, (filename, caption, video.author.clone(), video.shortcode.clone(), source_post_metadata)).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use async_trait::async_trait;
+use reqwest::header::USER_AGENT;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::scraper_poster::download_manager::download_media;
+use crate::scraper_poster::sources::{ContentSource, SourcedVideo};
+use crate::video::processing::{merge_audio, MERGE_AUDIO_STEP};
+
+const REDDIT_URL: &str = "https://www.reddit.com";
+/// Reddit throttles the requests that don't identify themselves
+const REDDIT_USER_AGENT: &str = "repost_rusty/0.1";
+const REDDIT_PAGE_SIZE: &str = "50";
+/// Reddit serves the audio separately from the video, older posts use the last name
+const REDDIT_AUDIO_TRACKS: [&str; 3] = ["DASH_AUDIO_128.mp4", "DASH_AUDIO_64.mp4", "DASH_audio.mp4"];
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubredditSort {
+    #[default]
+    Hot,
+    New,
+    Top,
+}
+
+impl SubredditSort {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SubredditSort::Hot => "hot",
+            SubredditSort::New => "new",
+            SubredditSort::Top => "top",
+        }
+    }
+}
+
+/// A subreddit whose videos are scraped through the public JSON API
+#[derive(Debug, Clone, Deserialize)]
+pub struct Subreddit {
+    pub subreddit: String,
+    #[serde(default)]
+    pub sort: SubredditSort,
+    /// How many videos are taken from the subreddit at each iteration
+    pub max_posts: usize,
+    pub hashtags: String,
+}
+
+#[async_trait]
+impl ContentSource for Subreddit {
+    fn name(&self) -> String {
+        format!("r/{}", self.subreddit)
+    }
+
+    fn hashtags(&self) -> String {
+        self.hashtags.clone()
+    }
+
+    async fn fetch_videos(&self) -> anyhow::Result<Vec<SourcedVideo>> {
+        let url = format!("{REDDIT_URL}/r/{}/{}.json", self.subreddit, self.sort.as_str());
+        let body = reqwest::Client::new().get(url).header(USER_AGENT, REDDIT_USER_AGENT).query(&[("limit", REDDIT_PAGE_SIZE)]).send().await?.error_for_status()?.text().await?;
+        let response: Value = serde_json::from_str(&body)?;
+
+        let videos = response["data"]["children"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|post| &post["data"])
+            .filter(|post| post["is_video"].as_bool().unwrap_or(false) && !post["over_18"].as_bool().unwrap_or(false) && !post["stickied"].as_bool().unwrap_or(false))
+            .filter_map(|post| {
+                Some(SourcedVideo {
+                    shortcode: format!("reddit_{}", post["id"].as_str()?),
+                    author: format!("u/{}", post["author"].as_str()?),
+                    caption: post["title"].as_str().unwrap_or_default().to_string(),
+                    url: post["media"]["reddit_video"]["fallback_url"].as_str()?.to_string(),
+                })
+            })
+            .take(self.max_posts)
+            .collect();
+
+        Ok(videos)
+    }
+
+    async fn download_video(&self, video: &SourcedVideo, filename: &str) -> anyhow::Result<Vec<String>> {
+        download_media(&video.url, filename).await?;
+
+        // e.g. https://v.redd.it/a1b2c3/DASH_720.mp4?source=fallback
+        let base_url = match video.url.split('?').next().and_then(|url| url.rsplit_once('/')) {
+            Some((base_url, _)) => base_url,
+            None => return Ok(vec![]),
+        };
+
+        let audio_filename = filename.replace(".mp4", "_audio.mp4");
+        for audio_track in REDDIT_AUDIO_TRACKS {
+            if download_media(&format!("{base_url}/{audio_track}"), &audio_filename).await.is_ok() {
+                let merged = merge_audio(filename, &audio_filename);
+                let _ = tokio::fs::remove_file(format!("temp/{audio_filename}")).await;
+                merged?;
+                return Ok(vec![MERGE_AUDIO_STEP.to_string()]);
+            }
+        }
+
+        // The video has no sound
+        Ok(vec![])
+    }
+}
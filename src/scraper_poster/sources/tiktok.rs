@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::scraper_poster::sources::yt_dlp::{download, fetch_playlist};
+use crate::scraper_poster::sources::{ContentSource, SourcedVideo};
+use crate::video::processing::{reencode, REENCODE_STEP};
+
+const TIKTOK_URL: &str = "https://www.tiktok.com";
+
+/// A TikTok account whose videos are scraped through yt-dlp
+#[derive(Debug, Clone, Deserialize)]
+pub struct TikTokAccount {
+    pub account: String,
+    /// How many videos are taken from the account at each iteration
+    pub max_posts: usize,
+    pub hashtags: String,
+}
+
+impl TikTokAccount {
+    fn account(&self) -> &str {
+        self.account.trim_start_matches('@')
+    }
+}
+
+#[async_trait]
+impl ContentSource for TikTokAccount {
+    fn name(&self) -> String {
+        format!("tiktok/@{}", self.account())
+    }
+
+    fn hashtags(&self) -> String {
+        self.hashtags.clone()
+    }
+
+    async fn fetch_videos(&self) -> anyhow::Result<Vec<SourcedVideo>> {
+        let entries = fetch_playlist(&format!("{TIKTOK_URL}/@{}", self.account()), self.max_posts)?;
+
+        let videos = entries
+            .iter()
+            .filter_map(|video| {
+                let id = video["id"].as_str()?;
+                let caption = video["description"].as_str().or(video["title"].as_str()).unwrap_or_default();
+                Some(SourcedVideo {
+                    shortcode: format!("tiktok_{id}"),
+                    author: format!("@{}", self.account()),
+                    caption: caption.to_string(),
+                    url: video["url"].as_str().map(str::to_string).unwrap_or_else(|| format!("{TIKTOK_URL}/@{}/video/{id}", self.account())),
+                })
+            })
+            .take(self.max_posts)
+            .collect();
+
+        Ok(videos)
+    }
+
+    async fn download_video(&self, video: &SourcedVideo, filename: &str) -> anyhow::Result<Vec<String>> {
+        download(&video.url, filename)?;
+
+        // TikTok often serves HEVC, which Instagram doesn't always accept
+        reencode(filename)?;
+        Ok(vec![REENCODE_STEP.to_string()])
+    }
+}
@@ -0,0 +1,82 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::scraper_poster::sources::yt_dlp::{download, fetch_playlist};
+use crate::scraper_poster::sources::{ContentSource, SourcedVideo};
+use crate::video::processing::{reencode, REENCODE_STEP};
+
+const YOUTUBE_URL: &str = "https://www.youtube.com";
+
+/// A YouTube channel whose shorts are scraped through yt-dlp
+#[derive(Debug, Clone, Deserialize)]
+pub struct YouTubeChannel {
+    /// Either the handle, with or without the "@", or the id of the channel, e.g. "UCX6OQ3DkcsbYNE6H8uQQuVA"
+    pub channel: String,
+    /// How many shorts are taken from the channel at each iteration
+    pub max_posts: usize,
+    pub hashtags: String,
+}
+
+impl YouTubeChannel {
+    fn is_channel_id(&self) -> bool {
+        self.channel.starts_with("UC") && self.channel.len() == 24
+    }
+
+    fn channel(&self) -> String {
+        if self.is_channel_id() {
+            self.channel.clone()
+        } else {
+            format!("@{}", self.channel.trim_start_matches('@'))
+        }
+    }
+
+    /// The shorts tab of the channel, the regular videos aren't in the right format for a reel
+    fn shorts_url(&self) -> String {
+        if self.is_channel_id() {
+            format!("{YOUTUBE_URL}/channel/{}/shorts", self.channel)
+        } else {
+            format!("{YOUTUBE_URL}/{}/shorts", self.channel())
+        }
+    }
+}
+
+#[async_trait]
+impl ContentSource for YouTubeChannel {
+    fn name(&self) -> String {
+        format!("youtube/{}", self.channel())
+    }
+
+    fn hashtags(&self) -> String {
+        self.hashtags.clone()
+    }
+
+    async fn fetch_videos(&self) -> anyhow::Result<Vec<SourcedVideo>> {
+        let entries = fetch_playlist(&self.shorts_url(), self.max_posts)?;
+
+        let videos = entries
+            .iter()
+            .filter_map(|short| {
+                let id = short["id"].as_str()?;
+                // The flat playlist only has the title of the shorts, which is what is shown on them anyway
+                let caption = short["title"].as_str().or(short["description"].as_str()).unwrap_or_default();
+                Some(SourcedVideo {
+                    shortcode: format!("youtube_{id}"),
+                    author: short["channel"].as_str().map(str::to_string).unwrap_or_else(|| self.channel()),
+                    caption: caption.to_string(),
+                    url: format!("{YOUTUBE_URL}/shorts/{id}"),
+                })
+            })
+            .take(self.max_posts)
+            .collect();
+
+        Ok(videos)
+    }
+
+    async fn download_video(&self, video: &SourcedVideo, filename: &str) -> anyhow::Result<Vec<String>> {
+        download(&video.url, filename)?;
+
+        // YouTube serves VP9 or AV1 for the best streams, which Instagram doesn't accept
+        reencode(filename)?;
+        Ok(vec![REENCODE_STEP.to_string()])
+    }
+}
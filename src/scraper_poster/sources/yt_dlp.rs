@@ -0,0 +1,42 @@
+use std::process::{Command, Stdio};
+
+use serde_json::Value;
+
+/// TikTok and YouTube have no public API for the videos of an account that would be worth the hassle, yt-dlp keeps up with their websites instead
+const YT_DLP: &str = "yt-dlp";
+
+/// Returns the entries of a playlist-like page, e.g. the videos of an account, without downloading them
+pub(crate) fn fetch_playlist(url: &str, max_posts: usize) -> anyhow::Result<Vec<Value>> {
+    let output = Command::new(YT_DLP).arg("--flat-playlist").arg("--dump-single-json").arg("--playlist-end").arg(max_posts.to_string()).arg(url).stdout(Stdio::piped()).stderr(Stdio::piped()).output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("yt-dlp failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let mut playlist: Value = serde_json::from_slice(&output.stdout)?;
+
+    match playlist["entries"].take() {
+        Value::Array(entries) => Ok(entries),
+        _ => Ok(vec![]),
+    }
+}
+
+/// Downloads a video into the temp folder as an mp4, the best video and audio streams are merged when they come apart
+pub(crate) fn download(url: &str, filename: &str) -> anyhow::Result<()> {
+    let output = Command::new(YT_DLP)
+        .arg("--format")
+        .arg("bv*+ba/b")
+        .arg("--merge-output-format")
+        .arg("mp4")
+        .arg("--force-overwrites")
+        .arg("--output")
+        .arg(format!("temp/{filename}"))
+        .arg(url)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()?;
+
+    if !output.status.success() {
+        anyhow::bail!("yt-dlp failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
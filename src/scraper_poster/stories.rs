@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use serde_json::Value;
+use serenity::all::MessageId;
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+
+use crate::database::database::{DatabaseTransaction, QueuedStory, SourceAccount, UserSettings};
+use crate::discord::utils::now_in_my_timezone;
+use crate::s3::helper::upload_to_s3;
+use crate::scraper_poster::formatting::{format_caption, Destination};
+use crate::scraper_poster::graph_api::{upload_story, GraphApiError};
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::download_manager::download_media;
+use crate::scraper_poster::utils::pause_scraper_if_needed;
+use crate::{FETCH_SLEEP_LEN, STORY_LIFETIME};
+
+const STORIES_TO_SCRAPE_PATH: &str = "config/stories_to_scrape.yaml";
+/// The library doesn't scrape stories, they are fetched from the private API with the cookies of the scraper session
+const REELS_MEDIA_URL: &str = "https://i.instagram.com/api/v1/feed/reels_media/";
+/// Id of the Instagram web app, the private API turns down the requests without one
+pub(crate) const INSTAGRAM_APP_ID: &str = "936619743392459";
+const VIDEO_MEDIA_TYPE: i64 = 2;
+
+/// A video currently on the story of a source account
+#[derive(Debug, Clone)]
+struct SourceStory {
+    story_id: String,
+    video_url: String,
+    expires_at: DateTime<Utc>,
+}
+
+/// Reads the accounts whose stories are scraped for the given account, the file is optional
+pub async fn read_stories_to_scrape(username: &str) -> Vec<String> {
+    let mut file = match File::open(STORIES_TO_SCRAPE_PATH).await {
+        Ok(file) => file,
+        Err(_) => return vec![],
+    };
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).await.expect("Unable to read the stories to scrape file");
+    let mut stories_to_scrape: HashMap<String, Vec<String>> = serde_yaml::from_str(&contents).expect("Error parsing the stories to scrape file");
+    stories_to_scrape.remove(username).unwrap_or_default()
+}
+
+impl ContentManager {
+    /// Downloads the video stories of the accounts, they wait in the story queue until they are reviewed or they expire
+    ///
+    /// Only the accounts of accounts_to_scrape.yaml can be looked at, since their ids are needed
+    pub(crate) async fn scrape_stories(&mut self, stories_to_scrape: &[String], accounts_being_scraped: &[SourceAccount]) {
+        if stories_to_scrape.is_empty() {
+            return;
+        }
+
+        let mut tx = self.database.begin_transaction().await;
+        self.println("Fetching stories...");
+        for source_account in accounts_being_scraped.iter().filter(|source_account| stories_to_scrape.contains(&source_account.profile)) {
+            pause_scraper_if_needed(&mut tx).await;
+            self.wait_for_instagram_budget().await;
+
+            let stories = match self.fetch_stories(&source_account.user_id).await {
+                Ok(stories) => stories,
+                Err(e) => {
+                    self.println(&format!("Error fetching the stories of {}: {}", source_account.display_name, e));
+                    continue;
+                }
+            };
+
+            for story in stories {
+                if tx.does_story_exist(&story.story_id).await {
+                    continue;
+                }
+
+                let filename = format!("story_{}.mp4", story.story_id);
+                if let Err(e) = download_media(&story.video_url, &filename).await {
+                    self.println(&format!("Error while downloading story {} | {}", story.story_id, e));
+                    continue;
+                }
+
+                let url = match upload_to_s3(&self.bucket, filename.clone(), format!("{}/{}", self.username, filename), true).await {
+                    Ok(url) => url,
+                    Err(e) => {
+                        self.println(&format!("Error while uploading story {} | {}", story.story_id, e));
+                        continue;
+                    }
+                };
+
+                // The times of the database are in the timezone of the user
+                let now = now_in_my_timezone(&tx.load_user_settings().await);
+                let queued_story = QueuedStory {
+                    username: self.username.clone(),
+                    story_id: story.story_id.clone(),
+                    original_author: source_account.display_name.clone(),
+                    url,
+                    status: "pending".to_string(),
+                    message_id: MessageId::new(1),
+                    expires_at: (now + (story.expires_at - Utc::now())).to_rfc3339(),
+                    added_at: now.to_rfc3339(),
+                };
+                tx.save_story(&queued_story).await;
+                self.println(&format!("Scraped story from {}: {}", source_account.display_name, story.story_id));
+            }
+
+            self.rate_limiter.pace(FETCH_SLEEP_LEN).await;
+        }
+    }
+
+    /// Returns the video stories of the account, through the proxy of the scraper if there's one
+    async fn fetch_stories(&self, user_id: &str) -> anyhow::Result<Vec<SourceStory>> {
+        let response = self.private_api_client().await?.get(REELS_MEDIA_URL).query(&[("reel_ids", user_id)]).header("X-IG-App-ID", INSTAGRAM_APP_ID).send().await?.error_for_status()?.text().await?;
+        let response: Value = serde_json::from_str(&response)?;
+
+        let stories = response["reels"][user_id]["items"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|item| item["media_type"].as_i64() == Some(VIDEO_MEDIA_TYPE))
+            .filter_map(|item| {
+                let expires_at = match item["expiring_at"].as_i64() {
+                    Some(expiring_at) => DateTime::from_timestamp(expiring_at, 0)?,
+                    None => DateTime::from_timestamp(item["taken_at"].as_i64()?, 0)? + ChronoDuration::seconds(STORY_LIFETIME.as_secs() as i64),
+                };
+                Some(SourceStory {
+                    story_id: item["id"].as_str()?.to_string(),
+                    video_url: item["video_versions"][0]["url"].as_str()?.to_string(),
+                    expires_at,
+                })
+            })
+            .collect();
+
+        Ok(stories)
+    }
+
+    /// Reshares the approved stories right away, they would expire before their turn in the queue
+    pub(crate) async fn publish_approved_stories(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction) {
+        if !user_settings.can_post {
+            return;
+        }
+
+        for mut story in tx.load_story_queue().await.into_iter().filter(|story| story.status == "approved") {
+            if user_settings.dry_run {
+                self.println(&format!("[dry run] Would reshare the story {} ({})", story.story_id, story.url));
+            } else if !self.is_offline {
+                let user_id = self.credentials.get("instagram_business_account_id").unwrap();
+                let access_token = self.credentials.get("fb_access_token").unwrap();
+
+                // The stories of the other accounts come without a caption, only their author is credited
+                let caption = format_caption(&format!("via @{}", story.original_author), "", Destination::Story);
+
+                self.coordinator.increment_counter("instagram_requests", Duration::from_secs(60 * 60)).await;
+                match upload_story(user_id, access_token, &story.url, &caption).await {
+                    Ok(_) | Err(GraphApiError::UploadSucceededButFailedToRetrieveId(_)) => self.println(&format!("Reshared the story {} on the story of the account", story.story_id)),
+                    // Tried again at the next iteration of the poster
+                    Err(GraphApiError::UploadFailedRecoverable(e)) => {
+                        self.println(&format!("Error while resharing the story {}, retrying later: {}", story.story_id, e));
+                        continue;
+                    }
+                    Err(e) => {
+                        self.println(&format!("Error while resharing the story {}: {}", story.story_id, e));
+                        story.status = "failed".to_string();
+                        tx.save_story(&story).await;
+                        continue;
+                    }
+                }
+            } else {
+                self.println(&format!("[!] Reshared story offline: {}", story.url));
+            }
+
+            story.status = "published".to_string();
+            tx.save_story(&story).await;
+        }
+    }
+}
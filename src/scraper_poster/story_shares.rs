@@ -0,0 +1,77 @@
+use std::time::Duration;
+
+use crate::database::database::{DatabaseTransaction, QueuedContent, StoryShare, UserSettings};
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::formatting::{format_caption, Destination};
+use crate::scraper_poster::graph_api::{upload_story, GraphApiError};
+use crate::scraper_poster::scraper::ContentManager;
+
+impl ContentManager {
+    /// Schedules the reel to be shared on the story of the account, once it had some time to get views on its own
+    pub(crate) async fn schedule_story_share(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction, queued_post: &QueuedContent, media_id: &str) {
+        let share_at = now_in_my_timezone(user_settings) + user_settings.story_share_delay();
+        let story_share = StoryShare {
+            username: queued_post.username.clone(),
+            original_shortcode: queued_post.original_shortcode.clone(),
+            media_id: media_id.to_string(),
+            url: queued_post.url.clone(),
+            status: "scheduled".to_string(),
+            story_id: "".to_string(),
+            error: "".to_string(),
+            share_at: share_at.to_rfc3339(),
+            attempted_at: "".to_string(),
+        };
+        tx.save_story_share(&story_share).await;
+        self.println(&format!("Sharing {} on the story at {}", queued_post.original_shortcode, share_at.format("%H:%M")));
+    }
+
+    /// Shares the reels whose delay is over on the story of the account, the scheduled ones are still shared if the setting was turned off since
+    pub(crate) async fn share_due_reels_on_story(&self, user_settings: &UserSettings, tx: &mut DatabaseTransaction) {
+        if !user_settings.can_post {
+            return;
+        }
+
+        let now = now_in_my_timezone(user_settings);
+        for mut story_share in tx.load_due_story_shares(&now.to_rfc3339()).await {
+            if self.is_offline {
+                self.println(&format!("[!] Shared reel on the story offline: {}", story_share.url));
+                story_share.status = "published".to_string();
+            } else {
+                let user_id = self.credentials.get("instagram_business_account_id").unwrap();
+                let access_token = self.credentials.get("fb_access_token").unwrap();
+
+                // The same caption variant as the reel, cut down for the story
+                let caption = match tx.get_published_content_by_shortcode(&story_share.original_shortcode).await {
+                    Some(published_content) => format_caption(&published_content.caption_variant, &published_content.hashtags, Destination::Story),
+                    None => "".to_string(),
+                };
+
+                self.coordinator.increment_counter("instagram_requests", Duration::from_secs(60 * 60)).await;
+                match upload_story(user_id, access_token, &story_share.url, &caption).await {
+                    Ok(story_id) => {
+                        self.println(&format!("[+] Shared {} on the story", story_share.original_shortcode));
+                        story_share.status = "published".to_string();
+                        story_share.story_id = story_id;
+                    }
+                    Err(GraphApiError::UploadSucceededButFailedToRetrieveId(_)) => {
+                        self.println(&format!("[+] Shared {} on the story, without getting its id", story_share.original_shortcode));
+                        story_share.status = "published".to_string();
+                    }
+                    // Tried again at the next iteration of the poster
+                    Err(GraphApiError::UploadFailedRecoverable(e)) => {
+                        self.println(&format!("Error while sharing {} on the story, retrying later: {}", story_share.original_shortcode, e));
+                        continue;
+                    }
+                    Err(e) => {
+                        self.println(&format!("[!] Couldn't share {} on the story: {}", story_share.original_shortcode, e));
+                        story_share.status = "failed".to_string();
+                        story_share.error = e.to_string();
+                    }
+                }
+            }
+
+            story_share.attempted_at = now.to_rfc3339();
+            tx.save_story_share(&story_share).await;
+        }
+    }
+}
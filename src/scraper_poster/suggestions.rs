@@ -0,0 +1,109 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::prelude::{SliceRandom, StdRng};
+use rand::SeedableRng;
+use serde_json::Value;
+use serenity::all::MessageId;
+
+use crate::database::database::{SourceAccount, SourceSuggestion};
+use crate::discord::utils::now_in_my_timezone;
+use crate::scraper_poster::scraper::ContentManager;
+use crate::scraper_poster::stories::INSTAGRAM_APP_ID;
+use crate::scraper_poster::utils::pause_scraper_if_needed;
+use crate::{FETCH_SLEEP_LEN, MAX_SOURCE_SUGGESTIONS_PER_ITERATION, SOURCES_TO_SUGGEST_FROM};
+
+/// The accounts Instagram suggests on the profile of an account
+const CHAINING_URL: &str = "https://i.instagram.com/api/v1/discover/chaining/";
+const WEB_PROFILE_INFO_URL: &str = "https://i.instagram.com/api/v1/users/web_profile_info/";
+
+impl ContentManager {
+    /// Looks at the accounts Instagram suggests alongside a few of the source accounts, the public ones that aren't scraped yet are suggested on Discord
+    ///
+    /// Enabled with suggest_sources in the credentials
+    pub(crate) async fn suggest_sources(&self, accounts_to_scrape: &HashMap<String, String>, accounts_being_scraped: &[SourceAccount]) {
+        if !self.credentials.get("suggest_sources").is_some_and(|value| value == "true") {
+            return;
+        }
+
+        let mut tx = self.database.begin_transaction().await;
+        // The dismissed suggestions are kept around, so that they aren't suggested again
+        let mut known_profiles: HashSet<String> = tx.load_source_suggestions().await.into_iter().map(|suggestion| suggestion.profile).collect();
+        known_profiles.extend(accounts_to_scrape.keys().cloned());
+        known_profiles.insert(self.username.clone());
+
+        let mut source_accounts: Vec<&SourceAccount> = accounts_being_scraped.iter().collect();
+        source_accounts.shuffle(&mut StdRng::from_entropy());
+
+        self.println("Looking for new source accounts...");
+        let mut suggested = 0;
+        for source_account in source_accounts.into_iter().take(SOURCES_TO_SUGGEST_FROM) {
+            pause_scraper_if_needed(&mut tx).await;
+            self.wait_for_instagram_budget().await;
+
+            let related_profiles = match self.fetch_related_profiles(&source_account.user_id).await {
+                Ok(related_profiles) => related_profiles,
+                Err(e) => {
+                    self.println(&format!("Error fetching the accounts related to {}: {}", source_account.display_name, e));
+                    continue;
+                }
+            };
+            self.rate_limiter.pace(FETCH_SLEEP_LEN).await;
+
+            for profile in related_profiles {
+                if suggested >= MAX_SOURCE_SUGGESTIONS_PER_ITERATION {
+                    return;
+                }
+                if known_profiles.contains(&profile) {
+                    continue;
+                }
+
+                self.wait_for_instagram_budget().await;
+                let followers = match self.fetch_follower_count(&profile).await {
+                    Ok(followers) => followers,
+                    Err(e) => {
+                        self.println(&format!("Error fetching the followers of {}: {}", profile, e));
+                        continue;
+                    }
+                };
+                self.rate_limiter.pace(FETCH_SLEEP_LEN).await;
+
+                let now = now_in_my_timezone(&tx.load_user_settings().await);
+                let suggestion = SourceSuggestion {
+                    username: self.username.clone(),
+                    profile: profile.clone(),
+                    found_through: source_account.profile.clone(),
+                    followers,
+                    category: accounts_to_scrape.get(&source_account.profile).cloned().unwrap_or_default(),
+                    status: "pending".to_string(),
+                    message_id: MessageId::new(1),
+                    suggested_at: now.to_rfc3339(),
+                };
+                tx.save_source_suggestion(&suggestion).await;
+                self.println(&format!("Suggested {} as a source account, found through {}", profile, source_account.display_name));
+
+                known_profiles.insert(profile);
+                suggested += 1;
+            }
+        }
+    }
+
+    /// Returns the usernames of the public accounts Instagram suggests alongside the given one
+    async fn fetch_related_profiles(&self, user_id: &str) -> anyhow::Result<Vec<String>> {
+        let response = self.private_api_client().await?.get(CHAINING_URL).query(&[("target_id", user_id)]).header("X-IG-App-ID", INSTAGRAM_APP_ID).send().await?.error_for_status()?.text().await?;
+        let response: Value = serde_json::from_str(&response)?;
+
+        let related_profiles = response["users"].as_array().into_iter().flatten().filter(|user| !user["is_private"].as_bool().unwrap_or(true)).filter_map(|user| user["username"].as_str().map(str::to_string)).collect();
+
+        Ok(related_profiles)
+    }
+
+    async fn fetch_follower_count(&self, profile: &str) -> anyhow::Result<i64> {
+        let response = self.private_api_client().await?.get(WEB_PROFILE_INFO_URL).query(&[("username", profile)]).header("X-IG-App-ID", INSTAGRAM_APP_ID).send().await?.error_for_status()?.text().await?;
+        let response: Value = serde_json::from_str(&response)?;
+
+        let Some(followers) = response["data"]["user"]["edge_followed_by"]["count"].as_i64() else {
+            anyhow::bail!("The profile of {} has no follower count", profile);
+        };
+        Ok(followers)
+    }
+}
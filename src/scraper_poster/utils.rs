@@ -1,13 +1,14 @@
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration as StdDuration;
 
-use chrono::Duration;
-use instagram_scraper_rs::User;
 use rand::prelude::{SliceRandom, StdRng};
+use rand::Rng;
 use reqwest_cookie_store::CookieStoreMutex;
 
-use crate::database::database::DatabaseTransaction;
-use crate::discord::utils::now_in_my_timezone;
+use crate::database::database::{DatabaseTransaction, SourceAccount};
+use crate::discord::utils::{is_in_scrape_window, now_in_my_timezone};
+use crate::maintenance::windows::current_maintenance_window;
 use crate::SCRAPER_REFRESH_RATE;
 
 pub async fn save_cookie_store_to_json(cookie_store_path: &String, cookie_store_mutex: Arc<CookieStoreMutex>) {
@@ -18,24 +19,34 @@ pub async fn save_cookie_store_to_json(cookie_store_path: &String, cookie_store_
     cookie_store_mutex.lock().unwrap().save_json(&mut writer).expect("ERROR in scraper utils, failed to save cookie_store!");
 }
 
+/// Randomizes the duration by up to 30% in either direction, to avoid acting at predictable times
+pub fn jittered_duration(duration: StdDuration) -> StdDuration {
+    let mut rng = rand::thread_rng();
+    let factor: f64 = rng.gen_range(0.7..=1.3);
+    StdDuration::from_secs_f64(duration.as_secs_f64() * factor)
+}
+
 pub async fn pause_scraper_if_needed(tx: &mut DatabaseTransaction) {
-    loop {
-        let bot_status = tx.load_bot_status().await;
-        if bot_status.manual_mode || bot_status.status != 0 {
-            tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
-        } else {
-            break;
-        }
+    while is_scraper_paused(tx).await {
+        tokio::time::sleep(SCRAPER_REFRESH_RATE).await;
     }
 }
 
+pub async fn is_scraper_paused(tx: &mut DatabaseTransaction) -> bool {
+    let bot_status = tx.load_bot_status().await;
+    let user_settings = tx.load_user_settings().await;
+    let is_in_maintenance = current_maintenance_window(now_in_my_timezone(&user_settings)).is_some();
+    // While draining the scraper stays idle, so that no new content is added to the pipeline
+    bot_status.manual_mode || bot_status.status != 0 || !bot_status.drain_until.is_empty() || is_in_maintenance || !user_settings.scraping_enabled || !is_in_scrape_window(&user_settings)
+}
+
 pub async fn set_bot_status_halted(tx: &mut DatabaseTransaction) {
     let mut bot_status = tx.load_bot_status().await;
     let mut user_settings = tx.load_user_settings().await;
     user_settings.can_post = false;
     bot_status.status = 1;
     bot_status.status_message = "halted  ⚠️".to_string();
-    bot_status.last_updated_at = (now_in_my_timezone(&user_settings) - Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+    bot_status.last_updated_at = (now_in_my_timezone(&user_settings) - user_settings.interface_update_interval()).to_rfc3339();
     println!(" [{}] HALTED! ", bot_status.username);
     tx.save_bot_status(&bot_status).await;
     tx.save_user_settings(&user_settings).await;
@@ -47,12 +58,12 @@ pub async fn set_bot_status_operational(tx: &mut DatabaseTransaction) {
     user_settings.can_post = true;
     bot_status.status = 0;
     bot_status.status_message = "operational  🟢".to_string();
-    bot_status.last_updated_at = (now_in_my_timezone(&user_settings) - Duration::milliseconds(user_settings.interface_update_interval)).to_rfc3339();
+    bot_status.last_updated_at = (now_in_my_timezone(&user_settings) - user_settings.interface_update_interval()).to_rfc3339();
     tx.save_bot_status(&bot_status).await;
     tx.save_user_settings(&user_settings).await;
 }
 
-pub fn process_caption(accounts_to_scrape: &HashMap<String, String>, hashtag_mapping: &HashMap<String, String>, mut rng: &mut StdRng, author: &User, caption: String) -> String {
+pub fn process_caption(accounts_to_scrape: &HashMap<String, String>, hashtag_mapping: &HashMap<String, String>, mut rng: &mut StdRng, author: &SourceAccount, caption: String) -> String {
     // Check if the caption contains any hashtags
 
     // Sadasscats
@@ -126,7 +137,7 @@ pub fn process_caption(accounts_to_scrape: &HashMap<String, String>, hashtag_map
         hashtags.shuffle(&mut rng);
         hashtags.join(" ")
     } else {
-        let hashtag_type = accounts_to_scrape.get(&author.username.clone()).unwrap().clone();
+        let hashtag_type = accounts_to_scrape.get(&author.profile).unwrap().clone();
         let specific_hashtags = hashtag_mapping.get(&hashtag_type).unwrap().clone();
         let general_hashtags = hashtag_mapping.get("general").unwrap().clone();
 
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::process::{Command, Stdio};
+
+use crate::database::database::{Database, SCHEMA_VERSION};
+use crate::profile::config_profile::CONFIG_PROFILE;
+use crate::scraper_poster::canary::validate_canary_steps;
+use crate::{init_bucket, RunMode};
+
+/// Set at build time, e.g. with GIT_HASH=$(git rev-parse --short HEAD) cargo build
+const GIT_HASH: Option<&str> = option_env!("GIT_HASH");
+
+/// The tools called from the command line with the argument printing their version, and whether the bot can work without them
+const EXTERNAL_TOOLS: [(&str, &str, bool); 3] = [("ffmpeg", "-version", false), ("ffprobe", "-version", false), ("yt-dlp", "--version", true)];
+
+/// What the instance found out about its environment at startup, so that a misconfigured deployment stands out right away
+#[derive(Debug, Clone)]
+pub(crate) struct SelfCheckReport {
+    run_mode: RunMode,
+    tools: Vec<ToolCheck>,
+    accounts: Vec<AccountCheck>,
+}
+
+#[derive(Debug, Clone)]
+struct ToolCheck {
+    name: &'static str,
+    is_optional: bool,
+    version: Result<String, String>,
+}
+
+#[derive(Debug, Clone)]
+struct AccountCheck {
+    username: String,
+    /// The schema version the database is at
+    database: Result<i32, String>,
+    s3: Result<(), String>,
+    canary_steps: Result<(), String>,
+}
+
+impl SelfCheckReport {
+    /// Checks the tools of the host, then the database and the bucket of each of the enabled accounts
+    pub(crate) async fn run(enabled_credentials: &[(&String, &HashMap<String, String>)], run_mode: RunMode) -> Self {
+        let tools = EXTERNAL_TOOLS
+            .iter()
+            .map(|(name, version_argument, is_optional)| ToolCheck {
+                name: *name,
+                is_optional: *is_optional,
+                version: tool_version(name, version_argument),
+            })
+            .collect();
+
+        let mut accounts = Vec::new();
+        for (username, credentials) in enabled_credentials {
+            let database = match Database::new(username.to_string(), (*credentials).clone()).await {
+                Ok(database) => Ok(database.begin_transaction().await.load_schema_version().await),
+                Err(e) => Err(e.to_string()),
+            };
+
+            let bucket = init_bucket((*credentials).clone());
+            let s3 = bucket.list_page(CONFIG_PROFILE.bucket_prefix.clone(), None, None, None, Some(1)).await.map(|_| ()).map_err(|e| e.to_string());
+
+            let canary_steps = validate_canary_steps(credentials);
+
+            accounts.push(AccountCheck { username: username.to_string(), database, s3, canary_steps });
+        }
+
+        Self { run_mode, tools, accounts }
+    }
+
+    pub(crate) fn has_failures(&self) -> bool {
+        self.tools.iter().any(|tool| tool.version.is_err() && !tool.is_optional) || self.accounts.iter().any(|account| account.database.is_err() || account.s3.is_err() || account.canary_steps.is_err())
+    }
+}
+
+impl fmt::Display for SelfCheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Starting repost_rusty {} ({}) with the {} profile, {:?} mode", env!("CARGO_PKG_VERSION"), GIT_HASH.unwrap_or("unknown commit"), CONFIG_PROFILE.name, self.run_mode)?;
+
+        for tool in &self.tools {
+            match &tool.version {
+                Ok(version) => writeln!(f, "✅ {}", version)?,
+                Err(e) if tool.is_optional => writeln!(f, "⚠️ {} is not available, only needed by some sources: {}", tool.name, e)?,
+                Err(e) => writeln!(f, "❌ {} is not available: {}", tool.name, e)?,
+            }
+        }
+
+        if self.accounts.is_empty() {
+            writeln!(f, "❌ No account is enabled in the credentials")?;
+        }
+        for account in &self.accounts {
+            let database = match &account.database {
+                Ok(version) if *version > SCHEMA_VERSION => format!("⚠️ database at schema version {}, newer than this build ({})", version, SCHEMA_VERSION),
+                Ok(version) => format!("✅ database at schema version {}", version),
+                Err(e) => format!("❌ database unreachable: {}", e),
+            };
+            let s3 = match &account.s3 {
+                Ok(_) => "✅ S3 reachable".to_string(),
+                Err(e) => format!("❌ S3 unreachable: {}", e),
+            };
+            writeln!(f, "{}: {}, {}", account.username, database, s3)?;
+            if let Err(e) = &account.canary_steps {
+                writeln!(f, "❌ {}: the canary step {}", account.username, e)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the first line printed by the tool, which holds its version
+fn tool_version(name: &str, version_argument: &str) -> Result<String, String> {
+    let output = Command::new(name).arg(version_argument).stdout(Stdio::piped()).stderr(Stdio::null()).output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(format!("exited with {}", output.status));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first_line = stdout.lines().next().unwrap_or_default();
+    // ffmpeg follows its version with the copyright
+    let version = first_line.split(" Copyright").next().unwrap_or_default().trim();
+    if version.starts_with(name) {
+        Ok(version.to_string())
+    } else {
+        Ok(format!("{} {}", name, version))
+    }
+}
@@ -0,0 +1,3 @@
+pub(crate) mod panics;
+pub(crate) mod tasks;
+pub(crate) mod watchdog;
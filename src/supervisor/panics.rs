@@ -0,0 +1,39 @@
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::panic::Location;
+use std::sync::Mutex;
+
+/// The panic hook runs on the thread that panicked, the supervisor picks the panic up from here once the task is gone
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Keeps the message, the location and the backtrace of the panics, on top of printing them as usual
+pub(crate) fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic_info| {
+        let panic = format!("{}\n{}", describe_panic(panic_info.payload(), panic_info.location()), Backtrace::force_capture());
+        tracing::error!("{}", panic);
+        if let Ok(mut last_panic) = LAST_PANIC.lock() {
+            *last_panic = Some(panic);
+        }
+        default_hook(panic_info);
+    }));
+}
+
+/// Returns the panic caught by the hook since the last call, if there was one
+pub(crate) fn take_last_panic() -> Option<String> {
+    LAST_PANIC.lock().ok()?.take()
+}
+
+/// e.g. "called `Option::unwrap()` on a `None` value at src/scraper_poster/scraper.rs:42:17"
+fn describe_panic(payload: &(dyn Any + Send), location: Option<&Location>) -> String {
+    let message = match (payload.downcast_ref::<&str>(), payload.downcast_ref::<String>()) {
+        (Some(message), _) => message.to_string(),
+        (_, Some(message)) => message.clone(),
+        _ => "unknown panic".to_string(),
+    };
+
+    match location {
+        Some(location) => format!("{} at {}:{}:{}", message, location.file(), location.line(), location.column()),
+        None => message,
+    }
+}
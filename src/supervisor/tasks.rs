@@ -0,0 +1,40 @@
+use std::future::Future;
+
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+
+use crate::database::database::Database;
+use crate::discord::utils::now_in_my_timezone;
+use crate::supervisor::panics::take_last_panic;
+use crate::SUBSYSTEM_RESTART_DELAY;
+
+/// Keeps a subsystem running, when one of its tasks crashes the others are stopped and all of them are started again
+///
+/// The crash is recorded in the bot status, so that the frontend can let the status channel know even when it runs in another process
+pub(crate) async fn supervise<F, Fut>(database: Database, subsystem: &'static str, mut start: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Vec<JoinHandle<anyhow::Result<()>>>>,
+{
+    loop {
+        let tasks = start().await;
+        let (result, _, remaining_tasks) = futures::future::select_all(tasks).await;
+        for task in remaining_tasks {
+            task.abort();
+        }
+
+        let crash = match result {
+            Err(e) if e.is_panic() => take_last_panic().unwrap_or_else(|| format!("panicked: {}", e)),
+            Err(e) => format!("was cancelled: {}", e),
+            Ok(Err(e)) => format!("returned an error: {:?}", e),
+            Ok(Ok(())) => "stopped on its own".to_string(),
+        };
+        tracing::error!("The {} subsystem crashed, restarting it in {} seconds: {}", subsystem, SUBSYSTEM_RESTART_DELAY.as_secs(), crash);
+
+        let mut tx = database.begin_transaction().await;
+        let crashed_at = now_in_my_timezone(&tx.load_user_settings().await).to_rfc3339();
+        tx.record_panic(&format!("{}: {}", subsystem, crash), &crashed_at).await;
+
+        sleep(SUBSYSTEM_RESTART_DELAY).await;
+    }
+}
@@ -0,0 +1,29 @@
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+
+/// An operation that was cancelled by its watchdog
+#[derive(Debug)]
+pub(crate) struct WatchdogTimeout {
+    operation: String,
+    limit: Duration,
+}
+
+impl fmt::Display for WatchdogTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} was cancelled after {} seconds", self.operation, self.limit.as_secs())
+    }
+}
+
+impl std::error::Error for WatchdogTimeout {}
+
+/// Runs an operation that could hang forever, it's dropped once it runs past the limit so that the loop waiting on it can move on
+///
+/// Whatever the operation left behind, like a partial download, is up to the caller to clean up
+pub(crate) async fn with_watchdog<F: Future>(operation: &str, limit: Duration, future: F) -> Result<F::Output, WatchdogTimeout> {
+    tokio::time::timeout(limit, future).await.map_err(|_| {
+        let timeout = WatchdogTimeout { operation: operation.to_string(), limit };
+        tracing::warn!("{}", timeout);
+        timeout
+    })
+}
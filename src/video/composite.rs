@@ -0,0 +1,64 @@
+use std::io::Cursor;
+
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImage, ImageFormat, Rgb, RgbImage};
+
+use crate::video::error::{VideoProcessingError, VideoProcessingResult};
+
+/// Height of each frame in the strips, enough to recognize the content while keeping them small in the database
+const FRAME_STRIP_HEIGHT: u32 = 160;
+const FRAME_BORDER: u32 = 4;
+const MATCHED_FRAME_COLOR: Rgb<u8> = Rgb([46, 204, 113]);
+const UNMATCHED_FRAME_COLOR: Rgb<u8> = Rgb([231, 76, 60]);
+
+/// Puts the sampled frames of a video next to each other, returns the strip encoded as a JPEG
+pub fn build_frame_strip(frames: &[&DynamicImage]) -> VideoProcessingResult<Vec<u8>> {
+    let frames: Vec<RgbImage> = frames.iter().map(|frame| frame.resize(u32::MAX, FRAME_STRIP_HEIGHT, FilterType::Triangle).to_rgb8()).collect();
+    let width = frames.iter().map(|frame| frame.width()).sum();
+
+    let mut strip = RgbImage::new(width, FRAME_STRIP_HEIGHT);
+    let mut x = 0;
+    for frame in &frames {
+        strip.copy_from(frame, x, 0).map_err(|_| VideoProcessingError::CompositeError)?;
+        x += frame.width();
+    }
+    encode_jpeg(strip)
+}
+
+/// Stacks the frames of the new video above the ones of the video it was matched with,
+/// each pair of frames is framed in green when it matched and in red otherwise
+pub fn build_duplicate_composite(new_strip: &[u8], matched_strip: &[u8], matched_frames: &[bool]) -> VideoProcessingResult<Vec<u8>> {
+    let new_strip = image::load_from_memory(new_strip).map_err(|_| VideoProcessingError::CompositeError)?.to_rgb8();
+    let matched_strip = image::load_from_memory(matched_strip).map_err(|_| VideoProcessingError::CompositeError)?.to_rgb8();
+
+    let width = new_strip.width().max(matched_strip.width()) + FRAME_BORDER * 2;
+    let height = FRAME_STRIP_HEIGHT * 2 + FRAME_BORDER * 3;
+    let mut composite = RgbImage::new(width, height);
+
+    for (row, strip) in [&new_strip, &matched_strip].into_iter().enumerate() {
+        let y = FRAME_BORDER + row as u32 * (FRAME_STRIP_HEIGHT + FRAME_BORDER);
+        let frame_width = strip.width() / matched_frames.len().max(1) as u32;
+
+        for (frame, is_matched) in matched_frames.iter().enumerate() {
+            let color = if *is_matched { MATCHED_FRAME_COLOR } else { UNMATCHED_FRAME_COLOR };
+            let x = FRAME_BORDER + frame as u32 * frame_width;
+            fill_rect(&mut composite, x.saturating_sub(FRAME_BORDER), y - FRAME_BORDER, frame_width + FRAME_BORDER * 2, FRAME_STRIP_HEIGHT + FRAME_BORDER * 2, color);
+        }
+        composite.copy_from(strip, FRAME_BORDER, y).map_err(|_| VideoProcessingError::CompositeError)?;
+    }
+    encode_jpeg(composite)
+}
+
+fn fill_rect(image: &mut RgbImage, x: u32, y: u32, width: u32, height: u32, color: Rgb<u8>) {
+    for pixel_y in y..(y + height).min(image.height()) {
+        for pixel_x in x..(x + width).min(image.width()) {
+            image.put_pixel(pixel_x, pixel_y, color);
+        }
+    }
+}
+
+fn encode_jpeg(image: RgbImage) -> VideoProcessingResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    DynamicImage::ImageRgb8(image).write_to(&mut Cursor::new(&mut bytes), ImageFormat::Jpeg).map_err(|_| VideoProcessingError::CompositeError)?;
+    Ok(bytes)
+}
@@ -8,4 +8,16 @@ pub enum VideoProcessingError {
     DurationError(String),
     #[error("Failed to extract frame {0} from video!")]
     FrameExtractionError(i32),
+    #[error("Failed to generate the preview for {0}!")]
+    PreviewGenerationError(String),
+    #[error("Failed to generate the preview clip for {0}!")]
+    ClipGenerationError(String),
+    #[error("Failed to merge the audio track into {0}!")]
+    AudioMergeError(String),
+    #[error("Failed to re-encode {0}!")]
+    ReencodeError(String),
+    #[error("Failed to build the compilation {0}!")]
+    CompilationError(String),
+    #[error("Failed to compose the frames!")]
+    CompositeError,
 }
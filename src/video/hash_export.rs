@@ -0,0 +1,55 @@
+use image_hasher::ImageHash;
+use serde::{Deserialize, Serialize};
+
+use crate::database::database::HashedVideo;
+
+/// Portable copy of the frame hashes of an account, so that another account can skip what was already posted here
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashExport {
+    pub account: String,
+    pub exported_at: String,
+    pub hashes: Vec<ExportedHash>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedHash {
+    pub original_shortcode: String,
+    pub duration: f64,
+    /// Base64 hashes of the four sampled frames, like in the database
+    pub frames: [String; 4],
+}
+
+impl HashExport {
+    pub fn new(account: &str, exported_at: &str, hashed_videos: &[HashedVideo]) -> Self {
+        let hashes = hashed_videos
+            .iter()
+            .map(|hashed_video| ExportedHash {
+                original_shortcode: hashed_video.original_shortcode.clone(),
+                duration: hashed_video.duration,
+                frames: [hashed_video.hash_frame_1.to_base64(), hashed_video.hash_frame_2.to_base64(), hashed_video.hash_frame_3.to_base64(), hashed_video.hash_frame_4.to_base64()],
+            })
+            .collect();
+
+        HashExport { account: account.to_string(), exported_at: exported_at.to_string(), hashes }
+    }
+
+    /// Returns the hashes attributed to the exporting account, the ones that can't be decoded are left out
+    pub fn into_hashed_videos(self) -> Vec<HashedVideo> {
+        let account = self.account;
+        self.hashes
+            .into_iter()
+            .filter_map(|exported_hash| {
+                let [frame_1, frame_2, frame_3, frame_4] = exported_hash.frames;
+                Some(HashedVideo {
+                    username: account.clone(),
+                    duration: exported_hash.duration,
+                    original_shortcode: exported_hash.original_shortcode,
+                    hash_frame_1: ImageHash::from_base64(&frame_1).ok()?,
+                    hash_frame_2: ImageHash::from_base64(&frame_2).ok()?,
+                    hash_frame_3: ImageHash::from_base64(&frame_3).ok()?,
+                    hash_frame_4: ImageHash::from_base64(&frame_4).ok()?,
+                })
+            })
+            .collect()
+    }
+}
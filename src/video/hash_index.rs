@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use image_hasher::ImageHash;
+
+use crate::database::database::HashedVideo;
+
+/// BK-tree over the frame hashes of the known videos, so that the duplicate checks don't go through the whole archive
+///
+/// The distance between two videos is the sum of the distances between their frame hashes, which is a metric,
+/// lookups only visit the branches that can hold a video within the searched distance
+/// The tree lives in memory and is rebuilt from the database when the scraper starts
+#[derive(Debug, Default)]
+pub struct HashIndex {
+    nodes: Vec<HashIndexNode>,
+}
+
+#[derive(Debug)]
+struct HashIndexNode {
+    hashed_video: HashedVideo,
+    /// Index of the child node at each distance from this one
+    children: HashMap<u32, usize>,
+}
+
+impl HashIndex {
+    pub fn new(hashed_videos: Vec<HashedVideo>) -> Self {
+        let mut hash_index = HashIndex::default();
+        for hashed_video in hashed_videos {
+            hash_index.insert(hashed_video);
+        }
+        hash_index
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn insert(&mut self, hashed_video: HashedVideo) {
+        let new_node = self.nodes.len();
+        if new_node == 0 {
+            self.nodes.push(HashIndexNode { hashed_video, children: HashMap::new() });
+            return;
+        }
+
+        let frame_hashes = frame_hashes(&hashed_video);
+        let mut current = 0;
+        loop {
+            let node = &self.nodes[current];
+            if node.hashed_video.original_shortcode == hashed_video.original_shortcode {
+                // The video got hashed again, keep the latest hashes
+                self.nodes[current].hashed_video = hashed_video;
+                return;
+            }
+
+            let distance = video_distance(&frame_hashes(&node.hashed_video), &frame_hashes);
+            match node.children.get(&distance) {
+                Some(child) => current = *child,
+                None => {
+                    self.nodes[current].children.insert(distance, new_node);
+                    self.nodes.push(HashIndexNode { hashed_video, children: HashMap::new() });
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Returns the videos whose frame hashes are within `max_distance` of the given ones, summed over the frames
+    pub fn find_within(&self, probe: &[&ImageHash; 4], max_distance: u32) -> Vec<&HashedVideo> {
+        let mut found = Vec::new();
+        if self.nodes.is_empty() {
+            return found;
+        }
+
+        let mut to_visit = vec![0];
+        while let Some(current) = to_visit.pop() {
+            let node = &self.nodes[current];
+            let distance = video_distance(&frame_hashes(&node.hashed_video), probe);
+            if distance <= max_distance {
+                found.push(&node.hashed_video);
+            }
+
+            // By the triangle inequality, only the children in this range can be close enough
+            let range = distance.saturating_sub(max_distance)..=distance + max_distance;
+            to_visit.extend(node.children.iter().filter(|(child_distance, _)| range.contains(child_distance)).map(|(_, child)| *child));
+        }
+        found
+    }
+}
+
+fn frame_hashes(hashed_video: &HashedVideo) -> [&ImageHash; 4] {
+    [&hashed_video.hash_frame_1, &hashed_video.hash_frame_2, &hashed_video.hash_frame_3, &hashed_video.hash_frame_4]
+}
+
+fn video_distance(a: &[&ImageHash; 4], b: &[&ImageHash; 4]) -> u32 {
+    a.iter().zip(b).map(|(a, b)| a.dist(b)).sum()
+}
@@ -1,2 +1,6 @@
+mod composite;
 mod error;
+pub mod hash_export;
+pub mod hash_index;
 pub mod processing;
+pub mod similarity;
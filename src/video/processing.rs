@@ -4,7 +4,27 @@ use std::process::Stdio;
 use image_hasher::HasherConfig;
 
 use crate::database::database::{DatabaseTransaction, HashedVideo};
+use crate::video::composite::{build_duplicate_composite, build_frame_strip};
 use crate::video::error::{VideoProcessingError, VideoProcessingResult};
+use crate::video::hash_index::HashIndex;
+use crate::video::similarity::compute_embedding;
+
+/// Height of the rendition shown on the Discord cards, the original file is always used for publishing
+const PREVIEW_HEIGHT: u32 = 480;
+/// Length in seconds of the looping clip shown on the compact cards
+const CLIP_LENGTH: u32 = 3;
+const CLIP_HEIGHT: u32 = 240;
+const CLIP_FPS: u32 = 12;
+/// The clips of a compilation are fitted in a vertical frame, the way the reels are shown
+const COMPILATION_WIDTH: u32 = 1080;
+const COMPILATION_HEIGHT: u32 = 1920;
+const COMPILATION_FPS: u32 = 30;
+/// Highest average distance between the frame hashes for two videos to be considered the same
+pub const DUPLICATE_MAX_DISTANCE: u32 = 3;
+/// How the processing steps are named in the provenance of the content, along with their ffmpeg arguments
+pub const MERGE_AUDIO_STEP: &str = "merge_audio -map 0:v:0 -map 1:a:0 -c copy";
+pub const REENCODE_STEP: &str = "reencode -c:v libx264 -preset medium -crf 20 -pix_fmt yuv420p -c:a aac -b:a 128k";
+pub const COMPILATION_STEP: &str = "compilation -filter_complex scale=1080:1920 concat -c:v libx264 -crf 20 -c:a aac";
 
 fn divide_number(n: i32) -> [i32; 4] {
     let part1 = 0;
@@ -17,7 +37,39 @@ fn divide_number(n: i32) -> [i32; 4] {
 
 /// Returns whether the video already exists in the database
 
-pub async fn process_video(tx: &mut DatabaseTransaction, video_path: &str, username: String, shortcode: String) -> VideoProcessingResult<bool> {
+/// What the duplicate detector found, so that the operator can check the match
+#[derive(Debug, Clone)]
+pub struct DuplicateMatch {
+    pub matched_shortcode: String,
+    /// The account the matched video belongs to, another one when its hashes were imported
+    pub matched_account: String,
+    /// Average distance between the frame hashes of the two videos
+    pub distance: u32,
+    /// Distance between each pair of sampled frames
+    pub frame_distances: [u32; 4],
+    /// The frames of both videos side by side as a JPEG, None when the frames of the matched video weren't kept
+    pub composite: Option<Vec<u8>>,
+}
+
+/// What the duplicate detector compared the video with, kept in the provenance of the content
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateCheck {
+    /// Set when the video is a duplicate
+    pub duplicate_match: Option<DuplicateMatch>,
+    /// Average distance to the closest known video, including the ones of another duration, None when no video was close enough to be compared
+    pub closest_distance: Option<u32>,
+    /// How many known videos the video could have matched
+    pub compared_videos: usize,
+}
+
+impl DuplicateMatch {
+    pub fn matched_frames(&self) -> [bool; 4] {
+        self.frame_distances.map(|frame_distance| frame_distance <= DUPLICATE_MAX_DISTANCE)
+    }
+}
+
+/// Hashes the video and compares it with the known ones, the check holds the closest match if it is a duplicate
+pub async fn process_video(tx: &mut DatabaseTransaction, hash_index: &mut HashIndex, video_path: &str, username: String, shortcode: String) -> VideoProcessingResult<DuplicateCheck> {
     //println!("Processing video: {}, shortcode {}, username {}", video_path, shortcode, username);
     let path = format!("temp/{video_path}");
 
@@ -49,27 +101,56 @@ pub async fn process_video(tx: &mut DatabaseTransaction, video_path: &str, usern
     let hash3 = hasher.hash_image(&image3);
     let hash4 = hasher.hash_image(&image4);
 
-    let hashed_videos = tx.load_hashed_videos().await;
-
-    let mut video_exists = false;
-    for hashed_video in hashed_videos {
-        if hashed_video.duration != duration_seconds {
-            continue;
-        }
+    // The average distance is rounded down, so the summed distance can go up to 3 more than 4 times the maximum
+    let candidates = hash_index.find_within(&[&hash1, &hash2, &hash3, &hash4], DUPLICATE_MAX_DISTANCE * 4 + 3);
 
+    let compared_videos = hash_index.len();
+    let mut closest_distance: Option<u32> = None;
+    let mut closest_match: Option<DuplicateMatch> = None;
+    for hashed_video in candidates {
         let dist1 = hashed_video.hash_frame_1.dist(&hash1);
         let dist2 = hashed_video.hash_frame_2.dist(&hash2);
         let dist3 = hashed_video.hash_frame_3.dist(&hash3);
         let dist4 = hashed_video.hash_frame_4.dist(&hash4);
 
         let avg_dist = (dist1 + dist2 + dist3 + dist4) / 4;
+        closest_distance = Some(closest_distance.map_or(avg_dist, |closest_distance| closest_distance.min(avg_dist)));
+
+        if hashed_video.duration != duration_seconds {
+            continue;
+        }
 
-        if avg_dist <= 3 {
-            video_exists = true;
+        if avg_dist <= DUPLICATE_MAX_DISTANCE && closest_match.as_ref().map_or(true, |closest_match| avg_dist < closest_match.distance) {
+            closest_match = Some(DuplicateMatch {
+                matched_shortcode: hashed_video.original_shortcode.clone(),
+                matched_account: hashed_video.username.clone(),
+                distance: avg_dist,
+                frame_distances: [dist1, dist2, dist3, dist4],
+                composite: None,
+            });
         }
     }
 
-    if !video_exists {
+    let frame_strip = match build_frame_strip(&[&image1, &image2, &image3, &image4]) {
+        Ok(frame_strip) => Some(frame_strip),
+        Err(e) => {
+            tracing::warn!("Couldn't build the frame strip of {}: {}", shortcode, e);
+            None
+        }
+    };
+
+    if let Some(closest_match) = closest_match.as_mut() {
+        if let (Some(frame_strip), Some(matched_strip)) = (&frame_strip, tx.load_frame_strip(&closest_match.matched_shortcode).await) {
+            closest_match.composite = build_duplicate_composite(frame_strip, &matched_strip, &closest_match.matched_frames()).ok();
+        }
+    } else {
+        if let Some(frame_strip) = frame_strip {
+            tx.save_frame_strip(&shortcode, &frame_strip).await;
+        }
+
+        let embedding = compute_embedding(&[&image1, &image2, &image3, &image4]);
+        tx.save_video_embedding(&shortcode, &embedding).await;
+
         let video_hash = HashedVideo {
             username,
             duration: duration_seconds,
@@ -81,6 +162,7 @@ pub async fn process_video(tx: &mut DatabaseTransaction, video_path: &str, usern
         };
 
         tx.save_hashed_video(&video_hash).await;
+        hash_index.insert(video_hash);
     }
 
     // Delete the extracted frames
@@ -89,7 +171,11 @@ pub async fn process_video(tx: &mut DatabaseTransaction, video_path: &str, usern
     tokio::fs::remove_file(&frame_3_path).await.unwrap();
     tokio::fs::remove_file(&frame_4_path).await.unwrap();
 
-    Ok(video_exists)
+    Ok(DuplicateCheck {
+        duplicate_match: closest_match,
+        closest_distance,
+        compared_videos,
+    })
 }
 
 fn get_total_frames(video_path: &str) -> VideoProcessingResult<i32> {
@@ -112,7 +198,7 @@ fn get_total_frames(video_path: &str) -> VideoProcessingResult<i32> {
     Ok(total_frames)
 }
 
-fn get_video_duration(video_path: &str) -> VideoProcessingResult<f64> {
+pub fn get_video_duration(video_path: &str) -> VideoProcessingResult<f64> {
     let output = Command::new("ffprobe")
         .arg("-v")
         .arg("error")
@@ -154,3 +240,227 @@ fn extract_frame(video_path: &str, frame_number: i32, output_path: &str) -> Vide
 
     Ok(())
 }
+
+/// e.g. "C7x9Yb1Nq2w_480p.mp4" for "C7x9Yb1Nq2w.mp4", the preview is stored next to the video in the bucket
+pub fn preview_file_name(video_path: &str) -> String {
+    video_path.replace(".mp4", &format!("_{}p.mp4", PREVIEW_HEIGHT))
+}
+
+/// Generates a low resolution rendition of the video, used to keep the review channel lightweight
+///
+/// Returns the file name of the preview, relative to the temp folder
+pub fn generate_preview(video_path: &str) -> VideoProcessingResult<String> {
+    let path = format!("temp/{video_path}");
+    let preview_file_name = preview_file_name(video_path);
+    let preview_path = format!("temp/{preview_file_name}");
+
+    let status = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-i")
+        .arg(&path)
+        .arg("-vf")
+        .arg(format!("scale=-2:'min({},ih)'", PREVIEW_HEIGHT))
+        .arg("-c:v")
+        .arg("libx264")
+        .arg("-preset")
+        .arg("veryfast")
+        .arg("-crf")
+        .arg("28")
+        .arg("-c:a")
+        .arg("aac")
+        .arg("-b:a")
+        .arg("96k")
+        .arg(&preview_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .status()
+        .unwrap();
+
+    if !status.success() {
+        return Err(VideoProcessingError::PreviewGenerationError(video_path.to_string()));
+    }
+
+    Ok(preview_file_name)
+}
+
+/// Generates a short looping animated webp from the start of the video, used to triage content without playing it
+///
+/// Returns the file name of the clip, relative to the temp folder
+pub fn generate_preview_clip(video_path: &str) -> VideoProcessingResult<String> {
+    let path = format!("temp/{video_path}");
+    let clip_file_name = video_path.replace(".mp4", "_clip.webp");
+    let clip_path = format!("temp/{clip_file_name}");
+
+    let status = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-t")
+        .arg(CLIP_LENGTH.to_string())
+        .arg("-i")
+        .arg(&path)
+        .arg("-vf")
+        .arg(format!("fps={},scale=-2:'min({},ih)'", CLIP_FPS, CLIP_HEIGHT))
+        .arg("-an")
+        .arg("-c:v")
+        .arg("libwebp")
+        .arg("-quality")
+        .arg("60")
+        .arg("-loop")
+        .arg("0")
+        .arg(&clip_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .status()
+        .unwrap();
+
+    if !status.success() {
+        return Err(VideoProcessingError::ClipGenerationError(video_path.to_string()));
+    }
+
+    Ok(clip_file_name)
+}
+
+/// Muxes a separately downloaded audio track into the video, replacing the original file
+pub fn merge_audio(video_path: &str, audio_path: &str) -> VideoProcessingResult<()> {
+    let path = format!("temp/{video_path}");
+    let merged_path = format!("temp/{}", video_path.replace(".mp4", "_merged.mp4"));
+
+    let status = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-i")
+        .arg(&path)
+        .arg("-i")
+        .arg(format!("temp/{audio_path}"))
+        .arg("-map")
+        .arg("0:v:0")
+        .arg("-map")
+        .arg("1:a:0")
+        .arg("-c")
+        .arg("copy")
+        .arg(&merged_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .status()
+        .unwrap();
+
+    if !status.success() || std::fs::rename(&merged_path, &path).is_err() {
+        return Err(VideoProcessingError::AudioMergeError(video_path.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Re-encodes the video to H.264 and AAC in place, the codecs Instagram accepts from any source
+pub fn reencode(video_path: &str) -> VideoProcessingResult<()> {
+    let path = format!("temp/{video_path}");
+    let reencoded_path = format!("temp/{}", video_path.replace(".mp4", "_reencoded.mp4"));
+
+    let status = Command::new("ffmpeg")
+        .arg("-y")
+        .arg("-i")
+        .arg(&path)
+        .arg("-c:v")
+        .arg("libx264")
+        .arg("-preset")
+        .arg("medium")
+        .arg("-crf")
+        .arg("20")
+        .arg("-pix_fmt")
+        .arg("yuv420p")
+        .arg("-c:a")
+        .arg("aac")
+        .arg("-b:a")
+        .arg("128k")
+        .arg("-movflags")
+        .arg("+faststart")
+        .arg(&reencoded_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .status()
+        .unwrap();
+
+    if !status.success() || std::fs::rename(&reencoded_path, &path).is_err() {
+        return Err(VideoProcessingError::ReencodeError(video_path.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Whether the video has an audio track, the reels made from a photo or a silent clip have none
+fn has_audio_stream(video_path: &str) -> bool {
+    let output = Command::new("ffprobe")
+        .arg("-v")
+        .arg("error")
+        .arg("-select_streams")
+        .arg("a")
+        .arg("-show_entries")
+        .arg("stream=index")
+        .arg("-of")
+        .arg("csv=p=0")
+        .arg(video_path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .unwrap();
+
+    !String::from_utf8_lossy(&output.stdout).trim().is_empty()
+}
+
+/// Joins the clips one after the other into a single vertical video, the clips without an audio track are given silence so that they can be concatenated
+///
+/// The clips and the compilation are relative to the temp folder
+pub fn build_compilation(clip_paths: &[String], compilation_path: &str) -> VideoProcessingResult<()> {
+    let mut command = Command::new("ffmpeg");
+    command.arg("-y");
+    for clip_path in clip_paths {
+        command.arg("-i").arg(format!("temp/{clip_path}"));
+    }
+
+    let mut filters = Vec::new();
+    let mut segments = String::new();
+    for (i, clip_path) in clip_paths.iter().enumerate() {
+        filters.push(format!(
+            "[{i}:v]scale={COMPILATION_WIDTH}:{COMPILATION_HEIGHT}:force_original_aspect_ratio=decrease,pad={COMPILATION_WIDTH}:{COMPILATION_HEIGHT}:(ow-iw)/2:(oh-ih)/2,setsar=1,fps={COMPILATION_FPS}[v{i}]"
+        ));
+        if has_audio_stream(&format!("temp/{clip_path}")) {
+            filters.push(format!("[{i}:a]aresample=44100,aformat=channel_layouts=stereo[a{i}]"));
+        } else {
+            let duration = get_video_duration(&format!("temp/{clip_path}"))?;
+            filters.push(format!("anullsrc=channel_layout=stereo:sample_rate=44100,atrim=duration={duration}[a{i}]"));
+        }
+        segments.push_str(&format!("[v{i}][a{i}]"));
+    }
+    filters.push(format!("{segments}concat=n={}:v=1:a=1[v][a]", clip_paths.len()));
+
+    let status = command
+        .arg("-filter_complex")
+        .arg(filters.join(";"))
+        .arg("-map")
+        .arg("[v]")
+        .arg("-map")
+        .arg("[a]")
+        .arg("-c:v")
+        .arg("libx264")
+        .arg("-preset")
+        .arg("medium")
+        .arg("-crf")
+        .arg("20")
+        .arg("-pix_fmt")
+        .arg("yuv420p")
+        .arg("-c:a")
+        .arg("aac")
+        .arg("-b:a")
+        .arg("128k")
+        .arg("-movflags")
+        .arg("+faststart")
+        .arg(format!("temp/{compilation_path}"))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .status()
+        .unwrap();
+
+    if !status.success() {
+        return Err(VideoProcessingError::CompilationError(compilation_path.to_string()));
+    }
+
+    Ok(())
+}
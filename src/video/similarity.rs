@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+
+use image::imageops::FilterType;
+use image::DynamicImage;
+
+use crate::database::database::VideoEmbedding;
+
+/// Each frame is shrunk to a grid of this size, the embedding holds the average color of each cell
+const EMBEDDING_GRID_SIZE: u32 = 4;
+
+/// Summarizes the video as the average colors of a coarse grid over the sampled frames
+///
+/// Cheap enough to compute for every video, and close for clips filmed in the same place or with the same subject
+pub fn compute_embedding(frames: &[&DynamicImage]) -> Vec<f32> {
+    let cell_count = (EMBEDDING_GRID_SIZE * EMBEDDING_GRID_SIZE * 3) as usize;
+    let mut embedding = vec![0.0; cell_count];
+
+    for frame in frames {
+        let grid = frame.resize_exact(EMBEDDING_GRID_SIZE, EMBEDDING_GRID_SIZE, FilterType::Triangle).to_rgb8();
+        for (value, channel) in embedding.iter_mut().zip(grid.as_raw()) {
+            *value += *channel as f32 / 255.0 / frames.len() as f32;
+        }
+    }
+    embedding
+}
+
+/// Cosine similarity of the embeddings once centered, 1 for videos that look the same
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    let center = |embedding: &[f32]| {
+        let mean = embedding.iter().sum::<f32>() / embedding.len().max(1) as f32;
+        embedding.iter().map(|value| value - mean).collect::<Vec<f32>>()
+    };
+    let (a, b) = (center(a), center(b));
+
+    let dot: f32 = a.iter().zip(&b).map(|(a, b)| a * b).sum();
+    let norm = a.iter().map(|value| value * value).sum::<f32>().sqrt() * b.iter().map(|value| value * value).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        return 0.0;
+    }
+    dot / norm
+}
+
+/// Groups the videos that look alike, each video joins the first cluster whose first video is similar enough
+///
+/// Returns the cluster of each shortcode
+pub fn cluster_embeddings(video_embeddings: &[VideoEmbedding], threshold: f32) -> HashMap<String, usize> {
+    let mut leaders: Vec<&VideoEmbedding> = Vec::new();
+    let mut clusters = HashMap::new();
+
+    for video_embedding in video_embeddings {
+        let cluster = match leaders.iter().position(|leader| similarity(&leader.embedding, &video_embedding.embedding) >= threshold) {
+            Some(cluster) => cluster,
+            None => {
+                leaders.push(video_embedding);
+                leaders.len() - 1
+            }
+        };
+        clusters.insert(video_embedding.original_shortcode.clone(), cluster);
+    }
+    clusters
+}
+
+/// Returns the longest run of consecutive shortcodes belonging to the same cluster
+pub fn longest_similar_run<'a>(shortcodes: &[&'a str], clusters: &HashMap<String, usize>) -> Vec<&'a str> {
+    let mut longest: Vec<&str> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for shortcode in shortcodes {
+        let cluster = clusters.get(*shortcode);
+        let continues_run = cluster.is_some() && current.last().and_then(|last| clusters.get(*last)) == cluster;
+        if !continues_run {
+            current.clear();
+        }
+        current.push(shortcode);
+
+        if current.len() > longest.len() {
+            longest = current.clone();
+        }
+    }
+    longest
+}
+
+/// Reorders the shortcodes so that no two similar ones are next to each other when possible, keeping the order otherwise
+pub fn diversify_order<'a>(shortcodes: &[&'a str], clusters: &HashMap<String, usize>) -> Vec<&'a str> {
+    let mut remaining: Vec<&str> = shortcodes.to_vec();
+    let mut diversified: Vec<&str> = Vec::with_capacity(shortcodes.len());
+
+    while !remaining.is_empty() {
+        let previous_cluster = diversified.last().and_then(|last| clusters.get(*last));
+        let next = remaining.iter().position(|shortcode| previous_cluster.is_none() || clusters.get(*shortcode) != previous_cluster).unwrap_or(0);
+        diversified.push(remaining.remove(next));
+    }
+    diversified
+}